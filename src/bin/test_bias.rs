@@ -1,17 +1,19 @@
 use anyhow::Result;
 use lux_mcp::llm::config::LLMConfig;
 use lux_mcp::session::SessionManager;
+use lux_mcp::settings::LuxSettings;
 use lux_mcp::tools::{BiasedReasoningRequest, BiasedReasoningTool};
 use std::sync::Arc;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // Set up environment
+    dotenv::dotenv().ok();
     std::env::set_var("RUST_LOG", "info");
     tracing_subscriber::fmt::init();
 
     // Initialize components
-    let config = LLMConfig::from_env()?;
+    let config = LLMConfig::from_settings(&LuxSettings::load()?)?;
     let session_manager = Arc::new(SessionManager::new(60)); // 60 minute TTL
     let tool = BiasedReasoningTool::new(config, session_manager)?;
 
@@ -27,6 +29,8 @@ async fn main() -> Result<()> {
         max_analysis_rounds: 2,
         file_paths: None,
         include_file_contents: true,
+        reasoning_effort: None,
+        verbosity: None,
     };
 
     println!("📝 Step 1: Initial Query");
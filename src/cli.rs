@@ -0,0 +1,546 @@
+//! Command line interface. `serve` (the default) runs the MCP server; the other
+//! subcommands let scripts and CI use lux without an MCP host.
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use migration::MigratorTrait;
+use rmcp::model::CallToolRequestParam;
+use serde_json::{json, Value};
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::db::{DatabaseConnection, DatabaseService};
+use crate::llm::embeddings::Embedder;
+use crate::llm::model_aliases::ModelResolver;
+use crate::llm::LLMConfig;
+use crate::monitoring::calibration::{self, CalibrationReport};
+use crate::server::LuxServer;
+use crate::settings::LuxSettings;
+use crate::tools::setup_config::{validate_providers, HttpProbe, Provider};
+
+#[derive(Parser)]
+#[command(
+    name = "lux-mcp",
+    version,
+    about = "Illuminate your thinking - metacognitive monitoring MCP server"
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Run the MCP server on stdio (default)
+    Serve,
+    /// Check API keys, model access, database connectivity and migrations
+    ///
+    /// In Azure OpenAI mode keys are only checked for presence and each model's
+    /// deployment is shown; neither is probed.
+    Doctor,
+    /// Invoke one tool and print its output
+    Call {
+        /// Tool name, e.g. confer
+        tool: String,
+        /// Tool arguments as a JSON object ("-" reads them from stdin)
+        #[arg(long, default_value = "{}")]
+        json: String,
+    },
+    /// Read sessions persisted in the database
+    ///
+    /// Conversation threads (continuation IDs) live in the running server's
+    /// memory and are not persisted, so they are not listed.
+    Sessions {
+        #[command(subcommand)]
+        command: SessionsCommand,
+    },
+    /// Print the model alias table
+    Models {
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print LLM audit records as JSON lines
+    Audit {
+        /// Only records for this session or thread ID
+        #[arg(long)]
+        session: Option<String>,
+        /// Only records made by this tool
+        #[arg(long)]
+        tool: Option<String>,
+        /// Audit log to read (default: LUX_AUDIT_LOG)
+        #[arg(long)]
+        file: Option<PathBuf>,
+        /// Only the most recent N records
+        #[arg(long)]
+        limit: Option<usize>,
+    },
+    /// Score the monitor's detectors against a labelled JSONL corpus
+    Calibrate {
+        /// Corpus of thought sequences with expected detectors per thought
+        corpus: PathBuf,
+        /// Sweep thresholds and suggest [monitoring] values
+        #[arg(long)]
+        sweep: bool,
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SessionsCommand {
+    /// Most recent sessions first
+    List {
+        #[arg(long, default_value_t = 20)]
+        limit: u64,
+    },
+    /// A session's reasoning steps and latest synthesis
+    Show {
+        /// External session ID
+        id: String,
+    },
+    /// A session and all its rows as JSON
+    Export {
+        /// External session ID
+        id: String,
+        /// Write to this file instead of stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+}
+
+pub fn print_audit(
+    session: Option<String>,
+    tool: Option<String>,
+    file: Option<PathBuf>,
+    limit: Option<usize>,
+) -> Result<()> {
+    let path = match file {
+        Some(path) => path,
+        None => crate::llm::audit::AuditConfig::from_env(&crate::settings::Env::default())
+            .map(|config| config.path)
+            .context("No audit log configured. Set LUX_AUDIT_LOG or pass --file")?,
+    };
+
+    let records = crate::llm::audit::read_records(&path, session.as_deref(), tool.as_deref())?;
+    let skip = limit.map_or(0, |limit| records.len().saturating_sub(limit));
+    for record in records.iter().skip(skip) {
+        println!("{}", serde_json::to_string(record)?);
+    }
+    Ok(())
+}
+
+/// Run every check and print the results. Returns false if any check failed.
+pub async fn doctor() -> Result<bool> {
+    let settings = LuxSettings::load()?;
+    let config = LLMConfig::from_settings(&settings)?;
+    let mut healthy = true;
+
+    println!("Configuration");
+    for path in LuxSettings::config_paths() {
+        if path.is_file() {
+            println!("  ✓ {}", path.display());
+        }
+    }
+    println!("  ✓ {} per-tool section(s)", config.tools.len());
+
+    let models = [
+        ("LUX_MODEL_REASONING", config.model_reasoning.clone()),
+        ("LUX_MODEL_NORMAL", config.model_normal.clone()),
+        ("LUX_MODEL_MINI", config.model_mini.clone()),
+        (
+            "LUX_MODEL_OPUS",
+            config.model_opus.clone().unwrap_or_default(),
+        ),
+        (
+            "LUX_MODEL_SONNET",
+            config.model_sonnet.clone().unwrap_or_default(),
+        ),
+        (
+            "LUX_MODEL_GROK",
+            config.model_grok.clone().unwrap_or_default(),
+        ),
+    ];
+    let models: Vec<(&str, &str)> = models
+        .iter()
+        .filter(|(_, model)| !model.is_empty())
+        .map(|(setting, model)| (*setting, model.as_str()))
+        .collect();
+
+    println!("API keys");
+    if let Some(azure) = &config.azure_openai {
+        // Azure has no cheap data-plane call that lists deployments, so only
+        // the configuration is reported
+        println!(
+            "  - Azure OpenAI mode ({}): keys and deployments are not probed",
+            azure.endpoint
+        );
+        for (name, key) in [
+            ("AZURE_OPENAI_API_KEY", &config.openai_api_key),
+            ("OPENROUTER_API_KEY", &config.openrouter_api_key),
+        ] {
+            match key {
+                Some(_) => println!("  ✓ {} set", name),
+                None => println!("  - {} not set", name),
+            }
+        }
+        healthy &= config.validate().is_ok();
+
+        println!("Models");
+        let resolver = ModelResolver::with_config(Some(config.clone()));
+        for (setting, model) in &models {
+            let resolved = resolver.resolve(model);
+            if resolver.is_openrouter_model(&resolved) {
+                println!("  - {} = {} (OpenRouter)", setting, model);
+            } else {
+                println!(
+                    "  - {} = {} → deployment {}",
+                    setting,
+                    model,
+                    azure.deployment_for(&resolved)
+                );
+            }
+        }
+    } else {
+        let probe = HttpProbe::from_env()?;
+        let (keys, model_checks) = validate_providers(
+            &probe,
+            [
+                (Provider::OpenAI, config.openai_api_key.as_deref()),
+                (Provider::OpenRouter, config.openrouter_api_key.as_deref()),
+            ],
+            &models,
+        )
+        .await;
+
+        for key in &keys {
+            let name = key.provider.key_var();
+            match (key.configured, &key.error) {
+                (false, _) => println!("  - {} not set", name),
+                (true, None) => println!("  ✓ {} accepted", name),
+                (true, Some(error)) => println!("  ✗ {} rejected: {}", name, error),
+            }
+        }
+        // At least one key, and no rejected ones
+        healthy &= keys.iter().any(|k| k.valid) && keys.iter().all(|k| !k.configured || k.valid);
+
+        println!("Models");
+        for model in &model_checks {
+            match &model.reason {
+                None => println!("  ✓ {} = {}", model.setting, model.model),
+                Some(reason) => println!("  ✗ {} = {}: {}", model.setting, model.model, reason),
+            }
+        }
+        // Only the role models are required; named models are optional extras
+        healthy &= model_checks
+            .iter()
+            .filter(|m| {
+                !matches!(
+                    m.setting.as_str(),
+                    "LUX_MODEL_OPUS" | "LUX_MODEL_SONNET" | "LUX_MODEL_GROK"
+                )
+            })
+            .all(|m| m.accessible);
+    }
+
+    println!("Database");
+    if settings.database.url.is_none() {
+        println!("  - not configured (DATABASE_URL), skipped");
+    } else {
+        match DatabaseConnection::with_settings(&settings.database).await {
+            Ok(db) => {
+                let conn = db.get_connection();
+                match conn.ping().await {
+                    Ok(()) => println!("  ✓ connected"),
+                    Err(e) => {
+                        println!("  ✗ ping failed: {}", e);
+                        healthy = false;
+                    }
+                }
+
+                match migration::Migrator::get_pending_migrations(conn).await {
+                    Ok(pending) if pending.is_empty() => println!("  ✓ migrations up to date"),
+                    Ok(pending) => {
+                        let names: Vec<_> = pending.iter().map(|m| m.name().to_string()).collect();
+                        println!("  ✗ pending migrations: {}", names.join(", "));
+                        healthy = false;
+                    }
+                    Err(e) => {
+                        println!("  ✗ could not read migration status: {}", e);
+                        healthy = false;
+                    }
+                }
+            }
+            Err(e) => {
+                println!("  ✗ connection failed: {}", e);
+                healthy = false;
+            }
+        }
+    }
+
+    Ok(healthy)
+}
+
+/// Invoke a tool once. Returns false if the tool reported an error.
+pub async fn call(tool: String, json: &str) -> Result<bool> {
+    let input = if json == "-" {
+        let mut input = String::new();
+        std::io::stdin().read_to_string(&mut input)?;
+        input
+    } else {
+        json.to_string()
+    };
+    let Value::Object(arguments) =
+        serde_json::from_str(&input).context("Invalid JSON in --json")?
+    else {
+        anyhow::bail!("--json must be a JSON object");
+    };
+
+    let server = LuxServer::new().await?;
+    let result = server
+        .invoke_tool(CallToolRequestParam {
+            name: tool.into(),
+            arguments: Some(arguments),
+        })
+        .await
+        .map_err(|e| anyhow::anyhow!("{}", e.message))?;
+
+    for content in &result.content {
+        match content.as_text() {
+            Some(text) => println!("{}", text.text),
+            None => println!("{}", serde_json::to_string(content)?),
+        }
+    }
+    Ok(!result.is_error.unwrap_or(false))
+}
+
+pub async fn sessions(command: SessionsCommand) -> Result<()> {
+    let settings = LuxSettings::load()?;
+    if settings.database.url.is_none() {
+        anyhow::bail!("No database configured. Set DATABASE_URL or [database] url in lux.toml");
+    }
+    let db = DatabaseConnection::with_settings(&settings.database)
+        .await
+        .context("Failed to connect to database")?;
+    let service = DatabaseService::new(db);
+
+    match command {
+        SessionsCommand::List { limit } => {
+            for session in service.list_sessions(limit).await? {
+                println!(
+                    "{}\t{}\t{}\t{}\t{}",
+                    session.session_external_id,
+                    session.session_type,
+                    session.status,
+                    session.created_at.format("%Y-%m-%d %H:%M:%S"),
+                    truncate(&session.query, 60)
+                );
+            }
+        }
+        SessionsCommand::Show { id } => {
+            let (session, steps, synthesis_states) = service
+                .get_session_with_data(&id)
+                .await?
+                .with_context(|| format!("Session {} not found", id))?;
+
+            println!(
+                "Session {} ({})",
+                session.session_external_id, session.session_type
+            );
+            println!("Status:  {}", session.status);
+            println!("Created: {}", session.created_at);
+            println!("Query:   {}", session.query);
+            for step in steps {
+                println!(
+                    "\n[{}] {} ({})",
+                    step.step_number,
+                    step.step_type,
+                    step.model_used.as_deref().unwrap_or("unknown model")
+                );
+                println!("{}", step.content);
+            }
+            if let Some(synthesis) = synthesis_states.last() {
+                println!(
+                    "\nSynthesis v{} (confidence {:.2}, clarity {:.2})",
+                    synthesis.version, synthesis.confidence_score, synthesis.clarity_score
+                );
+                if let Some(understanding) = &synthesis.current_understanding {
+                    println!("{}", understanding);
+                }
+            }
+        }
+        SessionsCommand::Export { id, output } => {
+            let export = service
+                .export_session(&id)
+                .await?
+                .with_context(|| format!("Session {} not found", id))?;
+            let text = serde_json::to_string_pretty(&export)?;
+            match output {
+                Some(path) => std::fs::write(&path, text)
+                    .with_context(|| format!("Failed to write {}", path.display()))?,
+                None => println!("{}", text),
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn models(as_json: bool) -> Result<()> {
+    let settings = LuxSettings::load()?;
+    let config = LLMConfig::from_settings(&settings)?;
+    let resolver = ModelResolver::with_config(Some(config.clone()));
+
+    let roles = [
+        ("reasoning", config.model_reasoning.as_str()),
+        ("normal", config.model_normal.as_str()),
+        ("mini", config.model_mini.as_str()),
+    ];
+    let aliases: Vec<Value> = resolver
+        .alias_table()
+        .into_iter()
+        .map(|(alias, model)| {
+            json!({
+                "alias": alias,
+                "provider": if resolver.is_openrouter_model(&model) { "openrouter" } else { "openai" },
+                "allowed": resolver.is_openrouter_model(&model) || resolver.is_allowed_model(&model),
+                "model": model,
+            })
+        })
+        .collect();
+
+    if as_json {
+        let roles: serde_json::Map<String, Value> = roles
+            .iter()
+            .map(|(role, model)| (role.to_string(), json!(model)))
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json!({ "roles": roles, "aliases": aliases }))?
+        );
+        return Ok(());
+    }
+
+    println!("Roles");
+    for (role, model) in roles {
+        println!("  {:<12} {}", role, model);
+    }
+    println!("Aliases");
+    for alias in &aliases {
+        println!(
+            "  {:<20} {:<40} {}{}",
+            alias["alias"].as_str().unwrap_or_default(),
+            alias["model"].as_str().unwrap_or_default(),
+            alias["provider"].as_str().unwrap_or_default(),
+            if alias["allowed"].as_bool() == Some(true) {
+                ""
+            } else {
+                " (blocked by model policy)"
+            }
+        );
+    }
+    Ok(())
+}
+
+/// Evaluate the detectors with the `[monitoring]` and `[embeddings]` settings
+/// from lux.toml, optionally sweeping thresholds
+pub async fn calibrate(corpus: PathBuf, sweep: bool, as_json: bool) -> Result<()> {
+    let settings = LuxSettings::load()?;
+    let embedder = Arc::new(Embedder::from_settings(&settings)?);
+    let corpus = calibration::load_corpus(&corpus)?;
+    let report = calibration::evaluate(&corpus, &settings.monitoring, &embedder).await;
+
+    if !sweep {
+        if as_json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            print_calibration(&report);
+        }
+        return Ok(());
+    }
+
+    let outcome = calibration::sweep(&corpus, &settings.monitoring, &embedder).await?;
+    if as_json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json!({ "current": report, "sweep": outcome }))?
+        );
+        return Ok(());
+    }
+
+    print_calibration(&report);
+    println!("Threshold sweep");
+    for suggestion in &outcome.suggestions {
+        println!(
+            "  {:<34} {:.2} -> {:.2}  (F1 {:.2} -> {:.2})",
+            suggestion.parameter,
+            suggestion.current,
+            suggestion.suggested,
+            suggestion.current_f1,
+            suggestion.suggested_f1
+        );
+    }
+    let overrides = calibration::suggested_overrides(&outcome);
+    if overrides.is_empty() {
+        println!("Current thresholds are already the best found on this corpus");
+    } else {
+        println!("With the suggestions applied");
+        print_calibration(&outcome.report);
+        println!("Suggested [monitoring] values");
+        for line in overrides {
+            println!("  {}", line);
+        }
+    }
+    Ok(())
+}
+
+fn print_calibration(report: &CalibrationReport) {
+    println!(
+        "{} sequences, {} thoughts ({} embeddings)",
+        report.sequences, report.thoughts, report.embeddings
+    );
+    println!(
+        "  {:<20} {:>9} {:>7} {:>5} {:>4} {:>4} {:>4}",
+        "detector", "precision", "recall", "F1", "TP", "FP", "FN"
+    );
+    for score in &report.detectors {
+        println!(
+            "  {:<20} {:>9.2} {:>7.2} {:>5.2} {:>4} {:>4} {:>4}",
+            score.detector,
+            score.precision,
+            score.recall,
+            score.f1,
+            score.confusion.true_positives,
+            score.confusion.false_positives,
+            score.confusion.false_negatives
+        );
+    }
+    println!(
+        "  False interventions: {} ({:.1}% of thoughts)",
+        report.false_interventions,
+        report.false_intervention_rate * 100.0
+    );
+    for mismatch in &report.mismatches {
+        println!(
+            "  {} thought {}: {} {}",
+            mismatch.sequence,
+            mismatch.thought,
+            mismatch.detector,
+            if mismatch.expected {
+                "missed"
+            } else {
+                "false positive"
+            }
+        );
+    }
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    let line = text.lines().next().unwrap_or_default();
+    if line.chars().count() > max_chars {
+        format!("{}…", line.chars().take(max_chars).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
@@ -289,6 +289,62 @@ impl DatabaseService {
         }
     }
 
+    /// Most recently created sessions first
+    pub async fn list_sessions(&self, limit: u64) -> Result<Vec<session::Model>> {
+        Ok(session::Entity::find()
+            .order_by_desc(session::Column::CreatedAt)
+            .limit(limit)
+            .all(self.db.get_connection())
+            .await?)
+    }
+
+    /// A session and every row that references it, as JSON
+    pub async fn export_session(&self, session_id: &str) -> Result<Option<serde_json::Value>> {
+        let conn = self.db.get_connection();
+        let Some(session) = session::Entity::find()
+            .filter(session::Column::SessionExternalId.eq(session_id))
+            .into_json()
+            .one(conn)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let id: Uuid = serde_json::from_value(session["id"].clone())?;
+
+        let models = session_model::Entity::find()
+            .filter(session_model::Column::SessionId.eq(id))
+            .order_by_asc(session_model::Column::CreatedAt)
+            .into_json()
+            .all(conn)
+            .await?;
+        let steps = reasoning_step::Entity::find()
+            .filter(reasoning_step::Column::SessionId.eq(id))
+            .order_by_asc(reasoning_step::Column::StepNumber)
+            .into_json()
+            .all(conn)
+            .await?;
+        let synthesis_states = synthesis_state::Entity::find()
+            .filter(synthesis_state::Column::SessionId.eq(id))
+            .order_by_asc(synthesis_state::Column::Version)
+            .into_json()
+            .all(conn)
+            .await?;
+        let bias_detections = bias_detection::Entity::find()
+            .filter(bias_detection::Column::SessionId.eq(id))
+            .order_by_asc(bias_detection::Column::StepNumber)
+            .into_json()
+            .all(conn)
+            .await?;
+
+        Ok(Some(json!({
+            "session": session,
+            "models": models,
+            "reasoning_steps": steps,
+            "synthesis_states": synthesis_states,
+            "bias_detections": bias_detections,
+        })))
+    }
+
     /// Mark session as completed
     pub async fn complete_session(&self, session_id: Uuid) -> Result<()> {
         let mut session: session::ActiveModel = session::Entity::find_by_id(session_id)
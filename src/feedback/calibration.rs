@@ -0,0 +1,205 @@
+use serde::{Deserialize, Serialize};
+
+/// How reported confidences are mapped to observed accuracy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CalibrationMethod {
+    /// Monotone step fit (pool adjacent violators); follows the data closely
+    #[default]
+    Isotonic,
+    /// Logistic fit of outcome on confidence; smoother with few outcomes
+    Platt,
+    /// Record outcomes but report raw confidences
+    Off,
+}
+
+impl std::fmt::Display for CalibrationMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CalibrationMethod::Isotonic => write!(f, "isotonic"),
+            CalibrationMethod::Platt => write!(f, "platt"),
+            CalibrationMethod::Off => write!(f, "off"),
+        }
+    }
+}
+
+/// A fitted map from raw confidence to the probability the answer holds up
+#[derive(Debug, Clone, PartialEq)]
+pub enum Calibrator {
+    /// Block means of the pooled fit, as (confidence, accuracy) points in
+    /// increasing order; values between points are interpolated
+    Isotonic { points: Vec<(f64, f64)> },
+    /// `p = 1 / (1 + exp(-(a * confidence + b)))`
+    Platt { a: f64, b: f64 },
+}
+
+impl Calibrator {
+    /// Fit from (raw confidence, outcome was positive) pairs. `None` for
+    /// `Off`, no samples, or a fit that didn't converge.
+    pub fn fit(method: CalibrationMethod, samples: &[(f64, bool)]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        match method {
+            CalibrationMethod::Isotonic => Some(Self::fit_isotonic(samples)),
+            CalibrationMethod::Platt => Self::fit_platt(samples),
+            CalibrationMethod::Off => None,
+        }
+    }
+
+    fn fit_isotonic(samples: &[(f64, bool)]) -> Self {
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        // (sum of confidences, sum of outcomes, count) per block
+        let mut blocks: Vec<(f64, f64, f64)> = Vec::new();
+        for (x, positive) in sorted {
+            let y = if positive { 1.0 } else { 0.0 };
+            match blocks.last_mut() {
+                // Equal confidences always share a block
+                Some(last) if (last.0 / last.2 - x).abs() < f64::EPSILON => {
+                    last.0 += x;
+                    last.1 += y;
+                    last.2 += 1.0;
+                }
+                _ => blocks.push((x, y, 1.0)),
+            }
+            // Merge backwards while the block means decrease
+            while blocks.len() > 1 {
+                let last = blocks[blocks.len() - 1];
+                let prev = blocks[blocks.len() - 2];
+                if prev.1 / prev.2 <= last.1 / last.2 {
+                    break;
+                }
+                blocks.pop();
+                let merged = blocks.last_mut().unwrap();
+                merged.0 += last.0;
+                merged.1 += last.1;
+                merged.2 += last.2;
+            }
+        }
+
+        Calibrator::Isotonic {
+            points: blocks
+                .into_iter()
+                .map(|(xs, ys, n)| (xs / n, ys / n))
+                .collect(),
+        }
+    }
+
+    fn fit_platt(samples: &[(f64, bool)]) -> Option<Self> {
+        // Platt's smoothed targets keep the fit finite on separable data
+        let positives = samples.iter().filter(|(_, p)| *p).count() as f64;
+        let negatives = samples.len() as f64 - positives;
+        let high = (positives + 1.0) / (positives + 2.0);
+        let low = 1.0 / (negatives + 2.0);
+
+        // Newton's method on the log loss, with a small ridge for stability
+        let (mut a, mut b) = (0.0_f64, 0.0_f64);
+        for _ in 0..100 {
+            let (mut ga, mut gb, mut haa, mut hab, mut hbb) = (0.0, 0.0, 1e-6, 0.0, 1e-6);
+            for &(x, positive) in samples {
+                let p = sigmoid(a * x + b);
+                let t = if positive { high } else { low };
+                let w = p * (1.0 - p);
+                ga += (p - t) * x;
+                gb += p - t;
+                haa += w * x * x;
+                hab += w * x;
+                hbb += w;
+            }
+            let det = haa * hbb - hab * hab;
+            if det.abs() < 1e-12 {
+                break;
+            }
+            let da = (hbb * ga - hab * gb) / det;
+            let db = (haa * gb - hab * ga) / det;
+            a -= da;
+            b -= db;
+            if da.abs() < 1e-9 && db.abs() < 1e-9 {
+                break;
+            }
+        }
+
+        (a.is_finite() && b.is_finite()).then_some(Calibrator::Platt { a, b })
+    }
+
+    /// Calibrated confidence for a raw one, in 0-1
+    pub fn apply(&self, confidence: f64) -> f64 {
+        let calibrated = match self {
+            Calibrator::Platt { a, b } => sigmoid(a * confidence + b),
+            Calibrator::Isotonic { points } => {
+                let upper = points.partition_point(|(x, _)| *x < confidence);
+                match (upper.checked_sub(1), points.get(upper)) {
+                    (None, Some(first)) => first.1,
+                    (Some(i), None) => points[i].1,
+                    (Some(i), Some(&(x1, y1))) => {
+                        let (x0, y0) = points[i];
+                        if (x1 - x0).abs() < f64::EPSILON {
+                            y1
+                        } else {
+                            y0 + (y1 - y0) * (confidence - x0) / (x1 - x0)
+                        }
+                    }
+                    (None, None) => confidence,
+                }
+            }
+        };
+        calibrated.clamp(0.0, 1.0)
+    }
+}
+
+fn sigmoid(z: f64) -> f64 {
+    1.0 / (1.0 + (-z).exp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A source that reports 0.9 but is right half the time, and 0.5 but
+    /// right a fifth of the time
+    fn overconfident() -> Vec<(f64, bool)> {
+        (0..20)
+            .map(|i| (0.9, i % 2 == 0))
+            .chain((0..20).map(|i| (0.5, i % 5 == 0)))
+            .collect()
+    }
+
+    #[test]
+    fn test_isotonic_fit() {
+        let calibrator = Calibrator::fit(CalibrationMethod::Isotonic, &overconfident()).unwrap();
+        assert!((calibrator.apply(0.9) - 0.5).abs() < 1e-9);
+        assert!((calibrator.apply(0.5) - 0.2).abs() < 1e-9);
+        // Interpolated between blocks, flat beyond them
+        assert!((calibrator.apply(0.7) - 0.35).abs() < 1e-9);
+        assert!((calibrator.apply(0.1) - 0.2).abs() < 1e-9);
+        assert!((calibrator.apply(1.0) - 0.5).abs() < 1e-9);
+
+        // Decreasing accuracy is pooled into a single block
+        let inverted = vec![(0.2, true), (0.4, true), (0.6, false), (0.8, false)];
+        let calibrator = Calibrator::fit(CalibrationMethod::Isotonic, &inverted).unwrap();
+        assert_eq!(
+            calibrator,
+            Calibrator::Isotonic {
+                points: vec![(0.5, 0.5)]
+            }
+        );
+    }
+
+    #[test]
+    fn test_platt_fit() {
+        let calibrator = Calibrator::fit(CalibrationMethod::Platt, &overconfident()).unwrap();
+        let high = calibrator.apply(0.9);
+        let low = calibrator.apply(0.5);
+        assert!(low < high);
+        assert!((high - 0.5).abs() < 0.05, "got {}", high);
+        assert!((low - 0.2).abs() < 0.05, "got {}", low);
+
+        // Perfectly separated outcomes still give a finite fit
+        let separable = vec![(0.2, false), (0.3, false), (0.8, true), (0.9, true)];
+        let calibrator = Calibrator::fit(CalibrationMethod::Platt, &separable).unwrap();
+        assert!(calibrator.apply(0.9) < 1.0 && calibrator.apply(0.2) > 0.0);
+        assert!(Calibrator::fit(CalibrationMethod::Off, &separable).is_none());
+    }
+}
@@ -0,0 +1,692 @@
+//! Outcomes of answers that reported a confidence, and the calibration of
+//! those confidences fit from them
+
+pub mod calibration;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+pub use calibration::{CalibrationMethod, Calibrator};
+
+/// What became of an answer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Accepted,
+    Rejected,
+    Correct,
+    Incorrect,
+}
+
+impl Outcome {
+    /// Whether the answer held up, which is what a confidence predicts
+    pub fn is_positive(self) -> bool {
+        matches!(self, Outcome::Accepted | Outcome::Correct)
+    }
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Accepted => write!(f, "accepted"),
+            Outcome::Rejected => write!(f, "rejected"),
+            Outcome::Correct => write!(f, "correct"),
+            Outcome::Incorrect => write!(f, "incorrect"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeedbackConfig {
+    /// JSONL file the predictions and outcomes are appended to. Defaults to
+    /// `lux/feedback.jsonl` in the user's data directory.
+    pub path: Option<PathBuf>,
+    pub method: CalibrationMethod,
+    /// Outcomes a model and tool need before their confidences are adjusted;
+    /// until then the tool's outcomes across all models are used
+    pub min_samples: usize,
+    /// Days an answer waits for its outcome before it is dropped
+    pub pending_ttl_days: u32,
+}
+
+impl Default for FeedbackConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            method: CalibrationMethod::Isotonic,
+            min_samples: 20,
+            pending_ttl_days: 30,
+        }
+    }
+}
+
+impl FeedbackConfig {
+    /// Configured path, then `$XDG_DATA_HOME/lux`, then `~/.local/share/lux`
+    pub fn resolved_path(&self) -> Option<PathBuf> {
+        self.path.clone().or_else(|| {
+            env::var_os("XDG_DATA_HOME")
+                .map(PathBuf::from)
+                .or_else(|| {
+                    env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))
+                })
+                .map(|dir| dir.join("lux").join("feedback.jsonl"))
+        })
+    }
+
+    /// Answers without an outcome recorded before this are dropped
+    fn pending_cutoff(&self) -> DateTime<Utc> {
+        Utc::now() - Duration::days(self.pending_ttl_days.into())
+    }
+}
+
+/// One reported confidence. `score` names which confidence of the tool it
+/// was (`step`, `final`, `bias_check`, ...).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Prediction {
+    pub answer_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub tool: String,
+    pub score: String,
+    pub model: String,
+    pub session: Option<String>,
+    /// Raw confidence, before calibration
+    pub confidence: f32,
+    pub outcome: Option<Outcome>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum FeedbackRecord {
+    Prediction(Prediction),
+    Outcome {
+        answer_id: String,
+        timestamp: DateTime<Utc>,
+        outcome: Outcome,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        note: Option<String>,
+    },
+}
+
+/// A confidence as reported to the caller
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calibrated {
+    /// Id to pass to the `feedback` tool
+    pub answer_id: String,
+    pub raw: f32,
+    pub confidence: f32,
+    /// Outcomes the calibration was fit on; 0 when the raw value is reported
+    pub samples: usize,
+}
+
+impl std::fmt::Display for Calibrated {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.samples > 0 {
+            write!(
+                f,
+                "{:.2} (raw {:.2}, calibrated on {} outcomes)",
+                self.confidence, self.raw, self.samples
+            )
+        } else {
+            write!(f, "{:.2}", self.confidence)
+        }
+    }
+}
+
+/// Which answer an outcome is for
+#[derive(Debug, Clone)]
+pub enum FeedbackTarget {
+    Answer(String),
+    /// The session's latest answer
+    Session(String),
+}
+
+/// Parameters of the `feedback` tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedbackRequest {
+    /// Answer id printed next to the confidence
+    #[serde(default)]
+    pub answer_id: Option<String>,
+    /// Session whose latest answer the outcome is for, when there's no answer id
+    #[serde(default)]
+    pub session_id: Option<String>,
+    pub outcome: Outcome,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+impl FeedbackRequest {
+    pub fn target(&self) -> Result<FeedbackTarget> {
+        match (&self.answer_id, &self.session_id) {
+            (Some(id), _) => Ok(FeedbackTarget::Answer(id.clone())),
+            (None, Some(session)) => Ok(FeedbackTarget::Session(session.clone())),
+            (None, None) => bail!("Either answer_id or session_id is required"),
+        }
+    }
+}
+
+/// Labelled outcomes of one tool confidence, for one model or all of them
+#[derive(Debug, Clone, Serialize)]
+pub struct CalibrationSummary {
+    pub tool: String,
+    pub score: String,
+    pub model: Option<String>,
+    pub outcomes: usize,
+    pub positive: usize,
+    pub mean_confidence: f32,
+    pub active: bool,
+}
+
+impl std::fmt::Display for CalibrationSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} ({}): {} of {} held up, mean reported confidence {:.2}{}",
+            self.tool,
+            self.score,
+            self.model.as_deref().unwrap_or("all models"),
+            self.positive,
+            self.outcomes,
+            self.mean_confidence,
+            if self.active {
+                ", calibration active"
+            } else {
+                ""
+            }
+        )
+    }
+}
+
+/// (tool, score, model); `None` model is the tool's fit across models
+type GroupKey = (String, String, Option<String>);
+
+#[derive(Default)]
+struct FeedbackState {
+    predictions: Vec<Prediction>,
+    by_id: HashMap<String, usize>,
+    /// Ids of the answers with an outcome, by the groups they are fit in
+    groups: HashMap<GroupKey, Vec<String>>,
+    calibrators: HashMap<GroupKey, (Calibrator, usize)>,
+    /// When answers without an outcome were last dropped
+    expired_at: Option<DateTime<Utc>>,
+}
+
+/// Records reported confidences and their outcomes, and calibrates new
+/// confidences from the outcomes so far
+pub struct FeedbackStore {
+    config: FeedbackConfig,
+    path: Option<PathBuf>,
+    state: Mutex<FeedbackState>,
+}
+
+impl FeedbackStore {
+    /// Keep records in memory only
+    pub fn in_memory(config: FeedbackConfig) -> Self {
+        Self {
+            config,
+            path: None,
+            state: Mutex::new(FeedbackState::default()),
+        }
+    }
+
+    /// Load the records at the configured path and append new ones to it
+    pub fn open(config: FeedbackConfig) -> Result<Self> {
+        let Some(path) = config.resolved_path() else {
+            warn!("No data directory for feedback; outcomes are kept in memory only");
+            return Ok(Self::in_memory(config));
+        };
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create feedback directory {}", parent.display())
+            })?;
+        }
+
+        let mut store = Self::in_memory(config);
+        if path.exists() {
+            let reader = BufReader::new(
+                fs::File::open(&path)
+                    .with_context(|| format!("Failed to open feedback log {}", path.display()))?,
+            );
+            let mut state = store.state.lock();
+            let mut records = 0;
+            for (line_number, line) in reader.lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                records += 1;
+                match serde_json::from_str(&line) {
+                    Ok(record) => state.apply(record),
+                    Err(e) => warn!(
+                        "Skipping malformed feedback record {}:{}: {}",
+                        path.display(),
+                        line_number + 1,
+                        e
+                    ),
+                }
+            }
+            state.expire(store.config.pending_cutoff());
+            state.refit(&store.config);
+            info!(
+                "Loaded {} confidence records from {}",
+                state.predictions.len(),
+                path.display()
+            );
+
+            // Fold outcomes into their predictions and drop expired answers
+            if records > state.predictions.len() {
+                Self::compact(&path, &state.predictions)?;
+            }
+        }
+        store.path = Some(path);
+        Ok(store)
+    }
+
+    /// Rewrite the log with one record per answer
+    fn compact(path: &Path, predictions: &[Prediction]) -> Result<()> {
+        let mut log = String::new();
+        for prediction in predictions {
+            log.push_str(&serde_json::to_string(&FeedbackRecord::Prediction(
+                prediction.clone(),
+            ))?);
+            log.push('\n');
+        }
+        let compacted = path.with_extension("jsonl.tmp");
+        fs::write(&compacted, log)
+            .and_then(|_| fs::rename(&compacted, path))
+            .with_context(|| format!("Failed to compact feedback log {}", path.display()))
+    }
+
+    pub fn config(&self) -> &FeedbackConfig {
+        &self.config
+    }
+
+    fn append(&self, record: &FeedbackRecord) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open feedback log {}", path.display()))?;
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    /// Record a confidence a tool is about to report and return it calibrated,
+    /// with the id its outcome can be reported under
+    pub fn record(
+        &self,
+        tool: &str,
+        score: &str,
+        model: &str,
+        session: Option<&str>,
+        confidence: f32,
+    ) -> Calibrated {
+        let prediction = Prediction {
+            answer_id: Uuid::new_v4().simple().to_string()[..12].to_string(),
+            timestamp: Utc::now(),
+            tool: tool.to_string(),
+            score: score.to_string(),
+            model: model.to_string(),
+            session: session.map(String::from),
+            confidence,
+            outcome: None,
+            note: None,
+        };
+
+        let mut state = self.state.lock();
+        let now = Utc::now();
+        if state
+            .expired_at
+            .is_none_or(|at| now - at > Duration::hours(1))
+        {
+            state.expire(self.config.pending_cutoff());
+            state.expired_at = Some(now);
+        }
+        let calibrated = state.calibrate(&prediction);
+        let record = FeedbackRecord::Prediction(prediction);
+        if let Err(e) = self.append(&record) {
+            warn!("Failed to write feedback record: {}", e);
+        }
+        state.apply(record);
+        calibrated
+    }
+
+    /// Record what became of an answer and refit the calibration
+    pub fn record_outcome(
+        &self,
+        target: &FeedbackTarget,
+        outcome: Outcome,
+        note: Option<String>,
+    ) -> Result<Prediction> {
+        let mut state = self.state.lock();
+        let index = match target {
+            FeedbackTarget::Answer(id) => state.by_id.get(id).copied(),
+            FeedbackTarget::Session(session) => state
+                .predictions
+                .iter()
+                .rposition(|p| p.session.as_deref() == Some(session)),
+        };
+        let Some(index) = index else {
+            match target {
+                FeedbackTarget::Answer(id) => bail!("No answer with id {}", id),
+                FeedbackTarget::Session(session) => {
+                    bail!(
+                        "No answer with a confidence recorded for session {}",
+                        session
+                    )
+                }
+            }
+        };
+
+        let record = FeedbackRecord::Outcome {
+            answer_id: state.predictions[index].answer_id.clone(),
+            timestamp: Utc::now(),
+            outcome,
+            note,
+        };
+        self.append(&record)?;
+        state.apply(record);
+        let prediction = state.predictions[index].clone();
+        for key in group_keys(&prediction) {
+            state.refit_group(key, &self.config);
+        }
+        Ok(prediction)
+    }
+
+    /// Outcomes behind the calibration of a tool's confidence, for the model
+    /// and across models
+    pub fn summary(&self, tool: &str, score: &str, model: &str) -> Vec<CalibrationSummary> {
+        let state = self.state.lock();
+        [Some(model.to_string()), None]
+            .into_iter()
+            .map(|model| {
+                let key = (tool.to_string(), score.to_string(), model);
+                let samples = state.samples(&key);
+                CalibrationSummary {
+                    outcomes: samples.len(),
+                    positive: samples.iter().filter(|(_, p)| *p).count(),
+                    mean_confidence: (samples.iter().map(|(c, _)| c).sum::<f64>()
+                        / samples.len().max(1) as f64) as f32,
+                    active: state.calibrators.contains_key(&key),
+                    tool: key.0,
+                    score: key.1,
+                    model: key.2,
+                }
+            })
+            .collect()
+    }
+}
+
+/// The model's group and the tool's group across models
+fn group_keys(prediction: &Prediction) -> [GroupKey; 2] {
+    [
+        (
+            prediction.tool.clone(),
+            prediction.score.clone(),
+            Some(prediction.model.clone()),
+        ),
+        (prediction.tool.clone(), prediction.score.clone(), None),
+    ]
+}
+
+impl FeedbackState {
+    fn apply(&mut self, record: FeedbackRecord) {
+        match record {
+            FeedbackRecord::Prediction(prediction) => {
+                if prediction.outcome.is_some() {
+                    self.add_to_groups(&prediction);
+                }
+                self.by_id
+                    .insert(prediction.answer_id.clone(), self.predictions.len());
+                self.predictions.push(prediction);
+            }
+            FeedbackRecord::Outcome {
+                answer_id,
+                outcome,
+                note,
+                ..
+            } => {
+                // A later outcome replaces an earlier one
+                if let Some(&index) = self.by_id.get(&answer_id) {
+                    let prediction = &mut self.predictions[index];
+                    let first = prediction.outcome.replace(outcome).is_none();
+                    prediction.note = note;
+                    if first {
+                        let prediction = prediction.clone();
+                        self.add_to_groups(&prediction);
+                    }
+                }
+            }
+        }
+    }
+
+    fn add_to_groups(&mut self, prediction: &Prediction) {
+        for key in group_keys(prediction) {
+            self.groups
+                .entry(key)
+                .or_default()
+                .push(prediction.answer_id.clone());
+        }
+    }
+
+    /// Drop answers that got no outcome since `cutoff`
+    fn expire(&mut self, cutoff: DateTime<Utc>) {
+        let before = self.predictions.len();
+        self.predictions
+            .retain(|p| p.outcome.is_some() || p.timestamp >= cutoff);
+        if self.predictions.len() < before {
+            info!(
+                "Dropped {} answers that got no outcome",
+                before - self.predictions.len()
+            );
+            self.by_id = self
+                .predictions
+                .iter()
+                .enumerate()
+                .map(|(index, p)| (p.answer_id.clone(), index))
+                .collect();
+        }
+    }
+
+    fn samples(&self, key: &GroupKey) -> Vec<(f64, bool)> {
+        self.groups
+            .get(key)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.predictions.get(*self.by_id.get(id)?))
+            .filter_map(|p| Some((p.confidence as f64, p.outcome?.is_positive())))
+            .collect()
+    }
+
+    fn refit(&mut self, config: &FeedbackConfig) {
+        self.calibrators.clear();
+        let keys: Vec<GroupKey> = self.groups.keys().cloned().collect();
+        for key in keys {
+            self.refit_group(key, config);
+        }
+    }
+
+    fn refit_group(&mut self, key: GroupKey, config: &FeedbackConfig) {
+        let samples = self.samples(&key);
+        let calibrator = (samples.len() >= config.min_samples.max(1))
+            .then(|| Calibrator::fit(config.method, &samples))
+            .flatten();
+        match calibrator {
+            Some(calibrator) => {
+                self.calibrators.insert(key, (calibrator, samples.len()));
+            }
+            None => {
+                self.calibrators.remove(&key);
+            }
+        }
+    }
+
+    fn calibrate(&self, prediction: &Prediction) -> Calibrated {
+        let tool = prediction.tool.clone();
+        let score = prediction.score.clone();
+        let fitted = self
+            .calibrators
+            .get(&(tool.clone(), score.clone(), Some(prediction.model.clone())))
+            .or_else(|| self.calibrators.get(&(tool, score, None)));
+
+        let (confidence, samples) = match fitted {
+            Some((calibrator, samples)) => (
+                calibrator.apply(prediction.confidence as f64) as f32,
+                *samples,
+            ),
+            None => (prediction.confidence, 0),
+        };
+        Calibrated {
+            answer_id: prediction.answer_id.clone(),
+            raw: prediction.confidence,
+            confidence,
+            samples,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outcomes_calibrate_later_confidences() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("feedback.jsonl");
+        let config = FeedbackConfig {
+            path: Some(path.clone()),
+            min_samples: 4,
+            ..FeedbackConfig::default()
+        };
+        let store = FeedbackStore::open(config.clone()).unwrap();
+
+        // Uncalibrated until enough outcomes are in
+        let first = store.record("traced_reasoning", "final", "o3", Some("s1"), 0.9);
+        assert_eq!((first.confidence, first.samples), (0.9, 0));
+        assert_eq!(first.to_string(), "0.90");
+
+        store
+            .record_outcome(
+                &FeedbackTarget::Answer(first.answer_id),
+                Outcome::Rejected,
+                None,
+            )
+            .unwrap();
+        for (i, outcome) in [Outcome::Correct, Outcome::Incorrect, Outcome::Accepted]
+            .into_iter()
+            .enumerate()
+        {
+            let session = format!("s{}", i + 2);
+            store.record("traced_reasoning", "final", "o3", Some(&session), 0.9);
+            store
+                .record_outcome(&FeedbackTarget::Session(session), outcome, None)
+                .unwrap();
+        }
+
+        // Right half the time at 0.9; other models borrow the tool's fit
+        let calibrated = store.record("traced_reasoning", "final", "o3", None, 0.9);
+        assert!((calibrated.confidence - 0.5).abs() < 1e-6);
+        assert_eq!(calibrated.samples, 4);
+        assert!(calibrated.to_string().contains("raw 0.90"));
+        let other = store.record("traced_reasoning", "final", "gpt-5", None, 0.9);
+        assert!((other.confidence - 0.5).abs() < 1e-6);
+        let other_tool = store.record("sequential_thinking_external", "thought", "o3", None, 0.9);
+        assert_eq!(other_tool.samples, 0);
+
+        assert!(store
+            .record_outcome(
+                &FeedbackTarget::Session("s9".into()),
+                Outcome::Correct,
+                None
+            )
+            .is_err());
+
+        // The log restores predictions, outcomes and the fit
+        let reopened = FeedbackStore::open(config).unwrap();
+        let summary = reopened.summary("traced_reasoning", "final", "o3");
+        assert_eq!((summary[0].outcomes, summary[0].positive), (4, 2));
+        assert!(summary[0].active);
+        let calibrated = reopened.record("traced_reasoning", "final", "o3", None, 0.9);
+        assert!((calibrated.confidence - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_open_drops_stale_answers_and_compacts_the_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("feedback.jsonl");
+        let prediction = |id: &str, days_ago: i64| {
+            FeedbackRecord::Prediction(Prediction {
+                answer_id: id.to_string(),
+                timestamp: Utc::now() - Duration::days(days_ago),
+                tool: "traced_reasoning".to_string(),
+                score: "final".to_string(),
+                model: "o3".to_string(),
+                session: None,
+                confidence: 0.8,
+                outcome: None,
+                note: None,
+            })
+        };
+        let records = [
+            prediction("stale", 40),
+            prediction("rated", 40),
+            prediction("fresh", 1),
+            FeedbackRecord::Outcome {
+                answer_id: "rated".to_string(),
+                timestamp: Utc::now(),
+                outcome: Outcome::Correct,
+                note: Some("shipped".to_string()),
+            },
+        ];
+        let log: Vec<String> = records
+            .iter()
+            .map(|r| serde_json::to_string(r).unwrap())
+            .collect();
+        fs::write(&path, log.join("\n")).unwrap();
+
+        let config = FeedbackConfig {
+            path: Some(path.clone()),
+            min_samples: 1,
+            ..FeedbackConfig::default()
+        };
+        let store = FeedbackStore::open(config).unwrap();
+        assert!(store
+            .record_outcome(
+                &FeedbackTarget::Answer("stale".into()),
+                Outcome::Correct,
+                None
+            )
+            .is_err());
+        let summary = store.summary("traced_reasoning", "final", "o3");
+        assert_eq!((summary[0].outcomes, summary[1].outcomes), (1, 1));
+
+        // One line per remaining answer, with its outcome and note
+        let compacted: Vec<Prediction> = fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| match serde_json::from_str(line).unwrap() {
+                FeedbackRecord::Prediction(p) => p,
+                FeedbackRecord::Outcome { .. } => panic!("outcome left in the log"),
+            })
+            .collect();
+        assert_eq!(compacted.len(), 2);
+        assert_eq!(compacted[0].outcome, Some(Outcome::Correct));
+        assert_eq!(compacted[0].note.as_deref(), Some("shipped"));
+    }
+}
@@ -2,12 +2,14 @@
 
 pub mod db;
 pub mod entities;
+pub mod feedback;
 pub mod llm;
 pub mod metachain;
 pub mod models;
 pub mod monitoring;
 pub mod server;
 pub mod session;
+pub mod settings;
 pub mod threading;
 pub mod tools;
 
@@ -0,0 +1,430 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::future::Future;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use super::client::{ChatMessage, LLMResponse, TokenUsage};
+use crate::settings::Env;
+
+const DEFAULT_MAX_FILE_BYTES: u64 = 10 * 1024 * 1024; // 10 MB
+const DEFAULT_MAX_FILES: usize = 5;
+const REDACTED: &str = "[REDACTED]";
+
+/// Secrets redacted from every record. When a pattern has a capture group,
+/// the first group is kept and only the rest of the match is replaced.
+const DEFAULT_REDACT_PATTERNS: &[&str] = &[
+    r"sk-[A-Za-z0-9_-]{16,}",
+    r"(?i)(bearer\s+)[A-Za-z0-9._~+/=-]{8,}",
+    r"AKIA[0-9A-Z]{16}",
+    r#"(?i)((?:api[_-]?key|secret|password|passwd|token)["']?\s*[:=]\s*["']?)[^\s"',]+"#,
+    r"-----BEGIN [A-Z ]*PRIVATE KEY-----[\s\S]*?-----END [A-Z ]*PRIVATE KEY-----",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditConfig {
+    pub path: PathBuf,
+    /// Extra redaction regexes, applied after the built-in ones
+    pub redact_patterns: Vec<String>,
+    /// Rotate once the log would grow past this size
+    pub max_file_bytes: u64,
+    /// Rotated files to keep (path.1 is the newest)
+    pub max_files: usize,
+}
+
+impl AuditConfig {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            redact_patterns: Vec::new(),
+            max_file_bytes: DEFAULT_MAX_FILE_BYTES,
+            max_files: DEFAULT_MAX_FILES,
+        }
+    }
+
+    /// Enabled by `LUX_AUDIT_LOG`; `LUX_AUDIT_REDACT` takes extra patterns separated by `;;`
+    pub fn from_env(env: &Env) -> Option<Self> {
+        let path = env.var("LUX_AUDIT_LOG").filter(|s| !s.is_empty())?;
+        let mut config = Self::new(path);
+
+        if let Some(patterns) = env.var("LUX_AUDIT_REDACT") {
+            config.redact_patterns = patterns
+                .split(";;")
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(String::from)
+                .collect();
+        }
+        if let Some(bytes) = env.parse("LUX_AUDIT_MAX_BYTES") {
+            config.max_file_bytes = bytes;
+        }
+        if let Some(files) = env.parse("LUX_AUDIT_MAX_FILES") {
+            config.max_files = files;
+        }
+
+        Some(config)
+    }
+}
+
+/// Which tool call and session an LLM request was made for
+#[derive(Debug, Clone, Default)]
+pub struct AuditContext {
+    pub tool: Option<String>,
+    /// Shared with the call's spawned tasks, so a session the tool creates
+    /// part-way through is seen by every later record
+    session: Arc<Mutex<Option<String>>>,
+}
+
+impl AuditContext {
+    pub fn new(tool: Option<String>, session: Option<String>) -> Self {
+        Self {
+            tool,
+            session: Arc::new(Mutex::new(session)),
+        }
+    }
+
+    pub fn session(&self) -> Option<String> {
+        self.session.lock().clone()
+    }
+}
+
+tokio::task_local! {
+    static CONTEXT: AuditContext;
+}
+
+/// Run a future with the audit context its LLM requests are attributed to
+pub async fn scope<F: Future>(context: AuditContext, future: F) -> F::Output {
+    CONTEXT.scope(context, future).await
+}
+
+/// Context of the current task; spawned tasks must re-enter it with `scope`
+pub fn current_context() -> AuditContext {
+    CONTEXT.try_with(|c| c.clone()).unwrap_or_default()
+}
+
+/// Attribute the current call's remaining LLM requests to a session or
+/// thread, once the tool has resolved or created it. No-op outside `scope`.
+pub fn set_session(session: impl Into<String>) {
+    let session = session.into();
+    let _ = CONTEXT.try_with(|c| *c.session.lock() = Some(session));
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub timestamp: DateTime<Utc>,
+    pub tool: Option<String>,
+    pub session: Option<String>,
+    pub provider: String,
+    pub model: String,
+    pub messages: Vec<ChatMessage>,
+    pub response: Option<String>,
+    pub error: Option<String>,
+    pub usage: Option<TokenUsage>,
+    pub latency_ms: u64,
+}
+
+pub struct AuditSink {
+    config: AuditConfig,
+    redactors: Vec<Regex>,
+    write_lock: Mutex<()>,
+}
+
+impl AuditSink {
+    pub fn new(config: AuditConfig) -> Result<Self> {
+        let redactors = DEFAULT_REDACT_PATTERNS
+            .iter()
+            .map(|p| p.to_string())
+            .chain(config.redact_patterns.iter().cloned())
+            .map(|pattern| {
+                Regex::new(&pattern)
+                    .with_context(|| format!("Invalid audit redaction pattern: {}", pattern))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if let Some(parent) = config.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).with_context(|| {
+                format!("Failed to create audit log directory {}", parent.display())
+            })?;
+        }
+
+        Ok(Self {
+            config,
+            redactors,
+            write_lock: Mutex::new(()),
+        })
+    }
+
+    fn redact(&self, text: &str) -> String {
+        self.redactors.iter().fold(text.to_string(), |text, regex| {
+            let replacement = if regex.captures_len() > 1 {
+                format!("${{1}}{}", REDACTED)
+            } else {
+                REDACTED.to_string()
+            };
+            regex.replace_all(&text, replacement.as_str()).into_owned()
+        })
+    }
+
+    /// Redact and append one record, rotating the file first if it would grow too large
+    pub fn write(&self, mut record: AuditRecord) -> Result<()> {
+        for message in &mut record.messages {
+            message.content = self.redact(&message.content);
+        }
+        record.response = record.response.map(|r| self.redact(&r));
+        record.error = record.error.map(|e| self.redact(&e));
+
+        let mut line = serde_json::to_string(&record)?;
+        line.push('\n');
+
+        let _guard = self.write_lock.lock();
+        self.rotate_if_needed(line.len() as u64)?;
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.config.path)
+            .with_context(|| format!("Failed to open audit log {}", self.config.path.display()))?;
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    fn rotate_if_needed(&self, incoming: u64) -> Result<()> {
+        let current = match fs::metadata(&self.config.path) {
+            Ok(metadata) => metadata.len(),
+            Err(_) => return Ok(()),
+        };
+        if current == 0 || current + incoming <= self.config.max_file_bytes {
+            return Ok(());
+        }
+
+        if self.config.max_files == 0 {
+            fs::remove_file(&self.config.path)?;
+            return Ok(());
+        }
+
+        // path.N-1 -> path.N, ..., path -> path.1; the oldest falls off the end
+        let oldest = rotated_path(&self.config.path, self.config.max_files);
+        if oldest.exists() {
+            fs::remove_file(&oldest)?;
+        }
+        for n in (1..self.config.max_files).rev() {
+            let from = rotated_path(&self.config.path, n);
+            if from.exists() {
+                fs::rename(&from, rotated_path(&self.config.path, n + 1))?;
+            }
+        }
+        fs::rename(&self.config.path, rotated_path(&self.config.path, 1))?;
+        info!("Rotated audit log {}", self.config.path.display());
+        Ok(())
+    }
+}
+
+fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(format!(".{}", n));
+    PathBuf::from(rotated)
+}
+
+static SINK: RwLock<Option<Arc<AuditSink>>> = RwLock::new(None);
+
+/// Install (or with `None`, remove) the process-wide audit sink
+pub fn install(config: Option<&AuditConfig>) -> Result<()> {
+    let sink = match config {
+        Some(config) => {
+            info!("LLM audit log enabled at {}", config.path.display());
+            Some(Arc::new(AuditSink::new(config.clone())?))
+        }
+        None => None,
+    };
+    *SINK.write().unwrap_or_else(|e| e.into_inner()) = sink;
+    Ok(())
+}
+
+pub fn is_enabled() -> bool {
+    SINK.read().map(|sink| sink.is_some()).unwrap_or(false)
+}
+
+/// Record one completed provider call. Audit failures are logged, never returned.
+pub fn record(
+    provider: &str,
+    model: &str,
+    messages: Vec<ChatMessage>,
+    result: &Result<LLMResponse>,
+    latency: Duration,
+) {
+    let Some(sink) = SINK.read().ok().and_then(|sink| sink.clone()) else {
+        return;
+    };
+
+    let context = current_context();
+    let (response, error, usage) = match result {
+        Ok(response) => (Some(response.content.clone()), None, response.usage.clone()),
+        Err(e) => (None, Some(e.to_string()), None),
+    };
+    let record = AuditRecord {
+        timestamp: Utc::now(),
+        session: context.session(),
+        tool: context.tool,
+        provider: provider.to_string(),
+        model: model.to_string(),
+        messages,
+        response,
+        error,
+        usage,
+        latency_ms: latency.as_millis() as u64,
+    };
+
+    if let Err(e) = sink.write(record) {
+        warn!("Failed to write LLM audit record: {}", e);
+    }
+}
+
+/// Read records from the log and its rotated files, oldest first
+pub fn read_records(
+    path: &Path,
+    session: Option<&str>,
+    tool: Option<&str>,
+) -> Result<Vec<AuditRecord>> {
+    let mut files: Vec<PathBuf> = (1..)
+        .map(|n| rotated_path(path, n))
+        .take_while(|p| p.exists())
+        .collect();
+    files.reverse();
+    files.push(path.to_path_buf());
+    if !files.iter().any(|f| f.exists()) {
+        anyhow::bail!("Audit log {} not found", path.display());
+    }
+
+    let mut records = Vec::new();
+    for file in files.iter().filter(|f| f.exists()) {
+        let reader = BufReader::new(
+            fs::File::open(file)
+                .with_context(|| format!("Failed to open audit log {}", file.display()))?,
+        );
+        for (line_number, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: AuditRecord = match serde_json::from_str(&line) {
+                Ok(record) => record,
+                Err(e) => {
+                    warn!(
+                        "Skipping malformed audit record {}:{}: {}",
+                        file.display(),
+                        line_number + 1,
+                        e
+                    );
+                    continue;
+                }
+            };
+            if session.is_some_and(|s| record.session.as_deref() != Some(s)) {
+                continue;
+            }
+            if tool.is_some_and(|t| record.tool.as_deref() != Some(t)) {
+                continue;
+            }
+            records.push(record);
+        }
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::client::Role;
+
+    /// A log path in a directory removed when the guard drops
+    fn temp_log() -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        (dir, path)
+    }
+
+    fn record(session: &str, content: &str) -> AuditRecord {
+        AuditRecord {
+            timestamp: Utc::now(),
+            tool: Some("confer".to_string()),
+            session: Some(session.to_string()),
+            provider: "openai".to_string(),
+            model: "gpt-5".to_string(),
+            messages: vec![ChatMessage {
+                role: Role::User,
+                content: content.to_string(),
+            }],
+            response: Some("ok".to_string()),
+            error: None,
+            usage: None,
+            latency_ms: 12,
+        }
+    }
+
+    #[test]
+    fn test_redaction() {
+        let (_dir, path) = temp_log();
+        let mut config = AuditConfig::new(path);
+        config.redact_patterns = vec![r"ACME-\d{6}".to_string()];
+        let sink = AuditSink::new(config).unwrap();
+
+        let redacted = sink.redact(
+            "key sk-abcdefghijklmnopqrstuv, Authorization: Bearer abc.def.ghi123, \
+             password=hunter22 customer ACME-123456",
+        );
+        assert!(!redacted.contains("sk-abcdefghijklmnopqrstuv"));
+        assert!(redacted.contains("Bearer [REDACTED]"));
+        assert!(redacted.contains("password=[REDACTED]"));
+        assert!(!redacted.contains("ACME-123456"));
+    }
+
+    #[test]
+    fn test_rotation_and_session_filter() {
+        let (_dir, path) = temp_log();
+        let mut config = AuditConfig::new(&path);
+        config.max_file_bytes = 400;
+        config.max_files = 2;
+        let sink = AuditSink::new(config).unwrap();
+
+        for i in 0..6 {
+            let session = if i % 2 == 0 { "a" } else { "b" };
+            sink.write(record(session, &format!("message {}", i)))
+                .unwrap();
+        }
+
+        assert!(rotated_path(&path, 1).exists());
+        assert!(!rotated_path(&path, 3).exists());
+
+        let all = read_records(&path, None, None).unwrap();
+        assert!(
+            all.len() < 6,
+            "oldest rotated file should have been dropped"
+        );
+        let session_a = read_records(&path, Some("a"), None).unwrap();
+        assert!(session_a.iter().all(|r| r.session.as_deref() == Some("a")));
+        // Records come back oldest first across rotated files
+        assert_eq!(all.last().unwrap().messages[0].content, "message 5");
+    }
+
+    #[tokio::test]
+    async fn test_session_set_during_call() {
+        let context = AuditContext::new(Some("traced_reasoning".to_string()), None);
+        let shared = context.clone();
+        scope(context, async {
+            assert_eq!(current_context().session(), None);
+            set_session("created-by-tool");
+            // Tasks spawned earlier with a copy of the context see it too
+            assert_eq!(shared.session().as_deref(), Some("created-by-tool"));
+        })
+        .await;
+        set_session("outside");
+        assert_eq!(current_context().session(), None);
+    }
+}
@@ -0,0 +1,123 @@
+use tracing::info;
+
+use super::client::{ReasoningEffort, Verbosity};
+
+/// Which optional request parameters a model accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelCapabilities {
+    pub reasoning_efforts: &'static [ReasoningEffort],
+    pub supports_verbosity: bool,
+    /// Whether token log probabilities can be requested. Reasoning models don't expose them.
+    pub supports_logprobs: bool,
+}
+
+const GPT5_EFFORTS: &[ReasoningEffort] = &[
+    ReasoningEffort::Minimal,
+    ReasoningEffort::Low,
+    ReasoningEffort::Medium,
+    ReasoningEffort::High,
+];
+
+// O-series models have no "minimal" effort
+const O_SERIES_EFFORTS: &[ReasoningEffort] = &[
+    ReasoningEffort::Low,
+    ReasoningEffort::Medium,
+    ReasoningEffort::High,
+];
+
+impl ModelCapabilities {
+    pub fn for_model(model: &str) -> Self {
+        if model == "gpt-5" || model.starts_with("gpt-5-") || model == "gpt5-mini" {
+            Self {
+                reasoning_efforts: GPT5_EFFORTS,
+                supports_verbosity: true,
+                supports_logprobs: false,
+            }
+        } else if model.starts_with("o3") || model.starts_with("o4") {
+            Self {
+                reasoning_efforts: O_SERIES_EFFORTS,
+                supports_verbosity: false,
+                supports_logprobs: false,
+            }
+        } else {
+            // Standard and OpenRouter models: neither parameter is sent. Logprobs
+            // are requested, though not every OpenRouter provider returns them.
+            Self {
+                reasoning_efforts: &[],
+                supports_verbosity: false,
+                supports_logprobs: true,
+            }
+        }
+    }
+
+    /// Returns the requested effort if the model accepts it, logging when it is dropped
+    pub fn reasoning_effort(
+        &self,
+        model: &str,
+        requested: Option<ReasoningEffort>,
+    ) -> Option<ReasoningEffort> {
+        let effort = requested?;
+        if self.reasoning_efforts.contains(&effort) {
+            Some(effort)
+        } else {
+            info!(
+                "Model '{}' doesn't support reasoning_effort {:?}. Ignoring it.",
+                model, effort
+            );
+            None
+        }
+    }
+
+    /// Returns the requested verbosity if the model accepts it, logging when it is dropped
+    pub fn verbosity(&self, model: &str, requested: Option<Verbosity>) -> Option<Verbosity> {
+        let verbosity = requested?;
+        if self.supports_verbosity {
+            Some(verbosity)
+        } else {
+            info!(
+                "Model '{}' doesn't support verbosity. Ignoring {:?}.",
+                model, verbosity
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gpt5_accepts_all_options() {
+        let caps = ModelCapabilities::for_model("gpt-5-mini");
+        assert_eq!(
+            caps.reasoning_effort("gpt-5-mini", Some(ReasoningEffort::Minimal)),
+            Some(ReasoningEffort::Minimal)
+        );
+        assert_eq!(
+            caps.verbosity("gpt-5-mini", Some(Verbosity::Low)),
+            Some(Verbosity::Low)
+        );
+    }
+
+    #[test]
+    fn test_unsupported_options_are_dropped() {
+        let o3 = ModelCapabilities::for_model("o3-pro-2025-06-10");
+        assert_eq!(
+            o3.reasoning_effort("o3", Some(ReasoningEffort::Minimal)),
+            None
+        );
+        assert_eq!(
+            o3.reasoning_effort("o3", Some(ReasoningEffort::Low)),
+            Some(ReasoningEffort::Low)
+        );
+        assert_eq!(o3.verbosity("o3", Some(Verbosity::High)), None);
+
+        let claude = ModelCapabilities::for_model("anthropic/claude-4-sonnet");
+        assert!(claude.reasoning_efforts.is_empty());
+        assert_eq!(
+            claude.reasoning_effort("claude", Some(ReasoningEffort::High)),
+            None
+        );
+    }
+}
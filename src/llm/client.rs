@@ -21,6 +21,115 @@ pub struct LLMResponse {
     pub model: String,
     pub usage: Option<TokenUsage>,
     pub finish_reason: Option<String>,
+    /// Provider-side response ID, set when the turn can be chained (Responses API)
+    #[serde(default)]
+    pub response_id: Option<String>,
+    /// Per-token log probabilities, when requested and the model exposes them
+    #[serde(default)]
+    pub logprobs: Option<Vec<TokenLogprob>>,
+}
+
+/// Log probability of one generated token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenLogprob {
+    pub token: String,
+    pub logprob: f32,
+    /// Log probabilities of the most likely candidates at this position
+    #[serde(default)]
+    pub top_logprobs: Vec<f32>,
+}
+
+/// Candidates requested per token when logprobs are on; enough to estimate entropy
+pub(crate) const TOP_LOGPROBS: u8 = 5;
+
+/// `logprobs` object of a Chat Completions choice, shared by OpenAI-compatible providers
+#[derive(Debug, Deserialize)]
+pub(crate) struct ChoiceLogprobs {
+    #[serde(default)]
+    content: Option<Vec<WireTokenLogprob>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WireTokenLogprob {
+    token: String,
+    logprob: f32,
+    #[serde(default)]
+    top_logprobs: Vec<WireTopLogprob>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WireTopLogprob {
+    logprob: f32,
+}
+
+impl ChoiceLogprobs {
+    /// `None` when the provider sent the object without token entries
+    pub(crate) fn into_tokens(self) -> Option<Vec<TokenLogprob>> {
+        let content = self.content.filter(|c| !c.is_empty())?;
+        Some(
+            content
+                .into_iter()
+                .map(|t| TokenLogprob {
+                    token: t.token,
+                    logprob: t.logprob,
+                    top_logprobs: t.top_logprobs.into_iter().map(|c| c.logprob).collect(),
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Summary of a response's token log probabilities
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LogprobStats {
+    /// exp of the mean negative log probability; 1.0 means fully certain
+    pub perplexity: f32,
+    /// Mean entropy of the candidate distribution per token, normalized to [0, 1].
+    /// `None` when the provider returned no candidates.
+    pub mean_entropy: Option<f32>,
+    pub tokens: usize,
+}
+
+impl LogprobStats {
+    /// `None` for an empty token list
+    pub fn from_logprobs(logprobs: &[TokenLogprob]) -> Option<Self> {
+        if logprobs.is_empty() {
+            return None;
+        }
+
+        let mean_logprob =
+            logprobs.iter().map(|t| t.logprob as f64).sum::<f64>() / logprobs.len() as f64;
+
+        let entropies: Vec<f64> = logprobs
+            .iter()
+            .filter(|t| !t.top_logprobs.is_empty())
+            .map(|t| normalized_entropy(&t.top_logprobs))
+            .collect();
+        let mean_entropy = (!entropies.is_empty())
+            .then(|| (entropies.iter().sum::<f64>() / entropies.len() as f64) as f32);
+
+        Some(Self {
+            perplexity: (-mean_logprob).exp() as f32,
+            mean_entropy,
+            tokens: logprobs.len(),
+        })
+    }
+}
+
+/// Entropy over the top candidates, with the probability mass they don't cover
+/// counted as one more outcome, divided by the maximum possible entropy
+fn normalized_entropy(top_logprobs: &[f32]) -> f64 {
+    let probabilities: Vec<f64> = top_logprobs.iter().map(|lp| (*lp as f64).exp()).collect();
+    let rest = (1.0 - probabilities.iter().sum::<f64>()).max(0.0);
+
+    let entropy: f64 = probabilities
+        .iter()
+        .chain(std::iter::once(&rest))
+        .filter(|p| **p > 0.0)
+        .map(|p| -p * p.ln())
+        .sum();
+    let outcomes = probabilities.len() + 1;
+    (entropy / (outcomes as f64).ln()).clamp(0.0, 1.0)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,6 +139,38 @@ pub struct TokenUsage {
     pub total_tokens: u32,
 }
 
+/// How much internal reasoning a reasoning model should spend on a request
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReasoningEffort {
+    Minimal,
+    Low,
+    Medium,
+    High,
+}
+
+/// How long and detailed the visible answer should be (GPT-5)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Verbosity {
+    Low,
+    Medium,
+    High,
+}
+
+/// Per-request options that not every provider understands
+#[derive(Debug, Clone, Default)]
+pub struct CompletionOptions {
+    /// Continue from a previous Responses API turn instead of resending the history
+    pub previous_response_id: Option<String>,
+    /// Requested reasoning effort; dropped for models without support
+    pub reasoning_effort: Option<ReasoningEffort>,
+    /// Requested verbosity; dropped for models without support
+    pub verbosity: Option<Verbosity>,
+    /// Request per-token log probabilities; dropped for models that don't expose them
+    pub logprobs: bool,
+}
+
 #[async_trait]
 pub trait LLMClient: Send + Sync {
     async fn complete(
@@ -39,6 +180,18 @@ pub trait LLMClient: Send + Sync {
         max_tokens: Option<u32>,
     ) -> Result<LLMResponse>;
 
+    /// Like `complete`, but with provider-specific options. Clients that don't
+    /// support an option ignore it.
+    async fn complete_with_options(
+        &self,
+        messages: Vec<ChatMessage>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+        _options: CompletionOptions,
+    ) -> Result<LLMResponse> {
+        self.complete(messages, temperature, max_tokens).await
+    }
+
     async fn complete_simple(
         &self,
         message: &str,
@@ -54,3 +207,36 @@ pub trait LLMClient: Send + Sync {
 
     fn get_model_name(&self) -> &str;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(logprob: f32, top_logprobs: Vec<f32>) -> TokenLogprob {
+        TokenLogprob {
+            token: "x".to_string(),
+            logprob,
+            top_logprobs,
+        }
+    }
+
+    #[test]
+    fn test_logprob_stats() {
+        assert!(LogprobStats::from_logprobs(&[]).is_none());
+
+        // Certain tokens: perplexity 1, no spread over candidates
+        let certain =
+            LogprobStats::from_logprobs(&[token(0.0, vec![0.0]), token(0.0, vec![0.0])]).unwrap();
+        assert!((certain.perplexity - 1.0).abs() < 1e-6);
+        assert_eq!(certain.mean_entropy, Some(0.0));
+
+        // Each token a coin flip between two candidates
+        let half = 0.5f32.ln();
+        let uncertain = LogprobStats::from_logprobs(&[token(half, vec![half, half])]).unwrap();
+        assert!((uncertain.perplexity - 2.0).abs() < 1e-4);
+        assert!(uncertain.mean_entropy.unwrap() > 0.6);
+
+        let no_candidates = LogprobStats::from_logprobs(&[token(half, vec![])]).unwrap();
+        assert_eq!(no_candidates.mean_entropy, None);
+    }
+}
@@ -1,7 +1,78 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::env;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
+use std::sync::Arc;
+
+use super::audit::AuditConfig;
+use super::client::LLMClient;
+use super::model_aliases::ModelResolver;
+use super::openai::OpenAIClient;
+use super::openrouter::OpenRouterClient;
+use crate::settings::{Env, LuxSettings, ToolSettings};
+
+const DEFAULT_AZURE_API_VERSION: &str = "2025-04-01-preview";
+
+/// Azure OpenAI settings. When present, all OpenAI traffic goes to Azure deployments.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AzureOpenAIConfig {
+    pub endpoint: String, // e.g. https://my-resource.openai.azure.com
+    pub api_version: String,
+    /// OpenAI model name -> Azure deployment name
+    pub deployments: HashMap<String, String>,
+}
+
+impl AzureOpenAIConfig {
+    fn from_env(env: &Env) -> Option<Self> {
+        let endpoint = env.var("AZURE_OPENAI_ENDPOINT").filter(|s| !s.is_empty())?;
+
+        Some(Self {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            api_version: env
+                .var("AZURE_OPENAI_API_VERSION")
+                .filter(|s| !s.is_empty())
+                .unwrap_or_else(|| DEFAULT_AZURE_API_VERSION.to_string()),
+            deployments: env
+                .var("AZURE_OPENAI_DEPLOYMENTS")
+                .map(|s| Self::parse_deployments(&s))
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Parse `model=deployment` pairs separated by commas
+    pub fn parse_deployments(value: &str) -> HashMap<String, String> {
+        value
+            .split(',')
+            .filter_map(|pair| {
+                let (model, deployment) = pair.split_once('=')?;
+                let (model, deployment) = (model.trim(), deployment.trim());
+                if model.is_empty() || deployment.is_empty() {
+                    None
+                } else {
+                    Some((model.to_string(), deployment.to_string()))
+                }
+            })
+            .collect()
+    }
+
+    /// Deployment serving a model; unmapped models use a deployment named after the model
+    pub fn deployment_for(&self, model: &str) -> String {
+        self.deployments
+            .get(model)
+            .cloned()
+            .unwrap_or_else(|| model.to_string())
+    }
+}
+
+impl fmt::Debug for AzureOpenAIConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AzureOpenAIConfig")
+            .field("endpoint", &self.endpoint)
+            .field("api_version", &self.api_version)
+            .field("deployments", &self.deployments)
+            .finish()
+    }
+}
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct LLMConfig {
@@ -25,67 +96,163 @@ pub struct LLMConfig {
     pub openai_base_url: Option<String>,
     pub openrouter_base_url: Option<String>,
 
+    // Azure OpenAI mode (openai_api_key then holds the Azure key)
+    pub azure_openai: Option<AzureOpenAIConfig>,
+
+    // JSONL audit log of provider requests (disabled unless LUX_AUDIT_LOG is set)
+    pub audit: Option<AuditConfig>,
+
     // Request settings
     pub request_timeout_secs: u64,
     pub max_retries: u32,
+
+    // Per-tool defaults from lux.toml
+    pub tools: HashMap<String, ToolSettings>,
 }
 
 impl LLMConfig {
-    pub fn from_env() -> Result<Self> {
-        // Load .env file if it exists
-        dotenv::dotenv().ok();
+    /// Build from the layered settings; environment variables win over lux.toml values
+    pub fn from_settings(settings: &LuxSettings) -> Result<Self> {
+        let llm = &settings.llm;
+        let env = &settings.env;
+
+        let azure_openai = AzureOpenAIConfig::from_env(env);
+
+        // In Azure mode the Azure key authenticates all OpenAI requests
+        let openai_api_key = if azure_openai.is_some() {
+            env.var("AZURE_OPENAI_API_KEY")
+                .or_else(|| env.var("OPENAI_API_KEY"))
+                .filter(|s| !s.is_empty())
+        } else {
+            env.var("OPENAI_API_KEY").filter(|s| !s.is_empty())
+        };
 
         Ok(Self {
             // API Keys (filter out empty strings)
-            openai_api_key: env::var("OPENAI_API_KEY").ok().filter(|s| !s.is_empty()),
-            openrouter_api_key: env::var("OPENROUTER_API_KEY")
-                .ok()
-                .filter(|s| !s.is_empty()),
+            openai_api_key,
+            openrouter_api_key: env.var("OPENROUTER_API_KEY").filter(|s| !s.is_empty()),
 
             // Model configuration with backward compatibility
-            model_reasoning: env::var("LUX_MODEL_REASONING")
-                .or_else(|_| env::var("LUX_DEFAULT_REASONING_MODEL")) // Backward compat
-                .unwrap_or_else(|_| "gpt-5".to_string()),
-            model_normal: env::var("LUX_MODEL_NORMAL")
-                .or_else(|_| env::var("LUX_DEFAULT_CHAT_MODEL")) // Backward compat
-                .unwrap_or_else(|_| "gpt-5".to_string()),
-            model_mini: env::var("LUX_MODEL_MINI")
-                .or_else(|_| env::var("LUX_DEFAULT_BIAS_CHECKER_MODEL")) // Backward compat
-                .unwrap_or_else(|_| "gpt-5-mini".to_string()),
+            model_reasoning: env
+                .var("LUX_MODEL_REASONING")
+                .or_else(|| env.var("LUX_DEFAULT_REASONING_MODEL")) // Backward compat
+                .or_else(|| llm.model_reasoning.clone())
+                .unwrap_or_else(|| "gpt-5".to_string()),
+            model_normal: env
+                .var("LUX_MODEL_NORMAL")
+                .or_else(|| env.var("LUX_DEFAULT_CHAT_MODEL")) // Backward compat
+                .or_else(|| llm.model_normal.clone())
+                .unwrap_or_else(|| "gpt-5".to_string()),
+            model_mini: env
+                .var("LUX_MODEL_MINI")
+                .or_else(|| env.var("LUX_DEFAULT_BIAS_CHECKER_MODEL")) // Backward compat
+                .or_else(|| llm.model_mini.clone())
+                .unwrap_or_else(|| "gpt-5-mini".to_string()),
 
             // Named model definitions with defaults
-            model_opus: env::var("LUX_MODEL_OPUS")
-                .ok()
+            model_opus: env
+                .var("LUX_MODEL_OPUS")
+                .or_else(|| llm.model_opus.clone())
                 .or_else(|| Some("anthropic/claude-4.1-opus".to_string())),
-            model_sonnet: env::var("LUX_MODEL_SONNET")
-                .ok()
+            model_sonnet: env
+                .var("LUX_MODEL_SONNET")
+                .or_else(|| llm.model_sonnet.clone())
                 .or_else(|| Some("anthropic/claude-4-sonnet".to_string())),
-            model_grok: env::var("LUX_MODEL_GROK")
-                .ok()
+            model_grok: env
+                .var("LUX_MODEL_GROK")
+                .or_else(|| llm.model_grok.clone())
                 .or_else(|| Some("x-ai/grok-beta".to_string())),
 
             // API endpoints
-            openai_base_url: env::var("OPENAI_BASE_URL").ok(),
-            openrouter_base_url: env::var("OPENROUTER_BASE_URL")
-                .ok()
+            openai_base_url: env
+                .var("OPENAI_BASE_URL")
+                .or_else(|| llm.openai_base_url.clone()),
+            openrouter_base_url: env
+                .var("OPENROUTER_BASE_URL")
+                .or_else(|| llm.openrouter_base_url.clone())
                 .or_else(|| Some("https://openrouter.ai/api/v1".to_string())),
+            azure_openai,
+            audit: AuditConfig::from_env(env),
 
             // Request settings
-            request_timeout_secs: env::var("LUX_REQUEST_TIMEOUT_SECS")
-                .ok()
-                .and_then(|s| s.parse().ok())
+            request_timeout_secs: env
+                .parse("LUX_REQUEST_TIMEOUT_SECS")
+                .or(llm.request_timeout_secs)
                 .unwrap_or(30),
-            max_retries: env::var("LUX_MAX_RETRIES")
-                .ok()
-                .and_then(|s| s.parse().ok())
+            max_retries: env
+                .parse("LUX_MAX_RETRIES")
+                .or(llm.max_retries)
                 .unwrap_or(3),
+            tools: settings.tools.clone(),
         })
     }
 
+    /// Defaults configured for a tool in lux.toml (empty when none)
+    pub fn tool(&self, name: &str) -> ToolSettings {
+        self.tools.get(name).cloned().unwrap_or_default()
+    }
+
+    /// Client for a resolved model: OpenRouter for `vendor/model` names, else
+    /// OpenAI (or its Azure deployment)
+    pub fn client_for(&self, model: &str) -> Result<Arc<dyn LLMClient>> {
+        if model.contains('/') {
+            let api_key = self
+                .openrouter_api_key
+                .clone()
+                .context("OpenRouter API key not configured. Please set OPENROUTER_API_KEY")?;
+            Ok(Arc::new(OpenRouterClient::new(
+                api_key,
+                model.to_string(),
+                self.openrouter_base_url.clone(),
+            )?))
+        } else {
+            let api_key = self
+                .openai_api_key
+                .clone()
+                .context("OpenAI API key not configured. Please set OPENAI_API_KEY")?;
+            Ok(Arc::new(OpenAIClient::with_config(
+                api_key,
+                model.to_string(),
+                self,
+            )?))
+        }
+    }
+
+    /// Models to try, in order, when `requested` is unavailable: the tool's
+    /// `fallback_models` from lux.toml, else the reasoning model and the GPT-5
+    /// family. Blocked models and repeats are dropped.
+    pub fn fallback_models(
+        &self,
+        tool: &str,
+        requested: &str,
+        resolver: &ModelResolver,
+    ) -> Vec<String> {
+        let chain = self.tool(tool).fallback_models.unwrap_or_else(|| {
+            vec![
+                self.model_reasoning.clone(),
+                "gpt-5".to_string(),
+                "gpt-5-mini".to_string(),
+            ]
+        });
+        let mut seen = HashSet::new();
+        chain
+            .iter()
+            .map(|m| resolver.resolve(m))
+            .filter(|m| m != requested && !resolver.is_blocked_model(m))
+            .filter(|m| seen.insert(m.clone()))
+            .collect()
+    }
+
     pub fn validate(&self) -> Result<()> {
         if self.openai_api_key.is_none() && self.openrouter_api_key.is_none() {
+            let openai_key = if self.azure_openai.is_some() {
+                "AZURE_OPENAI_API_KEY"
+            } else {
+                "OPENAI_API_KEY"
+            };
             anyhow::bail!(
-                "No API keys configured. Please set OPENAI_API_KEY or OPENROUTER_API_KEY"
+                "No API keys configured. Please set {} or OPENROUTER_API_KEY",
+                openai_key
             );
         }
         Ok(())
@@ -105,8 +272,11 @@ impl Default for LLMConfig {
             model_grok: Some("x-ai/grok-beta".to_string()),
             openai_base_url: None,
             openrouter_base_url: Some("https://openrouter.ai/api/v1".to_string()),
+            azure_openai: None,
+            audit: None,
             request_timeout_secs: 30,
             max_retries: 3,
+            tools: HashMap::new(),
         }
     }
 }
@@ -125,8 +295,43 @@ impl fmt::Debug for LLMConfig {
             .field("model_grok", &self.model_grok)
             .field("openai_base_url", &self.openai_base_url)
             .field("openrouter_base_url", &self.openrouter_base_url)
+            .field("azure_openai", &self.azure_openai)
+            .field("audit", &self.audit)
             .field("request_timeout_secs", &self.request_timeout_secs)
             .field("max_retries", &self.max_retries)
+            .field("tools", &self.tools)
             .finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fallback_models() {
+        let mut config = LLMConfig::default();
+        let resolver = ModelResolver::with_config(Some(config.clone()));
+        assert_eq!(
+            config.fallback_models("confer", "gpt-5", &resolver),
+            vec!["gpt-5-mini".to_string()]
+        );
+
+        // A configured chain replaces the built-in one, minus blocked models
+        config.tools.insert(
+            "traced_reasoning".to_string(),
+            ToolSettings {
+                fallback_models: Some(vec![
+                    "o3".to_string(),
+                    "gpt-5-mini".to_string(),
+                    "gpt-5".to_string(),
+                ]),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            config.fallback_models("traced_reasoning", "gpt-5-mini", &resolver),
+            vec!["gpt-5".to_string()]
+        );
+    }
+}
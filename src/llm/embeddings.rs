@@ -0,0 +1,568 @@
+//! Text embeddings for semantic similarity.
+//!
+//! `Embedder` is what the monitor and tools use. It optionally wraps a remote
+//! OpenAI-compatible `/embeddings` endpoint (OpenAI, or a local server such as
+//! Ollama or llama.cpp) and always has a local hashed n-gram embedding to fall
+//! back on. Remote embeddings are fetched ahead of time with `prefetch`, so the
+//! similarity lookups made while the monitor lock is held stay synchronous.
+//! Every text a thought is compared with has to be prefetched with it: two
+//! texts are only compared remotely when both have a remote vector.
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use parking_lot::{Mutex, RwLock};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::warn;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::llm::config::{AzureOpenAIConfig, LLMConfig};
+use crate::settings::LuxSettings;
+
+const LOCAL_DIMENSIONS: usize = 1024;
+const DEFAULT_CACHE_SIZE: usize = 1024;
+/// How long to stay on local embeddings after the remote provider fails
+const REMOTE_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Source of embedding vectors for a batch of texts
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    /// Name shown in status output, e.g. the model
+    fn name(&self) -> &str;
+
+    /// One vector per input text, in input order
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+}
+
+#[derive(Debug, Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dimensions: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+/// Client for OpenAI-compatible `/embeddings` endpoints
+pub struct OpenAIEmbeddings {
+    client: Client,
+    api_key: Option<String>,
+    base_url: String,
+    model: String,
+    dimensions: Option<u32>,
+    /// Deployment's embeddings URL in Azure mode
+    azure_url: Option<String>,
+}
+
+impl OpenAIEmbeddings {
+    /// `api_key` may be `None` for local servers that don't authenticate
+    pub fn new(
+        api_key: Option<String>,
+        model: String,
+        base_url: Option<String>,
+        dimensions: Option<u32>,
+    ) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .context("Failed to create HTTP client")?;
+
+        Ok(Self {
+            client,
+            api_key,
+            base_url: base_url
+                .unwrap_or_else(|| "https://api.openai.com/v1".to_string())
+                .trim_end_matches('/')
+                .to_string(),
+            model,
+            dimensions,
+            azure_url: None,
+        })
+    }
+
+    /// Client for the Azure deployment serving `model`
+    pub fn azure(
+        api_key: Option<String>,
+        model: String,
+        dimensions: Option<u32>,
+        azure: &AzureOpenAIConfig,
+    ) -> Result<Self> {
+        let azure_url = format!(
+            "{}/openai/deployments/{}/embeddings?api-version={}",
+            azure.endpoint,
+            azure.deployment_for(&model),
+            azure.api_version
+        );
+        Ok(Self {
+            azure_url: Some(azure_url),
+            ..Self::new(api_key, model, None, dimensions)?
+        })
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for OpenAIEmbeddings {
+    fn name(&self) -> &str {
+        &self.model
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let url = self
+            .azure_url
+            .clone()
+            .unwrap_or_else(|| format!("{}/embeddings", self.base_url));
+        let mut request = self.client.post(url).json(&EmbeddingRequest {
+            model: &self.model,
+            input: texts,
+            dimensions: self.dimensions,
+        });
+        request = match (&self.api_key, &self.azure_url) {
+            (Some(api_key), Some(_)) => request.header("api-key", api_key),
+            (Some(api_key), None) => request.header("Authorization", format!("Bearer {}", api_key)),
+            (None, _) => request,
+        };
+
+        let response = request
+            .send()
+            .await
+            .context("Failed to send embeddings request")?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("Embeddings API error ({}): {}", status, body);
+        }
+
+        let mut data = response
+            .json::<EmbeddingResponse>()
+            .await
+            .context("Failed to parse embeddings response")?
+            .data;
+        if data.len() != texts.len() {
+            anyhow::bail!(
+                "Embeddings API returned {} vectors for {} inputs",
+                data.len(),
+                texts.len()
+            );
+        }
+        data.sort_by_key(|d| d.index);
+        Ok(data.into_iter().map(|d| d.embedding).collect())
+    }
+}
+
+/// Local embedding from hashed word, word-bigram and character-trigram features.
+/// Catches paraphrases that share word stems; no notion of synonyms.
+pub struct HashedNgramEmbeddings {
+    dimensions: usize,
+}
+
+impl HashedNgramEmbeddings {
+    pub fn new(dimensions: usize) -> Self {
+        Self { dimensions }
+    }
+
+    pub fn embed(&self, text: &str) -> Vec<f32> {
+        let words: Vec<String> = text
+            .unicode_words()
+            .map(|w| w.to_lowercase())
+            .filter(|w| !STOP_WORDS.contains(&w.as_str()))
+            .collect();
+
+        let mut features: HashMap<String, f32> = HashMap::new();
+        for word in &words {
+            *features.entry(format!("w:{}", word)).or_default() += 1.0;
+            let padded: Vec<char> = format!("^{}$", word).chars().collect();
+            for trigram in padded.windows(3) {
+                *features.entry(trigram.iter().collect()).or_default() += 0.5;
+            }
+        }
+        for pair in words.windows(2) {
+            *features
+                .entry(format!("b:{} {}", pair[0], pair[1]))
+                .or_default() += 1.0;
+        }
+
+        let mut vector = vec![0.0f32; self.dimensions];
+        for (feature, count) in features {
+            let hash = fnv1a(feature.as_bytes());
+            let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+            // Sublinear weighting so one repeated word doesn't dominate
+            vector[(hash % self.dimensions as u64) as usize] += sign * (1.0 + count.ln());
+        }
+        vector
+    }
+}
+
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "the", "and", "or", "but", "if", "then", "so", "of", "to", "in", "on", "at", "by",
+    "for", "with", "from", "as", "is", "are", "was", "were", "be", "been", "being", "it", "its",
+    "this", "that", "these", "those", "we", "you", "they", "he", "she", "i", "me", "my", "our",
+    "your", "their", "them", "us", "do", "does", "did", "have", "has", "had", "not", "no", "can",
+    "could", "should", "would", "will", "may", "might", "must", "how", "what", "why", "when",
+    "where", "which", "who", "there", "here", "than", "too", "very", "also", "just", "about",
+    "into", "over", "under", "more", "most", "some", "any", "each", "other", "such", "only", "own",
+    "same", "both", "all", "let",
+];
+
+/// Stable across runs and platforms, unlike `DefaultHasher`
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Cosine similarity clamped to [0, 1]; 0.0 for empty or mismatched vectors
+pub fn cosine(a: &[f32], b: &[f32]) -> f64 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f64, 0.0f64, 0.0f64);
+    for (x, y) in a.iter().zip(b) {
+        dot += (*x as f64) * (*y as f64);
+        norm_a += (*x as f64).powi(2);
+        norm_b += (*y as f64).powi(2);
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    (dot / (norm_a.sqrt() * norm_b.sqrt())).clamp(0.0, 1.0)
+}
+
+#[derive(Default)]
+struct CacheEntry {
+    local: Option<Arc<Vec<f32>>>,
+    remote: Option<Arc<Vec<f32>>>,
+}
+
+/// Per-thought embeddings, evicted oldest first
+struct EmbeddingCache {
+    entries: HashMap<u64, CacheEntry>,
+    order: VecDeque<u64>,
+    capacity: usize,
+}
+
+impl EmbeddingCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            capacity: capacity.max(1),
+        }
+    }
+
+    fn entry(&mut self, key: u64) -> &mut CacheEntry {
+        if !self.entries.contains_key(&key) {
+            if self.order.len() >= self.capacity {
+                if let Some(oldest) = self.order.pop_front() {
+                    self.entries.remove(&oldest);
+                }
+            }
+            self.order.push_back(key);
+        }
+        self.entries.entry(key).or_default()
+    }
+}
+
+/// Semantic similarity over embeddings, remote when available and local otherwise
+pub struct Embedder {
+    remote: RwLock<Option<Arc<dyn EmbeddingProvider>>>,
+    local: HashedNgramEmbeddings,
+    cache: Mutex<EmbeddingCache>,
+    remote_backoff_until: Mutex<Option<Instant>>,
+}
+
+impl Default for Embedder {
+    fn default() -> Self {
+        Self::local()
+    }
+}
+
+impl Embedder {
+    /// Local hashed n-gram embeddings only
+    pub fn local() -> Self {
+        Self::new(None, DEFAULT_CACHE_SIZE)
+    }
+
+    pub fn new(remote: Option<Arc<dyn EmbeddingProvider>>, cache_size: usize) -> Self {
+        Self {
+            remote: RwLock::new(remote),
+            local: HashedNgramEmbeddings::new(LOCAL_DIMENSIONS),
+            cache: Mutex::new(EmbeddingCache::new(cache_size)),
+            remote_backoff_until: Mutex::new(None),
+        }
+    }
+
+    /// Remote embeddings are used when a model is configured. The API key comes
+    /// from LUX_EMBEDDINGS_API_KEY, then the OpenAI key the LLM clients use.
+    /// Without `base_url`, requests go where the LLM clients' do: the Azure
+    /// deployment named after the model in Azure mode, else OPENAI_BASE_URL.
+    pub fn from_settings(settings: &LuxSettings) -> Result<Self> {
+        let embeddings = &settings.embeddings;
+        let remote = match &embeddings.model {
+            Some(model) => {
+                let llm = LLMConfig::from_settings(settings)?;
+                let api_key = settings
+                    .env
+                    .var("LUX_EMBEDDINGS_API_KEY")
+                    .filter(|s| !s.is_empty())
+                    .or(llm.openai_api_key);
+                let provider = match (&embeddings.base_url, &llm.azure_openai) {
+                    (None, Some(azure)) => OpenAIEmbeddings::azure(
+                        api_key,
+                        model.clone(),
+                        embeddings.dimensions,
+                        azure,
+                    )?,
+                    (base_url, _) => OpenAIEmbeddings::new(
+                        api_key,
+                        model.clone(),
+                        base_url.clone().or(llm.openai_base_url),
+                        embeddings.dimensions,
+                    )?,
+                };
+                Some(Arc::new(provider) as Arc<dyn EmbeddingProvider>)
+            }
+            None => None,
+        };
+        Ok(Self::new(
+            remote,
+            embeddings.cache_size.unwrap_or(DEFAULT_CACHE_SIZE),
+        ))
+    }
+
+    /// Take over another embedder's provider and cache size, e.g. after a
+    /// config reload. Cached vectors are dropped; everyone holding this
+    /// embedder uses the new provider from the next prefetch on.
+    pub fn replace(&self, other: Embedder) {
+        let mut cache = self.cache.lock();
+        *self.remote.write() = other.remote.into_inner();
+        *cache = other.cache.into_inner();
+        *self.remote_backoff_until.lock() = None;
+    }
+
+    /// "local" or the remote provider's name
+    pub fn provider_name(&self) -> String {
+        self.remote
+            .read()
+            .as_ref()
+            .map_or("local", |remote| remote.name())
+            .to_string()
+    }
+
+    /// Fetch remote embeddings for texts not cached yet. Failures are logged and
+    /// leave those texts on local embeddings.
+    pub async fn prefetch(&self, texts: &[&str]) {
+        let Some(remote) = self.remote.read().clone() else {
+            return;
+        };
+        if self.remote_backed_off() {
+            return;
+        }
+
+        let missing: Vec<String> = {
+            let cache = self.cache.lock();
+            let mut missing: Vec<String> = Vec::new();
+            for text in texts {
+                let cached = cache
+                    .entries
+                    .get(&cache_key(text))
+                    .is_some_and(|entry| entry.remote.is_some());
+                if !cached && !text.trim().is_empty() && !missing.iter().any(|m| m == text) {
+                    missing.push(text.to_string());
+                }
+            }
+            missing
+        };
+        if missing.is_empty() {
+            return;
+        }
+
+        match remote.embed(&missing).await {
+            Ok(vectors) => {
+                let mut cache = self.cache.lock();
+                // Vectors from a provider replaced meanwhile don't belong in the cache
+                let current = self.remote.read().clone();
+                if !current.is_some_and(|current| Arc::ptr_eq(&current, &remote)) {
+                    return;
+                }
+                for (text, vector) in missing.iter().zip(vectors) {
+                    cache.entry(cache_key(text)).remote = Some(Arc::new(vector));
+                }
+            }
+            Err(e) => {
+                warn!(
+                    "Embedding provider {} failed, using local embeddings for {:?}: {:#}",
+                    remote.name(),
+                    REMOTE_BACKOFF,
+                    e
+                );
+                *self.remote_backoff_until.lock() = Some(Instant::now() + REMOTE_BACKOFF);
+            }
+        }
+    }
+
+    /// Similarity of two texts in [0, 1]. Uses remote embeddings when both texts
+    /// have one cached, local embeddings otherwise. While the provider is
+    /// failing every pair is compared locally, so the scores of one thought
+    /// don't mix the two embedding spaces.
+    pub fn similarity(&self, a: &str, b: &str) -> f64 {
+        if self.remote_backed_off() {
+            return cosine(&self.local_embedding(a), &self.local_embedding(b));
+        }
+        let (remote_a, remote_b) = {
+            let cache = self.cache.lock();
+            let remote = |text: &str| {
+                cache
+                    .entries
+                    .get(&cache_key(text))
+                    .and_then(|entry| entry.remote.clone())
+            };
+            (remote(a), remote(b))
+        };
+        if let (Some(a), Some(b)) = (remote_a, remote_b) {
+            return cosine(&a, &b);
+        }
+        cosine(&self.local_embedding(a), &self.local_embedding(b))
+    }
+
+    fn remote_backed_off(&self) -> bool {
+        self.remote_backoff_until
+            .lock()
+            .is_some_and(|until| Instant::now() < until)
+    }
+
+    fn local_embedding(&self, text: &str) -> Arc<Vec<f32>> {
+        let key = cache_key(text);
+        if let Some(vector) = self
+            .cache
+            .lock()
+            .entries
+            .get(&key)
+            .and_then(|entry| entry.local.clone())
+        {
+            return vector;
+        }
+        let vector = Arc::new(self.local.embed(text));
+        self.cache.lock().entry(key).local = Some(vector.clone());
+        vector
+    }
+}
+
+fn cache_key(text: &str) -> u64 {
+    fnv1a(text.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Maps each text to a one-hot vector on its first word's length
+    struct MockProvider {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl EmbeddingProvider for MockProvider {
+        fn name(&self) -> &str {
+            "mock"
+        }
+
+        async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if texts.iter().any(|text| text.starts_with("unreachable")) {
+                anyhow::bail!("provider unreachable");
+            }
+            Ok(texts
+                .iter()
+                .map(|text| {
+                    let mut vector = vec![0.0; 16];
+                    vector[text.split_whitespace().next().unwrap_or("").len() % 16] = 1.0;
+                    vector
+                })
+                .collect())
+        }
+    }
+
+    #[test]
+    fn test_local_similarity() {
+        let embedder = Embedder::local();
+        let paraphrase = embedder.similarity(
+            "TCP/IP enables network communication through packet switching",
+            "Network communication is enabled by TCP/IP using packet switching",
+        );
+        let unrelated = embedder.similarity(
+            "TCP/IP enables network communication through packet switching",
+            "Python is a programming language",
+        );
+        assert!(paraphrase > 0.5, "paraphrase similarity {}", paraphrase);
+        assert!(unrelated < 0.2, "unrelated similarity {}", unrelated);
+        assert_eq!(embedder.similarity("", "anything"), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_remote_embeddings_are_cached() {
+        let provider = Arc::new(MockProvider {
+            calls: AtomicUsize::new(0),
+        });
+        let embedder = Embedder::new(Some(provider.clone()), 8);
+
+        // Same first-word length means identical mock vectors
+        embedder.prefetch(&["alpha one", "gamma two"]).await;
+        embedder.prefetch(&["alpha one"]).await;
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(embedder.similarity("alpha one", "gamma two"), 1.0);
+
+        // Without a remote vector for both texts, local embeddings are used
+        assert!(embedder.similarity("alpha one", "delta three") < 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_failing_provider_compares_every_pair_locally() {
+        let provider = Arc::new(MockProvider {
+            calls: AtomicUsize::new(0),
+        });
+        let embedder = Embedder::new(Some(provider), 8);
+        embedder.prefetch(&["alpha one", "gamma two"]).await;
+        assert_eq!(embedder.similarity("alpha one", "gamma two"), 1.0);
+
+        // The next thought gets no remote vector, so the earlier pair must not
+        // keep its remote score next to the new thought's local ones
+        embedder.prefetch(&["unreachable thought"]).await;
+        let local = Embedder::local();
+        for (a, b) in [
+            ("alpha one", "gamma two"),
+            ("alpha one", "unreachable thought"),
+        ] {
+            assert_eq!(embedder.similarity(a, b), local.similarity(a, b));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replaced_provider_drops_cached_vectors() {
+        let provider = Arc::new(MockProvider {
+            calls: AtomicUsize::new(0),
+        });
+        let embedder = Embedder::new(Some(provider.clone()), 8);
+        embedder.prefetch(&["alpha one", "gamma two"]).await;
+        assert_eq!(embedder.provider_name(), "mock");
+
+        embedder.replace(Embedder::local());
+        assert_eq!(embedder.provider_name(), "local");
+        assert!(embedder.similarity("alpha one", "gamma two") < 1.0);
+        embedder.prefetch(&["alpha one", "gamma two"]).await;
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 1);
+    }
+}
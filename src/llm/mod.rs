@@ -1,9 +1,12 @@
+pub mod audit;
+pub mod capabilities;
 pub mod client;
 pub mod config;
+pub mod embeddings;
 pub mod model_aliases;
 pub mod openai;
 pub mod openrouter;
 pub mod token_config;
 
-pub use client::Role;
+pub use client::{ReasoningEffort, Role, Verbosity};
 pub use config::LLMConfig;
@@ -299,6 +299,20 @@ impl ModelResolver {
         model.to_string()
     }
 
+    /// Every alias with the model it resolves to (including configured named models), sorted by alias
+    pub fn alias_table(&self) -> Vec<(String, String)> {
+        let mut table: Vec<(String, String)> = self
+            .aliases
+            .keys()
+            .map(String::as_str)
+            .chain(["opus", "sonnet", "grok"])
+            .map(|alias| (alias.to_string(), self.resolve(alias)))
+            .collect();
+        table.sort();
+        table.dedup();
+        table
+    }
+
     pub fn is_openrouter_model(&self, model: &str) -> bool {
         let resolved = self.resolve(model);
         resolved.contains('/')
@@ -365,4 +379,17 @@ mod tests {
         assert!(resolver.is_openrouter_model("meta-llama/llama-3-70b"));
         assert!(!resolver.is_openrouter_model("gpt4"));
     }
+
+    #[test]
+    fn test_alias_table_uses_configured_models() {
+        let config = LLMConfig {
+            model_grok: Some("x-ai/grok-4".to_string()),
+            ..Default::default()
+        };
+        let table = ModelResolver::with_config(Some(config)).alias_table();
+
+        assert!(table.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(table.contains(&("grok".to_string(), "x-ai/grok-4".to_string())));
+        assert!(table.contains(&("gpt5".to_string(), "gpt-5".to_string())));
+    }
 }
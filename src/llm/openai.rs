@@ -1,8 +1,8 @@
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder};
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tracing::{debug, error, info, warn};
 
 // Token limits for different model families - OPTIMIZED FOR QUALITY
@@ -11,7 +11,13 @@ const GPT5_MAX_TOKENS: u32 = 128000; // GPT-5 supports up to 128K completion tok
 const O3_DEFAULT_TOKENS: u32 = 100000; // O3: Maximum reasoning (200K - 100K input)
 const STANDARD_DEFAULT_TOKENS: u32 = 20000; // Even standard models get more thinking space
 
-use super::client::{ChatMessage, LLMClient, LLMResponse, Role, TokenUsage};
+use super::audit;
+use super::capabilities::ModelCapabilities;
+use super::client::{
+    ChatMessage, ChoiceLogprobs, CompletionOptions, LLMClient, LLMResponse, ReasoningEffort, Role,
+    TokenUsage, Verbosity, TOP_LOGPROBS,
+};
+use super::config::LLMConfig;
 
 // Chat Completions API structures
 #[derive(Debug, Serialize)]
@@ -25,14 +31,22 @@ struct ChatCompletionRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     max_completion_tokens: Option<u32>, // For o4 models
     #[serde(skip_serializing_if = "Option::is_none")]
-    reasoning_effort: Option<String>, // For o4 models
+    reasoning_effort: Option<ReasoningEffort>, // For o4 models
+    #[serde(skip_serializing_if = "Option::is_none")]
+    logprobs: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_logprobs: Option<u8>,
 }
 
 // Responses API structures (for o3 models)
 #[derive(Debug, Serialize)]
 struct ResponsesRequest {
     model: String,
-    input: String,
+    input: Vec<ResponsesInputItem>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instructions: Option<String>, // System messages go here, not into input
+    #[serde(skip_serializing_if = "Option::is_none")]
+    previous_response_id: Option<String>, // Server-side history for chained turns
     #[serde(skip_serializing_if = "Option::is_none")]
     temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -43,14 +57,20 @@ struct ResponsesRequest {
     text: Option<TextConfig>, // For GPT-5 verbosity control
 }
 
+#[derive(Debug, Serialize, PartialEq)]
+struct ResponsesInputItem {
+    role: String, // user or assistant
+    content: String,
+}
+
 #[derive(Debug, Serialize)]
 struct ReasoningConfig {
-    effort: String, // minimal, low, medium, high
+    effort: ReasoningEffort, // minimal, low, medium, high
 }
 
 #[derive(Debug, Serialize)]
 struct TextConfig {
-    verbosity: String, // low, medium, high
+    verbosity: Verbosity, // low, medium, high
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -72,6 +92,8 @@ struct ChatCompletionResponse {
 struct ChatChoice {
     message: OpenAIMessage,
     finish_reason: Option<String>,
+    #[serde(default)]
+    logprobs: Option<ChoiceLogprobs>,
 }
 
 // Responses API response (for o3 models)
@@ -111,24 +133,63 @@ struct OpenAIUsage {
     total_tokens: u32,
 }
 
+// Error bodies: OpenAI (and Azure data plane) nest under `error`, while
+// Azure gateway failures such as a bad subscription key are flat
 #[derive(Debug, Deserialize)]
-struct OpenAIError {
-    error: OpenAIErrorDetail,
+#[serde(untagged)]
+enum OpenAIError {
+    Standard {
+        error: OpenAIErrorDetail,
+    },
+    Azure {
+        #[serde(rename = "statusCode")]
+        status_code: u16,
+        message: String,
+    },
 }
 
 #[derive(Debug, Deserialize)]
 struct OpenAIErrorDetail {
     message: String,
     #[serde(rename = "type")]
-    error_type: String,
+    error_type: Option<String>,
     code: Option<String>,
 }
 
+impl OpenAIError {
+    /// "<kind> - <message>" for error reporting; kind is the type, else the code
+    fn summary(&self) -> String {
+        match self {
+            OpenAIError::Standard { error } => {
+                let kind = error
+                    .error_type
+                    .as_deref()
+                    .or(error.code.as_deref())
+                    .unwrap_or("error");
+                format!("{} - {}", kind, error.message)
+            }
+            OpenAIError::Azure {
+                status_code,
+                message,
+            } => format!("azure_{} - {}", status_code, message),
+        }
+    }
+}
+
+/// Azure deployment a client is bound to
+#[derive(Debug, Clone)]
+struct AzureTarget {
+    endpoint: String,
+    deployment: String,
+    api_version: String,
+}
+
 pub struct OpenAIClient {
     client: Client,
     api_key: String,
     base_url: String,
     model: String,
+    azure: Option<AzureTarget>,
     max_retries: u32,
 }
 
@@ -146,10 +207,67 @@ impl OpenAIClient {
             api_key,
             base_url,
             model,
+            azure: None,
             max_retries: 3,
         })
     }
 
+    /// Create a client for the endpoint selected by the config: an Azure
+    /// deployment when Azure mode is configured, otherwise the OpenAI API
+    pub fn with_config(api_key: String, model: String, config: &LLMConfig) -> Result<Self> {
+        let mut client = Self::new(api_key, model, config.openai_base_url.clone())?;
+
+        if let Some(azure) = &config.azure_openai {
+            let deployment = azure.deployment_for(&client.model);
+            info!(
+                "Using Azure OpenAI deployment '{}' for model '{}'",
+                deployment, client.model
+            );
+            client.azure = Some(AzureTarget {
+                endpoint: azure.endpoint.clone(),
+                deployment,
+                api_version: azure.api_version.clone(),
+            });
+        }
+
+        Ok(client)
+    }
+
+    fn chat_completions_url(&self) -> String {
+        match &self.azure {
+            Some(azure) => format!(
+                "{}/openai/deployments/{}/chat/completions?api-version={}",
+                azure.endpoint, azure.deployment, azure.api_version
+            ),
+            None => format!("{}/chat/completions", self.base_url),
+        }
+    }
+
+    fn responses_url(&self) -> String {
+        match &self.azure {
+            Some(azure) => format!(
+                "{}/openai/responses?api-version={}",
+                azure.endpoint, azure.api_version
+            ),
+            None => format!("{}/responses", self.base_url),
+        }
+    }
+
+    /// Model name sent in request bodies; Azure routes by deployment name
+    fn request_model(&self) -> String {
+        match &self.azure {
+            Some(azure) => azure.deployment.clone(),
+            None => self.model.clone(),
+        }
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.azure {
+            Some(_) => request.header("api-key", &self.api_key),
+            None => request.header("Authorization", format!("Bearer {}", self.api_key)),
+        }
+    }
+
     fn is_o3_model(model: &str) -> bool {
         // O3 models use the Responses API
         model.starts_with("o3")
@@ -165,6 +283,11 @@ impl OpenAIClient {
         model == "gpt-5" || model.starts_with("gpt-5-") || model == "gpt5-mini"
     }
 
+    /// Returns true if requests for this model go through the Responses API
+    pub fn uses_responses_api(model: &str) -> bool {
+        Self::is_o3_model(model) || Self::is_gpt5_model(model)
+    }
+
     fn requires_default_temperature(model: &str) -> bool {
         // These models only support default temperature (1.0)
         Self::is_o4_model(model) 
@@ -199,13 +322,52 @@ impl OpenAIClient {
             .collect()
     }
 
+    /// Split messages into Responses API `instructions` (system) and role-tagged input items
+    fn convert_responses_input(
+        messages: &[ChatMessage],
+    ) -> (Option<String>, Vec<ResponsesInputItem>) {
+        let mut instructions = Vec::new();
+        let mut input = Vec::new();
+
+        for msg in messages {
+            match msg.role {
+                Role::System => instructions.push(msg.content.clone()),
+                Role::User | Role::Assistant => input.push(ResponsesInputItem {
+                    role: Self::convert_role(&msg.role),
+                    content: msg.content.clone(),
+                }),
+            }
+        }
+
+        // The Responses API requires at least one input item
+        if input.is_empty() && !instructions.is_empty() {
+            input.push(ResponsesInputItem {
+                role: "user".to_string(),
+                content: instructions.join("\n\n"),
+            });
+            instructions.clear();
+        }
+
+        let instructions = if instructions.is_empty() {
+            None
+        } else {
+            Some(instructions.join("\n\n"))
+        };
+
+        (instructions, input)
+    }
+
     async fn make_chat_request(
         &self,
         messages: Vec<ChatMessage>,
         temperature: Option<f32>,
         max_tokens: Option<u32>,
+        options: &CompletionOptions,
     ) -> Result<LLMResponse> {
-        let url = format!("{}/chat/completions", self.base_url);
+        let url = self.chat_completions_url();
+        let capabilities = ModelCapabilities::for_model(&self.model);
+        let logprobs = (options.logprobs && capabilities.supports_logprobs).then_some(true);
+        let top_logprobs = logprobs.map(|_| TOP_LOGPROBS);
 
         // Some models don't support custom temperature
         let adjusted_temperature = if Self::requires_default_temperature(&self.model) {
@@ -223,21 +385,31 @@ impl OpenAIClient {
         // O4 models require max_completion_tokens (GPT-5 uses Responses API, not here)
         let request = if Self::is_o4_model(&self.model) {
             ChatCompletionRequest {
-                model: self.model.clone(),
+                model: self.request_model(),
                 messages: Self::convert_messages(&messages),
                 temperature: adjusted_temperature,
                 max_tokens: None,
                 max_completion_tokens: max_tokens,
-                reasoning_effort: Some("high".to_string()), // O4 uses reasoning_effort
+                reasoning_effort: Some(
+                    capabilities
+                        .reasoning_effort(&self.model, options.reasoning_effort)
+                        .unwrap_or(ReasoningEffort::High),
+                ), // O4 uses reasoning_effort
+                logprobs,
+                top_logprobs,
             }
         } else {
             ChatCompletionRequest {
-                model: self.model.clone(),
+                model: self.request_model(),
                 messages: Self::convert_messages(&messages),
                 temperature: adjusted_temperature,
                 max_tokens,
                 max_completion_tokens: None,
-                reasoning_effort: None,
+                // Standard models have no reasoning effort; this logs and drops a request for one
+                reasoning_effort: capabilities
+                    .reasoning_effort(&self.model, options.reasoning_effort),
+                logprobs,
+                top_logprobs,
             }
         };
 
@@ -247,9 +419,7 @@ impl OpenAIClient {
             request.reasoning_effort);
 
         let response = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
+            .authorize(self.client.post(&url))
             .header("Content-Type", "application/json")
             .json(&request)
             .send()
@@ -266,7 +436,8 @@ impl OpenAIClient {
 
             let choice = parsed
                 .choices
-                .first()
+                .into_iter()
+                .next()
                 .context("No choices in OpenAI response")?;
 
             let usage = parsed.usage.map(|u| TokenUsage {
@@ -276,10 +447,12 @@ impl OpenAIClient {
             });
 
             Ok(LLMResponse {
-                content: choice.message.content.clone(),
+                content: choice.message.content,
                 model: parsed.model,
                 usage,
-                finish_reason: choice.finish_reason.clone(),
+                finish_reason: choice.finish_reason,
+                response_id: None,
+                logprobs: choice.logprobs.and_then(ChoiceLogprobs::into_tokens),
             })
         } else {
             let error_text = response
@@ -292,12 +465,7 @@ impl OpenAIClient {
             );
 
             if let Ok(error) = serde_json::from_str::<OpenAIError>(&error_text) {
-                anyhow::bail!(
-                    "OpenAI API error ({}): {} - {}",
-                    status,
-                    error.error.error_type,
-                    error.error.message
-                );
+                anyhow::bail!("OpenAI API error ({}): {}", status, error.summary());
             } else {
                 anyhow::bail!("OpenAI API error ({}): {}", status, error_text);
             }
@@ -309,56 +477,56 @@ impl OpenAIClient {
         messages: Vec<ChatMessage>,
         temperature: Option<f32>,
         max_tokens: Option<u32>,
+        options: &CompletionOptions,
     ) -> Result<LLMResponse> {
-        let url = format!("{}/responses", self.base_url);
+        let url = self.responses_url();
 
-        // Convert messages to a single input string
-        let input = messages
-            .iter()
-            .map(|msg| match msg.role {
-                Role::System => format!("System: {}", msg.content),
-                Role::User => format!("User: {}", msg.content),
-                Role::Assistant => format!("Assistant: {}", msg.content),
-            })
-            .collect::<Vec<_>>()
-            .join("\n");
+        let (instructions, input) = Self::convert_responses_input(&messages);
+        let capabilities = ModelCapabilities::for_model(&self.model);
+        let effort = capabilities
+            .reasoning_effort(&self.model, options.reasoning_effort)
+            .unwrap_or(ReasoningEffort::High); // Maximum reasoning unless asked otherwise
 
         let request = if Self::is_gpt5_model(&self.model) {
             // GPT-5 configuration with maximum reasoning and verbosity
             ResponsesRequest {
-                model: self.model.clone(),
+                model: self.request_model(),
                 input,
+                instructions,
+                previous_response_id: options.previous_response_id.clone(),
                 temperature: None, // GPT-5 doesn't support temperature (like O3)
                 max_output_tokens: max_tokens,
-                reasoning: Some(ReasoningConfig {
-                    effort: "high".to_string(), // Maximum reasoning for GPT-5
-                }),
+                reasoning: Some(ReasoningConfig { effort }),
                 text: Some(TextConfig {
-                    verbosity: "high".to_string(), // High verbosity for detailed responses
+                    // High verbosity for detailed responses unless asked otherwise
+                    verbosity: capabilities
+                        .verbosity(&self.model, options.verbosity)
+                        .unwrap_or(Verbosity::High),
                 }),
             }
         } else {
             // O3 configuration
             ResponsesRequest {
-                model: self.model.clone(),
+                model: self.request_model(),
                 input,
+                instructions,
+                previous_response_id: options.previous_response_id.clone(),
                 temperature: None, // O3 models don't support temperature
                 max_output_tokens: max_tokens,
-                reasoning: Some(ReasoningConfig {
-                    effort: "high".to_string(), // Maximum reasoning for O3
-                }),
-                text: None, // O3 doesn't support verbosity parameter
+                reasoning: Some(ReasoningConfig { effort }),
+                // O3 doesn't support verbosity; this logs and drops a request for it
+                text: capabilities
+                    .verbosity(&self.model, options.verbosity)
+                    .map(|verbosity| TextConfig { verbosity }),
             }
         };
 
-        info!("OpenAI Responses API request - Model: {}, Input length: {}, Temperature: {:?}, Max output tokens: {:?}, Reasoning effort: {:?}", 
-            request.model, request.input.len(), request.temperature, request.max_output_tokens,
+        info!("OpenAI Responses API request - Model: {}, Input items: {}, Previous response: {:?}, Temperature: {:?}, Max output tokens: {:?}, Reasoning effort: {:?}", 
+            request.model, request.input.len(), request.previous_response_id, request.temperature, request.max_output_tokens,
             request.reasoning.as_ref().map(|r| &r.effort));
 
         let response = self
-            .client
-            .post(&url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
+            .authorize(self.client.post(&url))
             .header("Content-Type", "application/json")
             .json(&request)
             .send()
@@ -412,6 +580,9 @@ impl OpenAIClient {
                 model: parsed.model,
                 usage,
                 finish_reason: Some("stop".to_string()),
+                response_id: Some(parsed.id),
+                // Reasoning models on the Responses API don't expose logprobs
+                logprobs: None,
             })
         } else {
             let error_text = response
@@ -425,25 +596,22 @@ impl OpenAIClient {
 
             if let Ok(error) = serde_json::from_str::<OpenAIError>(&error_text) {
                 anyhow::bail!(
-                    "OpenAI Responses API error ({}): {} - {}",
+                    "OpenAI Responses API error ({}): {}",
                     status,
-                    error.error.error_type,
-                    error.error.message
+                    error.summary()
                 );
             } else {
                 anyhow::bail!("OpenAI Responses API error ({}): {}", status, error_text);
             }
         }
     }
-}
 
-#[async_trait]
-impl LLMClient for OpenAIClient {
-    async fn complete(
+    async fn complete_with_retries(
         &self,
         messages: Vec<ChatMessage>,
         temperature: Option<f32>,
         max_tokens: Option<u32>,
+        options: CompletionOptions,
     ) -> Result<LLMResponse> {
         let mut last_error = None;
 
@@ -454,13 +622,19 @@ impl LLMClient for OpenAIClient {
                 tokio::time::sleep(delay).await;
             }
 
-            let result = if Self::is_o3_model(&self.model) || Self::is_gpt5_model(&self.model) {
+            let result = if Self::uses_responses_api(&self.model) {
                 info!("Using Responses API for model: {}", self.model);
-                self.make_responses_request(messages.clone(), temperature, max_tokens)
+                self.make_responses_request(messages.clone(), temperature, max_tokens, &options)
                     .await
             } else {
+                if options.previous_response_id.is_some() {
+                    debug!(
+                        "Ignoring previous_response_id for chat completions model: {}",
+                        self.model
+                    );
+                }
                 info!("Using chat completions API for model: {}", self.model);
-                self.make_chat_request(messages.clone(), temperature, max_tokens)
+                self.make_chat_request(messages.clone(), temperature, max_tokens, &options)
                     .await
             };
 
@@ -475,6 +649,8 @@ impl LLMClient for OpenAIClient {
                         if err_str.contains("invalid_api_key")
                             || err_str.contains("insufficient_quota")
                             || err_str.contains("model_not_found")
+                            || err_str.contains("DeploymentNotFound")
+                            || err_str.contains("invalid subscription key")
                         {
                             break;
                         }
@@ -485,8 +661,170 @@ impl LLMClient for OpenAIClient {
 
         Err(last_error.unwrap_or_else(|| anyhow::anyhow!("All retry attempts failed")))
     }
+}
+
+#[async_trait]
+impl LLMClient for OpenAIClient {
+    async fn complete(
+        &self,
+        messages: Vec<ChatMessage>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> Result<LLMResponse> {
+        self.complete_with_options(
+            messages,
+            temperature,
+            max_tokens,
+            CompletionOptions::default(),
+        )
+        .await
+    }
+
+    async fn complete_with_options(
+        &self,
+        messages: Vec<ChatMessage>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+        options: CompletionOptions,
+    ) -> Result<LLMResponse> {
+        let started = Instant::now();
+        let audited_messages = audit::is_enabled().then(|| messages.clone());
+
+        let result = self
+            .complete_with_retries(messages, temperature, max_tokens, options)
+            .await;
+
+        if let Some(messages) = audited_messages {
+            let provider = if self.azure.is_some() {
+                "azure"
+            } else {
+                "openai"
+            };
+            audit::record(provider, &self.model, messages, &result, started.elapsed());
+        }
+        result
+    }
 
     fn get_model_name(&self) -> &str {
         &self.model
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::config::AzureOpenAIConfig;
+
+    fn message(role: Role, content: &str) -> ChatMessage {
+        ChatMessage {
+            role,
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_responses_input_keeps_roles() {
+        let messages = vec![
+            message(Role::System, "Be precise."),
+            message(Role::User, "What is 2+2?"),
+            message(Role::Assistant, "4"),
+            message(Role::User, "And times 3?"),
+        ];
+
+        let (instructions, input) = OpenAIClient::convert_responses_input(&messages);
+
+        assert_eq!(instructions.as_deref(), Some("Be precise."));
+        let roles: Vec<_> = input.iter().map(|item| item.role.as_str()).collect();
+        assert_eq!(roles, vec!["user", "assistant", "user"]);
+        assert_eq!(input[2].content, "And times 3?");
+    }
+
+    #[test]
+    fn test_responses_input_system_only() {
+        let messages = vec![message(Role::System, "Summarize the rules.")];
+
+        let (instructions, input) = OpenAIClient::convert_responses_input(&messages);
+
+        assert!(instructions.is_none());
+        assert_eq!(
+            input,
+            vec![ResponsesInputItem {
+                role: "user".to_string(),
+                content: "Summarize the rules.".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_responses_request_chaining() {
+        let (instructions, input) =
+            OpenAIClient::convert_responses_input(&[message(Role::User, "Continue")]);
+        let request = ResponsesRequest {
+            model: "gpt-5".to_string(),
+            input,
+            instructions,
+            previous_response_id: Some("resp_123".to_string()),
+            temperature: None,
+            max_output_tokens: None,
+            reasoning: None,
+            text: None,
+        };
+
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["previous_response_id"], "resp_123");
+        assert_eq!(json["input"][0]["role"], "user");
+        assert!(json.get("instructions").is_none());
+    }
+
+    #[test]
+    fn test_azure_deployment_routing() {
+        let config = LLMConfig {
+            azure_openai: Some(AzureOpenAIConfig {
+                endpoint: "https://example.openai.azure.com".to_string(),
+                api_version: "2025-04-01-preview".to_string(),
+                deployments: AzureOpenAIConfig::parse_deployments(
+                    "gpt-5=prod-gpt5, o3 = reasoning",
+                ),
+            }),
+            ..Default::default()
+        };
+
+        let client =
+            OpenAIClient::with_config("key".to_string(), "gpt-5".to_string(), &config).unwrap();
+        assert_eq!(client.request_model(), "prod-gpt5");
+        assert_eq!(
+            client.responses_url(),
+            "https://example.openai.azure.com/openai/responses?api-version=2025-04-01-preview"
+        );
+        // Request shaping still keys off the logical model
+        assert!(OpenAIClient::uses_responses_api(client.get_model_name()));
+
+        let client =
+            OpenAIClient::with_config("key".to_string(), "gpt-4o".to_string(), &config).unwrap();
+        assert_eq!(
+            client.chat_completions_url(),
+            "https://example.openai.azure.com/openai/deployments/gpt-4o/chat/completions?api-version=2025-04-01-preview"
+        );
+    }
+
+    #[test]
+    fn test_error_shapes() {
+        let openai: OpenAIError = serde_json::from_str(
+            r#"{"error":{"message":"Bad key","type":"invalid_request_error","code":"invalid_api_key"}}"#,
+        )
+        .unwrap();
+        assert_eq!(openai.summary(), "invalid_request_error - Bad key");
+
+        let deployment: OpenAIError = serde_json::from_str(
+            r#"{"error":{"code":"DeploymentNotFound","message":"The API deployment for this resource does not exist."}}"#,
+        )
+        .unwrap();
+        assert!(deployment.summary().starts_with("DeploymentNotFound - "));
+
+        let gateway: OpenAIError = serde_json::from_str(
+            r#"{"statusCode":401,"message":"Access denied due to invalid subscription key or wrong API endpoint."}"#,
+        )
+        .unwrap();
+        assert!(gateway.summary().contains("invalid subscription key"));
+    }
+}
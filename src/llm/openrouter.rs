@@ -2,10 +2,14 @@ use anyhow::{Context, Result};
 use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tracing::{debug, warn};
 
-use super::client::{ChatMessage, LLMClient, LLMResponse, Role, TokenUsage};
+use super::audit;
+use super::client::{
+    ChatMessage, ChoiceLogprobs, CompletionOptions, LLMClient, LLMResponse, Role, TokenUsage,
+    TOP_LOGPROBS,
+};
 
 // OpenRouter uses the same format as OpenAI
 #[derive(Debug, Serialize)]
@@ -16,6 +20,10 @@ struct OpenRouterRequest {
     temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    logprobs: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top_logprobs: Option<u8>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -36,6 +44,9 @@ struct OpenRouterResponse {
 struct OpenRouterChoice {
     message: OpenRouterMessage,
     finish_reason: Option<String>,
+    // Only filled in by upstream providers that support logprobs
+    #[serde(default)]
+    logprobs: Option<ChoiceLogprobs>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -136,21 +147,21 @@ impl OpenRouterClient {
             }
         }
     }
-}
 
-#[async_trait]
-impl LLMClient for OpenRouterClient {
-    async fn complete(
+    async fn complete_with_retries(
         &self,
         messages: Vec<ChatMessage>,
         temperature: Option<f32>,
         max_tokens: Option<u32>,
+        options: &CompletionOptions,
     ) -> Result<LLMResponse> {
         let request = OpenRouterRequest {
             model: self.model.clone(),
             messages: Self::convert_messages(&messages),
             temperature,
             max_tokens,
+            logprobs: options.logprobs.then_some(true),
+            top_logprobs: options.logprobs.then_some(TOP_LOGPROBS),
         };
 
         let mut last_error = None;
@@ -166,7 +177,8 @@ impl LLMClient for OpenRouterClient {
                 Ok(response) => {
                     let choice = response
                         .choices
-                        .first()
+                        .into_iter()
+                        .next()
                         .context("No choices in OpenRouter response")?;
 
                     let usage = response.usage.map(|u| TokenUsage {
@@ -176,10 +188,12 @@ impl LLMClient for OpenRouterClient {
                     });
 
                     return Ok(LLMResponse {
-                        content: choice.message.content.clone(),
+                        content: choice.message.content,
                         model: response.model,
                         usage,
-                        finish_reason: choice.finish_reason.clone(),
+                        finish_reason: choice.finish_reason,
+                        response_id: None,
+                        logprobs: choice.logprobs.and_then(ChoiceLogprobs::into_tokens),
                     });
                 }
                 Err(e) => {
@@ -201,6 +215,50 @@ impl LLMClient for OpenRouterClient {
 
         Err(last_error.unwrap_or_else(|| anyhow::anyhow!("All retry attempts failed")))
     }
+}
+
+#[async_trait]
+impl LLMClient for OpenRouterClient {
+    async fn complete(
+        &self,
+        messages: Vec<ChatMessage>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+    ) -> Result<LLMResponse> {
+        self.complete_with_options(
+            messages,
+            temperature,
+            max_tokens,
+            CompletionOptions::default(),
+        )
+        .await
+    }
+
+    async fn complete_with_options(
+        &self,
+        messages: Vec<ChatMessage>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>,
+        options: CompletionOptions,
+    ) -> Result<LLMResponse> {
+        let started = Instant::now();
+        let audited_messages = audit::is_enabled().then(|| messages.clone());
+
+        let result = self
+            .complete_with_retries(messages, temperature, max_tokens, &options)
+            .await;
+
+        if let Some(messages) = audited_messages {
+            audit::record(
+                "openrouter",
+                &self.model,
+                messages,
+                &result,
+                started.elapsed(),
+            );
+        }
+        result
+    }
 
     fn get_model_name(&self) -> &str {
         &self.model
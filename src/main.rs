@@ -1,24 +1,31 @@
 use anyhow::Result;
+use clap::Parser;
 use rmcp::ServiceExt;
 use tokio::io::{stdin, stdout};
 use tracing::info;
 use tracing_subscriber::EnvFilter;
 
+mod cli;
 mod db;
 mod entities;
+mod feedback;
 mod llm;
 mod metachain;
 mod models;
 mod monitoring;
 mod server;
 mod session;
+mod settings;
 mod threading;
 mod tools;
 
+use cli::{Cli, Command};
 use server::LuxServer;
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
     // Load environment variables
     dotenv::dotenv().ok();
 
@@ -31,44 +38,73 @@ async fn main() -> Result<()> {
         .with_writer(std::io::stderr)
         .init();
 
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => serve().await,
+        Command::Doctor => {
+            if !cli::doctor().await? {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        Command::Call { tool, json } => {
+            if !cli::call(tool, &json).await? {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        Command::Sessions { command } => cli::sessions(command).await,
+        Command::Models { json } => cli::models(json),
+        Command::Audit {
+            session,
+            tool,
+            file,
+            limit,
+        } => cli::print_audit(session, tool, file, limit),
+        Command::Calibrate {
+            corpus,
+            sweep,
+            json,
+        } => cli::calibrate(corpus, sweep, json).await,
+    }
+}
+
+async fn serve() -> Result<()> {
     info!(
         "Starting Lux MCP Server v{} - Illuminating your thinking...",
         env!("CARGO_PKG_VERSION")
     );
 
-    // Check API keys (non-empty)
-    let openai_available = std::env::var("OPENAI_API_KEY")
-        .map(|key| !key.is_empty())
-        .unwrap_or(false);
-    let openrouter_available = std::env::var("OPENROUTER_API_KEY")
-        .map(|key| !key.is_empty())
-        .unwrap_or(false);
-
-    info!("API Configuration:");
-    info!(
-        "  OpenAI API key: {}",
-        if openai_available {
+    // Keys come from the same layers the LLM clients read: the environment,
+    // `.env` and, in Azure mode, AZURE_OPENAI_API_KEY
+    let settings = settings::LuxSettings::load()?;
+    let config = llm::config::LLMConfig::from_settings(&settings)?;
+    let available = |key: &Option<String>| {
+        if key.is_some() {
             "✓ Available"
         } else {
             "✗ Not found"
         }
-    );
+    };
+
+    info!("API Configuration:");
+    match &config.azure_openai {
+        Some(azure) => info!(
+            "  Azure OpenAI ({}) API key: {}",
+            azure.endpoint,
+            available(&config.openai_api_key)
+        ),
+        None => info!("  OpenAI API key: {}", available(&config.openai_api_key)),
+    }
     info!(
         "  OpenRouter API key: {}",
-        if openrouter_available {
-            "✓ Available"
-        } else {
-            "✗ Not found"
-        }
+        available(&config.openrouter_api_key)
     );
 
-    if !openai_available && !openrouter_available {
-        eprintln!("No API keys found! Please set OPENAI_API_KEY or OPENROUTER_API_KEY");
+    if let Err(e) = config.validate() {
+        eprintln!("{}", e);
         std::process::exit(1);
     }
 
-    // Load config to show model defaults
-    let config = llm::config::LLMConfig::from_env()?;
     info!("Model Configuration:");
     info!("  Reasoning Model: {}", config.model_reasoning);
     info!("  Normal Model: {}", config.model_normal);
@@ -101,6 +137,10 @@ async fn main() -> Result<()> {
     });
     info!("Session cleanup task started (5 minute interval)");
 
+    // Reload lux.toml and .env in place when they change
+    server.spawn_config_watcher();
+    info!("Configuration watcher started");
+
     // Create transport using stdin/stdout
     let transport = (stdin(), stdout());
 
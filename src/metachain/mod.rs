@@ -1,9 +1,34 @@
-use crate::monitoring::MonitoringSignals;
+//! Illumination: a metacognitive critique of one thought.
+//!
+//! The monitor's detectors say *that* something is off; the metachain asks the
+//! mini model *what* to do about it. It sees the monitoring signals and the
+//! recent thoughts and names the assumption to question, what was skipped and
+//! what to try next. Built-in alerts (walking in circles, following a false
+//! light) are rules over the same facts the monitor's rules use.
+
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use tracing::{info, warn};
+
+use crate::llm::{
+    client::ChatMessage, config::LLMConfig, model_aliases::ModelResolver, openai::OpenAIClient,
+    Role,
+};
+use crate::monitoring::policy::{self, Facts, PolicyAction, PolicyRule};
+use crate::monitoring::{MonitoringConfig, MonitoringSignals};
+
+/// Critiques are short; this leaves reasoning models room to think first
+const CRITIQUE_MAX_TOKENS: u32 = 8000;
+/// Recent thoughts shown to the model, newest last
+const RECENT_THOUGHTS: usize = 5;
+/// Characters kept per recent thought
+const MAX_THOUGHT_CHARS: usize = 1500;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ThoughtResponse {
     pub content: Vec<ResponseContent>,
+    pub critique: Critique,
     pub metadata: ThoughtMetadata,
 }
 
@@ -20,65 +45,305 @@ pub struct ThoughtMetadata {
     pub monitoring_status: Option<String>,
     pub intervention: Option<String>,
     pub quality_trend: Option<String>,
+    /// Model that wrote the critique
+    pub model: String,
+}
+
+/// What the reasoning should do differently
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Critique {
+    /// Assumption the thought rests on that deserves questioning
+    #[serde(default)]
+    pub assumption: String,
+    /// Steps, cases or alternatives the reasoning skipped
+    #[serde(default)]
+    pub skipped: Vec<String>,
+    /// One concrete thing to try next
+    #[serde(default)]
+    pub next_step: String,
+}
+
+impl fmt::Display for Critique {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.assumption.is_empty() {
+            writeln!(f, "• Question: {}", self.assumption)?;
+        }
+        for skipped in &self.skipped {
+            writeln!(f, "• Skipped: {}", skipped)?;
+        }
+        if !self.next_step.is_empty() {
+            writeln!(f, "• Next: {}", self.next_step)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parameters of the `illuminate` tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IlluminateRequest {
+    pub thought: String,
+    /// Position of the thought in the session (default: after the session's last one)
+    #[serde(default)]
+    pub thought_number: Option<usize>,
+    /// Session whose monitor and recent thoughts to use (default: a new session)
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// Model for the critique (default: the mini model)
+    #[serde(default)]
+    pub model: Option<String>,
 }
 
 pub struct MetachainEngine {
-    // Future: Add session management, thought history, etc.
+    /// Alerts written into the illuminated thought, checked in order
+    alerts: Vec<PolicyRule>,
+    model_resolver: ModelResolver,
+    config: LLMConfig,
 }
 
 impl MetachainEngine {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(monitoring: &MonitoringConfig, config: LLMConfig) -> Self {
+        Self {
+            alerts: builtin_alerts(monitoring.metachain_circular_threshold),
+            model_resolver: ModelResolver::with_config(Some(config.clone())),
+            config,
+        }
     }
 
+    /// Critique `thought` in light of the monitor's signals and the thoughts
+    /// before it (oldest first)
     pub async fn process_thought(
         &self,
         thought: &str,
         thought_number: usize,
-        monitoring_signals: Option<MonitoringSignals>,
-    ) -> ThoughtResponse {
-        let mut response_text = format!(
-            "💡 Illuminating thought {}: {}\n\n",
-            thought_number, thought
-        );
+        monitoring_signals: Option<&MonitoringSignals>,
+        recent_thoughts: &[String],
+        model: Option<&str>,
+    ) -> Result<ThoughtResponse> {
+        let model = self.resolve_model(model);
+        let alerts: Vec<String> = monitoring_signals
+            .map(|signals| {
+                let facts = Facts::from_signals(thought_number, signals);
+                policy::evaluate(&self.alerts, &facts)
+                    .into_iter()
+                    .map(|alert| alert.message)
+                    .collect()
+            })
+            .unwrap_or_default();
 
-        // Add monitoring insights if available
-        if let Some(ref signals) = monitoring_signals {
-            if signals.circular_score > 0.85 {
-                response_text.push_str("🔄 Shadow Alert: You're walking in circles in the dark. Seek a new light path.\n\n");
-            }
-
-            if signals.distractor_alert {
-                response_text.push_str("🔦 Refocus Beacon: You're following a false light. Return to the illuminated path.\n\n");
-            }
-
-            match signals.quality_trend.as_str() {
-                "declining" => {
-                    response_text.push_str("🌑 Dimming Light: Your thinking clarity is fading. Gather your insights before they're lost in shadow.\n\n");
-                }
-                "improving" => {
-                    response_text.push_str("🌟 Brightening: Your thinking is gaining clarity. Continue illuminating this path.\n\n");
-                }
-                _ => {}
-            }
-        }
+        info!("Illuminating thought {} with {}", thought_number, model);
+        let prompt = build_prompt(thought, thought_number, monitoring_signals, recent_thoughts);
+        let max_tokens = self
+            .config
+            .tool("illuminate")
+            .cap_tokens(OpenAIClient::get_optimal_tokens(&model).min(CRITIQUE_MAX_TOKENS));
+        let response = self
+            .config
+            .client_for(&model)?
+            .complete(
+                vec![
+                    ChatMessage {
+                        role: Role::System,
+                        content: SYSTEM_PROMPT.to_string(),
+                    },
+                    ChatMessage {
+                        role: Role::User,
+                        content: prompt,
+                    },
+                ],
+                self.config.tool("illuminate").temperature,
+                Some(max_tokens),
+            )
+            .await
+            .context("Illumination failed")?;
+        let critique = parse_critique(&response.content);
 
-        // Placeholder for actual illumination engine
-        response_text.push_str("This is where the illumination engine would shine light on your thoughts, connecting to LLMs to brighten the reasoning path.");
+        let mut text = format!("💡 Illuminating thought {}\n\n", thought_number);
+        for alert in &alerts {
+            text.push_str(alert);
+            text.push_str("\n\n");
+        }
+        text.push_str(&critique.to_string());
 
-        ThoughtResponse {
+        Ok(ThoughtResponse {
             content: vec![ResponseContent {
                 content_type: "text".to_string(),
-                text: response_text,
+                text,
             }],
+            critique,
             metadata: ThoughtMetadata {
                 thought_number,
-                monitoring_status: monitoring_signals.as_ref().map(|s| s.phase.clone()),
-                intervention: monitoring_signals
-                    .as_ref()
-                    .and_then(|s| s.intervention.clone()),
-                quality_trend: monitoring_signals.as_ref().map(|s| s.quality_trend.clone()),
+                monitoring_status: monitoring_signals.map(|s| s.phase.clone()),
+                intervention: monitoring_signals.and_then(|s| s.intervention.clone()),
+                quality_trend: monitoring_signals.map(|s| s.quality_trend.clone()),
+                model,
             },
+        })
+    }
+
+    /// The requested model, else `[tools.illuminate]`'s, else the mini model
+    fn resolve_model(&self, requested: Option<&str>) -> String {
+        let model = requested
+            .or(self.config.tool("illuminate").model.as_deref())
+            .map(|m| self.model_resolver.resolve(m))
+            .unwrap_or_else(|| self.config.model_mini.clone());
+        if self.model_resolver.is_blocked_model(&model) {
+            warn!(
+                "Illumination model '{}' is blocked. Falling back to '{}'.",
+                model, self.config.model_mini
+            );
+            return self.config.model_mini.clone();
+        }
+        model
+    }
+}
+
+const SYSTEM_PROMPT: &str = "You review another model's step-by-step reasoning. \
+    Be specific to the reasoning you are shown; never give generic advice. \
+    Respond with only a JSON object of this shape:\n\
+    {\"assumption\": \"the assumption in the latest thought most worth questioning, and why\", \
+    \"skipped\": [\"a step, case or alternative the reasoning skipped\"], \
+    \"next_step\": \"one concrete thing to try in the next thought\"}";
+
+fn build_prompt(
+    thought: &str,
+    thought_number: usize,
+    signals: Option<&MonitoringSignals>,
+    recent_thoughts: &[String],
+) -> String {
+    let mut prompt = String::new();
+
+    let earlier = &recent_thoughts[recent_thoughts.len().saturating_sub(RECENT_THOUGHTS)..];
+    if !earlier.is_empty() {
+        prompt.push_str("EARLIER THOUGHTS (oldest first):\n");
+        for (i, earlier_thought) in earlier.iter().enumerate() {
+            let number = thought_number.saturating_sub(earlier.len() - i);
+            prompt.push_str(&format!(
+                "[{}] {}\n",
+                number,
+                truncate(earlier_thought, MAX_THOUGHT_CHARS)
+            ));
         }
+        prompt.push('\n');
+    }
+
+    prompt.push_str(&format!(
+        "LATEST THOUGHT [{}]:\n{}\n\n",
+        thought_number,
+        truncate(thought, MAX_THOUGHT_CHARS * 2)
+    ));
+
+    if let Some(signals) = signals {
+        prompt.push_str(&format!(
+            "MONITORING:\n- phase: {}\n- circular score: {:.2}\n- quality trend: {}\n",
+            signals.phase, signals.circular_score, signals.quality_trend
+        ));
+        for signal in signals.detector_signals.iter().filter(|s| s.triggered) {
+            prompt.push_str(&format!(
+                "- {} triggered: {}\n",
+                signal.detector,
+                signal
+                    .reason
+                    .as_deref()
+                    .or(signal.message.as_deref())
+                    .unwrap_or("no reason given")
+            ));
+        }
+        if let Some(intervention) = &signals.intervention {
+            prompt.push_str(&format!("- intervention: {}\n", intervention));
+        }
+    }
+
+    prompt
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Parse the model's JSON, tolerating code fences and surrounding prose.
+/// Unparseable output becomes the next step as-is.
+fn parse_critique(content: &str) -> Critique {
+    let json = match (content.find('{'), content.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &content[start..=end],
+        _ => content,
+    };
+
+    serde_json::from_str(json).unwrap_or_else(|_| Critique {
+        next_step: content.trim().to_string(),
+        ..Default::default()
+    })
+}
+
+/// The metachain's alerts, as rules over the monitor's facts
+fn builtin_alerts(circular_threshold: f64) -> Vec<PolicyRule> {
+    [
+        (
+            "shadow_alert",
+            format!("circular_score > {}", circular_threshold),
+            "🔄 Shadow Alert: You're walking in circles in the dark. Seek a new light path.",
+        ),
+        (
+            "refocus_beacon",
+            "distractor_alert".to_string(),
+            "🔦 Refocus Beacon: You're following a false light. Return to the illuminated path.",
+        ),
+        (
+            "dimming_light",
+            "quality_trend == \"declining\"".to_string(),
+            "🌑 Dimming Light: Your thinking clarity is fading. Gather your insights before they're lost in shadow.",
+        ),
+        (
+            "brightening",
+            "quality_trend == \"improving\"".to_string(),
+            "🌟 Brightening: Your thinking is gaining clarity. Continue illuminating this path.",
+        ),
+    ]
+    .into_iter()
+    .map(|(name, when, message)| {
+        PolicyRule::new(name, &when, PolicyAction::Warn, message).expect("built-in alert")
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitoring::MetacognitiveMonitor;
+
+    #[test]
+    fn test_parse_critique() {
+        let critique = parse_critique(
+            "```json\n{\"assumption\": \"That the cache is never stale\", \"skipped\": [\"Invalidation on writes\"], \"next_step\": \"Trace a write through the cache\"}\n```",
+        );
+        assert_eq!(critique.assumption, "That the cache is never stale");
+        assert_eq!(critique.skipped, vec!["Invalidation on writes"]);
+        assert!(critique.to_string().contains("• Next: Trace a write"));
+
+        let fallback = parse_critique("Check the invalidation path.");
+        assert_eq!(fallback.next_step, "Check the invalidation path.");
+    }
+
+    #[test]
+    fn test_prompt_carries_signals_and_recent_thoughts() {
+        let mut monitor = MetacognitiveMonitor::new();
+        monitor.analyze_thought(
+            "Understanding recursion requires understanding recursion",
+            1,
+        );
+        let signals =
+            monitor.analyze_thought("To understand recursion, you must understand recursion", 2);
+
+        let prompt = build_prompt(
+            "To understand recursion, you must understand recursion",
+            2,
+            Some(&signals),
+            &["Understanding recursion requires understanding recursion".to_string()],
+        );
+        assert!(prompt.contains("[1] Understanding recursion requires"));
+        assert!(prompt.contains("LATEST THOUGHT [2]"));
+        assert!(prompt.contains("- circular_reasoning triggered: High circular score"));
     }
 }
@@ -0,0 +1,437 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::llm::embeddings::Embedder;
+use crate::monitoring::detector::{
+    self, CIRCULAR_REASONING, DISTRACTOR_FIXATION, QUALITY_DEGRADATION,
+};
+use crate::monitoring::lexicon::Lexicon;
+use crate::monitoring::{MetacognitiveMonitor, MonitoringConfig, MonitoringOverrides};
+
+/// Detectors a corpus can label
+pub const LABELS: &[&str] = &[CIRCULAR_REASONING, DISTRACTOR_FIXATION, QUALITY_DEGRADATION];
+
+/// Thresholds the sweep tries, with the detector each one belongs to and the
+/// range covered: (detector, parameter, low, high)
+const SWEEPS: &[(&str, &str, f64, f64)] = &[
+    (
+        CIRCULAR_REASONING,
+        "circular_similarity_threshold",
+        0.4,
+        0.9,
+    ),
+    (
+        CIRCULAR_REASONING,
+        "circular_intervention_threshold",
+        0.3,
+        0.9,
+    ),
+    (DISTRACTOR_FIXATION, "relevance_threshold", 0.05, 0.4),
+    (DISTRACTOR_FIXATION, "detail_density_threshold", 0.3, 0.8),
+    (QUALITY_DEGRADATION, "vocab_decline_threshold", 0.1, 0.5),
+    (QUALITY_DEGRADATION, "coherence_decline_threshold", 0.1, 0.5),
+    (QUALITY_DEGRADATION, "reasoning_decline_threshold", 0.1, 0.5),
+];
+
+const SWEEP_STEP: f64 = 0.05;
+
+/// One line of a calibration corpus: a thought sequence with the detectors
+/// expected to trigger on each thought
+#[derive(Debug, Clone, Deserialize)]
+pub struct CorpusCase {
+    pub id: String,
+    pub thoughts: Vec<LabelledThought>,
+    /// Domain lexicons the sequence is monitored with, on top of the config's
+    #[serde(default)]
+    pub lexicons: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LabelledThought {
+    pub text: String,
+    /// Detectors that should trigger on this thought; empty for a clean thought
+    #[serde(default)]
+    pub expect: Vec<String>,
+}
+
+/// Read a JSONL corpus. Blank lines are skipped.
+pub fn load_corpus(path: &Path) -> Result<Vec<CorpusCase>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read corpus {}", path.display()))?;
+    parse_corpus(&text).with_context(|| format!("Invalid corpus {}", path.display()))
+}
+
+fn parse_corpus(text: &str) -> Result<Vec<CorpusCase>> {
+    let mut corpus = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let case: CorpusCase =
+            serde_json::from_str(line).with_context(|| format!("line {}", index + 1))?;
+        for label in case.thoughts.iter().flat_map(|t| &t.expect) {
+            if !LABELS.contains(&label.as_str()) {
+                bail!(
+                    "line {}: unknown detector '{}' (expected one of {})",
+                    index + 1,
+                    label,
+                    LABELS.join(", ")
+                );
+            }
+        }
+        Lexicon::load(&case.lexicons).with_context(|| format!("line {}", index + 1))?;
+        corpus.push(case);
+    }
+    if corpus.is_empty() {
+        bail!("corpus has no sequences");
+    }
+    Ok(corpus)
+}
+
+/// Per-thought outcomes of one detector
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Confusion {
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub false_negatives: usize,
+    pub true_negatives: usize,
+}
+
+impl Confusion {
+    fn record(&mut self, expected: bool, triggered: bool) {
+        match (expected, triggered) {
+            (true, true) => self.true_positives += 1,
+            (false, true) => self.false_positives += 1,
+            (true, false) => self.false_negatives += 1,
+            (false, false) => self.true_negatives += 1,
+        }
+    }
+
+    /// 0.0 when the detector never triggered
+    pub fn precision(&self) -> f64 {
+        ratio(
+            self.true_positives,
+            self.true_positives + self.false_positives,
+        )
+    }
+
+    /// 0.0 when the corpus has no positives for the detector
+    pub fn recall(&self) -> f64 {
+        ratio(
+            self.true_positives,
+            self.true_positives + self.false_negatives,
+        )
+    }
+
+    pub fn f1(&self) -> f64 {
+        let (precision, recall) = (self.precision(), self.recall());
+        if precision + recall == 0.0 {
+            0.0
+        } else {
+            2.0 * precision * recall / (precision + recall)
+        }
+    }
+}
+
+fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DetectorScore {
+    pub detector: String,
+    #[serde(flatten)]
+    pub confusion: Confusion,
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+}
+
+/// A thought where a detector disagreed with the label
+#[derive(Debug, Clone, Serialize)]
+pub struct Mismatch {
+    pub sequence: String,
+    pub thought: usize,
+    pub detector: String,
+    /// True for a missed detection, false for a false positive
+    pub expected: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CalibrationReport {
+    pub sequences: usize,
+    pub thoughts: usize,
+    pub embeddings: String,
+    pub detectors: Vec<DetectorScore>,
+    /// Interventions raised by a detector the thought wasn't labelled with
+    pub false_interventions: usize,
+    /// False interventions per thought
+    pub false_intervention_rate: f64,
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl CalibrationReport {
+    pub fn f1(&self, detector: &str) -> f64 {
+        self.detectors
+            .iter()
+            .find(|d| d.detector == detector)
+            .map_or(0.0, |d| d.f1)
+    }
+}
+
+/// Run every sequence through a fresh monitor and score each built-in
+/// detector's `triggered` flag against the labels
+pub async fn evaluate(
+    corpus: &[CorpusCase],
+    config: &MonitoringConfig,
+    embedder: &Arc<Embedder>,
+) -> CalibrationReport {
+    let detectors: Vec<&str> = LABELS
+        .iter()
+        .copied()
+        .filter(|name| !config.disabled_detectors.iter().any(|d| d == name))
+        .collect();
+    let mut confusion = vec![Confusion::default(); detectors.len()];
+    let mut thoughts = 0;
+    let mut false_interventions = 0;
+    let mut mismatches = Vec::new();
+
+    for case in corpus {
+        let texts: Vec<&str> = case.thoughts.iter().map(|t| t.text.as_str()).collect();
+        embedder.prefetch(&texts).await;
+
+        let mut case_config = config.clone();
+        case_config.lexicons.extend(case.lexicons.iter().cloned());
+        let mut monitor = MetacognitiveMonitor::with_embedder(case_config, embedder.clone());
+        for (index, thought) in case.thoughts.iter().enumerate() {
+            let signals = monitor.analyze_thought(&thought.text, index + 1);
+            thoughts += 1;
+
+            let expected = |name: &str| thought.expect.iter().any(|e| e == name);
+            for (name, counts) in detectors.iter().zip(confusion.iter_mut()) {
+                let triggered = signals
+                    .detector_signals
+                    .iter()
+                    .any(|s| s.detector == *name && s.triggered);
+                counts.record(expected(name), triggered);
+                if expected(name) != triggered {
+                    mismatches.push(Mismatch {
+                        sequence: case.id.clone(),
+                        thought: index + 1,
+                        detector: name.to_string(),
+                        expected: expected(name),
+                    });
+                }
+            }
+            if detector::strongest(&signals.detector_signals)
+                .is_some_and(|winner| !expected(&winner.detector))
+            {
+                false_interventions += 1;
+            }
+        }
+    }
+
+    CalibrationReport {
+        sequences: corpus.len(),
+        thoughts,
+        embeddings: embedder.provider_name(),
+        detectors: detectors
+            .iter()
+            .zip(confusion)
+            .map(|(name, confusion)| DetectorScore {
+                detector: name.to_string(),
+                confusion,
+                precision: confusion.precision(),
+                recall: confusion.recall(),
+                f1: confusion.f1(),
+            })
+            .collect(),
+        false_interventions,
+        false_intervention_rate: ratio(false_interventions, thoughts),
+        mismatches,
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SweepPoint {
+    pub value: f64,
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ThresholdSuggestion {
+    pub detector: String,
+    pub parameter: String,
+    pub current: f64,
+    pub suggested: f64,
+    pub current_f1: f64,
+    pub suggested_f1: f64,
+    pub points: Vec<SweepPoint>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SweepOutcome {
+    pub suggestions: Vec<ThresholdSuggestion>,
+    /// Starting config with every suggestion applied
+    pub config: MonitoringConfig,
+    /// Evaluation of `config`
+    pub report: CalibrationReport,
+}
+
+/// Sweep each threshold in turn, keeping the value with the best F1 for its
+/// detector before moving to the next one. Ties keep the value closest to the
+/// current one, so thresholds only move when the corpus says they should.
+pub async fn sweep(
+    corpus: &[CorpusCase],
+    base: &MonitoringConfig,
+    embedder: &Arc<Embedder>,
+) -> Result<SweepOutcome> {
+    let mut config = base.clone();
+    let mut suggestions = Vec::new();
+
+    for &(detector, parameter, low, high) in SWEEPS {
+        if config.disabled_detectors.iter().any(|d| d == detector) {
+            continue;
+        }
+        let current = threshold(&config, parameter)?;
+        let current_f1 = evaluate(corpus, &config, embedder).await.f1(detector);
+
+        let steps = ((high - low) / SWEEP_STEP).round() as usize;
+        let mut points = Vec::with_capacity(steps + 1);
+        for i in 0..=steps {
+            // Rounded so grid values print and compare cleanly
+            let value = ((low + SWEEP_STEP * i as f64) * 100.0).round() / 100.0;
+            let report = evaluate(
+                corpus,
+                &with_threshold(&config, parameter, value)?,
+                embedder,
+            )
+            .await;
+            let score = report
+                .detectors
+                .iter()
+                .find(|d| d.detector == detector)
+                .context("swept detector missing from report")?;
+            points.push(SweepPoint {
+                value,
+                precision: score.precision,
+                recall: score.recall,
+                f1: score.f1,
+            });
+        }
+
+        let best = points
+            .iter()
+            .max_by(|a, b| {
+                a.f1.total_cmp(&b.f1).then_with(|| {
+                    (b.value - current)
+                        .abs()
+                        .total_cmp(&(a.value - current).abs())
+                })
+            })
+            .context("empty sweep")?;
+        let (suggested, suggested_f1) = if best.f1 > current_f1 {
+            (best.value, best.f1)
+        } else {
+            (current, current_f1)
+        };
+        config = with_threshold(&config, parameter, suggested)?;
+
+        suggestions.push(ThresholdSuggestion {
+            detector: detector.to_string(),
+            parameter: parameter.to_string(),
+            current,
+            suggested,
+            current_f1,
+            suggested_f1,
+            points,
+        });
+    }
+
+    let report = evaluate(corpus, &config, embedder).await;
+    Ok(SweepOutcome {
+        suggestions,
+        config,
+        report,
+    })
+}
+
+fn threshold(config: &MonitoringConfig, parameter: &str) -> Result<f64> {
+    serde_json::to_value(config)?[parameter]
+        .as_f64()
+        .with_context(|| format!("unknown threshold {}", parameter))
+}
+
+fn with_threshold(
+    config: &MonitoringConfig,
+    parameter: &str,
+    value: f64,
+) -> Result<MonitoringConfig> {
+    let overrides: MonitoringOverrides = serde_json::from_value(json!({ parameter: value }))?;
+    Ok(config.with_overrides(&overrides))
+}
+
+/// `[monitoring]` lines for the thresholds a sweep changed
+pub fn suggested_overrides(outcome: &SweepOutcome) -> Vec<String> {
+    outcome
+        .suggestions
+        .iter()
+        .filter(|s| s.suggested != s.current)
+        .map(|s| format!("{} = {}", s.parameter, Value::from(s.suggested)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confusion_scores() {
+        let mut confusion = Confusion::default();
+        for (expected, triggered) in [(true, true), (true, false), (false, true), (false, false)] {
+            confusion.record(expected, triggered);
+        }
+        confusion.record(true, true);
+        assert!((confusion.precision() - 2.0 / 3.0).abs() < 1e-9);
+        assert!((confusion.recall() - 2.0 / 3.0).abs() < 1e-9);
+        assert!((confusion.f1() - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(Confusion::default().f1(), 0.0);
+    }
+
+    #[test]
+    fn test_corpus_rejects_unknown_labels() {
+        let line = r#"{"id": "a", "thoughts": [{"text": "x", "expect": ["circular"]}]}"#;
+        let err = parse_corpus(line).unwrap_err();
+        assert!(format!("{:#}", err).contains("unknown detector 'circular'"));
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_scores_repetition() {
+        let corpus = parse_corpus(
+            r#"{"id": "loop", "thoughts": [
+                {"text": "Understanding recursion requires understanding recursion"},
+                {"text": "To understand recursion, you must understand recursion", "expect": ["circular_reasoning"]}
+            ]}"#
+            .replace('\n', " ")
+            .as_str(),
+        )
+        .unwrap();
+        let embedder = Arc::new(Embedder::local());
+        let report = evaluate(&corpus, &MonitoringConfig::default(), &embedder).await;
+
+        assert_eq!(report.thoughts, 2);
+        let circular = &report.detectors[0];
+        assert_eq!(circular.detector, CIRCULAR_REASONING);
+        assert_eq!(circular.confusion.true_positives, 1);
+        assert_eq!(circular.confusion.false_positives, 0);
+    }
+}
@@ -0,0 +1,209 @@
+//! Change-point detection over quality metric series.
+//!
+//! A linear trend over the last few thoughts smooths a sudden cliff into a
+//! gentle slope and reacts to single noisy values. Here a one-sided CUSUM
+//! raises the alarm once low values have accumulated, and Bayesian online
+//! change-point detection (Adams & MacKay, 2007) locates the step where the
+//! series moved to a new level. A drop is only reported when both agree.
+
+/// Allowed downward deviation per value, in noise units, before CUSUM accumulates
+const CUSUM_SLACK: f64 = 0.5;
+/// Prior probability that any value starts a new segment
+const HAZARD: f64 = 0.1;
+/// Noise scale used when the series barely moves
+const MIN_NOISE: f64 = 0.02;
+/// Posterior probability the located change point needs
+const MIN_POSTERIOR: f64 = 0.5;
+/// Values after the change needed to tell a new level from one bad value
+const MIN_AFTER: usize = 2;
+/// Smallest drop in level (metrics are in [0, 1]) worth reporting
+const MIN_DROP: f64 = 0.15;
+
+/// Where a series dropped to a lower level
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangePoint {
+    /// Index of the first value at the new level
+    pub index: usize,
+    /// Mean level before and after the change
+    pub before: f64,
+    pub after: f64,
+    /// Posterior probability of the change at `index`
+    pub probability: f64,
+}
+
+/// Find a sustained drop in `values`. The first `reference_len` values set the
+/// expected level; `threshold` is the CUSUM decision threshold in noise units.
+pub fn detect_drop(values: &[f64], reference_len: usize, threshold: f64) -> Option<ChangePoint> {
+    let reference_len = reference_len.max(2);
+    if values.len() < reference_len + MIN_AFTER {
+        return None;
+    }
+
+    let noise = noise_scale(values);
+    let alarm_start = cusum_alarm(values, reference_len, noise, threshold)?;
+    let (index, probability) = bayesian_change_point(values, noise)?;
+    if index < alarm_start || probability < MIN_POSTERIOR || values.len() - index < MIN_AFTER {
+        return None;
+    }
+
+    // A steady decline also ends up at a lower level; a sudden drop gets
+    // there in one step
+    let before = mean(&values[..index]);
+    let after = mean(&values[index..]);
+    if before - after < MIN_DROP || values[index - 1] - values[index] < MIN_DROP {
+        return None;
+    }
+
+    Some(ChangePoint {
+        index,
+        before,
+        after,
+        probability,
+    })
+}
+
+/// Start of the CUSUM excursion that is raising an alarm at the end of
+/// `values`, if any. Each value adds at most half the threshold, so a single
+/// outlier cannot raise the alarm alone.
+fn cusum_alarm(values: &[f64], reference_len: usize, noise: f64, threshold: f64) -> Option<usize> {
+    let reference = mean(&values[..reference_len]);
+    let mut sum = 0.0;
+    let mut start = reference_len;
+    let mut alarm = None;
+
+    for (i, &value) in values.iter().enumerate().skip(reference_len) {
+        let deviation = ((reference - value) / noise - CUSUM_SLACK).min(threshold / 2.0);
+        sum = (sum + deviation).max(0.0);
+        if sum == 0.0 {
+            start = i + 1;
+            alarm = None;
+        } else if sum >= threshold {
+            alarm = Some(start);
+        }
+    }
+
+    alarm
+}
+
+/// Most probable start of the current segment and its posterior probability,
+/// or `None` when the whole series is most likely one segment. Values are
+/// Gaussian around a segment mean with known noise; each segment mean has a
+/// Gaussian prior centred on the series mean.
+fn bayesian_change_point(values: &[f64], noise: f64) -> Option<(usize, f64)> {
+    struct Run {
+        probability: f64,
+        /// Posterior over the segment mean
+        mean: f64,
+        variance: f64,
+    }
+
+    let noise_variance = noise * noise;
+    let prior_mean = mean(values);
+    let prior_variance = values.iter().map(|v| (v - prior_mean).powi(2)).sum::<f64>()
+        / values.len() as f64
+        + noise_variance;
+    let update = |mean: f64, variance: f64, value: f64| {
+        let posterior = 1.0 / (1.0 / variance + 1.0 / noise_variance);
+        (
+            posterior * (mean / variance + value / noise_variance),
+            posterior,
+        )
+    };
+
+    // runs[i] is the segment of the last i + 1 values
+    let mut runs: Vec<Run> = Vec::with_capacity(values.len());
+    for &value in values {
+        let (mean, variance) = update(prior_mean, prior_variance, value);
+        let new_run = if runs.is_empty() { 1.0 } else { 0.0 };
+        let mut next = vec![Run {
+            probability: new_run,
+            mean,
+            variance,
+        }];
+
+        let fresh = gaussian(value, prior_mean, prior_variance + noise_variance);
+        for run in &runs {
+            let grown = gaussian(value, run.mean, run.variance + noise_variance);
+            next[0].probability += run.probability * HAZARD * fresh;
+            let (mean, variance) = update(run.mean, run.variance, value);
+            next.push(Run {
+                probability: run.probability * (1.0 - HAZARD) * grown,
+                mean,
+                variance,
+            });
+        }
+
+        let total: f64 = next.iter().map(|r| r.probability).sum();
+        if total > 0.0 {
+            next.iter_mut().for_each(|r| r.probability /= total);
+        }
+        runs = next;
+    }
+
+    let (run, probability) = runs
+        .iter()
+        .enumerate()
+        .map(|(i, r)| (i, r.probability))
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+    let index = values.len() - (run + 1);
+    (index > 0).then_some((index, probability))
+}
+
+/// Robust noise estimate from successive differences; a single step barely
+/// moves their median
+fn noise_scale(values: &[f64]) -> f64 {
+    let mut differences: Vec<f64> = values.windows(2).map(|w| (w[1] - w[0]).abs()).collect();
+    if differences.is_empty() {
+        return MIN_NOISE;
+    }
+    differences.sort_by(f64::total_cmp);
+    let median = differences[differences.len() / 2];
+
+    // Median absolute difference of two Gaussian values is 0.954 sigma
+    (median / 0.954).max(MIN_NOISE)
+}
+
+fn gaussian(x: f64, mean: f64, variance: f64) -> f64 {
+    (-(x - mean).powi(2) / (2.0 * variance)).exp() / (2.0 * std::f64::consts::PI * variance).sqrt()
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len().max(1) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOISY_LEVEL: [f64; 6] = [0.72, 0.78, 0.70, 0.76, 0.74, 0.69];
+
+    #[test]
+    fn test_sudden_drop_is_located() {
+        let mut values = NOISY_LEVEL.to_vec();
+        values.extend([0.31, 0.28, 0.35]);
+
+        let change = detect_drop(&values, 3, 4.0).unwrap();
+        assert_eq!(change.index, 6);
+        assert!(change.before > 0.7 && change.after < 0.35);
+    }
+
+    #[test]
+    fn test_noise_and_single_outliers_are_ignored() {
+        assert!(detect_drop(&NOISY_LEVEL, 3, 4.0).is_none());
+
+        let mut values = NOISY_LEVEL.to_vec();
+        values.extend([0.2, 0.75, 0.71]);
+        assert!(detect_drop(&values, 3, 4.0).is_none());
+
+        // Wait for a second low value before calling it a new level
+        let mut values = NOISY_LEVEL.to_vec();
+        values.push(0.3);
+        assert!(detect_drop(&values, 3, 4.0).is_none());
+    }
+
+    #[test]
+    fn test_gradual_decline_is_not_a_sudden_drop() {
+        let values: Vec<f64> = (0..10).map(|i| 0.8 - 0.04 * i as f64).collect();
+        assert!(detect_drop(&values, 3, 4.0).is_none());
+    }
+}
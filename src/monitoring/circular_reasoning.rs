@@ -1,5 +1,11 @@
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
-use unicode_segmentation::UnicodeSegmentation;
+use std::sync::Arc;
+use tracing::warn;
+
+use crate::monitoring::language::{is_meaningful_word, Language};
+use crate::monitoring::lexicon::Lexicon;
+use crate::monitoring::MonitoringConfig;
 
 /// Advanced circular reasoning detection using TF-IDF and concept extraction
 pub struct CircularReasoningDetector {
@@ -7,74 +13,59 @@ pub struct CircularReasoningDetector {
     concept_index: ConceptIndex,
     /// Minimum similarity threshold for circular detection
     similarity_threshold: f64,
+    /// Domain terms recognized as concepts
+    lexicon: Arc<Lexicon>,
 }
 
 impl CircularReasoningDetector {
     pub fn new() -> Self {
+        Self::with_threshold(0.6) // Adjusted for semantic similarity detection
+    }
+
+    pub fn with_threshold(similarity_threshold: f64) -> Self {
         Self {
             concept_index: ConceptIndex::new(),
-            similarity_threshold: 0.6, // Adjusted for semantic similarity detection
+            similarity_threshold,
+            lexicon: Lexicon::load(&[]).unwrap_or_default(),
+        }
+    }
+
+    /// Take thresholds and lexicons from the monitoring config. A lexicon that
+    /// fails to load leaves the built-in one in place.
+    pub fn configure(&mut self, config: &MonitoringConfig) {
+        self.similarity_threshold = config.circular_similarity_threshold;
+        match Lexicon::load(&config.lexicons) {
+            Ok(lexicon) => self.lexicon = lexicon,
+            Err(e) => warn!("Keeping the built-in lexicon: {:#}", e),
         }
     }
 
-    /// Extract key concepts from text using NLP-like techniques
+    /// Domain terms in use
+    pub fn lexicon(&self) -> &Lexicon {
+        &self.lexicon
+    }
+
+    /// Extract key concepts from text using NLP-like techniques. Tokens, stems and
+    /// stop words follow the detected language; the phrase patterns below are
+    /// English and simply don't match elsewhere. Lexicon terms are concepts even
+    /// when short (`k8s`), and synonyms come out as the same concept.
     pub fn extract_concepts(&self, text: &str) -> Vec<Concept> {
-        let tokens = self.tokenize(text);
+        let language = Language::detect(text);
+        let tokens = language.tokenize(text);
         let mut concepts = Vec::new();
 
         // Extract noun phrases (simplified without true POS tagging)
         let noun_indicators = [
             "the", "a", "an", "this", "that", "these", "those", "how", "what", "why",
         ];
-        let important_words = self.identify_important_words(&tokens);
+        let important_words = self.identify_important_words(&tokens, language);
 
         // Extract multi-word concepts first (bigrams/trigrams) - prioritize compound concepts
         for i in 0..tokens.len().saturating_sub(1) {
-            // Look for common compound concepts
-            if i + 1 < tokens.len() {
-                let bigram = format!("{} {}", tokens[i], tokens[i + 1]);
-                let bigram_lower = bigram.to_lowercase();
-
-                // Check for known compound terms
-                let compound_terms = [
-                    "neural network",
-                    "deep learning",
-                    "machine learning",
-                    "gradient descent",
-                    "back propagation",
-                    "chain rule",
-                    "learning rate",
-                    "activation function",
-                    "loss function",
-                    "weight update",
-                    "error minimization",
-                    "feature extraction",
-                ];
-
-                for compound in &compound_terms {
-                    if bigram_lower.contains(compound) || compound.contains(&bigram_lower) {
-                        concepts.push(Concept::Compound(bigram.clone()));
-                        break;
-                    }
-                }
-
-                // Also check if two adjacent meaningful words form a concept
-                if self.is_meaningful_concept(&tokens[i])
-                    && self.is_meaningful_concept(&tokens[i + 1])
-                {
-                    // Check if they commonly go together (simple heuristic)
-                    if (tokens[i] == "neural" && tokens[i + 1] == "network")
-                        || (tokens[i] == "neural" && tokens[i + 1] == "networks")
-                        || (tokens[i] == "back" && tokens[i + 1] == "propagation")
-                        || (tokens[i] == "machine" && tokens[i + 1] == "learning")
-                    {
-                        let compound = format!(
-                            "{} {}",
-                            self.basic_stem(&tokens[i]),
-                            self.basic_stem(&tokens[i + 1])
-                        );
-                        concepts.push(Concept::Compound(compound));
-                    }
+            // Multi-word terms of the lexicons, in their reported spelling
+            if let Some((term, len)) = self.lexicon.term_at(&tokens[i..]) {
+                if len > 1 {
+                    concepts.push(Concept::Compound(term.to_string()));
                 }
             }
 
@@ -87,9 +78,9 @@ impl CircularReasoningDetector {
                 {
                     let compound = format!(
                         "{} {} {}",
-                        self.basic_stem(&tokens[i]),
+                        language.stem(&tokens[i]),
                         tokens[i + 1],
-                        self.basic_stem(&tokens[i + 2])
+                        language.stem(&tokens[i + 2])
                     );
                     concepts.push(Concept::Compound(compound));
                 }
@@ -98,9 +89,9 @@ impl CircularReasoningDetector {
 
         // Extract single-word concepts with stemming (after compounds to avoid duplication)
         for word in &important_words {
-            if self.is_meaningful_concept(word) {
+            if self.is_meaningful_concept(word) || self.lexicon.contains_term(word) {
                 // Skip if already part of a compound concept
-                let word_stem = self.basic_stem(word);
+                let word_stem = self.lexicon.canonicalize(&language.stem(word));
                 let already_in_compound = concepts.iter().any(|c| match c {
                     Concept::Compound(s) | Concept::Phrase(s) => s.contains(&word_stem),
                     _ => false,
@@ -117,7 +108,7 @@ impl CircularReasoningDetector {
             if noun_indicators.contains(&tokens[i].as_str())
                 && self.is_meaningful_concept(&tokens[i + 1])
             {
-                let phrase = format!("{} {}", tokens[i], self.basic_stem(&tokens[i + 1]));
+                let phrase = format!("{} {}", tokens[i], language.stem(&tokens[i + 1]));
                 concepts.push(Concept::Phrase(phrase));
             }
         }
@@ -141,12 +132,12 @@ impl CircularReasoningDetector {
             "improve",
         ];
         for (i, token) in tokens.iter().enumerate() {
-            let stemmed_verb = self.basic_stem(token);
+            let stemmed_verb = language.stem(token);
             if action_verbs.contains(&token.as_str())
                 || action_verbs.contains(&stemmed_verb.as_str())
             {
                 if i + 1 < tokens.len() && self.is_meaningful_concept(&tokens[i + 1]) {
-                    let action = format!("{} {}", stemmed_verb, self.basic_stem(&tokens[i + 1]));
+                    let action = format!("{} {}", stemmed_verb, language.stem(&tokens[i + 1]));
                     concepts.push(Concept::Action(action));
                 }
             }
@@ -179,18 +170,28 @@ impl CircularReasoningDetector {
         self.cosine_similarity(&tf1, &tf2)
     }
 
-    /// Detect circular reasoning patterns
+    /// Detect circular reasoning patterns using concept TF-IDF similarity
     pub fn detect_pattern(
         &self,
         current_thought: &str,
         thought_history: &[String],
     ) -> CircularPattern {
-        let current_concepts = self.extract_concepts(current_thought);
+        self.detect_pattern_with(current_thought, thought_history, |a, b| {
+            self.calculate_similarity(&self.extract_concepts(a), &self.extract_concepts(b))
+        })
+    }
 
+    /// Detect circular reasoning patterns with a caller-supplied text similarity
+    /// in [0, 1], e.g. cosine over embeddings
+    pub fn detect_pattern_with(
+        &self,
+        current_thought: &str,
+        thought_history: &[String],
+        similarity: impl Fn(&str, &str) -> f64,
+    ) -> CircularPattern {
         // Check for direct repetition
         for (idx, past_thought) in thought_history.iter().enumerate() {
-            let past_concepts = self.extract_concepts(past_thought);
-            let similarity = self.calculate_similarity(&current_concepts, &past_concepts);
+            let similarity = similarity(current_thought, past_thought);
 
             if similarity >= self.similarity_threshold {
                 return CircularPattern::Direct {
@@ -202,7 +203,7 @@ impl CircularReasoningDetector {
 
         // Check for cyclic patterns (A→B→C→A)
         if thought_history.len() >= 3 {
-            let cycle = self.detect_cyclic_pattern(&current_concepts, thought_history);
+            let cycle = self.detect_cyclic_pattern(current_thought, thought_history, &similarity);
             if let Some(pattern) = cycle {
                 return pattern;
             }
@@ -210,7 +211,7 @@ impl CircularReasoningDetector {
 
         // Check for conceptual loops (same concepts, different words)
         let conceptual_similarity =
-            self.detect_conceptual_loops(&current_concepts, thought_history);
+            self.detect_conceptual_loops(current_thought, thought_history, &similarity);
         if conceptual_similarity > 0.7 {
             return CircularPattern::Conceptual {
                 average_similarity: conceptual_similarity,
@@ -220,66 +221,18 @@ impl CircularReasoningDetector {
         CircularPattern::None
     }
 
-    /// Tokenize text into words
-    fn tokenize(&self, text: &str) -> Vec<String> {
-        text.unicode_words()
-            .map(|w| w.to_lowercase())
-            .filter(|w| w.len() > 2)
-            .collect()
-    }
-
-    /// Basic stemming for common English suffixes
-    fn basic_stem(&self, word: &str) -> String {
-        let word_lower = word.to_lowercase();
-
-        // Handle common verb forms
-        if word_lower.ends_with("ing") && word_lower.len() > 5 {
-            // understanding -> understand
-            if word_lower.ends_with("ding") {
-                return word_lower[..word_lower.len() - 3].to_string();
-            }
-            return word_lower[..word_lower.len() - 3].to_string();
-        }
-
-        if word_lower.ends_with("ed") && word_lower.len() > 4 {
-            return word_lower[..word_lower.len() - 2].to_string();
-        }
-
-        if word_lower.ends_with("es") && word_lower.len() > 4 {
-            // requires -> require
-            return word_lower[..word_lower.len() - 1].to_string();
-        }
-
-        if word_lower.ends_with("s") && word_lower.len() > 3 && !word_lower.ends_with("ss") {
-            return word_lower[..word_lower.len() - 1].to_string();
-        }
-
-        word_lower
-    }
-
     /// Identify important words (remove stop words)
-    fn identify_important_words(&self, tokens: &[String]) -> Vec<String> {
-        let stop_words: HashSet<&str> = [
-            "the", "a", "an", "and", "or", "but", "in", "on", "at", "to", "for", "of", "with",
-            "by", "from", "up", "about", "into", "through", "during", "before", "after", "above",
-            "below", "between", "under", "is", "are", "was", "were", "been", "be", "have", "has",
-            "had", "do", "does", "did", "will", "would", "should", "could", "may", "might", "must",
-            "can", "could",
-        ]
-        .iter()
-        .cloned()
-        .collect();
-
+    fn identify_important_words(&self, tokens: &[String], language: Language) -> Vec<String> {
         tokens
             .iter()
-            .filter(|word| !stop_words.contains(word.as_str()))
+            .filter(|word| !language.is_stop_word(word))
             .cloned()
             .collect()
     }
 
     /// Check if a word is a meaningful concept
     fn is_meaningful_concept(&self, word: &str) -> bool {
-        word.len() > 3 && word.chars().all(|c| c.is_alphabetic())
+        is_meaningful_word(word)
     }
 
     /// Check if a phrase is meaningful
@@ -331,25 +284,23 @@ impl CircularReasoningDetector {
     /// Detect cyclic patterns in thought history
     fn detect_cyclic_pattern(
         &self,
-        current_concepts: &[Concept],
+        current_thought: &str,
         history: &[String],
+        similarity: &impl Fn(&str, &str) -> f64,
     ) -> Option<CircularPattern> {
         if history.len() < 3 {
             return None;
         }
 
         // Check if current thought completes a cycle
-        let first_concepts = self.extract_concepts(&history[0]);
-        let similarity_to_first = self.calculate_similarity(current_concepts, &first_concepts);
+        let similarity_to_first = similarity(current_thought, &history[0]);
 
         if similarity_to_first > 0.7 {
             // Verify intermediate steps are different
-            let mut intermediate_similarities = Vec::new();
-            for i in 1..history.len() {
-                let concepts = self.extract_concepts(&history[i]);
-                let sim = self.calculate_similarity(&concepts, &first_concepts);
-                intermediate_similarities.push(sim);
-            }
+            let intermediate_similarities: Vec<f64> = history[1..]
+                .iter()
+                .map(|thought| similarity(thought, &history[0]))
+                .collect();
 
             // If intermediate steps are sufficiently different, we have a cycle
             let avg_intermediate = intermediate_similarities.iter().sum::<f64>()
@@ -366,7 +317,13 @@ impl CircularReasoningDetector {
     }
 
     /// Detect conceptual loops (same ideas, different words)
-    fn detect_conceptual_loops(&self, current_concepts: &[Concept], history: &[String]) -> f64 {
+    fn detect_conceptual_loops(
+        &self,
+        current_thought: &str,
+        history: &[String],
+        similarity: &impl Fn(&str, &str) -> f64,
+    ) -> f64 {
+        let current_concepts = self.extract_concepts(current_thought);
         if history.is_empty() || current_concepts.is_empty() {
             return 0.0;
         }
@@ -381,10 +338,10 @@ impl CircularReasoningDetector {
             }
 
             // Calculate actual semantic similarity, not just distribution
-            let semantic_sim = self.calculate_similarity(current_concepts, &past_concepts);
+            let semantic_sim = similarity(current_thought, past_thought);
 
             // Also consider concept type distribution
-            let current_distribution = self.build_concept_distribution(current_concepts);
+            let current_distribution = self.build_concept_distribution(&current_concepts);
             let past_distribution = self.build_concept_distribution(&past_concepts);
             let distribution_sim =
                 self.compare_distributions(&current_distribution, &past_distribution);
@@ -437,7 +394,7 @@ impl CircularReasoningDetector {
 }
 
 /// Represents different types of concepts extracted from text
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Concept {
     Single(String),   // Single word concept
     Phrase(String),   // Multi-word phrase
@@ -0,0 +1,375 @@
+use std::fmt;
+
+/// A simple assertion pulled out of one sentence: "<subject> <verb> [not] <predicate>"
+#[derive(Debug, Clone, PartialEq)]
+pub struct Claim {
+    /// Last content words before the verb, lowercased ("connection pool")
+    pub subject: String,
+    /// Verb normalized to its class ("be", "can", "do", ...)
+    pub verb: String,
+    /// First content words after the verb ("thread-safe"), without any number
+    pub predicate: String,
+    pub negated: bool,
+    /// Number asserted by the claim, with the word after it as unit
+    pub value: Option<f64>,
+    pub unit: Option<String>,
+    /// Sentence the claim came from, for reporting
+    pub sentence: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContradictionKind {
+    /// Same statement asserted and denied
+    Polarity,
+    /// Same quantity given different values
+    Numeric,
+}
+
+/// Conflict between a claim in the current thought and one from an earlier thought
+#[derive(Debug, Clone)]
+pub struct Contradiction {
+    pub kind: ContradictionKind,
+    pub earlier_step: u32,
+    pub later_step: u32,
+    pub earlier: Claim,
+    pub later: Claim,
+}
+
+impl fmt::Display for Contradiction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let what = match self.kind {
+            ContradictionKind::Polarity => "contradicts",
+            ContradictionKind::Numeric => "gives a different value than",
+        };
+        write!(
+            f,
+            "Step {} {} step {}: \"{}\" vs \"{}\"",
+            self.later_step, what, self.earlier_step, self.later.sentence, self.earlier.sentence
+        )
+    }
+}
+
+/// Verbs a claim can hang on, mapped to a class and whether the form is negated
+const VERBS: &[(&str, &str, bool)] = &[
+    ("is", "be", false),
+    ("are", "be", false),
+    ("was", "be", false),
+    ("were", "be", false),
+    ("isn't", "be", true),
+    ("aren't", "be", true),
+    ("wasn't", "be", true),
+    ("weren't", "be", true),
+    ("can", "can", false),
+    ("cannot", "can", true),
+    ("can't", "can", true),
+    ("does", "do", false),
+    ("do", "do", false),
+    ("doesn't", "do", true),
+    ("don't", "do", true),
+    ("will", "will", false),
+    ("won't", "will", true),
+    ("has", "have", false),
+    ("have", "have", false),
+    ("hasn't", "have", true),
+    ("haven't", "have", true),
+    ("should", "should", false),
+    ("shouldn't", "should", true),
+    ("must", "must", false),
+    ("uses", "use", false),
+    ("takes", "take", false),
+    ("requires", "require", false),
+    ("returns", "return", false),
+];
+
+const DETERMINERS: &[&str] = &[
+    "the", "a", "an", "our", "your", "its", "their", "each", "every", "all",
+];
+
+/// Subjects that refer back to something else and can't be matched across thoughts
+const PRONOUNS: &[&str] = &[
+    "it", "this", "that", "these", "those", "they", "he", "she", "we", "i", "you", "which", "what",
+    "who", "there", "here",
+];
+
+/// Words that end the predicate: what follows qualifies the claim. Clause
+/// punctuation is tokenized as ",".
+const CLAUSE_BREAKS: &[&str] = &[
+    ",", "when", "if", "unless", "under", "because", "since", "but", "and", "or", "which", "that",
+    "while", "so", "although", "though", "for", "with", "without", "in", "on", "at", "by", "as",
+];
+
+/// Sentences containing these are hedged or hypothetical and make no claim
+const HEDGES: &[&str] = &[
+    "might",
+    "may",
+    "could",
+    "would",
+    "perhaps",
+    "maybe",
+    "possibly",
+    "probably",
+    "assume",
+    "assuming",
+    "suppose",
+    "hypothetically",
+    "whether",
+];
+
+/// Relative difference below which two numbers count as the same value
+const NUMERIC_TOLERANCE: f64 = 0.01;
+
+/// Extract the claims made by a thought, at most one per sentence
+pub fn extract_claims(text: &str) -> Vec<Claim> {
+    split_sentences(text)
+        .into_iter()
+        .filter_map(claim_from_sentence)
+        .collect()
+}
+
+/// Find the first claim in `thought` that conflicts with a claim from an earlier step.
+/// The most recent conflicting step wins.
+pub fn find_contradiction<'a>(
+    thought: &str,
+    step: u32,
+    history: impl DoubleEndedIterator<Item = (u32, &'a str)>,
+) -> Option<Contradiction> {
+    let claims = extract_claims(thought);
+    if claims.is_empty() {
+        return None;
+    }
+
+    for (earlier_step, earlier_text) in history.rev() {
+        if earlier_step >= step {
+            continue;
+        }
+        for earlier in extract_claims(earlier_text) {
+            for later in &claims {
+                if let Some(kind) = conflict(&earlier, later) {
+                    return Some(Contradiction {
+                        kind,
+                        earlier_step,
+                        later_step: step,
+                        earlier,
+                        later: later.clone(),
+                    });
+                }
+            }
+        }
+    }
+    None
+}
+
+fn conflict(a: &Claim, b: &Claim) -> Option<ContradictionKind> {
+    if a.subject != b.subject || a.verb != b.verb || a.predicate != b.predicate {
+        return None;
+    }
+
+    match (a.value, b.value) {
+        (None, None) if a.negated != b.negated => Some(ContradictionKind::Polarity),
+        (Some(x), Some(y)) if !a.negated && !b.negated && a.unit == b.unit => {
+            let scale = x.abs().max(y.abs()).max(f64::EPSILON);
+            ((x - y).abs() / scale > NUMERIC_TOLERANCE).then_some(ContradictionKind::Numeric)
+        }
+        _ => None,
+    }
+}
+
+fn split_sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+
+    for (i, &(pos, c)) in chars.iter().enumerate() {
+        let next = chars.get(i + 1).map(|&(_, n)| n);
+        // A period only ends a sentence before whitespace, so "3.5" and "std::sync" stay intact
+        let ends = match c {
+            '\n' | '!' | '?' | ';' => true,
+            '.' => next.is_none_or(char::is_whitespace),
+            _ => false,
+        };
+        if ends {
+            sentences.push(&text[start..pos + c.len_utf8()]);
+            start = pos + c.len_utf8();
+        }
+    }
+    sentences.push(&text[start..]);
+
+    sentences
+        .into_iter()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+fn tokenize(sentence: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    for raw in sentence.split_whitespace() {
+        let word = raw
+            .trim_matches(|c: char| !(c.is_alphanumeric() || c == '%'))
+            .to_lowercase()
+            // Typographic apostrophes in contractions
+            .replace('\u{2019}', "'");
+        if !word.is_empty() {
+            tokens.push(word);
+        }
+        if raw.ends_with([',', ':']) {
+            tokens.push(",".to_string());
+        }
+    }
+    tokens
+}
+
+fn claim_from_sentence(sentence: &str) -> Option<Claim> {
+    if sentence.ends_with('?') {
+        return None;
+    }
+    let tokens = tokenize(sentence);
+    if tokens.iter().any(|t| HEDGES.contains(&t.as_str())) {
+        return None;
+    }
+
+    let (verb_at, verb, mut negated) = tokens.iter().enumerate().find_map(|(i, t)| {
+        VERBS
+            .iter()
+            .find(|(form, _, _)| form == t)
+            .map(|&(_, class, negated)| (i, class, negated))
+    })?;
+
+    // The subject starts after any leading clause ("Looking closer, the cache is ...")
+    let clause_start = tokens[..verb_at]
+        .iter()
+        .rposition(|t| CLAUSE_BREAKS.contains(&t.as_str()))
+        .map_or(0, |i| i + 1);
+    let subject: Vec<&str> = tokens[clause_start..verb_at]
+        .iter()
+        .map(String::as_str)
+        .filter(|t| !DETERMINERS.contains(t))
+        .collect();
+    let subject_words = &subject[subject.len().saturating_sub(2)..];
+    if subject_words.is_empty() || subject_words.iter().any(|t| PRONOUNS.contains(t)) {
+        return None;
+    }
+
+    let mut rest = tokens[verb_at + 1..].iter().map(String::as_str).peekable();
+    if let Some(&word) = rest.peek() {
+        if matches!(word, "not" | "never" | "no") {
+            negated = !negated;
+            rest.next();
+        }
+    }
+    // "not only ... but also" asserts rather than denies
+    if negated && rest.peek() == Some(&"only") {
+        return None;
+    }
+
+    let mut predicate = Vec::new();
+    let mut value = None;
+    let mut unit = None;
+    let mut words = rest.take_while(|t| !CLAUSE_BREAKS.contains(t));
+    while let Some(word) = words.next() {
+        if value.is_none() {
+            if let Some((number, attached_unit)) = parse_number(word) {
+                value = Some(number);
+                unit = attached_unit.or_else(|| words.next().map(str::to_string));
+                break;
+            }
+        }
+        if DETERMINERS.contains(&word) {
+            continue;
+        }
+        predicate.push(word);
+        if predicate.len() == 2 {
+            break;
+        }
+    }
+    if predicate.is_empty() && value.is_none() {
+        return None;
+    }
+
+    Some(Claim {
+        subject: subject_words.join(" "),
+        verb: verb.to_string(),
+        predicate: predicate.join(" "),
+        negated,
+        value,
+        unit,
+        sentence: sentence.to_string(),
+    })
+}
+
+/// "30", "1,024", "2.5" or a number with a unit attached ("30ms", "40%")
+fn parse_number(word: &str) -> Option<(f64, Option<String>)> {
+    let split = word
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+        .unwrap_or(word.len());
+    let (digits, suffix) = word.split_at(split);
+    if !digits.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let number = digits.replace(',', "").parse().ok()?;
+    let unit = (!suffix.is_empty()).then(|| suffix.to_string());
+    Some((number, unit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polarity_contradiction_cites_both_steps() {
+        let history = [
+            (1, "Beginning analysis of the cache."),
+            (
+                2,
+                "The cache is thread-safe because every access takes the lock.",
+            ),
+            (3, "Eviction runs on a background task."),
+        ];
+        let contradiction = find_contradiction(
+            "Looking closer, the cache is not thread-safe when eviction runs.",
+            4,
+            history.into_iter(),
+        )
+        .unwrap();
+
+        assert_eq!(contradiction.kind, ContradictionKind::Polarity);
+        assert_eq!(contradiction.earlier_step, 2);
+        assert_eq!(contradiction.later_step, 4);
+        assert!(contradiction
+            .to_string()
+            .starts_with("Step 4 contradicts step 2"));
+    }
+
+    #[test]
+    fn test_numeric_contradiction() {
+        let history = [(2, "The default timeout is 30 seconds.")];
+        let contradiction = find_contradiction(
+            "So the default timeout is 60 seconds.",
+            5,
+            history.into_iter(),
+        )
+        .unwrap();
+        assert_eq!(contradiction.kind, ContradictionKind::Numeric);
+
+        assert!(
+            find_contradiction("The default timeout is 30 seconds.", 5, history.into_iter())
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_hedged_and_unrelated_claims_are_ignored() {
+        let history = [(1, "The parser is recursive.")];
+        for thought in [
+            "The parser might not be recursive.",
+            "Is the parser not recursive?",
+            "The lexer is not recursive.",
+            "It is not recursive.",
+        ] {
+            assert!(
+                find_contradiction(thought, 2, history.into_iter()).is_none(),
+                "{thought}"
+            );
+        }
+    }
+}
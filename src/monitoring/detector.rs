@@ -0,0 +1,695 @@
+//! Detector plug-in point for the metacognitive monitor.
+//!
+//! Every detector sees each thought together with the recent history and
+//! returns a scored [`DetectorSignal`]. The monitor runs all registered
+//! detectors and raises the intervention of the highest-priority signal that
+//! triggered. Domain detectors are added by implementing [`ThoughtDetector`]
+//! and registering it on a monitor, or on the `SessionManager` so every new
+//! session gets one.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use super::circular_reasoning::{CircularPattern, CircularReasoningDetector};
+use super::distractor_fixation::DistractorFixationDetector;
+use super::grounding::GroundingSources;
+use super::quality_degradation::{
+    DegradationAnalysis, DegradationPattern, QualityDegradationDetector,
+};
+use super::session_memory::{SessionMemory, ThoughtFingerprint};
+use super::MonitoringConfig;
+use crate::llm::embeddings::Embedder;
+
+pub const CIRCULAR_REASONING: &str = "circular_reasoning";
+pub const DISTRACTOR_FIXATION: &str = "distractor_fixation";
+pub const QUALITY_DEGRADATION: &str = "quality_degradation";
+pub const GROUNDING: &str = "grounding";
+
+/// The thought being analyzed and what came before it
+pub struct ThoughtContext<'a> {
+    pub thought: &'a str,
+    pub thought_number: usize,
+    /// Earlier thoughts of the session, oldest first (bounded window)
+    pub history: &'a [String],
+    /// Semantic similarity between texts
+    pub embedder: &'a Embedder,
+    /// Fingerprints of every earlier thought of the session, beyond `history`
+    pub memory: &'a SessionMemory,
+    /// Fingerprint of this thought
+    pub fingerprint: &'a ThoughtFingerprint,
+    /// Files the session was given; empty when the tool got no `file_paths`
+    pub sources: &'a GroundingSources,
+}
+
+/// One detector's verdict on one thought
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectorSignal {
+    /// Registry name of the detector
+    pub detector: String,
+    /// Strength of the signal, 0.0 (nothing found) to 1.0
+    pub score: f64,
+    /// Whether the detector asks for an intervention
+    pub triggered: bool,
+    /// Higher priority wins when several detectors trigger on the same thought
+    pub priority: i32,
+    /// Reasoning phase reported while this intervention is active
+    pub phase: Option<String>,
+    /// Type recorded in the intervention history
+    pub intervention_type: Option<String>,
+    /// Guidance shown to the reasoner
+    pub message: Option<String>,
+    /// Why the detector triggered
+    pub reason: Option<String>,
+    /// Detector-specific data
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    pub details: Value,
+}
+
+impl DetectorSignal {
+    /// A signal that doesn't ask for an intervention
+    pub fn quiet(detector: &str, score: f64) -> Self {
+        Self {
+            detector: detector.to_string(),
+            score,
+            triggered: false,
+            priority: 0,
+            phase: None,
+            intervention_type: None,
+            message: None,
+            reason: None,
+            details: Value::Null,
+        }
+    }
+
+    /// A signal asking for an intervention of the given type
+    pub fn intervention(
+        detector: &str,
+        score: f64,
+        priority: i32,
+        phase: &str,
+        intervention_type: &str,
+        message: impl Into<String>,
+        reason: impl Into<String>,
+    ) -> Self {
+        Self {
+            detector: detector.to_string(),
+            score,
+            triggered: true,
+            priority,
+            phase: Some(phase.to_string()),
+            intervention_type: Some(intervention_type.to_string()),
+            message: Some(message.into()),
+            reason: Some(reason.into()),
+            details: Value::Null,
+        }
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = details;
+        self
+    }
+}
+
+/// A check run on every thought of a session
+pub trait ThoughtDetector: Send {
+    /// Unique name used to enable, disable or replace the detector
+    fn name(&self) -> &str;
+
+    /// Analyze a thought. Detectors may keep per-session state.
+    fn analyze(&mut self, context: &ThoughtContext) -> DetectorSignal;
+
+    /// Forget per-session state
+    fn reset_session(&mut self) {}
+
+    /// Pick up changed thresholds, e.g. a tool call's `monitoring` overrides.
+    /// Per-session state is kept.
+    fn configure(&mut self, _config: &MonitoringConfig) {}
+
+    /// Per-session state to persist with the monitor, if the detector keeps any
+    fn snapshot(&self) -> Option<Value> {
+        None
+    }
+
+    /// Take back state produced by [`ThoughtDetector::snapshot`]
+    fn restore(&mut self, _state: Value) -> Result<()> {
+        Ok(())
+    }
+
+    /// Texts besides the history that `analyze` compares a thought with
+    fn compared_texts(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// Builds a detector for a new monitor from the session's configuration
+pub type DetectorFactory = Arc<dyn Fn(&MonitoringConfig) -> Box<dyn ThoughtDetector> + Send + Sync>;
+
+/// Ordered set of detectors run by one monitor
+#[derive(Default)]
+pub struct DetectorRegistry {
+    detectors: Vec<Box<dyn ThoughtDetector>>,
+    /// Registered detectors skipped for now (a call's `disabled_detectors`)
+    disabled: Vec<String>,
+}
+
+impl DetectorRegistry {
+    /// The built-in detectors, minus those listed in `config.disabled_detectors`
+    pub fn builtin(config: &MonitoringConfig) -> Self {
+        let mut registry = Self::default();
+        let builtins: [Box<dyn ThoughtDetector>; 4] = [
+            Box::new(CircularDetector::new(config)),
+            Box::new(DistractorDetector::new(config)),
+            Box::new(DegradationDetector::new(config)),
+            Box::new(GroundingDetector),
+        ];
+        for detector in builtins {
+            if !config
+                .disabled_detectors
+                .iter()
+                .any(|d| d == detector.name())
+            {
+                registry.register(detector);
+            }
+        }
+        registry
+    }
+
+    /// Add a detector, replacing any registered under the same name
+    pub fn register(&mut self, detector: Box<dyn ThoughtDetector>) {
+        match self.position(detector.name()) {
+            Some(index) => self.detectors[index] = detector,
+            None => self.detectors.push(detector),
+        }
+    }
+
+    /// Remove a detector by name. Returns whether it was registered.
+    #[allow(dead_code)] // Registry editing is for library users; the binary runs the built-ins
+    pub fn remove(&mut self, name: &str) -> bool {
+        let before = self.detectors.len();
+        self.detectors.retain(|d| d.name() != name);
+        self.detectors.len() != before
+    }
+
+    #[allow(dead_code)]
+    pub fn contains(&self, name: &str) -> bool {
+        self.position(name).is_some()
+    }
+
+    #[allow(dead_code)]
+    pub fn names(&self) -> Vec<&str> {
+        self.detectors.iter().map(|d| d.name()).collect()
+    }
+
+    /// Run every enabled detector on the thought, in registration order
+    pub fn analyze(&mut self, context: &ThoughtContext) -> Vec<DetectorSignal> {
+        let disabled = &self.disabled;
+        self.detectors
+            .iter_mut()
+            .filter(|detector| !disabled.iter().any(|d| d == detector.name()))
+            .map(|detector| detector.analyze(context))
+            .collect()
+    }
+
+    pub fn reset_session(&mut self) {
+        for detector in &mut self.detectors {
+            detector.reset_session();
+        }
+    }
+
+    /// Texts besides the history that the detectors compare thoughts with
+    pub fn compared_texts(&self) -> Vec<String> {
+        self.detectors
+            .iter()
+            .flat_map(|d| d.compared_texts())
+            .collect()
+    }
+
+    /// State of every detector that keeps any, by name
+    pub fn snapshot(&self) -> BTreeMap<String, Value> {
+        self.detectors
+            .iter()
+            .filter_map(|d| Some((d.name().to_string(), d.snapshot()?)))
+            .collect()
+    }
+
+    /// Restore detector state by name. State for detectors that aren't
+    /// registered is ignored.
+    pub fn restore(&mut self, states: BTreeMap<String, Value>) -> Result<()> {
+        for (name, state) in states {
+            if let Some(index) = self.position(&name) {
+                self.detectors[index]
+                    .restore(state)
+                    .with_context(|| format!("Failed to restore detector {}", name))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Pass new thresholds to every detector and skip those the config disables
+    pub fn configure(&mut self, config: &MonitoringConfig) {
+        for detector in &mut self.detectors {
+            detector.configure(config);
+        }
+        self.disabled = config.disabled_detectors.clone();
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.detectors.iter().position(|d| d.name() == name)
+    }
+}
+
+/// The triggered signal with the highest priority; the earliest registered wins ties
+pub fn strongest(signals: &[DetectorSignal]) -> Option<&DetectorSignal> {
+    signals
+        .iter()
+        .filter(|s| s.triggered)
+        .fold(None, |best: Option<&DetectorSignal>, signal| match best {
+            Some(best) if best.priority >= signal.priority => Some(best),
+            _ => Some(signal),
+        })
+}
+
+/// Repetition of earlier thoughts, directly, in cycles or conceptually
+struct CircularDetector {
+    detector: CircularReasoningDetector,
+    intervention_threshold: f64,
+    /// Fingerprint similarity at which a thought returns to one outside the window
+    long_cycle_threshold: f64,
+}
+
+impl CircularDetector {
+    fn new(config: &MonitoringConfig) -> Self {
+        let mut detector = CircularReasoningDetector::new();
+        detector.configure(config);
+        Self {
+            detector,
+            intervention_threshold: config.circular_intervention_threshold,
+            long_cycle_threshold: config.circular_similarity_threshold,
+        }
+    }
+}
+
+impl ThoughtDetector for CircularDetector {
+    fn name(&self) -> &str {
+        CIRCULAR_REASONING
+    }
+
+    fn analyze(&mut self, context: &ThoughtContext) -> DetectorSignal {
+        if context.history.is_empty() {
+            return DetectorSignal::quiet(CIRCULAR_REASONING, 0.0);
+        }
+
+        let score =
+            match self
+                .detector
+                .detect_pattern_with(context.thought, context.history, |a, b| {
+                    context.embedder.similarity(a, b)
+                }) {
+                CircularPattern::None => 0.0,
+                CircularPattern::Direct {
+                    similarity_score, ..
+                } => similarity_score,
+                // Cycles and conceptual loops are weaker evidence than direct repetition
+                CircularPattern::Cyclic { cycle_strength, .. } => cycle_strength * 0.9,
+                CircularPattern::Conceptual { average_similarity } => average_similarity * 0.8,
+            };
+
+        // Returns to thoughts older than the window count like cycles
+        let long_cycle = context.memory.find_return(
+            context.fingerprint,
+            context.history.len(),
+            self.long_cycle_threshold,
+        );
+        if let Some(cycle) = long_cycle.filter(|c| c.similarity * 0.9 > score) {
+            let score = cycle.similarity * 0.9;
+            if score > self.intervention_threshold {
+                return DetectorSignal::intervention(
+                    CIRCULAR_REASONING,
+                    score,
+                    30,
+                    "overthinking",
+                    CIRCULAR_REASONING,
+                    format!(
+                        "This returns to thought {}. Build on what was concluded since rather than starting over.",
+                        cycle.earlier_thought
+                    ),
+                    format!(
+                        "Returns to thought {} after {} thoughts (similarity {:.2})",
+                        cycle.earlier_thought, cycle.period, cycle.similarity
+                    ),
+                )
+                .with_details(serde_json::json!({
+                    "earlier_thought": cycle.earlier_thought,
+                    "period": cycle.period,
+                }));
+            }
+            return DetectorSignal::quiet(CIRCULAR_REASONING, score);
+        }
+
+        if score > self.intervention_threshold {
+            DetectorSignal::intervention(
+                CIRCULAR_REASONING,
+                score,
+                30,
+                "overthinking",
+                CIRCULAR_REASONING,
+                "Consider breaking out of this loop with a new perspective.",
+                format!("High circular score: {:.2}", score),
+            )
+        } else {
+            DetectorSignal::quiet(CIRCULAR_REASONING, score)
+        }
+    }
+
+    fn configure(&mut self, config: &MonitoringConfig) {
+        self.detector.configure(config);
+        self.intervention_threshold = config.circular_intervention_threshold;
+        self.long_cycle_threshold = config.circular_similarity_threshold;
+    }
+}
+
+/// Drift away from the original question or into excessive detail. The
+/// signal's details carry the thought's `relevance`, and when it triggers the
+/// `pattern` and the measure that tripped it.
+struct DistractorDetector {
+    detector: DistractorFixationDetector,
+}
+
+impl DistractorDetector {
+    fn new(config: &MonitoringConfig) -> Self {
+        let mut detector = DistractorFixationDetector::new();
+        detector.configure(config);
+        Self { detector }
+    }
+}
+
+impl ThoughtDetector for DistractorDetector {
+    fn name(&self) -> &str {
+        DISTRACTOR_FIXATION
+    }
+
+    fn compared_texts(&self) -> Vec<String> {
+        self.detector
+            .original_query()
+            .map(str::to_string)
+            .into_iter()
+            .collect()
+    }
+
+    fn analyze(&mut self, context: &ThoughtContext) -> DetectorSignal {
+        // The first substantial thought stands in for the original query
+        if !self.detector.has_original_concepts() && !context.thought.trim().is_empty() {
+            self.detector.set_original_query(context.thought);
+        }
+
+        let (is_distracted, pattern) = self
+            .detector
+            .detect_fixation_with(context.thought, |a, b| context.embedder.similarity(a, b));
+        let relevance = self.detector.last_relevance().unwrap_or(1.0);
+        if is_distracted {
+            let mut details = serde_json::to_value(&pattern).unwrap_or_default();
+            if let Value::Object(details) = &mut details {
+                details.insert("relevance".to_string(), relevance.into());
+            }
+            DetectorSignal::intervention(
+                DISTRACTOR_FIXATION,
+                1.0,
+                20,
+                "distracted",
+                DISTRACTOR_FIXATION,
+                pattern
+                    .intervention_message()
+                    .unwrap_or_else(|| "Refocus on the core problem statement.".to_string()),
+                "Excessive detail or tangential content detected",
+            )
+            .with_details(details)
+        } else {
+            DetectorSignal::quiet(DISTRACTOR_FIXATION, 0.0)
+                .with_details(serde_json::json!({ "relevance": relevance }))
+        }
+    }
+
+    fn reset_session(&mut self) {
+        self.detector.reset_session();
+    }
+
+    fn configure(&mut self, config: &MonitoringConfig) {
+        self.detector.configure(config);
+    }
+
+    fn snapshot(&self) -> Option<Value> {
+        serde_json::to_value(self.detector.state()).ok()
+    }
+
+    fn restore(&mut self, state: Value) -> Result<()> {
+        self.detector.restore_state(serde_json::from_value(state)?);
+        Ok(())
+    }
+}
+
+/// Declining vocabulary, coherence and reasoning depth over the session.
+/// The signal's details carry the quality `trend` and the thought's readings
+/// (`coherence`, `information_density`, `reasoning_depth`, `vocabulary_diversity`).
+struct DegradationDetector {
+    detector: QualityDegradationDetector,
+}
+
+impl DegradationDetector {
+    fn new(config: &MonitoringConfig) -> Self {
+        let mut detector = QualityDegradationDetector::new();
+        detector.configure(config);
+        Self { detector }
+    }
+}
+
+impl ThoughtDetector for DegradationDetector {
+    fn name(&self) -> &str {
+        QUALITY_DEGRADATION
+    }
+
+    fn analyze(&mut self, context: &ThoughtContext) -> DetectorSignal {
+        let analysis = self
+            .detector
+            .analyze_thought(context.thought, context.thought_number);
+        let metrics = &analysis.current_metrics;
+        let details = serde_json::json!({
+            "trend": quality_trend(&analysis),
+            "coherence": metrics.coherence(),
+            "information_density": metrics.information_density(),
+            "reasoning_depth": metrics.reasoning_depth(),
+            "vocabulary_diversity": metrics.vocabulary_diversity(),
+        });
+
+        // Within degradation, the most severe pattern decides the intervention
+        let most_severe = analysis
+            .degradation_patterns
+            .iter()
+            .max_by_key(|p| match p {
+                DegradationPattern::SuddenDrop { .. } => 5,
+                DegradationPattern::CognitiveFatigue { .. } => 4,
+                DegradationPattern::ReasoningSimplification { .. } => 3,
+                DegradationPattern::CoherenceBreakdown { .. } => 2,
+                DegradationPattern::VocabularyDecline { .. } => 1,
+            });
+
+        let Some(pattern) = most_severe else {
+            return DetectorSignal::quiet(QUALITY_DEGRADATION, analysis.overall_score)
+                .with_details(details);
+        };
+
+        let (phase, intervention_type, message) = match pattern {
+            DegradationPattern::SuddenDrop { step, .. } => (
+                "dropping",
+                "sudden_drop",
+                format!(
+                    "Quality dropped sharply at step {}. Revisit that step before building on it.",
+                    step
+                ),
+            ),
+            DegradationPattern::CognitiveFatigue { .. } => (
+                "fatigue",
+                "cognitive_fatigue",
+                analysis.recommendations.first().cloned().unwrap_or_else(|| {
+                    "Cognitive fatigue detected. Consider taking a break or concluding your analysis."
+                        .to_string()
+                }),
+            ),
+            DegradationPattern::ReasoningSimplification { .. } => (
+                "simplifying",
+                "reasoning_simplification",
+                "Your reasoning is becoming simplified. Return to deeper analysis.".to_string(),
+            ),
+            DegradationPattern::CoherenceBreakdown { .. } => (
+                "fragmenting",
+                "coherence_breakdown",
+                "Your thoughts are becoming fragmented. Focus on clear connections between ideas."
+                    .to_string(),
+            ),
+            DegradationPattern::VocabularyDecline { .. } => (
+                "repetitive",
+                "vocabulary_decline",
+                "Your vocabulary is becoming repetitive. Try to express ideas with more variety."
+                    .to_string(),
+            ),
+        };
+
+        DetectorSignal::intervention(
+            QUALITY_DEGRADATION,
+            analysis.overall_score,
+            10,
+            phase,
+            intervention_type,
+            message,
+            pattern.description(),
+        )
+        .with_details(details)
+    }
+
+    fn reset_session(&mut self) {
+        self.detector.reset_session();
+    }
+
+    fn configure(&mut self, config: &MonitoringConfig) {
+        self.detector.configure(config);
+    }
+
+    fn snapshot(&self) -> Option<Value> {
+        serde_json::to_value(self.detector.state()).ok()
+    }
+
+    fn restore(&mut self, state: Value) -> Result<()> {
+        self.detector.restore_state(serde_json::from_value(state)?);
+        Ok(())
+    }
+}
+
+/// Code references that the session's files don't contain. It names the
+/// exact reference, so it outranks the other built-ins.
+struct GroundingDetector;
+
+impl ThoughtDetector for GroundingDetector {
+    fn name(&self) -> &str {
+        GROUNDING
+    }
+
+    fn analyze(&mut self, context: &ThoughtContext) -> DetectorSignal {
+        let check = context.sources.check(context.thought);
+        let score = check.ungrounded.len() as f64 / check.checked.max(1) as f64;
+        let details = serde_json::json!({
+            "checked": check.checked,
+            "ungrounded": check.ungrounded,
+        });
+        let Some(first) = check.ungrounded.first() else {
+            return DetectorSignal::quiet(GROUNDING, score).with_details(details);
+        };
+
+        let mut message = first.to_string();
+        if check.ungrounded.len() > 1 {
+            message.push_str(&format!(" (and {} more)", check.ungrounded.len() - 1));
+        }
+        message.push_str(". Check the provided files before relying on it.");
+
+        DetectorSignal::intervention(
+            GROUNDING,
+            score,
+            40,
+            "ungrounded",
+            "ungrounded_reference",
+            message,
+            format!(
+                "{} of {} code references are not in the provided files",
+                check.ungrounded.len(),
+                check.checked
+            ),
+        )
+        .with_details(details)
+    }
+}
+
+fn quality_trend(analysis: &DegradationAnalysis) -> &'static str {
+    if analysis.degradation_patterns.is_empty() {
+        return "stable";
+    }
+
+    let has_severe = analysis.degradation_patterns.iter().any(|p| match p {
+        DegradationPattern::CognitiveFatigue { .. } | DegradationPattern::SuddenDrop { .. } => true,
+        DegradationPattern::ReasoningSimplification { depth_decline, .. } => *depth_decline > 0.4,
+        _ => false,
+    });
+
+    if has_severe || analysis.overall_score > 0.6 {
+        "degrading"
+    } else if analysis.overall_score > 0.3 {
+        "declining"
+    } else {
+        "stable"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct KeywordDetector;
+
+    impl ThoughtDetector for KeywordDetector {
+        fn name(&self) -> &str {
+            "skipped_threat_model"
+        }
+
+        fn analyze(&mut self, context: &ThoughtContext) -> DetectorSignal {
+            if context.thought.contains("authentication") && !context.thought.contains("threat") {
+                DetectorSignal::intervention(
+                    self.name(),
+                    1.0,
+                    50,
+                    "security_review",
+                    "skipped_threat_model",
+                    "Write down the threat model before designing authentication.",
+                    "Authentication discussed without a threat model",
+                )
+            } else {
+                DetectorSignal::quiet(self.name(), 0.0)
+            }
+        }
+    }
+
+    #[test]
+    fn test_builtin_registry_respects_disabled() {
+        let config = MonitoringConfig {
+            disabled_detectors: vec![DISTRACTOR_FIXATION.to_string()],
+            ..Default::default()
+        };
+        let registry = DetectorRegistry::builtin(&config);
+        assert_eq!(
+            registry.names(),
+            vec![CIRCULAR_REASONING, QUALITY_DEGRADATION, GROUNDING]
+        );
+    }
+
+    #[test]
+    fn test_strongest_signal_wins() {
+        let mut registry = DetectorRegistry::builtin(&MonitoringConfig::default());
+        registry.register(Box::new(KeywordDetector));
+        assert!(registry.contains("skipped_threat_model"));
+
+        let signals = registry.analyze(&ThoughtContext {
+            thought: "We add password authentication to the login endpoint",
+            thought_number: 1,
+            history: &[],
+            embedder: &Embedder::local(),
+            memory: &SessionMemory::default(),
+            fingerprint: &ThoughtFingerprint::new("", 1),
+            sources: &GroundingSources::default(),
+        });
+        assert_eq!(signals.len(), 5);
+        let winner = strongest(&signals).unwrap();
+        assert_eq!(winner.detector, "skipped_threat_model");
+
+        assert!(registry.remove("skipped_threat_model"));
+        assert!(!registry.remove("skipped_threat_model"));
+    }
+}
@@ -1,15 +1,20 @@
 use crate::monitoring::circular_reasoning::{CircularReasoningDetector, Concept};
+use crate::monitoring::language::Language;
+use crate::monitoring::MonitoringConfig;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashSet, VecDeque};
 
 /// Advanced distractor fixation detection for metacognitive monitoring
 pub struct DistractorFixationDetector {
     /// Concepts from the original query/problem
     original_concepts: Option<Vec<Concept>>,
+    /// The original query itself, for semantic similarity
+    original_query: Option<String>,
     /// History of relevance scores to track drift
     relevance_history: VecDeque<f64>,
     /// History of detail density scores
     detail_density_history: VecDeque<f64>,
-    /// Threshold for low relevance detection
+    /// Average relevance over recent thoughts below which they count as drifting
     relevance_threshold: f64,
     /// Threshold for high detail density
     detail_threshold: f64,
@@ -23,27 +28,54 @@ impl DistractorFixationDetector {
     pub fn new() -> Self {
         Self {
             original_concepts: None,
+            original_query: None,
             relevance_history: VecDeque::with_capacity(10),
             detail_density_history: VecDeque::with_capacity(10),
-            relevance_threshold: 0.15, // Lowered for better detection without embeddings
-            detail_threshold: 0.5,     // Lowered for better detail detection
+            relevance_threshold: 0.25,
+            detail_threshold: 0.5, // Lowered for better detail detection
             history_window: 5,
             concept_extractor: CircularReasoningDetector::new(),
         }
     }
 
+    /// Take thresholds and lexicons from the monitoring config. A shorter
+    /// window drops the oldest history.
+    pub fn configure(&mut self, config: &MonitoringConfig) {
+        self.concept_extractor.configure(config);
+        self.relevance_threshold = config.relevance_threshold;
+        self.detail_threshold = config.detail_density_threshold;
+        self.history_window = config.distractor_window.max(1);
+        while self.relevance_history.len() > self.history_window {
+            self.relevance_history.pop_front();
+        }
+        while self.detail_density_history.len() > self.history_window {
+            self.detail_density_history.pop_front();
+        }
+    }
+
     /// Set the original query concepts (should be called on first thought)
     pub fn set_original_query(&mut self, query: &str) {
         if self.original_concepts.is_none() && !query.trim().is_empty() {
             let concepts = self.concept_extractor.extract_concepts(query);
             if !concepts.is_empty() {
                 self.original_concepts = Some(concepts);
+                self.original_query = Some(query.to_string());
             }
         }
     }
 
     /// Detect distractor fixation patterns in the current thought
     pub fn detect_fixation(&mut self, thought: &str) -> (bool, DistractorPattern) {
+        self.detect_fixation_with(thought, |_, _| 0.0)
+    }
+
+    /// Detect distractor fixation, also counting a thought as relevant when
+    /// `similarity` (e.g. cosine over embeddings) relates it to the original query
+    pub fn detect_fixation_with(
+        &mut self,
+        thought: &str,
+        similarity: impl Fn(&str, &str) -> f64,
+    ) -> (bool, DistractorPattern) {
         // Extract concepts from current thought
         let current_concepts = self.concept_extractor.extract_concepts(thought);
 
@@ -55,7 +87,13 @@ impl DistractorFixationDetector {
                 println!("Current concepts: {:?}", current_concepts);
                 println!("Original concepts: {:?}", original);
             }
-            let score = self.calculate_relevance_score(&current_concepts, original);
+            // Shared key concepts and semantic closeness are both evidence of relevance
+            let concept_score = self.calculate_relevance_score(&current_concepts, original);
+            let semantic_score = self
+                .original_query
+                .as_deref()
+                .map_or(0.0, |query| similarity(thought, query));
+            let score = concept_score.max(semantic_score);
             #[cfg(test)]
             println!("Relevance score: {}", score);
             score
@@ -111,14 +149,21 @@ impl DistractorFixationDetector {
             }
         }
 
-        // Also check for related concepts (one contains part of the other)
+        // Also check for related concepts (one contains part of the other).
+        // Subtopics the lexicons tie to an original concept keep the thought
+        // on topic, like a shared concept.
+        let lexicon = self.concept_extractor.lexicon();
         let mut related_bonus = 0.0;
+        let mut subtopics = 0;
         for orig in original {
+            let mut subtopic = false;
             for curr in current {
                 if self.concepts_related(orig, curr) && !self.concepts_match(orig, curr) {
                     related_bonus += 0.1;
+                    subtopic |= lexicon.related(&orig.to_string(), &curr.to_string());
                 }
             }
+            subtopics += usize::from(subtopic);
         }
 
         // Base relevance on multiple factors
@@ -131,7 +176,7 @@ impl DistractorFixationDetector {
             + (match_ratio * 0.1);
 
         // Ensure minimum relevance for any concept overlap
-        if matched_concepts > 0 || base_similarity > 0.2 {
+        if matched_concepts > 0 || subtopics > 0 || base_similarity > 0.2 {
             combined_score.max(0.3)
         } else {
             combined_score
@@ -141,8 +186,10 @@ impl DistractorFixationDetector {
 
     /// Check if two concepts match (with some fuzziness)
     fn concepts_match(&self, c1: &Concept, c2: &Concept) -> bool {
-        let s1 = c1.to_string().to_lowercase();
-        let s2 = c2.to_string().to_lowercase();
+        // Synonyms are spelled the same after canonicalizing
+        let lexicon = self.concept_extractor.lexicon();
+        let s1 = lexicon.canonicalize(&c1.to_string().to_lowercase());
+        let s2 = lexicon.canonicalize(&c2.to_string().to_lowercase());
 
         // Exact match
         if s1 == s2 {
@@ -167,84 +214,9 @@ impl DistractorFixationDetector {
         let s1 = c1.to_string().to_lowercase();
         let s2 = c2.to_string().to_lowercase();
 
-        // Domain-specific relationships for ML/neural networks
-        let ml_relationships: &[(&str, &[&str])] = &[
-            // Neural network concepts
-            (
-                "neural network",
-                &[
-                    "backpropagation",
-                    "gradient",
-                    "weights",
-                    "training",
-                    "layer",
-                    "neuron",
-                    "perceptron",
-                ],
-            ),
-            (
-                "backpropagation",
-                &["neural", "gradient", "chain rule", "derivative", "learning"],
-            ),
-            (
-                "gradient",
-                &[
-                    "descent",
-                    "backpropagation",
-                    "optimization",
-                    "derivative",
-                    "learning",
-                ],
-            ),
-            (
-                "learning",
-                &[
-                    "training",
-                    "optimization",
-                    "gradient",
-                    "backpropagation",
-                    "neural",
-                ],
-            ),
-            // General ML concepts
-            (
-                "machine learning",
-                &[
-                    "algorithm",
-                    "model",
-                    "training",
-                    "prediction",
-                    "classification",
-                ],
-            ),
-            (
-                "deep learning",
-                &["neural", "layer", "convolution", "recurrent", "transformer"],
-            ),
-            // Programming concepts
-            (
-                "recursion",
-                &["recursive", "base case", "call stack", "self-reference"],
-            ),
-            (
-                "tcp/ip",
-                &["network", "protocol", "packet", "internet", "communication"],
-            ),
-            (
-                "network",
-                &["communication", "protocol", "tcp", "http", "connection"],
-            ),
-        ];
-
-        // Check domain relationships
-        for (concept, related_terms) in ml_relationships {
-            if s1.contains(concept) || s2.contains(concept) {
-                for term in *related_terms {
-                    if s1.contains(term) || s2.contains(term) {
-                        return true;
-                    }
-                }
-            }
+        // Subtopics, broader terms and synonyms from the lexicons
+        if self.concept_extractor.lexicon().related(&s1, &s2) {
+            return true;
         }
 
         // Split into meaningful words (skip short words)
@@ -254,11 +226,12 @@ impl DistractorFixationDetector {
         // Check if any meaningful word from one appears in the other
         for w1 in &words1 {
             for w2 in &words2 {
-                // Check if words share a common root (simple heuristic)
-                if w1.len() > 4 && w2.len() > 4 {
-                    let min_len = w1.len().min(w2.len());
-                    let prefix_len = (min_len * 2 / 3).max(4);
-                    if w1[..prefix_len] == w2[..prefix_len] {
+                // Check if words share a common root (simple heuristic); counted in
+                // chars so umlauts and CJK don't split a byte sequence
+                let (len1, len2) = (w1.chars().count(), w2.chars().count());
+                if len1 > 4 && len2 > 4 {
+                    let prefix_len = (len1.min(len2) * 2 / 3).max(4);
+                    if w1.chars().take(prefix_len).eq(w2.chars().take(prefix_len)) {
                         return true;
                     }
                 }
@@ -275,8 +248,10 @@ impl DistractorFixationDetector {
 
     /// Calculate detail density in the thought
     fn calculate_detail_density(&self, thought: &str) -> f64 {
+        let language = Language::detect(thought);
         let words: Vec<&str> = thought.split_whitespace().collect();
-        if words.is_empty() {
+        let word_count = language.word_count(thought);
+        if words.is_empty() || word_count == 0 {
             return 0.0;
         }
 
@@ -284,7 +259,13 @@ impl DistractorFixationDetector {
         let mut technical_count = 0;
         let mut number_count = 0;
 
+        let lexicon = self.concept_extractor.lexicon();
         for word in &words {
+            // Domain terms (`k8s`, `PCI-DSS`) are the topic, not detail
+            if lexicon.contains_term(word) {
+                continue;
+            }
+
             // Count numbers
             if word.chars().any(|c| c.is_numeric()) {
                 number_count += 1;
@@ -299,14 +280,12 @@ impl DistractorFixationDetector {
             }
 
             // Count detail indicators
-            if is_detail_word(word) {
-                detail_count += 1;
-            }
+            detail_count += detail_word_count(word, language);
         }
 
         // Calculate density as ratio of detail-heavy elements to total words
         let total_details = detail_count + technical_count + (number_count * 2); // Weight numbers more
-        total_details as f64 / words.len() as f64
+        total_details as f64 / word_count as f64
     }
 
     /// Update tracking histories
@@ -359,8 +338,11 @@ impl DistractorFixationDetector {
             let avg_relevance =
                 recent_relevance.iter().sum::<f64>() / recent_relevance.len() as f64;
 
-            // More lenient detection - if average is below 0.25 and at least 2 values are low
-            let low_count = recent_relevance.iter().filter(|&&r| r < 0.3).count();
+            // Drifting when the average is low and at least 2 thoughts are near the threshold
+            let low_count = recent_relevance
+                .iter()
+                .filter(|&&r| r < self.relevance_threshold + 0.05)
+                .count();
 
             #[cfg(test)]
             {
@@ -370,7 +352,7 @@ impl DistractorFixationDetector {
                 );
             }
 
-            if avg_relevance < 0.25 && low_count >= 2 {
+            if avg_relevance < self.relevance_threshold && low_count >= 2 {
                 return DistractorPattern::TangentialDrift { avg_relevance };
             }
         }
@@ -418,10 +400,44 @@ impl DistractorFixationDetector {
     /// Reset session state
     pub fn reset_session(&mut self) {
         self.original_concepts = None;
+        self.original_query = None;
         self.relevance_history.clear();
         self.detail_density_history.clear();
     }
 
+    /// Per-session state, for persisting the monitor
+    pub fn state(&self) -> DistractorState {
+        DistractorState {
+            original_concepts: self.original_concepts.clone(),
+            original_query: self.original_query.clone(),
+            relevance_history: self.relevance_history.clone(),
+            detail_density_history: self.detail_density_history.clone(),
+        }
+    }
+
+    pub fn restore_state(&mut self, state: DistractorState) {
+        self.original_concepts = state.original_concepts;
+        self.original_query = state.original_query;
+        self.relevance_history = state.relevance_history;
+        self.detail_density_history = state.detail_density_history;
+        while self.relevance_history.len() > self.history_window {
+            self.relevance_history.pop_front();
+        }
+        while self.detail_density_history.len() > self.history_window {
+            self.detail_density_history.pop_front();
+        }
+    }
+
+    /// Relevance of the last thought to the original query
+    pub fn last_relevance(&self) -> Option<f64> {
+        self.relevance_history.back().copied()
+    }
+
+    /// The query thoughts are compared with, once set
+    pub fn original_query(&self) -> Option<&str> {
+        self.original_query.as_deref()
+    }
+
     /// Check if detector has original concepts set
     pub fn has_original_concepts(&self) -> bool {
         self.original_concepts.is_some()
@@ -442,7 +458,17 @@ impl DistractorFixationDetector {
 }
 
 /// Types of distractor fixation patterns
-#[derive(Debug, Clone)]
+/// What [`DistractorFixationDetector`] remembers about a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DistractorState {
+    original_concepts: Option<Vec<Concept>>,
+    original_query: Option<String>,
+    relevance_history: VecDeque<f64>,
+    detail_density_history: VecDeque<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "pattern", rename_all = "snake_case")]
 pub enum DistractorPattern {
     /// Gradual drift away from original topic
     TangentialDrift { avg_relevance: f64 },
@@ -478,60 +504,19 @@ impl DistractorPattern {
     }
 }
 
-/// Check if a word is a detail indicator
-fn is_detail_word(word: &str) -> bool {
-    let detail_indicators = [
-        "specifically",
-        "particularly",
-        "exactly",
-        "precisely",
-        "detail",
-        "detailed",
-        "specific",
-        "precise",
-        "enumerate",
-        "list",
-        "itemize",
-        "specify",
-        "step",
-        "sub-step",
-        "point",
-        "subpoint",
-        "first",
-        "second",
-        "third",
-        "fourth",
-        "fifth",
-        "1.",
-        "2.",
-        "3.",
-        "4.",
-        "5.",
-        "a)",
-        "b)",
-        "c)",
-        "d)",
-        "e)",
-        "1a",
-        "1b",
-        "1c",
-        "2a",
-        "2b",
-        "2c",
-        "initialize",
-        "calculate",
-        "compute",
-        "apply",
-        "formula",
-        "equation",
-        "algorithm",
-        "procedure",
-    ];
-
-    let word_lower = word.to_lowercase();
-    detail_indicators
+/// Count detail indicators in a whitespace-separated chunk. Latin-script words
+/// count once; a CJK chunk is a whole clause and can hold several.
+fn detail_word_count(chunk: &str, language: Language) -> usize {
+    let chunk_lower = chunk.to_lowercase();
+    let mut matches = language
+        .detail_words()
         .iter()
-        .any(|&indicator| word_lower.contains(indicator))
+        .filter(|&&indicator| chunk_lower.contains(indicator));
+    if language.is_cjk() {
+        matches.count()
+    } else {
+        usize::from(matches.next().is_some())
+    }
 }
 
 /// Calculate variance of a numeric vector
@@ -740,4 +725,30 @@ mod tests {
         assert!(!distracted);
         assert!(matches!(pattern, DistractorPattern::None));
     }
+
+    #[test]
+    fn test_lexicon_subtopics() {
+        let thoughts = [
+            "The deployment replaces pods one at a time, and the new pods fail their readiness probe for the first 40 seconds while the JVM warms up.",
+            "Kubelet kills the container after three failed liveness probe checks at 10s intervals, so the liveness probe needs an initialDelaySeconds above 40.",
+            "A startup probe with failureThreshold 12 and periodSeconds 5 covers the warm-up without delaying liveness checks later.",
+        ];
+        let run = |lexicons: Vec<String>| {
+            let mut detector = DistractorFixationDetector::new();
+            detector.configure(&MonitoringConfig {
+                lexicons,
+                ..MonitoringConfig::default()
+            });
+            detector.set_original_query(
+                "Why do our payment API pods restart during every Kubernetes rollout?",
+            );
+            thoughts
+                .iter()
+                .any(|thought| detector.detect_fixation(thought).0)
+        };
+
+        // Probes are a subtopic of pod restarts only to the Kubernetes pack
+        assert!(run(Vec::new()));
+        assert!(!run(vec!["kubernetes".to_string()]));
+    }
 }
@@ -0,0 +1,493 @@
+//! Grounding of code references against the files a tool was given.
+//!
+//! Models reasoning over `file_paths` still mention functions, types, config
+//! keys and files that aren't there. Code-like references are pulled out of a
+//! thought (backtick spans, calls such as `parse_config(`, `Type::method`
+//! paths, snake_case names and file paths) and looked up in the loaded files.
+//! References the caller's own prompt makes are taken as given, and so are
+//! references in a sentence that proposes adding something new.
+
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+
+/// Names every codebase uses without defining them
+const COMMON_NAMES: &[&str] = &[
+    "self",
+    "Self",
+    "this",
+    "true",
+    "false",
+    "null",
+    "nil",
+    "None",
+    "Some",
+    "Ok",
+    "Err",
+    "new",
+    "main",
+    "String",
+    "str",
+    "Vec",
+    "Option",
+    "Result",
+    "Box",
+    "Arc",
+    "HashMap",
+    "HashSet",
+    "unwrap",
+    "expect",
+    "clone",
+    "len",
+    "iter",
+    "map",
+    "collect",
+    "into",
+    "from",
+    "to_string",
+    "as_str",
+    "push",
+    "get",
+    "set",
+    "insert",
+    "remove",
+    "contains",
+    "print",
+    "println",
+    "format",
+    "console",
+    "log",
+    "json",
+    "int",
+    "float",
+    "dict",
+    "list",
+    "async",
+    "await",
+    "return",
+    "fn",
+    "def",
+    "let",
+    "const",
+    "var",
+    "function",
+    "class",
+    "struct",
+    "impl",
+    "trait",
+    "enum",
+    "mod",
+    "use",
+    "import",
+    "pub",
+];
+
+/// Words that mark a sentence as proposing new code rather than describing existing code
+const PROPOSAL_WORDS: &[&str] = &[
+    "add",
+    "adding",
+    "create",
+    "creating",
+    "introduce",
+    "introducing",
+    "define",
+    "defining",
+    "new",
+    "rename",
+    "renaming",
+    "extract",
+    "extracting",
+    "implement",
+    "implementing",
+    "write",
+    "writing",
+    "missing",
+];
+
+/// Extensions that make a bare word a file path
+const FILE_EXTENSIONS: &[&str] = &[
+    "rs", "py", "ts", "tsx", "js", "jsx", "go", "java", "kt", "rb", "c", "h", "cpp", "hpp", "cs",
+    "swift", "toml", "json", "yaml", "yml", "sql", "sh", "md",
+];
+
+/// Punctuation that makes a backtick span a code snippet rather than prose
+const SNIPPET_MARKERS: &[&str] = &["(", "=", ";", "{", "->", "::"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReferenceKind {
+    /// Function, type, field or config key
+    Identifier,
+    Path,
+    /// Quoted code, matched verbatim up to whitespace
+    Snippet,
+}
+
+/// A reference in a thought that the provided files don't contain
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UngroundedReference {
+    pub reference: String,
+    pub kind: ReferenceKind,
+    /// The files searched, for reporting
+    pub searched: String,
+}
+
+impl fmt::Display for UngroundedReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ReferenceKind::Path => write!(
+                f,
+                "`{}` is not among the provided files ({})",
+                self.reference, self.searched
+            ),
+            _ => write!(
+                f,
+                "`{}` is not present in {}",
+                self.reference, self.searched
+            ),
+        }
+    }
+}
+
+/// Outcome of checking one text
+#[derive(Debug, Clone, Default)]
+pub struct GroundingCheck {
+    /// References that were looked up
+    pub checked: usize,
+    pub ungrounded: Vec<UngroundedReference>,
+}
+
+/// Loaded files to check references against
+#[derive(Debug, Clone, Default)]
+pub struct GroundingSources {
+    files: Vec<(String, String)>,
+    /// Identifier tokens of every file and of the prompt
+    identifiers: HashSet<String>,
+    /// Whitespace-normalized file contents and prompt, for snippets
+    normalized: Vec<String>,
+}
+
+impl GroundingSources {
+    /// Sources from `(path, content)` pairs; references in `prompt` count as grounded
+    pub fn new(files: &[(String, String)], prompt: &str) -> Self {
+        let texts = files
+            .iter()
+            .map(|(_, content)| content.as_str())
+            .chain([prompt]);
+        let mut identifiers = HashSet::new();
+        let mut normalized = Vec::new();
+        for text in texts {
+            identifiers.extend(identifier_tokens(text));
+            normalized.push(normalize_whitespace(text));
+        }
+
+        Self {
+            files: files.to_vec(),
+            identifiers,
+            normalized,
+        }
+    }
+
+    /// No files were loaded, so nothing can be checked
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Look up every code reference in `text`
+    pub fn check(&self, text: &str) -> GroundingCheck {
+        let mut check = GroundingCheck::default();
+        if self.is_empty() {
+            return check;
+        }
+
+        for (reference, kind) in extract_references(text) {
+            let grounded = match kind {
+                ReferenceKind::Identifier => match self.identifier_grounded(&reference) {
+                    Some(grounded) => grounded,
+                    None => continue,
+                },
+                ReferenceKind::Path => self.path_grounded(&reference),
+                ReferenceKind::Snippet => {
+                    let snippet = normalize_whitespace(&reference);
+                    self.normalized.iter().any(|text| text.contains(&snippet))
+                }
+            };
+            check.checked += 1;
+            if !grounded {
+                check.ungrounded.push(UngroundedReference {
+                    reference,
+                    kind,
+                    searched: self.searched(),
+                });
+            }
+        }
+        check
+    }
+
+    /// Whether every distinctive segment of `a::b.c()` is a known identifier;
+    /// `None` when there's nothing distinctive to check
+    fn identifier_grounded(&self, reference: &str) -> Option<bool> {
+        let name = reference.split('(').next().unwrap_or(reference);
+        let segments: Vec<&str> = name
+            .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+            .filter(|s| {
+                s.len() > 1 && !COMMON_NAMES.contains(s) && !s.chars().all(|c| c.is_ascii_digit())
+            })
+            .collect();
+        if segments.is_empty() {
+            return None;
+        }
+        Some(segments.iter().all(|s| self.identifiers.contains(*s)))
+    }
+
+    fn path_grounded(&self, reference: &str) -> bool {
+        let reference = reference.trim_start_matches("./");
+        let loaded = self.files.iter().any(|(path, _)| {
+            path == reference
+                || path.ends_with(&format!("/{}", reference))
+                || reference.ends_with(&format!("/{}", file_name(path)))
+        });
+        if loaded || self.normalized.iter().any(|text| text.contains(reference)) {
+            return true;
+        }
+
+        // Files that exist next to the loaded ones were only not provided.
+        // They resolve against the directories of the request's files, not
+        // the server's working directory or the filesystem root.
+        let reference = Path::new(reference);
+        reference.is_relative()
+            && self.files.iter().any(|(path, _)| {
+                Path::new(path)
+                    .ancestors()
+                    .skip(1)
+                    .filter(|dir| dir.file_name().is_some() || !dir.has_root())
+                    .any(|dir| dir.join(reference).exists())
+            })
+    }
+
+    fn searched(&self) -> String {
+        match self.files.as_slice() {
+            [(path, _)] => path.clone(),
+            files if files.len() <= 3 => files
+                .iter()
+                .map(|(path, _)| path.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+            files => format!("any of the {} provided files", files.len()),
+        }
+    }
+}
+
+/// Code references in `text`, in order of appearance and without repeats
+fn extract_references(text: &str) -> Vec<(String, ReferenceKind)> {
+    let mut references: Vec<(String, ReferenceKind)> = Vec::new();
+    let mut add = |reference: &str, kind: ReferenceKind, offset: usize| {
+        let reference = reference.trim_matches(|c: char| ",.:;!?\"'".contains(c));
+        if reference.is_empty()
+            || proposes_new_code(text, offset)
+            || references.iter().any(|(r, _)| r == reference)
+        {
+            return;
+        }
+        references.push((reference.to_string(), kind));
+    };
+
+    let mut offset = 0;
+    for (i, part) in text.split("```").enumerate() {
+        // Fenced blocks usually hold proposed code, so only prose is read
+        if i % 2 == 0 {
+            let mut span_offset = offset;
+            for (j, span) in part.split('`').enumerate() {
+                if j % 2 == 1 {
+                    classify_span(span, span_offset, &mut add);
+                } else {
+                    find_bare_references(span, span_offset, &mut add);
+                }
+                span_offset += span.len() + 1;
+            }
+        }
+        offset += part.len() + 3;
+    }
+    references
+}
+
+/// A backtick span: a snippet if it reads like code, otherwise one reference
+fn classify_span(span: &str, offset: usize, add: &mut impl FnMut(&str, ReferenceKind, usize)) {
+    let span = span.trim();
+    if span.contains(char::is_whitespace) {
+        if SNIPPET_MARKERS.iter().any(|m| span.contains(m)) {
+            add(span, ReferenceKind::Snippet, offset);
+        }
+    } else if has_file_extension(span) || span.contains('/') {
+        add(span, ReferenceKind::Path, offset);
+    } else if span.chars().any(|c| c.is_alphabetic()) {
+        add(span, ReferenceKind::Identifier, offset);
+    }
+}
+
+/// Code-shaped words in prose: calls, `::` paths, snake_case names and file paths
+fn find_bare_references(
+    prose: &str,
+    offset: usize,
+    add: &mut impl FnMut(&str, ReferenceKind, usize),
+) {
+    let mut position = 0;
+    for word in prose.split_whitespace() {
+        let start = prose[position..]
+            .find(word)
+            .map_or(position, |i| position + i);
+        position = start + word.len();
+        let word = word.trim_matches(|c: char| "([{\"'".contains(c));
+        if word.contains("://") {
+            continue;
+        }
+
+        let name = word.split('(').next().unwrap_or(word);
+        let name = name.trim_end_matches(|c: char| ",.:;!?)]}\"'".contains(c));
+        // Prose needs a directory or a lowercase file name: "and/or" and
+        // "Node.js" aren't paths
+        let lowercase_file =
+            has_file_extension(name) && name.chars().next().is_some_and(|c| c.is_lowercase());
+        if lowercase_file || (name.contains('/') && has_file_extension(name)) {
+            add(name, ReferenceKind::Path, offset + start);
+        } else if name.chars().next().is_some_and(|c| c.is_alphabetic())
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == ':' || c == '.')
+            && (name.contains('_') || name.contains("::") || word.contains(&format!("{}(", name)))
+        {
+            let call = word.contains(&format!("{}(", name));
+            let reference = if call {
+                format!("{}()", name)
+            } else {
+                name.to_string()
+            };
+            add(&reference, ReferenceKind::Identifier, offset + start);
+        }
+    }
+}
+
+fn has_file_extension(word: &str) -> bool {
+    !word.contains("://")
+        && word.rsplit_once('.').is_some_and(|(stem, extension)| {
+            !stem.is_empty() && !stem.ends_with('.') && FILE_EXTENSIONS.contains(&extension)
+        })
+}
+
+/// Whether the sentence around `offset` proposes adding code before mentioning it
+fn proposes_new_code(text: &str, offset: usize) -> bool {
+    let offset = offset.min(text.len());
+    let Some(before) = text.get(..offset) else {
+        return false;
+    };
+    let sentence_start = before
+        .char_indices()
+        .rev()
+        // A period ends a sentence only before whitespace, not inside `a.b`
+        .find(|&(i, c)| {
+            matches!(c, '\n' | '!' | '?')
+                || (c == '.' && before[i + 1..].starts_with(char::is_whitespace))
+        })
+        .map_or(0, |(i, _)| i + 1);
+    before[sentence_start..]
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|word| PROPOSAL_WORDS.contains(&word.to_lowercase().as_str()))
+}
+
+fn identifier_tokens(text: &str) -> impl Iterator<Item = String> + '_ {
+    // Kebab-case config keys are kept whole as well as split
+    text.split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+        .flat_map(|token| {
+            let parts = token.split('-').map(str::to_string);
+            std::iter::once(token.to_string()).chain(parts)
+        })
+        .filter(|token| !token.is_empty())
+}
+
+fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sources() -> GroundingSources {
+        let files = vec![(
+            "src/cache.rs".to_string(),
+            "pub struct ProductCache { ttl_secs: u64 }\n\
+             impl ProductCache {\n    pub fn get_product(&self, id: u64) -> Option<Product> {\n        self.lookup(id)\n    }\n}\n"
+                .to_string(),
+        )];
+        GroundingSources::new(&files, "Why does `ProductCache` miss after restarts?")
+    }
+
+    #[test]
+    fn test_missing_references_are_reported() {
+        let check = sources().check(
+            "ProductCache::get_product looks up `ttl_secs`, but the miss comes from \
+             `evict_stale()` and refresh_interval in src/eviction.rs.",
+        );
+        let ungrounded: Vec<&str> = check
+            .ungrounded
+            .iter()
+            .map(|u| u.reference.as_str())
+            .collect();
+        assert_eq!(
+            ungrounded,
+            ["evict_stale()", "refresh_interval", "src/eviction.rs"]
+        );
+        assert_eq!(check.checked, 5);
+        assert_eq!(
+            check.ungrounded[0].to_string(),
+            "`evict_stale()` is not present in src/cache.rs"
+        );
+    }
+
+    #[test]
+    fn test_paths_resolve_against_the_request_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src/eviction.rs"), "").unwrap();
+        let cache = dir.join("src/cache.rs").display().to_string();
+        let sources = GroundingSources::new(&[(cache, "pub struct Cache;".to_string())], "");
+
+        // A sibling of the provided file, but not a path in the server's directory
+        let check = sources.check("The miss comes from src/eviction.rs, not src/lib.rs.");
+        let ungrounded: Vec<&str> = check
+            .ungrounded
+            .iter()
+            .map(|u| u.reference.as_str())
+            .collect();
+        assert_eq!(ungrounded, ["src/lib.rs"]);
+    }
+
+    #[test]
+    fn test_snippets_match_up_to_whitespace() {
+        let sources = sources();
+        let check = sources.check("It calls `pub fn get_product(&self, id: u64)` directly.");
+        assert_eq!((check.checked, check.ungrounded.len()), (1, 0));
+
+        let check = sources.check("It runs `let cached = self.fetch(id);` first.");
+        assert_eq!(check.ungrounded[0].kind, ReferenceKind::Snippet);
+    }
+
+    #[test]
+    fn test_proposals_and_prose_are_not_checked() {
+        let check = sources().check(
+            "We should add `evict_stale()` to the cache. Run `cargo test` afterwards; \
+             see https://example.com/docs/cache.html for background.",
+        );
+        assert_eq!(check.checked, 0);
+        assert!(GroundingSources::default()
+            .check("`anything()`")
+            .ungrounded
+            .is_empty());
+    }
+}
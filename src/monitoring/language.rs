@@ -0,0 +1,507 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Language of a thought, as far as the monitor's text heuristics care
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    English,
+    German,
+    Japanese,
+    Chinese,
+}
+
+const ENGLISH_STOP_WORDS: &[&str] = &[
+    "the", "a", "an", "and", "or", "but", "in", "on", "at", "to", "for", "of", "with", "by",
+    "from", "up", "about", "into", "through", "during", "before", "after", "above", "below",
+    "between", "under", "is", "are", "was", "were", "been", "be", "have", "has", "had", "do",
+    "does", "did", "will", "would", "should", "could", "may", "might", "must", "can",
+];
+
+const GERMAN_STOP_WORDS: &[&str] = &[
+    "der", "die", "das", "den", "dem", "des", "ein", "eine", "einer", "eines", "einem", "einen",
+    "und", "oder", "aber", "im", "an", "am", "auf", "zu", "zum", "zur", "für", "von", "vom", "mit",
+    "bei", "aus", "nach", "über", "unter", "durch", "ist", "sind", "war", "waren", "sein", "hat",
+    "haben", "hatte", "wird", "werden", "wurde", "kann", "können", "muss", "müssen", "soll",
+    "sollte", "nicht", "auch", "als", "wie", "wenn", "dass", "es", "sich", "so", "noch", "nur",
+    "schon", "sehr", "ich", "wir", "sie", "er", "man", "dies", "diese", "dieser", "dieses", "daß",
+    "kein", "keine",
+];
+
+/// Characters that only carry grammar; Chinese runs are split at them before
+/// bigramming, as Japanese runs are at hiragana
+const CHINESE_STOP_CHARS: &[char] = &[
+    '的', '了', '是', '在', '和', '也', '就', '都', '而', '及', '与', '这', '那', '之', '或', '把',
+    '被', '对', '很', '还', '又', '着', '个', '我', '你', '他', '她', '它', '们',
+];
+
+const ENGLISH_COHERENCE_MARKERS: &[&str] = &[
+    "therefore",
+    "however",
+    "moreover",
+    "furthermore",
+    "consequently",
+    "thus",
+    "hence",
+    "accordingly",
+    "nevertheless",
+    "nonetheless",
+    "meanwhile",
+    "subsequently",
+    "initially",
+    "finally",
+    "additionally",
+    "specifically",
+    "particularly",
+    "especially",
+    "notably",
+    "importantly",
+];
+
+const GERMAN_COHERENCE_MARKERS: &[&str] = &[
+    "daher",
+    "deshalb",
+    "deswegen",
+    "jedoch",
+    "außerdem",
+    "ferner",
+    "folglich",
+    "somit",
+    "allerdings",
+    "dennoch",
+    "trotzdem",
+    "zunächst",
+    "anschließend",
+    "schließlich",
+    "zusätzlich",
+    "insbesondere",
+    "beispielsweise",
+    "stattdessen",
+    "darüber hinaus",
+    "einerseits",
+    "andererseits",
+];
+
+const JAPANESE_COHERENCE_MARKERS: &[&str] = &[
+    "したがって",
+    "しかし",
+    "ただし",
+    "さらに",
+    "そのため",
+    "つまり",
+    "一方",
+    "まず",
+    "次に",
+    "最後に",
+    "特に",
+    "例えば",
+    "なぜなら",
+    "結果として",
+    "それでも",
+    "加えて",
+];
+
+const CHINESE_COHERENCE_MARKERS: &[&str] = &[
+    "因此",
+    "但是",
+    "然而",
+    "此外",
+    "而且",
+    "所以",
+    "首先",
+    "其次",
+    "最后",
+    "特别是",
+    "例如",
+    "因为",
+    "总之",
+    "另外",
+    "相反",
+    "同时",
+];
+
+const ENGLISH_DETAIL_WORDS: &[&str] = &[
+    "specifically",
+    "particularly",
+    "exactly",
+    "precisely",
+    "detail",
+    "detailed",
+    "specific",
+    "precise",
+    "enumerate",
+    "list",
+    "itemize",
+    "specify",
+    "step",
+    "sub-step",
+    "point",
+    "subpoint",
+    "first",
+    "second",
+    "third",
+    "fourth",
+    "fifth",
+    "1.",
+    "2.",
+    "3.",
+    "4.",
+    "5.",
+    "a)",
+    "b)",
+    "c)",
+    "d)",
+    "e)",
+    "1a",
+    "1b",
+    "1c",
+    "2a",
+    "2b",
+    "2c",
+    "initialize",
+    "calculate",
+    "compute",
+    "apply",
+    "formula",
+    "equation",
+    "algorithm",
+    "procedure",
+];
+
+const GERMAN_DETAIL_WORDS: &[&str] = &[
+    "insbesondere",
+    "genau",
+    "exakt",
+    "speziell",
+    "detail",
+    "einzeln",
+    "auflisten",
+    "aufzählen",
+    "schritt",
+    "punkt",
+    "erstens",
+    "zweitens",
+    "drittens",
+    "1.",
+    "2.",
+    "3.",
+    "a)",
+    "b)",
+    "c)",
+    "initialisieren",
+    "berechnen",
+    "anwenden",
+    "formel",
+    "gleichung",
+    "algorithmus",
+    "verfahren",
+];
+
+const JAPANESE_DETAIL_WORDS: &[&str] = &[
+    "具体的",
+    "詳細",
+    "正確",
+    "厳密",
+    "手順",
+    "ステップ",
+    "第一",
+    "第二",
+    "第三",
+    "初期化",
+    "計算",
+    "数式",
+    "方程式",
+    "アルゴリズム",
+    "適用",
+];
+
+const CHINESE_DETAIL_WORDS: &[&str] = &[
+    "具体",
+    "详细",
+    "精确",
+    "准确",
+    "步骤",
+    "第一",
+    "第二",
+    "第三",
+    "初始化",
+    "计算",
+    "公式",
+    "方程",
+    "算法",
+    "应用",
+];
+
+impl Language {
+    /// Guess the language of a text from its script, and for Latin script from
+    /// umlauts and which stop words it uses
+    pub fn detect(text: &str) -> Self {
+        let mut kana = 0;
+        let mut han = 0;
+        let mut latin = 0;
+        for c in text.chars() {
+            if is_kana(c) {
+                kana += 1;
+            } else if is_han(c) {
+                han += 1;
+            } else if c.is_alphabetic() {
+                latin += 1;
+            }
+        }
+
+        if kana + han > latin {
+            // Japanese mixes kanji with kana; Chinese has no kana at all
+            return if kana > 0 {
+                Language::Japanese
+            } else {
+                Language::Chinese
+            };
+        }
+
+        let words: Vec<String> = text.unicode_words().map(str::to_lowercase).collect();
+        let count = |list: &[&str]| words.iter().filter(|w| list.contains(&w.as_str())).count();
+        let umlauts = text.chars().filter(|c| "äöüßÄÖÜ".contains(*c)).count();
+        if count(GERMAN_STOP_WORDS) + umlauts > count(ENGLISH_STOP_WORDS) {
+            Language::German
+        } else {
+            Language::English
+        }
+    }
+
+    pub fn is_cjk(self) -> bool {
+        matches!(self, Language::Japanese | Language::Chinese)
+    }
+
+    /// Lowercased word tokens. Unicode word boundaries split kanji/hanzi and
+    /// hiragana into single characters, so CJK runs are rejoined, split at
+    /// grammatical characters and indexed as overlapping bigrams.
+    pub fn tokenize(self, text: &str) -> Vec<String> {
+        if !self.is_cjk() {
+            return text
+                .unicode_words()
+                .map(str::to_lowercase)
+                .filter(|w| w.chars().count() > 2)
+                .collect();
+        }
+
+        let mut tokens = Vec::new();
+        let mut run: Vec<char> = Vec::new();
+        for word in text.unicode_words() {
+            let mut chars = word.chars();
+            let single = match (chars.next(), chars.next()) {
+                (Some(c), None) if is_han(c) || is_kana(c) => Some(c),
+                _ => None,
+            };
+            match single {
+                Some(c) if !self.is_stop_char(c) => run.push(c),
+                _ => {
+                    push_bigrams(&mut tokens, &run);
+                    run.clear();
+                    // Katakana words and Latin terms come through whole
+                    if single.is_none() && word.chars().count() >= 2 {
+                        tokens.push(word.to_lowercase());
+                    }
+                }
+            }
+        }
+        push_bigrams(&mut tokens, &run);
+        tokens
+    }
+
+    /// Reduce a word to a crude stem so inflected forms compare equal
+    pub fn stem(self, word: &str) -> String {
+        match self {
+            Language::English => stem_english(word),
+            Language::German => stem_german(word),
+            // No inflection to strip from bigrams and katakana
+            Language::Japanese | Language::Chinese => word.to_lowercase(),
+        }
+    }
+
+    pub fn is_stop_word(self, word: &str) -> bool {
+        match self {
+            Language::English => ENGLISH_STOP_WORDS.contains(&word),
+            Language::German => GERMAN_STOP_WORDS.contains(&word),
+            Language::Japanese | Language::Chinese => word.chars().all(|c| self.is_stop_char(c)),
+        }
+    }
+
+    /// Connectives that signal a structured argument
+    pub fn coherence_markers(self) -> &'static [&'static str] {
+        match self {
+            Language::English => ENGLISH_COHERENCE_MARKERS,
+            Language::German => GERMAN_COHERENCE_MARKERS,
+            Language::Japanese => JAPANESE_COHERENCE_MARKERS,
+            Language::Chinese => CHINESE_COHERENCE_MARKERS,
+        }
+    }
+
+    /// Words that signal enumeration or low-level detail
+    pub fn detail_words(self) -> &'static [&'static str] {
+        match self {
+            Language::English => ENGLISH_DETAIL_WORDS,
+            Language::German => GERMAN_DETAIL_WORDS,
+            Language::Japanese => JAPANESE_DETAIL_WORDS,
+            Language::Chinese => CHINESE_DETAIL_WORDS,
+        }
+    }
+
+    /// Word count comparable across languages. CJK has no spaces, so it counts
+    /// one word per two characters, about the average word length.
+    pub fn word_count(self, text: &str) -> usize {
+        if self.is_cjk() {
+            let cjk_chars = text.chars().filter(|c| is_han(*c) || is_kana(*c)).count();
+            let other_words = text
+                .unicode_words()
+                .filter(|w| !w.chars().any(|c| is_han(c) || is_kana(c)))
+                .count();
+            cjk_chars.div_ceil(2) + other_words
+        } else {
+            text.split_whitespace().count()
+        }
+    }
+
+    fn is_stop_char(self, c: char) -> bool {
+        match self {
+            // Hiragana is almost entirely particles and inflection
+            Language::Japanese => is_hiragana(c),
+            Language::Chinese => CHINESE_STOP_CHARS.contains(&c),
+            Language::English | Language::German => false,
+        }
+    }
+}
+
+/// A concept candidate: alphabetic, and long enough to carry meaning
+pub fn is_meaningful_word(word: &str) -> bool {
+    let chars = word.chars().count();
+    let min_chars = if word.chars().any(|c| is_han(c) || is_kana(c)) {
+        2
+    } else {
+        4
+    };
+    chars >= min_chars && word.chars().all(|c| c.is_alphabetic())
+}
+
+/// A lone kanji is too ambiguous to index, so runs shorter than two yield nothing
+fn push_bigrams(tokens: &mut Vec<String>, run: &[char]) {
+    tokens.extend(run.windows(2).map(|pair| pair.iter().collect::<String>()));
+}
+
+fn is_hiragana(c: char) -> bool {
+    ('\u{3040}'..='\u{309F}').contains(&c)
+}
+
+fn is_kana(c: char) -> bool {
+    is_hiragana(c)
+        || ('\u{30A0}'..='\u{30FF}').contains(&c)
+        || ('\u{31F0}'..='\u{31FF}').contains(&c)
+}
+
+fn is_han(c: char) -> bool {
+    ('\u{4E00}'..='\u{9FFF}').contains(&c)
+        || ('\u{3400}'..='\u{4DBF}').contains(&c)
+        || ('\u{F900}'..='\u{FAFF}').contains(&c)
+}
+
+/// Strip common English suffixes
+fn stem_english(word: &str) -> String {
+    let word_lower = word.to_lowercase();
+
+    // Handle common verb forms
+    if word_lower.ends_with("ing") && word_lower.len() > 5 {
+        // understanding -> understand
+        return word_lower[..word_lower.len() - 3].to_string();
+    }
+
+    if word_lower.ends_with("ed") && word_lower.len() > 4 {
+        return word_lower[..word_lower.len() - 2].to_string();
+    }
+
+    if word_lower.ends_with("es") && word_lower.len() > 4 {
+        // requires -> require
+        return word_lower[..word_lower.len() - 1].to_string();
+    }
+
+    if word_lower.ends_with('s') && word_lower.len() > 3 && !word_lower.ends_with("ss") {
+        return word_lower[..word_lower.len() - 1].to_string();
+    }
+
+    word_lower
+}
+
+/// Light German stemmer in the style of CISTEM: fold umlauts, then strip
+/// inflectional endings while the word stays long enough
+fn stem_german(word: &str) -> String {
+    let mut stem: Vec<char> = word
+        .to_lowercase()
+        .replace('ß', "ss")
+        .chars()
+        .map(|c| match c {
+            'ä' => 'a',
+            'ö' => 'o',
+            'ü' => 'u',
+            other => other,
+        })
+        .collect();
+
+    loop {
+        let len = stem.len();
+        let ends_with = |suffix: &str| {
+            let suffix: Vec<char> = suffix.chars().collect();
+            stem.ends_with(&suffix)
+        };
+        if len > 5 && (ends_with("em") || ends_with("er") || ends_with("nd")) {
+            stem.truncate(len - 2);
+        } else if len > 4 && (ends_with("e") || ends_with("s") || ends_with("n") || ends_with("t"))
+        {
+            stem.truncate(len - 1);
+        } else {
+            break;
+        }
+    }
+
+    stem.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(
+            Language::detect("The cache is not thread-safe under load"),
+            Language::English
+        );
+        assert_eq!(
+            Language::detect("Der Cache ist nicht threadsicher, wenn viele Anfragen kommen"),
+            Language::German
+        );
+        assert_eq!(
+            Language::detect("このキャッシュはスレッドセーフではない"),
+            Language::Japanese
+        );
+        assert_eq!(
+            Language::detect("这个缓存不是线程安全的"),
+            Language::Chinese
+        );
+    }
+
+    #[test]
+    fn test_cjk_tokenization() {
+        let tokens = Language::Japanese.tokenize("キャッシュの競合状態を分析する");
+        assert!(tokens.contains(&"キャッシュ".to_string()));
+        assert!(tokens.contains(&"競合".to_string()));
+        assert!(tokens.contains(&"分析".to_string()));
+        // Particles never end up inside a token
+        assert!(tokens
+            .iter()
+            .all(|t| !t.contains('の') && !t.contains('を')));
+    }
+
+    #[test]
+    fn test_german_stemming() {
+        let stem = |w| Language::German.stem(w);
+        assert_eq!(stem("Verbindungen"), stem("Verbindung"));
+        assert_eq!(stem("Lösungen"), stem("Lösung"));
+        assert_eq!(stem("Anfragen"), stem("Anfrage"));
+    }
+}
@@ -0,0 +1,488 @@
+//! Domain vocabularies for concept matching. A pack lists synonyms, broader
+//! terms and groups of related terms; the circular and distractor detectors
+//! use the merged packs of a session to recognize multi-word terms, treat
+//! synonyms as one concept and accept subtopics as relevant.
+
+use anyhow::{anyhow, bail, Context, Result};
+use parking_lot::Mutex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::SystemTime;
+use tracing::warn;
+
+use crate::monitoring::language::Language;
+use crate::settings::LuxSettings;
+
+/// Packs compiled into the binary. `general` is always loaded.
+const BUILTIN_PACKS: &[(&str, &str)] = &[
+    ("general", include_str!("lexicons/general.toml")),
+    ("kubernetes", include_str!("lexicons/kubernetes.toml")),
+    ("payments", include_str!("lexicons/payments.toml")),
+    ("databases", include_str!("lexicons/databases.toml")),
+    ("web-apis", include_str!("lexicons/web-apis.toml")),
+];
+
+/// Merged lexicons kept for reuse; the oldest is dropped beyond this
+const MAX_CACHED_LEXICONS: usize = 32;
+
+/// Each pack of a merged lexicon, with the file and modification time it
+/// was read from
+type CacheKey = Vec<(String, Option<PackVersion>)>;
+type PackVersion = (PathBuf, Option<SystemTime>);
+type LexiconCache = Mutex<Vec<(CacheKey, Arc<Lexicon>)>>;
+
+/// Where a pack is read from
+enum PackSource {
+    Builtin(&'static str),
+    File(PathBuf),
+}
+
+impl PackSource {
+    /// A pack by name: a path ending in `.toml`, `<name>.toml` in one of
+    /// [`lexicon_dirs`], or a built-in pack, in that order
+    fn locate(name: &str) -> Result<Self> {
+        if name.ends_with(".toml") {
+            return Ok(Self::File(PathBuf::from(name)));
+        }
+        for dir in lexicon_dirs() {
+            let path = dir.join(format!("{}.toml", name));
+            if path.is_file() {
+                return Ok(Self::File(path));
+            }
+        }
+        match BUILTIN_PACKS.iter().find(|(builtin, _)| *builtin == name) {
+            Some((_, toml)) => Ok(Self::Builtin(toml)),
+            None => bail!(
+                "Unknown lexicon '{}' (built-in: {})",
+                name,
+                builtin_lexicons().collect::<Vec<_>>().join(", ")
+            ),
+        }
+    }
+
+    /// Identifies the pack's current contents: the file and when it changed
+    fn version(&self) -> Option<PackVersion> {
+        match self {
+            Self::Builtin(_) => None,
+            Self::File(path) => Some((
+                path.clone(),
+                std::fs::metadata(path).and_then(|m| m.modified()).ok(),
+            )),
+        }
+    }
+}
+
+/// A lexicon file as written. Terms are matched case-insensitively and after
+/// stemming, so `pods` finds `pod`; words of two characters or less are
+/// ignored, as they are in concept extraction.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LexiconPack {
+    pub name: String,
+    pub description: String,
+    /// Groups of interchangeable terms; the first is the one reported
+    pub synonyms: Vec<Vec<String>>,
+    /// Broader terms and the narrower terms they cover
+    pub hypernyms: Vec<Hypernym>,
+    /// Groups of terms that belong to the same subtopic
+    pub related: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Hypernym {
+    pub broader: String,
+    pub terms: Vec<String>,
+}
+
+impl LexiconPack {
+    pub fn parse(toml: &str) -> Result<Self> {
+        config::Config::builder()
+            .add_source(config::File::from_str(toml, config::FileFormat::Toml))
+            .build()?
+            .try_deserialize()
+            .map_err(Into::into)
+    }
+
+    fn from_source(name: &str, source: &PackSource) -> Result<Self> {
+        match source {
+            PackSource::Builtin(toml) => {
+                Self::parse(toml).with_context(|| format!("Invalid built-in lexicon '{}'", name))
+            }
+            PackSource::File(path) => Self::read(path),
+        }
+    }
+
+    fn read(path: &Path) -> Result<Self> {
+        let toml = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read lexicon {}", path.display()))?;
+        let mut pack =
+            Self::parse(&toml).with_context(|| format!("Invalid lexicon {}", path.display()))?;
+        if pack.name.is_empty() {
+            pack.name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+        }
+        Ok(pack)
+    }
+}
+
+/// Names of the packs that ship with lux
+pub fn builtin_lexicons() -> impl Iterator<Item = &'static str> {
+    BUILTIN_PACKS.iter().map(|(name, _)| *name)
+}
+
+/// Check the lexicons a tool call asked for. A call may name built-in packs
+/// and packs in [`lexicon_dirs`]; paths are only read from `lux.toml`. Why a
+/// pack failed to load goes to the log, not to the caller.
+pub fn check_request_lexicons(names: &[String]) -> Result<()> {
+    for name in names {
+        let plain = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !plain {
+            bail!(
+                "Invalid lexicon '{}': name a built-in pack or one in the lexicons directory",
+                name
+            );
+        }
+        PackSource::locate(name)?;
+    }
+    Lexicon::load(names).map(drop).map_err(|e| {
+        warn!("Failed to load lexicons {:?}: {:#}", names, e);
+        anyhow!(
+            "Lexicons {:?} could not be loaded; see the server log",
+            names
+        )
+    })
+}
+
+/// Directories searched for `<name>.toml`, highest precedence first:
+/// `./lexicons`, then `~/.config/lux/lexicons`
+pub fn lexicon_dirs() -> Vec<PathBuf> {
+    LuxSettings::config_paths()
+        .iter()
+        .rev()
+        .map(|path| match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.join("lexicons"),
+            _ => PathBuf::from("lexicons"),
+        })
+        .collect()
+}
+
+/// Merged, normalized packs. Terms get an id; synonyms share one.
+#[derive(Debug, Default)]
+pub struct Lexicon {
+    /// Spelling of a term (lowercase or stemmed words) to its id
+    forms: HashMap<String, usize>,
+    /// Spelling each term is reported as
+    canonical: Vec<String>,
+    /// Related-term groups each term is in
+    groups: Vec<Vec<usize>>,
+    /// Broader terms of each term
+    broader: Vec<Vec<usize>>,
+    group_count: usize,
+    /// Words in the longest term
+    max_words: usize,
+}
+
+impl Lexicon {
+    /// The `general` pack merged with the named ones. Later calls share the
+    /// result until a pack file changes.
+    pub fn load(names: &[String]) -> Result<Arc<Self>> {
+        static CACHE: OnceLock<LexiconCache> = OnceLock::new();
+
+        let mut sources = vec![("general", PackSource::locate("general")?)];
+        for name in names.iter().filter(|name| name.as_str() != "general") {
+            sources.push((name, PackSource::locate(name)?));
+        }
+        let key: CacheKey = sources
+            .iter()
+            .map(|(name, source)| (name.to_string(), source.version()))
+            .collect();
+        let cache = CACHE.get_or_init(Default::default);
+        if let Some((_, lexicon)) = cache.lock().iter().find(|(cached, _)| *cached == key) {
+            return Ok(lexicon.clone());
+        }
+
+        let packs = sources
+            .iter()
+            .map(|(name, source)| LexiconPack::from_source(name, source))
+            .collect::<Result<Vec<_>>>()?;
+        let lexicon = Arc::new(Self::from_packs(&packs));
+        let mut cache = cache.lock();
+        if cache.len() >= MAX_CACHED_LEXICONS {
+            cache.remove(0);
+        }
+        cache.push((key, lexicon.clone()));
+        Ok(lexicon)
+    }
+
+    pub fn from_packs(packs: &[LexiconPack]) -> Self {
+        let mut lexicon = Self::default();
+
+        // Synonyms first, so later mentions of any spelling find the shared id
+        for pack in packs {
+            for group in &pack.synonyms {
+                let terms: Vec<Vec<String>> = group
+                    .iter()
+                    .map(|term| normalize(term))
+                    .filter(|words| !words.is_empty())
+                    .collect();
+                let Some(first) = terms.first() else {
+                    continue;
+                };
+                let id = terms
+                    .iter()
+                    .find_map(|words| lexicon.lookup(words))
+                    .unwrap_or_else(|| lexicon.insert(first));
+                for words in &terms {
+                    lexicon.index(words, id);
+                }
+            }
+        }
+
+        for pack in packs {
+            for hypernym in &pack.hypernyms {
+                let Some(broader) = lexicon.term(&hypernym.broader) else {
+                    continue;
+                };
+                for term in &hypernym.terms {
+                    if let Some(id) = lexicon.term(term).filter(|id| *id != broader) {
+                        if !lexicon.broader[id].contains(&broader) {
+                            lexicon.broader[id].push(broader);
+                        }
+                    }
+                }
+            }
+            for group in &pack.related {
+                let group_id = lexicon.group_count;
+                lexicon.group_count += 1;
+                for term in group {
+                    if let Some(id) = lexicon.term(term) {
+                        if !lexicon.groups[id].contains(&group_id) {
+                            lexicon.groups[id].push(group_id);
+                        }
+                    }
+                }
+            }
+        }
+
+        lexicon
+    }
+
+    /// Whether a word as written (`K8s,`, `PCI-DSS`) is a term
+    pub fn contains_term(&self, word: &str) -> bool {
+        let words = normalize(word);
+        !words.is_empty() && self.lookup(&words).is_some()
+    }
+
+    /// Longest term at the start of `tokens`, as its reported spelling and the
+    /// number of tokens it covers
+    pub fn term_at<S: AsRef<str>>(&self, tokens: &[S]) -> Option<(&str, usize)> {
+        (1..=self.max_words.min(tokens.len()))
+            .rev()
+            .find_map(|len| {
+                let words: Vec<&str> = tokens[..len].iter().map(AsRef::as_ref).collect();
+                self.lookup(&words)
+                    .map(|id| (self.canonical[id].as_str(), len))
+            })
+    }
+
+    /// `concept` with every term replaced by its reported spelling, so
+    /// synonyms compare equal
+    pub fn canonicalize(&self, concept: &str) -> String {
+        let words: Vec<&str> = concept.split_whitespace().collect();
+        let mut out = Vec::with_capacity(words.len());
+        let mut i = 0;
+        while i < words.len() {
+            match self.term_at(&words[i..]) {
+                Some((term, len)) => {
+                    out.push(term);
+                    i += len;
+                }
+                None => {
+                    out.push(words[i]);
+                    i += 1;
+                }
+            }
+        }
+        out.join(" ")
+    }
+
+    /// Whether the concepts mention terms of one related group, a term and
+    /// its broader term, or two terms with a common broader term. A narrower
+    /// term is also related to the groups of its broader term.
+    pub fn related(&self, concept1: &str, concept2: &str) -> bool {
+        let terms1 = self.terms_in(concept1);
+        if terms1.is_empty() {
+            return false;
+        }
+        let terms2 = self.terms_in(concept2);
+        terms1
+            .iter()
+            .any(|&a| terms2.iter().any(|&b| self.terms_related(a, b)))
+    }
+
+    fn terms_related(&self, a: usize, b: usize) -> bool {
+        // A term also stands for its broader terms and their groups
+        let with_broader = |id: usize| std::iter::once(id).chain(self.broader[id].iter().copied());
+        with_broader(a).any(|x| {
+            with_broader(b).any(|y| {
+                x == y
+                    || self.groups[x]
+                        .iter()
+                        .any(|group| self.groups[y].contains(group))
+            })
+        })
+    }
+
+    /// Ids of every term in `concept`, including terms inside longer ones
+    fn terms_in(&self, concept: &str) -> Vec<usize> {
+        let words: Vec<&str> = concept.split_whitespace().collect();
+        let mut ids = Vec::new();
+        for start in 0..words.len() {
+            for end in start + 1..=(start + self.max_words).min(words.len()) {
+                if let Some(id) = self.lookup(&words[start..end]) {
+                    if !ids.contains(&id) {
+                        ids.push(id);
+                    }
+                }
+            }
+        }
+        ids
+    }
+
+    /// Id of a term given as normalized words, trying them as written and
+    /// then stemmed
+    fn lookup<S: AsRef<str>>(&self, words: &[S]) -> Option<usize> {
+        let (written, stemmed) = spellings(words);
+        self.forms
+            .get(&written)
+            .or_else(|| self.forms.get(&stemmed))
+            .copied()
+    }
+
+    /// Id of a term, adding it if new; `None` if nothing is left after
+    /// normalizing
+    fn term(&mut self, term: &str) -> Option<usize> {
+        let words = normalize(term);
+        if words.is_empty() {
+            return None;
+        }
+        Some(self.lookup(&words).unwrap_or_else(|| self.insert(&words)))
+    }
+
+    fn insert(&mut self, words: &[String]) -> usize {
+        let id = self.canonical.len();
+        self.canonical.push(spellings(words).1);
+        self.groups.push(Vec::new());
+        self.broader.push(Vec::new());
+        self.index(words, id);
+        id
+    }
+
+    fn index(&mut self, words: &[String], id: usize) {
+        let (written, stemmed) = spellings(words);
+        self.forms.entry(written).or_insert(id);
+        self.forms.entry(stemmed).or_insert(id);
+        self.max_words = self.max_words.max(words.len());
+    }
+}
+
+/// Words of a term, split and filtered like the detectors' tokens
+fn normalize(term: &str) -> Vec<String> {
+    Language::English.tokenize(term)
+}
+
+/// The words joined as written and with each stemmed, matching how concepts
+/// are spelled
+fn spellings<S: AsRef<str>>(words: &[S]) -> (String, String) {
+    let written: Vec<&str> = words.iter().map(AsRef::as_ref).collect();
+    let stemmed: Vec<String> = written
+        .iter()
+        .map(|word| Language::English.stem(word))
+        .collect();
+    (written.join(" "), stemmed.join(" "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_packs_parse() {
+        for name in builtin_lexicons() {
+            let source = PackSource::locate(name).unwrap();
+            let pack = LexiconPack::from_source(name, &source).unwrap();
+            assert_eq!(pack.name, name);
+            assert!(!pack.description.is_empty());
+        }
+        assert!(PackSource::locate("no-such-pack").is_err());
+    }
+
+    #[test]
+    fn test_request_lexicons_are_names_only() {
+        assert!(check_request_lexicons(&["kubernetes".to_string()]).is_ok());
+        for name in ["/etc/passwd.toml", "../secrets", "no-such-pack"] {
+            assert!(check_request_lexicons(&[name.to_string()]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_changed_pack_file_is_read_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ledger.toml");
+        let names = [path.display().to_string()];
+
+        std::fs::write(&path, "synonyms = [[\"ledger\", \"journal\"]]").unwrap();
+        assert!(Lexicon::load(&names).unwrap().contains_term("journal"));
+
+        std::fs::write(&path, "synonyms = [[\"ledger\", \"register\"]]").unwrap();
+        let modified = SystemTime::now() + std::time::Duration::from_secs(5);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        let reloaded = Lexicon::load(&names).unwrap();
+        assert!(reloaded.contains_term("register"));
+        assert!(!reloaded.contains_term("journal"));
+    }
+
+    #[test]
+    fn test_synonyms_and_relations() {
+        let pack = LexiconPack::parse(
+            r#"
+            name = "test"
+            synonyms = [["kubernetes", "k8s"], ["horizontal pod autoscaler", "hpa"]]
+            related = [["deployment", "rollout"]]
+
+            [[hypernyms]]
+            broader = "workload"
+            terms = ["pod", "deployment"]
+            "#,
+        )
+        .unwrap();
+        let lexicon = Lexicon::from_packs(&[pack]);
+
+        assert_eq!(lexicon.canonicalize("k8s cluster"), "kubernete cluster");
+        assert_eq!(
+            lexicon.term_at(&["horizontal", "pods", "autoscaler", "target"]),
+            Some(("horizontal pod autoscaler", 3))
+        );
+        assert!(lexicon.contains_term("HPA,"));
+
+        // Group, broader term and sibling under a common broader term
+        assert!(lexicon.related("deployment", "staged rollout"));
+        assert!(lexicon.related("pod", "workload"));
+        assert!(lexicon.related("pod", "deployment"));
+        assert!(!lexicon.related("pod", "rollout"));
+        assert!(!lexicon.related("pod", "banana"));
+    }
+}
@@ -1,14 +1,245 @@
+use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::VecDeque;
+use serde_json::Value;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Arc;
 
-mod circular_reasoning;
-use circular_reasoning::{CircularPattern, CircularReasoningDetector};
+use crate::llm::embeddings::Embedder;
+use grounding::GroundingSources;
+use policy::{Facts, PolicyAction, PolicyDecision, PolicyRule};
+use session_memory::{SessionMemory, ThoughtFingerprint};
+use subgoals::{CoverageMap, SubgoalTracker};
 
+mod change_point;
+mod circular_reasoning;
 mod distractor_fixation;
-use distractor_fixation::DistractorFixationDetector;
-
 mod quality_degradation;
-use quality_degradation::{DegradationAnalysis, DegradationPattern, QualityDegradationDetector};
+
+pub mod calibration;
+pub mod contradiction;
+pub mod detector;
+pub mod grounding;
+pub mod language;
+pub mod lexicon;
+pub mod policy;
+pub mod session_memory;
+pub mod subgoals;
+pub use detector::{
+    DetectorFactory, DetectorRegistry, DetectorSignal, ThoughtContext, ThoughtDetector,
+};
+
+/// Named threshold sets. `strict` intervenes earlier, `lenient` later.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MonitoringPreset {
+    Strict,
+    #[default]
+    Balanced,
+    Lenient,
+}
+
+/// Detection thresholds for the metacognitive monitor. Deserializes from
+/// [`MonitoringOverrides`], so a config section can name a preset and
+/// override individual values on top of it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "MonitoringOverrides")]
+pub struct MonitoringConfig {
+    /// Preset the values started from
+    pub preset: MonitoringPreset,
+    /// Embedding similarity at which a thought counts as repeating an earlier one
+    pub circular_similarity_threshold: f64,
+    /// Circular score above which an intervention is raised
+    pub circular_intervention_threshold: f64,
+    /// Average relevance to the original query below which recent thoughts count as drifting
+    pub relevance_threshold: f64,
+    /// Detail density above which a thought counts as fixating on details
+    pub detail_density_threshold: f64,
+    /// Thoughts the distractor detector looks back over
+    pub distractor_window: usize,
+    /// Declines (as fractions) that count as quality degradation
+    pub vocab_decline_threshold: f64,
+    pub coherence_decline_threshold: f64,
+    pub reasoning_decline_threshold: f64,
+    /// Thoughts needed before degradation is judged
+    pub degradation_min_thoughts: usize,
+    /// Accumulated fall of a quality metric, in noise units, that counts as a
+    /// sudden drop
+    pub sudden_drop_threshold: f64,
+    /// Circular score above which the metachain warns about walking in circles
+    pub metachain_circular_threshold: f64,
+    /// Built-in detectors to leave out (`circular_reasoning`, `distractor_fixation`,
+    /// `quality_degradation`, `grounding`)
+    pub disabled_detectors: Vec<String>,
+    /// Intervention rules checked after the detectors; the first match replaces
+    /// their intervention
+    pub rules: Vec<PolicyRule>,
+    /// Domain lexicons for concept matching, on top of the built-in `general`
+    /// pack: built-in names (`kubernetes`, `payments`, ...), `<name>.toml` in
+    /// a lexicon directory, or paths to `.toml` files
+    pub lexicons: Vec<String>,
+}
+
+impl MonitoringConfig {
+    pub fn preset(preset: MonitoringPreset) -> Self {
+        match preset {
+            MonitoringPreset::Strict => Self {
+                preset,
+                circular_similarity_threshold: 0.5,
+                circular_intervention_threshold: 0.4,
+                relevance_threshold: 0.3,
+                detail_density_threshold: 0.4,
+                distractor_window: 5,
+                vocab_decline_threshold: 0.15,
+                coherence_decline_threshold: 0.2,
+                reasoning_decline_threshold: 0.25,
+                degradation_min_thoughts: 3,
+                sudden_drop_threshold: 3.0,
+                metachain_circular_threshold: 0.75,
+                disabled_detectors: Vec::new(),
+                rules: Vec::new(),
+                lexicons: Vec::new(),
+            },
+            MonitoringPreset::Balanced => Self {
+                preset,
+                circular_similarity_threshold: 0.6,
+                circular_intervention_threshold: 0.5,
+                relevance_threshold: 0.25,
+                detail_density_threshold: 0.5,
+                distractor_window: 5,
+                vocab_decline_threshold: 0.2,
+                coherence_decline_threshold: 0.25,
+                reasoning_decline_threshold: 0.3,
+                degradation_min_thoughts: 3,
+                sudden_drop_threshold: 4.0,
+                metachain_circular_threshold: 0.85,
+                disabled_detectors: Vec::new(),
+                rules: Vec::new(),
+                lexicons: Vec::new(),
+            },
+            MonitoringPreset::Lenient => Self {
+                preset,
+                circular_similarity_threshold: 0.7,
+                circular_intervention_threshold: 0.65,
+                relevance_threshold: 0.2,
+                detail_density_threshold: 0.65,
+                distractor_window: 6,
+                vocab_decline_threshold: 0.3,
+                coherence_decline_threshold: 0.35,
+                reasoning_decline_threshold: 0.4,
+                degradation_min_thoughts: 4,
+                sudden_drop_threshold: 5.0,
+                metachain_circular_threshold: 0.9,
+                disabled_detectors: Vec::new(),
+                rules: Vec::new(),
+                lexicons: Vec::new(),
+            },
+        }
+    }
+
+    /// These values with `overrides` applied. A preset in `overrides` replaces
+    /// every threshold first; disabled detectors, rules and lexicons are kept
+    /// unless overridden too.
+    pub fn with_overrides(&self, overrides: &MonitoringOverrides) -> Self {
+        let mut config = match overrides.preset {
+            Some(preset) => Self {
+                disabled_detectors: self.disabled_detectors.clone(),
+                rules: self.rules.clone(),
+                lexicons: self.lexicons.clone(),
+                ..Self::preset(preset)
+            },
+            None => self.clone(),
+        };
+
+        fn set<T: Clone>(target: &mut T, value: &Option<T>) {
+            if let Some(value) = value {
+                *target = value.clone();
+            }
+        }
+        set(
+            &mut config.circular_similarity_threshold,
+            &overrides.circular_similarity_threshold,
+        );
+        set(
+            &mut config.circular_intervention_threshold,
+            &overrides.circular_intervention_threshold,
+        );
+        set(
+            &mut config.relevance_threshold,
+            &overrides.relevance_threshold,
+        );
+        set(
+            &mut config.detail_density_threshold,
+            &overrides.detail_density_threshold,
+        );
+        set(&mut config.distractor_window, &overrides.distractor_window);
+        set(
+            &mut config.vocab_decline_threshold,
+            &overrides.vocab_decline_threshold,
+        );
+        set(
+            &mut config.coherence_decline_threshold,
+            &overrides.coherence_decline_threshold,
+        );
+        set(
+            &mut config.reasoning_decline_threshold,
+            &overrides.reasoning_decline_threshold,
+        );
+        set(
+            &mut config.degradation_min_thoughts,
+            &overrides.degradation_min_thoughts,
+        );
+        set(
+            &mut config.sudden_drop_threshold,
+            &overrides.sudden_drop_threshold,
+        );
+        set(
+            &mut config.metachain_circular_threshold,
+            &overrides.metachain_circular_threshold,
+        );
+        set(
+            &mut config.disabled_detectors,
+            &overrides.disabled_detectors,
+        );
+        set(&mut config.rules, &overrides.rules);
+        set(&mut config.lexicons, &overrides.lexicons);
+        config
+    }
+}
+
+impl Default for MonitoringConfig {
+    fn default() -> Self {
+        Self::preset(MonitoringPreset::Balanced)
+    }
+}
+
+/// A preset and individual thresholds to apply on top of it, as written in the
+/// `[monitoring]` section of `lux.toml` or passed as a tool call's `monitoring`
+/// parameter. Unset fields keep their current value.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MonitoringOverrides {
+    pub preset: Option<MonitoringPreset>,
+    pub circular_similarity_threshold: Option<f64>,
+    pub circular_intervention_threshold: Option<f64>,
+    pub relevance_threshold: Option<f64>,
+    pub detail_density_threshold: Option<f64>,
+    pub distractor_window: Option<usize>,
+    pub vocab_decline_threshold: Option<f64>,
+    pub coherence_decline_threshold: Option<f64>,
+    pub reasoning_decline_threshold: Option<f64>,
+    pub degradation_min_thoughts: Option<usize>,
+    pub sudden_drop_threshold: Option<f64>,
+    pub metachain_circular_threshold: Option<f64>,
+    pub disabled_detectors: Option<Vec<String>>,
+    pub rules: Option<Vec<PolicyRule>>,
+    pub lexicons: Option<Vec<String>>,
+}
+
+impl From<MonitoringOverrides> for MonitoringConfig {
+    fn from(overrides: MonitoringOverrides) -> Self {
+        Self::default().with_overrides(&overrides)
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitoringSignals {
@@ -17,6 +248,16 @@ pub struct MonitoringSignals {
     pub quality_trend: String,
     pub phase: String,
     pub intervention: Option<String>,
+    /// Type of the raised intervention, e.g. `circular_reasoning`
+    pub intervention_type: Option<String>,
+    /// Every detector's signal for this thought, in registration order
+    pub detector_signals: Vec<DetectorSignal>,
+    /// Rule that decided the intervention, if one matched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decision: Option<PolicyDecision>,
+    /// Which subgoals of the query the thoughts addressed so far
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<CoverageMap>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -37,140 +278,359 @@ pub struct QualityMetrics {
     pub trend: String,
 }
 
+/// Quality readings of the latest thought analyzed. Scores are in [0, 1];
+/// for the first three, higher is worse.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonitorMetrics {
+    pub thought_number: usize,
+    /// Similarity to earlier thoughts
+    pub circular_score: f64,
+    /// 1.0 while the thoughts fixate on a distractor
+    pub distractor_score: f64,
+    /// Decline of quality over the session
+    pub degradation_score: f64,
+    /// Use of connectives that structure an argument
+    pub coherence: f64,
+    pub information_density: f64,
+    /// Reasoning indicators and logical structure
+    pub reasoning_depth: f64,
+    /// Relevance to the session's first thought
+    pub relevance: f64,
+    /// `stable`, `declining` or `degrading`
+    pub trend: String,
+    /// Interventions recorded this session
+    pub interventions: usize,
+}
+
+impl MonitorMetrics {
+    fn from_signals(thought_number: usize, signals: &MonitoringSignals) -> Self {
+        let signal = |name: &str| signals.detector_signals.iter().find(|s| s.detector == name);
+        let reading = |name: &str, key: &str, default: f64| {
+            signal(name)
+                .and_then(|s| s.details[key].as_f64())
+                .unwrap_or(default)
+        };
+        Self {
+            thought_number,
+            circular_score: signals.circular_score,
+            distractor_score: signal(detector::DISTRACTOR_FIXATION).map_or(0.0, |s| s.score),
+            degradation_score: signal(detector::QUALITY_DEGRADATION).map_or(0.0, |s| s.score),
+            coherence: reading(detector::QUALITY_DEGRADATION, "coherence", 0.0),
+            information_density: reading(detector::QUALITY_DEGRADATION, "information_density", 0.0),
+            reasoning_depth: reading(detector::QUALITY_DEGRADATION, "reasoning_depth", 0.0),
+            relevance: reading(detector::DISTRACTOR_FIXATION, "relevance", 1.0),
+            trend: signals.quality_trend.clone(),
+            interventions: 0,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InterventionRecord {
     pub thought_number: usize,
     pub intervention_type: String,
     pub reason: String,
+    /// Rule that matched, and what it asked for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<PolicyAction>,
+}
+
+/// Everything a monitor knows about its session, for persisting it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonitorSnapshot {
+    pub config: MonitoringConfig,
+    pub thought_history: Vec<String>,
+    pub intervention_history: Vec<InterventionRecord>,
+    pub quality_scores: Vec<f64>,
+    pub memory: SessionMemory,
+    /// State of detectors that keep any, by detector name
+    #[serde(default)]
+    pub detectors: BTreeMap<String, Value>,
+    #[serde(default)]
+    pub subgoals: SubgoalTracker,
+    #[serde(default)]
+    pub metrics: Option<MonitorMetrics>,
 }
 
 pub struct MetacognitiveMonitor {
     thought_history: VecDeque<String>,
     intervention_history: Vec<InterventionRecord>,
     quality_scores: VecDeque<f64>,
-    circular_detector: CircularReasoningDetector,
-    distractor_detector: DistractorFixationDetector,
-    quality_degradation_detector: QualityDegradationDetector,
+    /// Fingerprints and summaries of the whole session
+    memory: SessionMemory,
+    /// Requirements of the query and which thoughts addressed them
+    subgoals: SubgoalTracker,
+    /// Readings of the latest thought
+    metrics: Option<MonitorMetrics>,
+    /// Files the session's thoughts are checked against
+    sources: GroundingSources,
+    detectors: DetectorRegistry,
+    embedder: Arc<Embedder>,
+    /// Thresholds the session was created with
+    session_config: MonitoringConfig,
+    /// Thresholds in effect: the session's, with the current call's overrides
+    config: MonitoringConfig,
 }
 
 impl MetacognitiveMonitor {
     pub fn new() -> Self {
+        Self::with_config(MonitoringConfig::default())
+    }
+
+    pub fn with_config(config: MonitoringConfig) -> Self {
+        Self::with_embedder(config, Arc::new(Embedder::local()))
+    }
+
+    pub fn with_embedder(config: MonitoringConfig, embedder: Arc<Embedder>) -> Self {
         Self {
             thought_history: VecDeque::with_capacity(10),
             intervention_history: Vec::new(),
             quality_scores: VecDeque::with_capacity(5),
-            circular_detector: CircularReasoningDetector::new(),
-            distractor_detector: DistractorFixationDetector::new(),
-            quality_degradation_detector: QualityDegradationDetector::new(),
+            memory: SessionMemory::default(),
+            subgoals: SubgoalTracker::default(),
+            metrics: None,
+            sources: GroundingSources::default(),
+            detectors: DetectorRegistry::builtin(&config),
+            embedder,
+            session_config: config.clone(),
+            config,
+        }
+    }
+
+    /// Thresholds in effect for the next thought
+    pub fn config(&self) -> &MonitoringConfig {
+        &self.config
+    }
+
+    /// Use the session's thresholds with one call's overrides applied; `None`
+    /// goes back to the session's own. Detector state is kept either way.
+    /// Fails on lexicons a tool call may not name.
+    pub fn apply_overrides(&mut self, overrides: Option<&MonitoringOverrides>) -> Result<()> {
+        if let Some(lexicons) = overrides.and_then(|o| o.lexicons.as_ref()) {
+            lexicon::check_request_lexicons(lexicons)?;
+        }
+        let config = match overrides {
+            Some(overrides) => self.session_config.with_overrides(overrides),
+            None => self.session_config.clone(),
+        };
+        if config != self.config {
+            self.detectors.configure(&config);
+            self.config = config;
+        }
+        Ok(())
+    }
+
+    /// Texts the next thought is compared with: the recent thoughts and what
+    /// the detectors keep, e.g. the original query. Prefetch their embeddings
+    /// together with the thought's.
+    pub fn compared_texts(&self) -> Vec<String> {
+        let mut texts = self.recent_thoughts();
+        texts.extend(self.detectors.compared_texts());
+        texts
+    }
+
+    /// The last few thoughts analyzed, oldest first
+    pub fn recent_thoughts(&self) -> Vec<String> {
+        self.thought_history.iter().cloned().collect()
+    }
+
+    /// Quality readings of the latest thought, or `None` before the first
+    pub fn metrics(&self) -> Option<MonitorMetrics> {
+        self.metrics.clone().map(|metrics| MonitorMetrics {
+            interventions: self.intervention_history.len(),
+            ..metrics
+        })
+    }
+
+    /// Fingerprints and rolling summaries of every thought so far
+    pub fn memory(&self) -> &SessionMemory {
+        &self.memory
+    }
+
+    /// Serializable copy of the session state, taken between thoughts
+    pub fn snapshot(&self) -> MonitorSnapshot {
+        MonitorSnapshot {
+            config: self.session_config.clone(),
+            thought_history: self.thought_history.iter().cloned().collect(),
+            intervention_history: self.intervention_history.clone(),
+            quality_scores: self.quality_scores.iter().copied().collect(),
+            memory: self.memory.clone(),
+            detectors: self.detectors.snapshot(),
+            subgoals: self.subgoals.clone(),
+            metrics: self.metrics.clone(),
         }
     }
 
+    /// Continue a session from a snapshot. Detectors are kept as registered;
+    /// those named in the snapshot get their state back.
+    pub fn restore(&mut self, snapshot: MonitorSnapshot) -> Result<()> {
+        self.detectors.configure(&snapshot.config);
+        self.detectors.restore(snapshot.detectors)?;
+        self.session_config = snapshot.config.clone();
+        self.config = snapshot.config;
+        self.thought_history = snapshot.thought_history.into();
+        self.intervention_history = snapshot.intervention_history;
+        self.quality_scores = snapshot.quality_scores.into();
+        self.memory = snapshot.memory;
+        self.subgoals = snapshot.subgoals;
+        self.metrics = snapshot.metrics;
+        Ok(())
+    }
+
+    /// Detectors run on every thought; register domain detectors here
+    pub fn detectors_mut(&mut self) -> &mut DetectorRegistry {
+        &mut self.detectors
+    }
+
+    /// Track coverage of these subgoals from the next thought on
+    pub fn set_subgoals(&mut self, subgoals: SubgoalTracker) {
+        self.subgoals = subgoals;
+    }
+
+    /// Check the following thoughts against these files. Sources are not part
+    /// of a snapshot: tools set them again whenever they load `file_paths`.
+    pub fn set_sources(&mut self, sources: GroundingSources) {
+        self.sources = sources;
+    }
+
+    /// Match concepts with these domain lexicons for the rest of the session.
+    /// Fails on a lexicon that can't be found or read, or that a tool call
+    /// may not name.
+    pub fn set_lexicons(&mut self, lexicons: Vec<String>) -> Result<()> {
+        lexicon::check_request_lexicons(&lexicons)?;
+        self.session_config.lexicons = lexicons.clone();
+        if self.config.lexicons != lexicons {
+            self.config.lexicons = lexicons;
+            self.detectors.configure(&self.config);
+        }
+        Ok(())
+    }
+
     pub fn reset_session(&mut self) {
         self.thought_history.clear();
         self.intervention_history.clear();
         self.quality_scores.clear();
-        self.distractor_detector.reset_session();
-        self.quality_degradation_detector.reset_session();
+        self.memory.clear();
+        self.subgoals = SubgoalTracker::default();
+        self.metrics = None;
+        self.sources = GroundingSources::default();
+        self.detectors.reset_session();
     }
 
     pub fn analyze_thought(&mut self, thought: &str, thought_number: usize) -> MonitoringSignals {
-        // Set original query on first substantial thought
-        if !self.distractor_detector.has_original_concepts() && !thought.trim().is_empty() {
-            self.distractor_detector.set_original_query(thought);
-        }
-
-        // Calculate metrics BEFORE adding to history
-        let circular_score = self.detect_circular_reasoning(thought);
-        let distractor_alert = self.detect_distractor_fixation(thought);
-
-        // Use advanced quality degradation analysis
-        let degradation_analysis = self
-            .quality_degradation_detector
-            .analyze_thought(thought, thought_number);
-        let quality_trend = self.determine_quality_trend(&degradation_analysis);
+        // Detectors compare against history BEFORE the thought is added to it
+        let history: Vec<String> = self.thought_history.iter().cloned().collect();
+        let fingerprint = ThoughtFingerprint::new(thought, thought_number);
+        let detector_signals = self.detectors.analyze(&ThoughtContext {
+            thought,
+            thought_number,
+            history: &history,
+            embedder: &self.embedder,
+            memory: &self.memory,
+            fingerprint: &fingerprint,
+            sources: &self.sources,
+        });
+        self.memory.record(fingerprint);
+        self.subgoals.observe(thought, thought_number);
 
-        // Add to history AFTER calculating metrics
         self.thought_history.push_back(thought.to_string());
         if self.thought_history.len() > 10 {
             self.thought_history.pop_front();
         }
 
+        let signal = |name: &str| detector_signals.iter().find(|s| s.detector == name);
+        let circular_score = signal(detector::CIRCULAR_REASONING).map_or(0.0, |s| s.score);
+        let distractor_alert = signal(detector::DISTRACTOR_FIXATION).is_some_and(|s| s.triggered);
+        let degradation = signal(detector::QUALITY_DEGRADATION);
+        let quality_trend = degradation
+            .and_then(|s| s.details["trend"].as_str())
+            .unwrap_or("stable")
+            .to_string();
+
         // Store quality score for backward compatibility
-        let quality_score = 1.0 - degradation_analysis.overall_score;
-        self.quality_scores.push_back(quality_score);
-        if self.quality_scores.len() > 5 {
-            self.quality_scores.pop_front();
+        if let Some(degradation) = degradation {
+            self.quality_scores.push_back(1.0 - degradation.score);
+            if self.quality_scores.len() > 5 {
+                self.quality_scores.pop_front();
+            }
         }
 
-        // Determine phase and intervention with degradation analysis
-        let (phase, intervention) = self.determine_intervention_with_degradation(
-            circular_score,
-            distractor_alert,
-            &degradation_analysis,
-            thought_number,
-        );
-
-        MonitoringSignals {
+        let winner = detector::strongest(&detector_signals);
+        let phase = winner.map_or("exploration".to_string(), |w| {
+            w.phase.clone().unwrap_or_else(|| "development".to_string())
+        });
+        let intervention = winner.and_then(|w| w.message.clone());
+        let intervention_type = winner.map(|w| {
+            w.intervention_type
+                .clone()
+                .unwrap_or_else(|| w.detector.clone())
+        });
+        let reason = winner.and_then(|w| w.reason.clone());
+        let mut signals = MonitoringSignals {
             circular_score,
             distractor_alert,
             quality_trend,
             phase,
             intervention,
-        }
-    }
-
-    fn detect_circular_reasoning(&self, current_thought: &str) -> f64 {
-        // Use advanced circular reasoning detection
-        if self.thought_history.is_empty() {
-            return 0.0;
-        }
-
-        // Convert VecDeque to Vec for the detector
-        let history: Vec<String> = self.thought_history.iter().cloned().collect();
+            intervention_type,
+            detector_signals,
+            decision: None,
+            coverage: (!self.subgoals.is_empty()).then(|| self.subgoals.coverage()),
+        };
 
-        // Detect circular patterns
-        let pattern = self
-            .circular_detector
-            .detect_pattern(current_thought, &history);
-
-        // Convert pattern to score
-        match pattern {
-            CircularPattern::None => 0.0,
-            CircularPattern::Direct {
-                similarity_score, ..
-            } => similarity_score,
-            CircularPattern::Cyclic { cycle_strength, .. } => cycle_strength * 0.9, // Slightly lower weight for cycles
-            CircularPattern::Conceptual { average_similarity } => average_similarity * 0.8, // Lower weight for conceptual loops
+        let earlier_interventions = self
+            .intervention_history
+            .iter()
+            .filter(|r| r.thought_number != thought_number)
+            .count();
+        let facts = Facts::from_signals(thought_number, &signals)
+            .with("interventions", earlier_interventions);
+        signals.decision = policy::evaluate(&self.config.rules, &facts)
+            .into_iter()
+            .next();
+
+        match &signals.decision {
+            Some(decision) => {
+                let intervention_type = signals
+                    .intervention_type
+                    .get_or_insert_with(|| "policy".to_string())
+                    .clone();
+                if signals.phase == "exploration" {
+                    signals.phase = "development".to_string();
+                }
+                signals.intervention = Some(decision.message.clone());
+                self.record_intervention(InterventionRecord {
+                    thought_number,
+                    intervention_type,
+                    reason: reason.unwrap_or_else(|| format!("Rule '{}' matched", decision.rule)),
+                    rule: Some(decision.rule.clone()),
+                    action: Some(decision.action),
+                });
+            }
+            None => {
+                if let Some(intervention_type) = &signals.intervention_type {
+                    self.record_intervention(InterventionRecord {
+                        thought_number,
+                        intervention_type: intervention_type.clone(),
+                        reason: reason.unwrap_or_default(),
+                        rule: None,
+                        action: None,
+                    });
+                }
+            }
         }
-    }
 
-    fn detect_distractor_fixation(&mut self, thought: &str) -> bool {
-        // Use advanced distractor fixation detection
-        let (is_distracted, _pattern) = self.distractor_detector.detect_fixation(thought);
-        is_distracted
+        self.metrics = Some(MonitorMetrics::from_signals(thought_number, &signals));
+        signals
     }
 
-    fn determine_quality_trend(&self, analysis: &DegradationAnalysis) -> String {
-        // Determine trend based on degradation patterns
-        if analysis.degradation_patterns.is_empty() {
-            return "stable".to_string();
-        }
-
-        // Check for severe degradation patterns
-        let has_severe = analysis.degradation_patterns.iter().any(|p| match p {
-            DegradationPattern::CognitiveFatigue { .. } => true,
-            DegradationPattern::ReasoningSimplification { depth_decline, .. } => {
-                depth_decline > &0.4
-            }
-            _ => false,
-        });
-
-        if has_severe || analysis.overall_score > 0.6 {
-            "degrading".to_string()
-        } else if analysis.overall_score > 0.3 {
-            "declining".to_string()
-        } else {
-            "stable".to_string()
+    /// Analyzing the same thought again replaces its record
+    fn record_intervention(&mut self, record: InterventionRecord) {
+        match self.intervention_history.last_mut() {
+            Some(last) if last.thought_number == record.thought_number => *last = record,
+            _ => self.intervention_history.push(record),
         }
     }
 
@@ -240,145 +700,6 @@ impl MetacognitiveMonitor {
         score.clamp(0.0, 1.0)
     }
 
-    fn determine_intervention_with_degradation(
-        &mut self,
-        circular_score: f64,
-        distractor_alert: bool,
-        degradation_analysis: &DegradationAnalysis,
-        thought_number: usize,
-    ) -> (String, Option<String>) {
-        let mut phase = "exploration".to_string();
-        let mut intervention = None;
-
-        // Check circular reasoning first (highest priority)
-        if circular_score > 0.5 {
-            phase = "overthinking".to_string();
-            intervention =
-                Some("Consider breaking out of this loop with a new perspective.".to_string());
-            self.intervention_history.push(InterventionRecord {
-                thought_number,
-                intervention_type: "circular_reasoning".to_string(),
-                reason: format!("High circular score: {:.2}", circular_score),
-            });
-        }
-        // Check distractor fixation
-        else if distractor_alert {
-            phase = "distracted".to_string();
-            intervention = Some("Refocus on the core problem statement.".to_string());
-            self.intervention_history.push(InterventionRecord {
-                thought_number,
-                intervention_type: "distractor_fixation".to_string(),
-                reason: "Excessive detail or tangential content detected".to_string(),
-            });
-        }
-        // Check quality degradation patterns
-        else if !degradation_analysis.degradation_patterns.is_empty() {
-            // Find the most severe pattern
-            let most_severe = degradation_analysis
-                .degradation_patterns
-                .iter()
-                .max_by_key(|p| match p {
-                    DegradationPattern::CognitiveFatigue { .. } => 4,
-                    DegradationPattern::ReasoningSimplification { .. } => 3,
-                    DegradationPattern::CoherenceBreakdown { .. } => 2,
-                    DegradationPattern::VocabularyDecline { .. } => 1,
-                });
-
-            if let Some(pattern) = most_severe {
-                match pattern {
-                    DegradationPattern::CognitiveFatigue { .. } => {
-                        phase = "fatigue".to_string();
-                        if let Some(recommendation) = degradation_analysis.recommendations.first() {
-                            intervention = Some(recommendation.clone());
-                        } else {
-                            intervention = Some("Cognitive fatigue detected. Consider taking a break or concluding your analysis.".to_string());
-                        }
-                        self.intervention_history.push(InterventionRecord {
-                            thought_number,
-                            intervention_type: "cognitive_fatigue".to_string(),
-                            reason: pattern.description(),
-                        });
-                    }
-                    DegradationPattern::ReasoningSimplification { .. } => {
-                        phase = "simplifying".to_string();
-                        intervention = Some(
-                            "Your reasoning is becoming simplified. Return to deeper analysis."
-                                .to_string(),
-                        );
-                        self.intervention_history.push(InterventionRecord {
-                            thought_number,
-                            intervention_type: "reasoning_simplification".to_string(),
-                            reason: pattern.description(),
-                        });
-                    }
-                    DegradationPattern::CoherenceBreakdown { .. } => {
-                        phase = "fragmenting".to_string();
-                        intervention = Some("Your thoughts are becoming fragmented. Focus on clear connections between ideas.".to_string());
-                        self.intervention_history.push(InterventionRecord {
-                            thought_number,
-                            intervention_type: "coherence_breakdown".to_string(),
-                            reason: pattern.description(),
-                        });
-                    }
-                    DegradationPattern::VocabularyDecline { .. } => {
-                        phase = "repetitive".to_string();
-                        intervention = Some("Your vocabulary is becoming repetitive. Try to express ideas with more variety.".to_string());
-                        self.intervention_history.push(InterventionRecord {
-                            thought_number,
-                            intervention_type: "vocabulary_decline".to_string(),
-                            reason: pattern.description(),
-                        });
-                    }
-                }
-            }
-        }
-
-        (phase, intervention)
-    }
-
-    fn determine_intervention(
-        &mut self,
-        circular_score: f64,
-        distractor_alert: bool,
-        quality_trend: &str,
-        thought_number: usize,
-    ) -> (String, Option<String>) {
-        let mut phase = "exploration".to_string();
-        let mut intervention = None;
-
-        if circular_score > 0.5 {
-            // Lowered threshold to match updated detection
-            phase = "overthinking".to_string();
-            intervention =
-                Some("Consider breaking out of this loop with a new perspective.".to_string());
-            self.intervention_history.push(InterventionRecord {
-                thought_number,
-                intervention_type: "circular_reasoning".to_string(),
-                reason: format!("High circular score: {:.2}", circular_score),
-            });
-        } else if distractor_alert {
-            phase = "distracted".to_string();
-            intervention = Some("Refocus on the core problem statement.".to_string());
-            self.intervention_history.push(InterventionRecord {
-                thought_number,
-                intervention_type: "distractor_fixation".to_string(),
-                reason: "Excessive detail or tangential content detected".to_string(),
-            });
-        } else if quality_trend == "degrading" {
-            phase = "fatigue".to_string();
-            intervention = Some(
-                "Quality declining. Consider consolidating insights and concluding.".to_string(),
-            );
-            self.intervention_history.push(InterventionRecord {
-                thought_number,
-                intervention_type: "quality_degradation".to_string(),
-                reason: "Consistent decline in reasoning quality".to_string(),
-            });
-        }
-
-        (phase, intervention)
-    }
-
     pub fn get_status(&self) -> MonitoringStatus {
         let latest_quality = self.quality_scores.back().cloned().unwrap_or(0.5);
         let quality_trend = if self.quality_scores.len() >= 3 {
@@ -400,15 +721,16 @@ impl MetacognitiveMonitor {
             "insufficient_data".to_string()
         };
 
+        let metrics = self.metrics.as_ref();
         MonitoringStatus {
             cognitive_load: self.calculate_cognitive_load(),
             current_phase: self.determine_current_phase(),
-            circular_reasoning_score: 0.0, // Would be calculated from recent thoughts
-            distractor_fixation_score: 0.0, // Would be calculated from recent thoughts
+            circular_reasoning_score: metrics.map_or(0.0, |m| m.circular_score),
+            distractor_fixation_score: metrics.map_or(0.0, |m| m.distractor_score),
             quality_metrics: QualityMetrics {
-                coherence: latest_quality,
-                information_density: 0.5, // Placeholder
-                relevance: 0.8,           // Placeholder
+                coherence: metrics.map_or(latest_quality, |m| m.coherence),
+                information_density: metrics.map_or(0.0, |m| m.information_density),
+                relevance: metrics.map_or(1.0, |m| m.relevance),
                 trend: quality_trend,
             },
             intervention_history: self.intervention_history.clone(),
@@ -443,6 +765,7 @@ mod tests {
     #[test]
     fn test_circular_reasoning_detection() {
         let mut monitor = MetacognitiveMonitor::new();
+        assert!(monitor.metrics().is_none());
 
         // First thought
         let signals1 = monitor.analyze_thought(
@@ -456,6 +779,11 @@ mod tests {
             monitor.analyze_thought("To understand recursion, you must understand recursion", 2);
         assert!(signals2.circular_score > 0.5); // Should detect similarity (adjusted for conceptual detection)
         assert!(signals2.intervention.is_some());
+
+        let metrics = monitor.metrics().unwrap();
+        assert_eq!(metrics.thought_number, 2);
+        assert_eq!(metrics.circular_score, signals2.circular_score);
+        assert_eq!(metrics.interventions, 1);
     }
 
     #[test]
@@ -473,6 +801,104 @@ mod tests {
         assert_eq!(signals.quality_trend, "degrading");
     }
 
+    #[test]
+    fn test_disabled_detector_is_skipped() {
+        let mut monitor = MetacognitiveMonitor::with_config(MonitoringConfig {
+            disabled_detectors: vec!["circular_reasoning".to_string()],
+            ..Default::default()
+        });
+
+        monitor.analyze_thought(
+            "Understanding recursion requires understanding recursion",
+            1,
+        );
+        let signals =
+            monitor.analyze_thought("To understand recursion, you must understand recursion", 2);
+        assert_eq!(signals.circular_score, 0.0);
+        assert_ne!(
+            signals.intervention_type.as_deref(),
+            Some("circular_reasoning")
+        );
+        assert!(!monitor.detectors_mut().contains("circular_reasoning"));
+    }
+
+    #[test]
+    fn test_preset_with_overrides() {
+        let config: MonitoringConfig = serde_json::from_value(serde_json::json!({
+            "preset": "strict",
+            "relevance_threshold": 0.3
+        }))
+        .unwrap();
+        assert_eq!(config.preset, MonitoringPreset::Strict);
+        assert_eq!(config.relevance_threshold, 0.3);
+        assert_eq!(
+            config.circular_intervention_threshold,
+            MonitoringConfig::preset(MonitoringPreset::Strict).circular_intervention_threshold
+        );
+        assert!(serde_json::from_value::<MonitoringOverrides>(
+            serde_json::json!({ "relevance": 0.3 })
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_call_overrides_apply_to_one_call() {
+        let mut monitor = MetacognitiveMonitor::new();
+        monitor
+            .apply_overrides(Some(&MonitoringOverrides {
+                preset: Some(MonitoringPreset::Lenient),
+                distractor_window: Some(8),
+                ..Default::default()
+            }))
+            .unwrap();
+        assert_eq!(monitor.config().preset, MonitoringPreset::Lenient);
+        assert_eq!(monitor.config().distractor_window, 8);
+
+        monitor.apply_overrides(None).unwrap();
+        assert_eq!(monitor.config(), &MonitoringConfig::default());
+    }
+
+    #[test]
+    fn test_restored_monitor_catches_long_period_return() {
+        let thoughts = [
+            "The retry queue drops messages when the broker restarts",
+            "Index fragmentation slows nightly reporting jobs",
+            "Stylesheet bundling delays the first paint",
+            "Canary releases limit the blast radius of regressions",
+            "Structured logging makes incident timelines searchable",
+            "Product listings belong in a read-through cache",
+            "Refresh tokens rotate after every successful exchange",
+            "Payment webhooks need idempotency keys",
+            "Feature flags decouple deployment from release",
+            "Latency percentiles reveal tail behaviour that averages hide",
+            "Column renames require expand and contract migrations",
+        ];
+        let mut monitor = MetacognitiveMonitor::new();
+        for (i, thought) in thoughts.iter().enumerate() {
+            monitor.analyze_thought(thought, i + 1);
+        }
+
+        let json = serde_json::to_string(&monitor.snapshot()).unwrap();
+        let mut restored = MetacognitiveMonitor::new();
+        restored
+            .restore(serde_json::from_str(&json).unwrap())
+            .unwrap();
+        assert_eq!(restored.memory().summaries().len(), 1);
+        assert!(restored
+            .snapshot()
+            .detectors
+            .contains_key(detector::DISTRACTOR_FIXATION));
+
+        let signals = restored.analyze_thought(
+            "When the broker restarts the retry queue drops messages",
+            12,
+        );
+        assert_eq!(
+            signals.intervention_type.as_deref(),
+            Some(detector::CIRCULAR_REASONING)
+        );
+    }
+
     #[test]
     fn test_session_reset() {
         let mut monitor = MetacognitiveMonitor::new();
@@ -488,4 +914,109 @@ mod tests {
         let signals = monitor.analyze_thought("First thought", 1);
         assert!(signals.circular_score < 0.5);
     }
+
+    #[test]
+    fn test_thoughts_are_checked_against_sources() {
+        let mut monitor = MetacognitiveMonitor::new();
+        let files = vec![(
+            "src/retry.rs".to_string(),
+            "pub fn backoff_delay(attempt: u32) -> Duration { BASE * 2u32.pow(attempt) }"
+                .to_string(),
+        )];
+        monitor.set_sources(GroundingSources::new(&files, "How do retries back off?"));
+
+        let signals = monitor.analyze_thought(
+            "Retries wait for backoff_delay(), capped by max_backoff_secs.",
+            1,
+        );
+        assert_eq!(
+            signals.intervention_type.as_deref(),
+            Some("ungrounded_reference")
+        );
+        assert!(signals
+            .intervention
+            .unwrap()
+            .starts_with("`max_backoff_secs` is not present in src/retry.rs"));
+
+        // A new session starts without files
+        monitor.reset_session();
+        let signals = monitor.analyze_thought("Retries are capped by max_backoff_secs.", 1);
+        assert!(signals.intervention.is_none());
+    }
+
+    #[test]
+    fn test_matching_rule_decides_and_is_logged() {
+        let rules = serde_json::json!([
+            {
+                "name": "loop_late",
+                "when": "circular_score > 0.5 && step >= 2",
+                "action": "force_revision",
+                "message": "Thought {step} loops back ({circular_score}). Revise it."
+            },
+            {
+                "name": "long_session",
+                "when": "step > 10",
+                "action": "force_conclusion",
+                "message": "Wrap up."
+            }
+        ]);
+        let mut monitor = MetacognitiveMonitor::with_config(
+            serde_json::from_value(serde_json::json!({ "rules": rules })).unwrap(),
+        );
+
+        monitor.analyze_thought(
+            "Understanding recursion requires understanding recursion",
+            1,
+        );
+        let signals =
+            monitor.analyze_thought("To understand recursion, you must understand recursion", 2);
+        let decision = signals.decision.unwrap();
+        assert_eq!(decision.rule, "loop_late");
+        assert_eq!(decision.action, PolicyAction::ForceRevision);
+        assert_eq!(
+            signals.intervention.as_deref(),
+            Some(decision.message.as_str())
+        );
+        assert!(decision.message.starts_with("Thought 2 loops back (0."));
+
+        // Rules apply without any detector triggering
+        let signals = monitor.analyze_thought("Caching product listings cuts lookups", 11);
+        assert_eq!(signals.intervention_type.as_deref(), Some("policy"));
+
+        let history = monitor.get_status().intervention_history;
+        let logged: Vec<_> = history.iter().filter_map(|r| r.rule.as_deref()).collect();
+        assert_eq!(logged, ["loop_late", "long_session"]);
+        assert_eq!(history[0].intervention_type, "circular_reasoning");
+    }
+
+    #[test]
+    fn test_subgoal_coverage_is_reported_and_persisted() {
+        let mut monitor = MetacognitiveMonitor::with_config(
+            serde_json::from_value(serde_json::json!({ "rules": [{
+                "name": "open_subgoals",
+                "when": "subgoals.open_critical > 0",
+                "action": "warn",
+                "message": "Subgoals open: {subgoals.open_critical}"
+            }]}))
+            .unwrap(),
+        );
+        let query = "Explain why the settlement queue stalls and propose a retry strategy.";
+        monitor.analyze_thought(query, 1);
+        monitor.set_subgoals(SubgoalTracker::from_query(query, 1));
+
+        let signals = monitor.analyze_thought(
+            "The settlement queue stalls because workers hold locks while waiting.",
+            2,
+        );
+        let coverage = signals.coverage.unwrap();
+        assert_eq!((coverage.addressed, coverage.open_critical), (1, vec![2]));
+        assert_eq!(signals.decision.unwrap().message, "Subgoals open: 1");
+
+        let json = serde_json::to_string(&monitor.snapshot()).unwrap();
+        let mut restored = MetacognitiveMonitor::new();
+        restored
+            .restore(serde_json::from_str(&json).unwrap())
+            .unwrap();
+        assert_eq!(restored.snapshot().subgoals, monitor.snapshot().subgoals);
+    }
 }
@@ -0,0 +1,596 @@
+//! Configurable intervention rules.
+//!
+//! Detectors decide what they see; rules decide what to do about it. A rule
+//! pairs a condition over the facts of one thought (`circular_score > 0.6 &&
+//! step > 3`) with an action and a message template, so interventions can be
+//! tuned from `lux.toml` without recompiling. Rules are checked in order and
+//! the first match wins.
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::MonitoringSignals;
+
+/// What a matching rule asks the reasoning tool to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyAction {
+    /// Show the message and carry on
+    Warn,
+    /// Rework the current thought before continuing
+    ForceRevision,
+    /// Stop reasoning and conclude
+    ForceConclusion,
+    /// Continue with the rule's `model`
+    SwitchModel,
+}
+
+impl fmt::Display for PolicyAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PolicyAction::Warn => "warn",
+            PolicyAction::ForceRevision => "force_revision",
+            PolicyAction::ForceConclusion => "force_conclusion",
+            PolicyAction::SwitchModel => "switch_model",
+        })
+    }
+}
+
+/// One intervention rule, as written under `[[monitoring.rules]]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RuleDefinition")]
+pub struct PolicyRule {
+    pub name: String,
+    pub when: Condition,
+    pub action: PolicyAction,
+    /// Model to continue with; required by `switch_model`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Guidance shown to the reasoner; `{name}` placeholders are filled from the facts
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleDefinition {
+    name: String,
+    when: Condition,
+    action: PolicyAction,
+    #[serde(default)]
+    model: Option<String>,
+    message: String,
+}
+
+impl TryFrom<RuleDefinition> for PolicyRule {
+    type Error = anyhow::Error;
+
+    fn try_from(rule: RuleDefinition) -> Result<Self> {
+        if rule.action == PolicyAction::SwitchModel && rule.model.is_none() {
+            bail!("Rule '{}' switches model but names no `model`", rule.name);
+        }
+        Ok(Self {
+            name: rule.name,
+            when: rule.when,
+            action: rule.action,
+            model: rule.model,
+            message: rule.message,
+        })
+    }
+}
+
+impl PolicyRule {
+    pub fn new(name: &str, when: &str, action: PolicyAction, message: &str) -> Result<Self> {
+        Self::try_from(RuleDefinition {
+            name: name.to_string(),
+            when: when.parse()?,
+            action,
+            model: None,
+            message: message.to_string(),
+        })
+    }
+}
+
+/// A rule that matched, with its message filled in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyDecision {
+    pub rule: String,
+    pub action: PolicyAction,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    pub message: String,
+}
+
+/// Named values a condition can test and a message can quote
+#[derive(Debug, Clone, Default)]
+pub struct Facts(BTreeMap<String, Value>);
+
+impl Facts {
+    /// Facts about one thought: `step`, `circular_score`, `distractor_alert`,
+    /// `quality_trend`, `phase`, the strongest detector's `intervention_type`
+    /// and `message`, and per detector `<name>.score`, `<name>.triggered`,
+    /// `<name>.message` and the scalar entries of its details (arrays by length)
+    pub fn from_signals(step: usize, signals: &MonitoringSignals) -> Self {
+        let mut facts = Self::default()
+            .with("step", step)
+            .with("circular_score", signals.circular_score)
+            .with("distractor_alert", signals.distractor_alert)
+            .with("quality_trend", signals.quality_trend.as_str())
+            .with("phase", signals.phase.as_str())
+            .with(
+                "intervention_type",
+                signals.intervention_type.as_deref().unwrap_or_default(),
+            )
+            .with(
+                "message",
+                signals.intervention.as_deref().unwrap_or_default(),
+            );
+
+        if let Some(coverage) = &signals.coverage {
+            facts = facts
+                .with("subgoals", coverage.subgoals.len())
+                .with("subgoals.addressed", coverage.addressed)
+                .with("subgoals.open_critical", coverage.open_critical.len());
+        }
+
+        for signal in &signals.detector_signals {
+            let name = &signal.detector;
+            facts = facts
+                .with(&format!("{}.score", name), signal.score)
+                .with(&format!("{}.triggered", name), signal.triggered)
+                .with(
+                    &format!("{}.message", name),
+                    signal.message.as_deref().unwrap_or_default(),
+                );
+            if let Value::Object(details) = &signal.details {
+                for (key, value) in details {
+                    let value = match value {
+                        Value::Array(items) => Value::from(items.len()),
+                        Value::Object(_) | Value::Null => continue,
+                        scalar => scalar.clone(),
+                    };
+                    facts.0.insert(format!("{}.{}", name, key), value);
+                }
+            }
+        }
+        facts
+    }
+
+    pub fn with(mut self, name: &str, value: impl Into<Value>) -> Self {
+        self.0.insert(name.to_string(), value.into());
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.0.get(name)
+    }
+
+    /// `template` with every `{name}` replaced by that fact. Numbers get two
+    /// decimals unless whole; unknown placeholders are left as written.
+    pub fn render(&self, template: &str) -> String {
+        let mut rendered = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(open) = rest.find('{') {
+            rendered.push_str(&rest[..open]);
+            let after = &rest[open + 1..];
+            match after.find('}').and_then(|close| {
+                let value = self.get(&after[..close])?;
+                Some((close, value))
+            }) {
+                Some((close, value)) => {
+                    rendered.push_str(&display(value));
+                    rest = &after[close + 1..];
+                }
+                None => {
+                    rendered.push('{');
+                    rest = after;
+                }
+            }
+        }
+        rendered.push_str(rest);
+        rendered
+    }
+}
+
+fn display(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Number(number) => match number.as_f64() {
+            Some(n) if n.fract() != 0.0 => format!("{:.2}", n),
+            _ => number.to_string(),
+        },
+        other => other.to_string(),
+    }
+}
+
+/// Decisions of every rule that matches, in rule order
+pub fn evaluate(rules: &[PolicyRule], facts: &Facts) -> Vec<PolicyDecision> {
+    rules
+        .iter()
+        .filter(|rule| rule.when.matches(facts))
+        .map(|rule| PolicyDecision {
+            rule: rule.name.clone(),
+            action: rule.action,
+            model: rule.model.clone(),
+            message: facts.render(&rule.message),
+        })
+        .collect()
+}
+
+/// A boolean expression over facts: comparisons (`> >= < <= == !=`) of
+/// names, numbers, `"strings"` and `true`/`false`, combined with `&&`, `||`,
+/// `!` and parentheses. A bare name is true when its fact is true, non-zero
+/// or non-empty. Names that aren't facts fail every comparison.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Condition {
+    source: String,
+    expr: Expr,
+}
+
+impl Condition {
+    pub fn matches(&self, facts: &Facts) -> bool {
+        self.expr.eval(facts)
+    }
+}
+
+impl PartialEq for Condition {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl std::str::FromStr for Condition {
+    type Err = anyhow::Error;
+
+    fn from_str(source: &str) -> Result<Self> {
+        let tokens =
+            tokenize(source).map_err(|e| anyhow!("Invalid condition `{}`: {}", source, e))?;
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+        };
+        let expr = parser
+            .or()
+            .and_then(|expr| match parser.peek() {
+                None => Ok(expr),
+                Some(token) => Err(format!("unexpected {}", token)),
+            })
+            .map_err(|e| anyhow!("Invalid condition `{}`: {}", source, e))?;
+        Ok(Self {
+            source: source.to_string(),
+            expr,
+        })
+    }
+}
+
+impl TryFrom<String> for Condition {
+    type Error = anyhow::Error;
+
+    fn try_from(source: String) -> Result<Self> {
+        source.parse()
+    }
+}
+
+impl From<Condition> for String {
+    fn from(condition: Condition) -> Self {
+        condition.source
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Operand, Comparison, Operand),
+    Truthy(Operand),
+}
+
+#[derive(Debug, Clone)]
+enum Operand {
+    Fact(String),
+    Literal(Value),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Expr {
+    fn eval(&self, facts: &Facts) -> bool {
+        match self {
+            Expr::Or(left, right) => left.eval(facts) || right.eval(facts),
+            Expr::And(left, right) => left.eval(facts) && right.eval(facts),
+            Expr::Not(inner) => !inner.eval(facts),
+            Expr::Truthy(operand) => match operand.value(facts) {
+                Some(Value::Bool(b)) => *b,
+                Some(Value::Number(n)) => n.as_f64().is_some_and(|n| n != 0.0),
+                Some(Value::String(s)) => !s.is_empty(),
+                _ => false,
+            },
+            Expr::Compare(left, comparison, right) => {
+                let (Some(left), Some(right)) = (left.value(facts), right.value(facts)) else {
+                    return false;
+                };
+                let ordering = match (left, right) {
+                    (Value::Number(a), Value::Number(b)) => a
+                        .as_f64()
+                        .zip(b.as_f64())
+                        .and_then(|(a, b)| a.partial_cmp(&b)),
+                    (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+                    (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+                    _ => None,
+                };
+                let Some(ordering) = ordering else {
+                    return false;
+                };
+                match comparison {
+                    Comparison::Greater => ordering.is_gt(),
+                    Comparison::GreaterOrEqual => ordering.is_ge(),
+                    Comparison::Less => ordering.is_lt(),
+                    Comparison::LessOrEqual => ordering.is_le(),
+                    Comparison::Equal => ordering.is_eq(),
+                    Comparison::NotEqual => ordering.is_ne(),
+                }
+            }
+        }
+    }
+}
+
+impl Operand {
+    fn value<'a>(&'a self, facts: &'a Facts) -> Option<&'a Value> {
+        match self {
+            Operand::Fact(name) => facts.get(name),
+            Operand::Literal(value) => Some(value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Name(String),
+    Literal(Value),
+    Compare(Comparison),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Name(name) => write!(f, "`{}`", name),
+            Token::Literal(value) => write!(f, "`{}`", value),
+            Token::Compare(_) => f.write_str("comparison"),
+            Token::And => f.write_str("`&&`"),
+            Token::Or => f.write_str("`||`"),
+            Token::Not => f.write_str("`!`"),
+            Token::Open => f.write_str("`(`"),
+            Token::Close => f.write_str("`)`"),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> std::result::Result<Vec<Token>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let two = |token| (token, 2);
+        let (token, width) = match (c, next) {
+            _ if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            ('&', Some('&')) => two(Token::And),
+            ('|', Some('|')) => two(Token::Or),
+            ('>', Some('=')) => two(Token::Compare(Comparison::GreaterOrEqual)),
+            ('<', Some('=')) => two(Token::Compare(Comparison::LessOrEqual)),
+            ('=', Some('=')) => two(Token::Compare(Comparison::Equal)),
+            ('!', Some('=')) => two(Token::Compare(Comparison::NotEqual)),
+            ('>', _) => (Token::Compare(Comparison::Greater), 1),
+            ('<', _) => (Token::Compare(Comparison::Less), 1),
+            ('!', _) => (Token::Not, 1),
+            ('(', _) => (Token::Open, 1),
+            (')', _) => (Token::Close, 1),
+            ('"', _) | ('\'', _) => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|&q| q == c)
+                    .ok_or("unterminated string")?;
+                let text: String = chars[i + 1..i + 1 + end].iter().collect();
+                (Token::Literal(Value::from(text)), end + 2)
+            }
+            _ if c.is_ascii_digit() || (c == '-' && next.is_some_and(|n| n.is_ascii_digit())) => {
+                let len = 1 + chars[i + 1..]
+                    .iter()
+                    .take_while(|n| n.is_ascii_digit() || **n == '.')
+                    .count();
+                let text: String = chars[i..i + len].iter().collect();
+                let number: f64 = text
+                    .parse()
+                    .map_err(|_| format!("invalid number `{}`", text))?;
+                (Token::Literal(Value::from(number)), len)
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                let len = chars[i..]
+                    .iter()
+                    .take_while(|n| n.is_alphanumeric() || **n == '_' || **n == '.')
+                    .count();
+                let name: String = chars[i..i + len].iter().collect();
+                let token = match name.as_str() {
+                    "true" => Token::Literal(Value::Bool(true)),
+                    "false" => Token::Literal(Value::Bool(false)),
+                    _ => Token::Name(name),
+                };
+                (token, len)
+            }
+            _ => return Err(format!("unexpected character `{}`", c)),
+        };
+        tokens.push(token);
+        i += width;
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent: `||` binds loosest, then `&&`, then `!`, then comparisons
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+type ParseResult = std::result::Result<Expr, String>;
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn or(&mut self) -> ParseResult {
+        let mut expr = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.position += 1;
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> ParseResult {
+        let mut expr = self.unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.position += 1;
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> ParseResult {
+        if self.peek() == Some(&Token::Not) {
+            self.position += 1;
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.peek() == Some(&Token::Open) {
+            self.position += 1;
+            let expr = self.or()?;
+            return match self.next() {
+                Some(Token::Close) => Ok(expr),
+                _ => Err("missing `)`".to_string()),
+            };
+        }
+
+        let left = self.operand()?;
+        match self.peek() {
+            Some(&Token::Compare(comparison)) => {
+                self.position += 1;
+                Ok(Expr::Compare(left, comparison, self.operand()?))
+            }
+            _ => Ok(Expr::Truthy(left)),
+        }
+    }
+
+    fn operand(&mut self) -> std::result::Result<Operand, String> {
+        match self.next() {
+            Some(Token::Name(name)) => Ok(Operand::Fact(name)),
+            Some(Token::Literal(value)) => Ok(Operand::Literal(value)),
+            Some(token) => Err(format!("expected a name or value, found {}", token)),
+            None => Err("condition ends early".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn facts() -> Facts {
+        Facts::default()
+            .with("step", 5)
+            .with("circular_score", 0.72)
+            .with("distractor_alert", false)
+            .with("quality_trend", "degrading")
+    }
+
+    #[test]
+    fn test_conditions_evaluate_over_facts() {
+        let holds = |source: &str| source.parse::<Condition>().unwrap().matches(&facts());
+
+        assert!(holds("circular_score > 0.6 && step > 3"));
+        assert!(!holds("circular_score > 0.6 && step > 5"));
+        assert!(holds("step >= 5 || distractor_alert"));
+        assert!(holds("!distractor_alert && quality_trend == \"degrading\""));
+        assert!(holds(
+            "(step < 3 || circular_score > 0.7) && quality_trend != 'stable'"
+        ));
+        // Unknown names never satisfy a comparison
+        assert!(!holds("grounding.checked > 0"));
+        assert!(!holds("grounding.checked != 0"));
+
+        for invalid in ["step >", "(step > 3", "step > 3 step", "step = 3", "\"open"] {
+            assert!(invalid.parse::<Condition>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_rules_match_in_order_and_render_messages() {
+        let rules: Vec<PolicyRule> = serde_json::from_value(serde_json::json!([
+            {
+                "name": "loop",
+                "when": "circular_score > 0.6 && step > 3",
+                "action": "force_revision",
+                "message": "Step {step} repeats earlier ones ({circular_score}); {unknown} stays"
+            },
+            {
+                "name": "escalate",
+                "when": "step > 4",
+                "action": "switch_model",
+                "model": "o3",
+                "message": "Escalating"
+            }
+        ]))
+        .unwrap();
+
+        let decisions = evaluate(&rules, &facts());
+        assert_eq!(decisions.len(), 2);
+        assert_eq!(decisions[0].action, PolicyAction::ForceRevision);
+        assert_eq!(
+            decisions[0].message,
+            "Step 5 repeats earlier ones (0.72); {unknown} stays"
+        );
+        assert_eq!(decisions[1].model.as_deref(), Some("o3"));
+
+        let missing_model = serde_json::json!({
+            "name": "escalate",
+            "when": "step > 4",
+            "action": "switch_model",
+            "message": "Escalating"
+        });
+        assert!(serde_json::from_value::<PolicyRule>(missing_model).is_err());
+    }
+}
@@ -1,5 +1,10 @@
+use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+use crate::monitoring::change_point;
+use crate::monitoring::language::Language;
+use crate::monitoring::MonitoringConfig;
 
 /// Comprehensive quality degradation detection for metacognitive monitoring
 pub struct QualityDegradationDetector {
@@ -14,20 +19,42 @@ pub struct QualityDegradationDetector {
 }
 
 /// Core quality metrics tracked per thought
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QualityMetrics {
     /// Linguistic quality indicators
     linguistic: LinguisticMetrics,
     /// Content quality indicators
     content: ContentMetrics,
-    /// Timestamp for temporal analysis
+    /// Timestamp for temporal analysis; restored metrics get the restore time
+    #[serde(skip, default = "Instant::now")]
     timestamp: std::time::Instant,
     /// Thought index in the session
     thought_index: usize,
 }
 
+impl QualityMetrics {
+    /// Type-token ratio of the thought's words
+    pub fn vocabulary_diversity(&self) -> f64 {
+        self.linguistic.vocabulary_diversity
+    }
+
+    /// Use of connectives that structure an argument
+    pub fn coherence(&self) -> f64 {
+        self.linguistic.coherence_markers
+    }
+
+    pub fn information_density(&self) -> f64 {
+        self.content.information_density
+    }
+
+    /// Reasoning indicators and logical structure
+    pub fn reasoning_depth(&self) -> f64 {
+        self.content.reasoning_depth
+    }
+}
+
 /// Linguistic quality metrics
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LinguisticMetrics {
     /// Vocabulary diversity (type-token ratio)
     vocabulary_diversity: f64,
@@ -40,7 +67,7 @@ pub struct LinguisticMetrics {
 }
 
 /// Content quality metrics
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContentMetrics {
     /// Information density per sentence
     information_density: f64,
@@ -72,6 +99,8 @@ pub struct DetectionConfig {
     min_metrics_for_detection: usize,
     /// Window size for temporal analysis
     temporal_window_size: usize,
+    /// CUSUM decision threshold, in noise units, for a sudden drop
+    sudden_drop_threshold: f64,
 }
 
 impl Default for DetectionConfig {
@@ -82,17 +111,33 @@ impl Default for DetectionConfig {
             reasoning_decline_threshold: 0.3,  // 30% decline
             min_metrics_for_detection: 3,
             temporal_window_size: 5,
+            sudden_drop_threshold: 4.0,
         }
     }
 }
 
 impl QualityDegradationDetector {
     pub fn new() -> Self {
+        Self::with_config(DetectionConfig::default())
+    }
+
+    /// Take decline thresholds (fractions, e.g. 0.2 = 20% decline) and the
+    /// minimum history length from the monitoring config
+    pub fn configure(&mut self, config: &MonitoringConfig) {
+        self.config.vocab_decline_threshold = config.vocab_decline_threshold;
+        self.config.coherence_decline_threshold = config.coherence_decline_threshold;
+        self.config.reasoning_decline_threshold = config.reasoning_decline_threshold;
+        self.config.min_metrics_for_detection = config.degradation_min_thoughts.max(2);
+        self.config.sudden_drop_threshold = config.sudden_drop_threshold;
+        self.temporal_analyzer.min_data_points = self.config.min_metrics_for_detection;
+    }
+
+    fn with_config(config: DetectionConfig) -> Self {
         Self {
             metrics_history: VecDeque::with_capacity(20),
             temporal_analyzer: TemporalAnalyzer::new(5, 3),
             session_start: std::time::Instant::now(),
-            config: DetectionConfig::default(),
+            config,
         }
     }
 
@@ -101,7 +146,14 @@ impl QualityDegradationDetector {
         // Calculate all metrics for the current thought
         let metrics = self.calculate_metrics(thought, thought_index);
 
-        // Add to history
+        // Add to history; analyzing the same thought again replaces its metrics
+        if self
+            .metrics_history
+            .back()
+            .is_some_and(|last| last.thought_index == thought_index)
+        {
+            self.metrics_history.pop_back();
+        }
         self.metrics_history.push_back(metrics.clone());
         if self.metrics_history.len() > 20 {
             self.metrics_history.pop_front();
@@ -137,16 +189,21 @@ impl QualityDegradationDetector {
 
     /// Calculate linguistic quality metrics
     fn calculate_linguistic_metrics(&self, thought: &str) -> LinguisticMetrics {
-        // Tokenize the thought
-        let words: Vec<&str> = thought
-            .split_whitespace()
-            .filter(|w| w.chars().all(|c| c.is_alphabetic() || c == '\''))
-            .collect();
+        // Tokenize the thought; CJK has no spaces to split on
+        let language = Language::detect(thought);
+        let words: Vec<String> = if language.is_cjk() {
+            language.tokenize(thought)
+        } else {
+            thought
+                .split_whitespace()
+                .filter(|w| w.chars().all(|c| c.is_alphabetic() || c == '\''))
+                .map(|w| w.to_lowercase())
+                .collect()
+        };
         let sentences = self.split_sentences(thought);
 
         // Calculate vocabulary diversity (type-token ratio)
-        let unique_words: std::collections::HashSet<String> =
-            words.iter().map(|w| w.to_lowercase()).collect();
+        let unique_words: std::collections::HashSet<&String> = words.iter().collect();
         let vocabulary_diversity = if words.is_empty() {
             0.0
         } else {
@@ -197,7 +254,7 @@ impl QualityDegradationDetector {
     /// Split text into sentences
     fn split_sentences<'a>(&self, text: &'a str) -> Vec<&'a str> {
         // Simple sentence splitting - can be improved with proper NLP
-        text.split(|c| c == '.' || c == '!' || c == '?')
+        text.split(['.', '!', '?', '。', '！', '？'])
             .map(|s| s.trim())
             .filter(|s| !s.is_empty())
             .collect()
@@ -219,38 +276,18 @@ impl QualityDegradationDetector {
         (avg_length / 20.0).min(1.0)
     }
 
-    /// Calculate coherence marker usage
+    /// Calculate coherence marker usage, with markers for the thought's language
     fn calculate_coherence_markers(&self, thought: &str) -> f64 {
-        let coherence_words = [
-            "therefore",
-            "however",
-            "moreover",
-            "furthermore",
-            "consequently",
-            "thus",
-            "hence",
-            "accordingly",
-            "nevertheless",
-            "nonetheless",
-            "meanwhile",
-            "subsequently",
-            "initially",
-            "finally",
-            "additionally",
-            "specifically",
-            "particularly",
-            "especially",
-            "notably",
-            "importantly",
-        ];
+        let language = Language::detect(thought);
 
         let thought_lower = thought.to_lowercase();
-        let marker_count = coherence_words
+        let marker_count = language
+            .coherence_markers()
             .iter()
             .filter(|&word| thought_lower.contains(word))
             .count();
 
-        let word_count = thought.split_whitespace().count();
+        let word_count = language.word_count(thought);
         if word_count == 0 {
             return 0.0;
         }
@@ -504,9 +541,52 @@ impl QualityDegradationDetector {
             patterns.push(pattern);
         }
 
+        if let Some(pattern) = self.detect_sudden_drop() {
+            patterns.push(pattern);
+        }
+
         patterns
     }
 
+    /// Detect a recent step where a metric fell to a lower level and stayed
+    /// there. Of several metrics dropping, the largest drop is reported.
+    fn detect_sudden_drop(&self) -> Option<DegradationPattern> {
+        let len = self.metrics_history.len();
+        type Metric = fn(&QualityMetrics) -> f64;
+        let series: [(&'static str, Metric); 7] = [
+            ("vocabulary_diversity", |m| {
+                m.linguistic.vocabulary_diversity
+            }),
+            ("sentence_complexity", |m| m.linguistic.sentence_complexity),
+            ("coherence_markers", |m| m.linguistic.coherence_markers),
+            ("grammar_quality", |m| m.linguistic.grammar_quality),
+            ("information_density", |m| m.content.information_density),
+            ("reasoning_depth", |m| m.content.reasoning_depth),
+            // Abstraction level is left out: a move to concrete examples is no loss
+            ("evidence_support", |m| m.content.evidence_support),
+        ];
+
+        series
+            .iter()
+            .filter_map(|&(metric, value)| {
+                let values: Vec<f64> = self.metrics_history.iter().map(value).collect();
+                let change = change_point::detect_drop(
+                    &values,
+                    self.config.min_metrics_for_detection,
+                    self.config.sudden_drop_threshold,
+                )?;
+                // Older drops have been reported while they were recent
+                (len - change.index <= self.config.temporal_window_size).then_some((metric, change))
+            })
+            .max_by(|a, b| (a.1.before - a.1.after).total_cmp(&(b.1.before - b.1.after)))
+            .map(|(metric, change)| DegradationPattern::SuddenDrop {
+                step: self.metrics_history[change.index].thought_index,
+                metric,
+                before: change.before,
+                after: change.after,
+            })
+    }
+
     /// Detect vocabulary diversity decline
     fn detect_vocabulary_decline(&self) -> Option<DegradationPattern> {
         let vocab_scores: Vec<f64> = self
@@ -716,6 +796,9 @@ impl QualityDegradationDetector {
                     variability,
                     ..
                 } => ((*metrics_affected as f64 / 4.0) + variability, 1.2),
+                DegradationPattern::SuddenDrop { before, after, .. } => {
+                    ((before - after) / before.max(0.01) * 2.0, 2.0) // Relative drop
+                }
             };
 
             score += pattern_score * weight;
@@ -760,6 +843,12 @@ impl QualityDegradationDetector {
                         );
                     }
                 }
+                DegradationPattern::SuddenDrop { step, .. } => {
+                    recommendations.push(format!(
+                        "Quality dropped sharply at step {}. Revisit what changed there before building on it.",
+                        step
+                    ));
+                }
             }
         }
 
@@ -782,6 +871,30 @@ impl QualityDegradationDetector {
         self.metrics_history.clear();
         self.session_start = std::time::Instant::now();
     }
+
+    /// Per-session state, for persisting the monitor
+    pub fn state(&self) -> DegradationState {
+        DegradationState {
+            metrics_history: self.metrics_history.clone(),
+            session_secs: self.session_start.elapsed().as_secs_f64(),
+        }
+    }
+
+    pub fn restore_state(&mut self, state: DegradationState) {
+        self.metrics_history = state.metrics_history;
+        let elapsed = Duration::try_from_secs_f64(state.session_secs).unwrap_or_default();
+        self.session_start = Instant::now()
+            .checked_sub(elapsed)
+            .unwrap_or_else(Instant::now);
+    }
+}
+
+/// What [`QualityDegradationDetector`] remembers about a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DegradationState {
+    metrics_history: VecDeque<QualityMetrics>,
+    /// Session duration so far, carried over on restore
+    session_secs: f64,
 }
 
 impl TemporalAnalyzer {
@@ -894,6 +1007,14 @@ pub enum DegradationPattern {
         metrics_affected: usize,
         variability: f64,
     },
+    /// A metric fell to a lower level in one step and stayed there
+    SuddenDrop {
+        /// Thought where the lower level started
+        step: usize,
+        metric: &'static str,
+        before: f64,
+        after: f64,
+    },
 }
 
 impl DegradationPattern {
@@ -942,6 +1063,20 @@ impl DegradationPattern {
                     session_duration.as_secs() as f64 / 60.0, metrics_affected, variability * 100.0
                 )
             }
+            DegradationPattern::SuddenDrop {
+                step,
+                metric,
+                before,
+                after,
+            } => {
+                format!(
+                    "Quality dropped sharply at step {}: {} fell from {:.2} to {:.2}",
+                    step,
+                    metric.replace('_', " "),
+                    before,
+                    after
+                )
+            }
         }
     }
 }
@@ -980,6 +1115,19 @@ mod tests {
         assert!(metrics.coherence_markers < 0.3);
     }
 
+    #[test]
+    fn test_coherence_markers_follow_language() {
+        let detector = QualityDegradationDetector::new();
+
+        let german = "Zunächst prüfen wir die Sperre. Jedoch greift der zweite Thread ohne sie zu. Daher ist der Zugriff nicht sicher.";
+        assert!(detector.calculate_coherence_markers(german) > 0.5);
+
+        let japanese = "まずロックを確認する。しかし二つ目のスレッドはロックなしでアクセスする。したがってこのアクセスは安全ではない。";
+        assert!(detector.calculate_coherence_markers(japanese) > 0.5);
+        let metrics = detector.calculate_linguistic_metrics(japanese);
+        assert!(metrics.vocabulary_diversity > 0.5);
+    }
+
     #[test]
     fn test_reasoning_depth_calculation() {
         let detector = QualityDegradationDetector::new();
@@ -1077,4 +1225,32 @@ mod tests {
             "Expected degradation patterns to be detected during the session"
         );
     }
+
+    #[test]
+    fn test_sudden_drop_reports_its_step() {
+        let mut detector = QualityDegradationDetector::new();
+        let steady = [
+            "The cache layer therefore reduces database load, because repeated product lookups are served from memory instead of disk.",
+            "Consequently the invalidation strategy matters, since stale prices would otherwise reach customers after every catalog update.",
+            "However a write-through policy keeps entries fresh, because each update refreshes the cached copy before readers see it.",
+            "Therefore the remaining risk is memory pressure, since popular categories could evict entries that are expensive to rebuild.",
+            "Moreover eviction by frequency protects hot entries, because rarely requested products are dropped before bestsellers are.",
+            "Thus the design balances freshness against cost, since every layer is justified by measured lookup patterns in production.",
+        ];
+        for (i, thought) in steady.iter().enumerate() {
+            detector.analyze_thought(thought, i + 1);
+        }
+
+        detector.analyze_thought("Cache it.", 7);
+        let analysis = detector.analyze_thought("Just cache.", 8);
+        let step = analysis.degradation_patterns.iter().find_map(|p| match p {
+            DegradationPattern::SuddenDrop { step, .. } => Some(*step),
+            _ => None,
+        });
+        assert_eq!(step, Some(7));
+        assert!(analysis
+            .recommendations
+            .iter()
+            .any(|r| r.contains("dropped sharply at step 7")));
+    }
 }
@@ -0,0 +1,269 @@
+//! Long-horizon memory of a monitoring session.
+//!
+//! Detectors compare full thought texts only within a short window. Every
+//! thought also leaves a compact fingerprint that is kept for the whole
+//! session, so returns to much earlier thoughts can still be noticed, and
+//! every [`SUMMARY_INTERVAL`] thoughts are condensed into a rolling summary.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+use crate::llm::embeddings::fnv1a;
+use crate::monitoring::language::{is_meaningful_word, Language};
+
+/// Thoughts condensed into one rolling summary
+pub const SUMMARY_INTERVAL: usize = 10;
+/// Concepts listed per summary
+const SUMMARY_CONCEPTS: usize = 8;
+/// Concepts kept per fingerprint; the SimHash covers all of them
+const FINGERPRINT_CONCEPTS: usize = 32;
+/// Oldest fingerprints are dropped beyond this many
+const MAX_FINGERPRINTS: usize = 1000;
+/// Intermediate thoughts must be this far from the revisited one for a cycle
+const INTERMEDIATE_SIMILARITY: f64 = 0.5;
+
+/// Compact trace of one thought
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThoughtFingerprint {
+    pub thought_number: usize,
+    /// Stemmed content words, sorted and deduplicated
+    pub concepts: Vec<String>,
+    /// 64-bit SimHash of the concepts: similar thoughts differ in few bits
+    pub simhash: u64,
+}
+
+impl ThoughtFingerprint {
+    pub fn new(text: &str, thought_number: usize) -> Self {
+        let language = Language::detect(text);
+        let concepts: BTreeSet<String> = language
+            .tokenize(text)
+            .into_iter()
+            .filter(|word| !language.is_stop_word(word) && is_meaningful_word(word))
+            .map(|word| language.stem(&word))
+            .collect();
+
+        Self {
+            thought_number,
+            simhash: simhash(&concepts),
+            concepts: concepts.into_iter().take(FINGERPRINT_CONCEPTS).collect(),
+        }
+    }
+
+    /// Similarity in [0, 1]: concept overlap, or SimHash agreement when that is
+    /// higher (it also covers concepts beyond the stored ones)
+    pub fn similarity(&self, other: &Self) -> f64 {
+        if self.concepts.is_empty() || other.concepts.is_empty() {
+            return 0.0;
+        }
+        let shared = self
+            .concepts
+            .iter()
+            .filter(|c| other.concepts.binary_search(c).is_ok())
+            .count();
+        let jaccard = shared as f64 / (self.concepts.len() + other.concepts.len() - shared) as f64;
+
+        // Unrelated hashes differ in about half of the 64 bits
+        let distance = (self.simhash ^ other.simhash).count_ones() as f64;
+        let hash_similarity = (1.0 - distance / 32.0).max(0.0);
+
+        jaccard.max(hash_similarity)
+    }
+}
+
+fn simhash(concepts: &BTreeSet<String>) -> u64 {
+    let mut weights = [0i32; 64];
+    for concept in concepts {
+        let hash = fnv1a(concept.as_bytes());
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            *weight += if (hash >> bit) & 1 == 1 { 1 } else { -1 };
+        }
+    }
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(_, &weight)| weight > 0)
+        .fold(0, |hash, (bit, _)| hash | 1 << bit)
+}
+
+/// What a block of [`SUMMARY_INTERVAL`] thoughts was about
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RollingSummary {
+    pub first_thought: usize,
+    pub last_thought: usize,
+    /// Most frequent concepts of the block
+    pub top_concepts: Vec<String>,
+    /// Concepts of the block that no earlier thought mentioned
+    pub new_concepts: usize,
+}
+
+/// A thought that returns to one from before the detectors' window
+#[derive(Debug, Clone, PartialEq)]
+pub struct LongCycle {
+    pub earlier_thought: usize,
+    /// Thoughts between the two, plus one
+    pub period: usize,
+    pub similarity: f64,
+}
+
+/// Fingerprints and summaries of every thought in a session
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionMemory {
+    fingerprints: Vec<ThoughtFingerprint>,
+    summaries: Vec<RollingSummary>,
+    /// Every concept seen so far, for counting new ones per summary
+    seen_concepts: BTreeSet<String>,
+    /// Thoughts recorded since the last summary
+    pending: usize,
+}
+
+impl SessionMemory {
+    /// Remember a thought. Analyzing the same thought again replaces its fingerprint.
+    pub fn record(&mut self, fingerprint: ThoughtFingerprint) {
+        if let Some(last) = self.fingerprints.last_mut() {
+            if last.thought_number == fingerprint.thought_number {
+                *last = fingerprint;
+                return;
+            }
+        }
+
+        self.fingerprints.push(fingerprint);
+        if self.fingerprints.len() > MAX_FINGERPRINTS {
+            self.fingerprints.remove(0);
+        }
+        self.pending += 1;
+        if self.pending == SUMMARY_INTERVAL {
+            self.summarize();
+        }
+    }
+
+    /// Number of the most recently recorded thought
+    pub fn last_thought_number(&self) -> Option<usize> {
+        self.fingerprints.last().map(|f| f.thought_number)
+    }
+
+    pub fn summaries(&self) -> &[RollingSummary] {
+        &self.summaries
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// The best match for `fingerprint` among thoughts older than the last
+    /// `recent` ones, if the thoughts in between moved on to something else
+    pub fn find_return(
+        &self,
+        fingerprint: &ThoughtFingerprint,
+        recent: usize,
+        threshold: f64,
+    ) -> Option<LongCycle> {
+        let older = self.fingerprints.len().saturating_sub(recent);
+        let (index, similarity) = self.fingerprints[..older]
+            .iter()
+            .enumerate()
+            .map(|(i, earlier)| (i, fingerprint.similarity(earlier)))
+            .filter(|&(_, similarity)| similarity >= threshold)
+            .max_by(|a, b| a.1.total_cmp(&b.1))?;
+
+        let earlier = &self.fingerprints[index];
+        let between = &self.fingerprints[index + 1..];
+        let drift = between.iter().map(|f| earlier.similarity(f)).sum::<f64>()
+            / between.len().max(1) as f64;
+        if drift >= INTERMEDIATE_SIMILARITY {
+            // Still on the same topic all along rather than coming back to it
+            return None;
+        }
+
+        Some(LongCycle {
+            earlier_thought: earlier.thought_number,
+            period: fingerprint
+                .thought_number
+                .saturating_sub(earlier.thought_number),
+            similarity,
+        })
+    }
+
+    fn summarize(&mut self) {
+        let block = &self.fingerprints[self.fingerprints.len().saturating_sub(self.pending)..];
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for concept in block.iter().flat_map(|f| &f.concepts) {
+            *counts.entry(concept).or_default() += 1;
+        }
+        let mut ranked: Vec<(&str, usize)> = counts.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+        let new_concepts = ranked
+            .iter()
+            .filter(|(concept, _)| !self.seen_concepts.contains(*concept))
+            .count();
+        let summary = RollingSummary {
+            first_thought: block.first().map_or(0, |f| f.thought_number),
+            last_thought: block.last().map_or(0, |f| f.thought_number),
+            top_concepts: ranked
+                .iter()
+                .take(SUMMARY_CONCEPTS)
+                .map(|(concept, _)| concept.to_string())
+                .collect(),
+            new_concepts,
+        };
+        self.seen_concepts
+            .extend(ranked.into_iter().map(|(concept, _)| concept.to_string()));
+        self.summaries.push(summary);
+        self.pending = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_period_return_is_found() {
+        let topics = [
+            "The connection pool exhausts its sockets under heavy load",
+            "Database indexes on the orders table speed up reporting queries",
+            "Frontend bundle size affects initial page rendering time",
+            "Deployment pipelines should run integration tests before release",
+            "Logging verbosity must stay configurable through environment variables",
+            "Caching product listings reduces repeated catalog lookups",
+            "Authentication tokens expire after fifteen minutes of inactivity",
+            "Message queues decouple payment processing from checkout",
+            "Feature flags allow gradual rollout to selected customers",
+            "Metrics dashboards track latency percentiles across regions",
+            "Schema migrations need backward compatible column changes",
+            "Retry policies with jitter avoid synchronized thundering herds",
+        ];
+        let mut memory = SessionMemory::default();
+        for (i, text) in topics.iter().enumerate() {
+            memory.record(ThoughtFingerprint::new(text, i + 1));
+        }
+        assert_eq!(memory.summaries().len(), 1);
+        assert_eq!(memory.summaries()[0].last_thought, 10);
+
+        let current = ThoughtFingerprint::new(
+            "Under heavy load the connection pool exhausts its sockets",
+            13,
+        );
+        let cycle = memory.find_return(&current, 10, 0.6).unwrap();
+        assert_eq!(cycle.earlier_thought, 1);
+        assert_eq!(cycle.period, 12);
+
+        // Thoughts inside the recent window are left to the text-based checks
+        assert!(memory.find_return(&current, 12, 0.6).is_none());
+    }
+
+    #[test]
+    fn test_memory_round_trips_through_json() {
+        let mut memory = SessionMemory::default();
+        memory.record(ThoughtFingerprint::new("Caching reduces lookups", 1));
+        memory.record(ThoughtFingerprint::new(
+            "Caching reduces database lookups",
+            1,
+        ));
+        assert_eq!(memory.fingerprints.len(), 1);
+
+        let restored: SessionMemory =
+            serde_json::from_value(serde_json::to_value(&memory).unwrap()).unwrap();
+        assert_eq!(restored.fingerprints, memory.fingerprints);
+    }
+}
@@ -0,0 +1,490 @@
+//! Requirements of the original query and how far the thoughts covered them.
+//!
+//! Relevance to the query as a whole cannot tell that one requirement was
+//! finally addressed while another was never touched. The query is split into
+//! subgoals: list items when it has a list, otherwise the sentences that ask
+//! for something. Each thought then marks the subgoals whose concepts it
+//! covers as partially addressed or addressed.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::monitoring::language::{is_meaningful_word, Language};
+
+/// Share of a subgoal's concepts one thought must cover to address it
+const ADDRESSED: f64 = 0.6;
+/// Share of a subgoal's concepts the thoughts together must cover to touch it
+const PARTIAL: f64 = 0.25;
+/// Subgoals beyond this many are dropped
+const MAX_SUBGOALS: usize = 12;
+
+/// Verbs that ask for work; they say what to do with a subject, not what it is
+const REQUEST_VERBS: &[&str] = &[
+    "add",
+    "analyse",
+    "analyze",
+    "assess",
+    "avoid",
+    "build",
+    "check",
+    "compare",
+    "create",
+    "describe",
+    "design",
+    "determine",
+    "discuss",
+    "document",
+    "estimate",
+    "evaluate",
+    "explain",
+    "find",
+    "fix",
+    "give",
+    "handle",
+    "identify",
+    "implement",
+    "include",
+    "investigate",
+    "keep",
+    "list",
+    "make",
+    "outline",
+    "propose",
+    "provide",
+    "recommend",
+    "refactor",
+    "review",
+    "show",
+    "suggest",
+    "summarize",
+    "support",
+    "test",
+    "update",
+    "use",
+    "write",
+];
+
+/// Words that turn a sentence into a requirement wherever they appear
+const REQUIREMENT_WORDS: &[&str] = &[
+    "must", "should", "need", "needs", "want", "wants", "ensure", "required", "requires",
+];
+
+const QUESTION_WORDS: &[&str] = &[
+    "how", "why", "what", "which", "when", "where", "who", "whether",
+];
+
+/// Phrases that make a requirement optional
+const OPTIONAL_MARKERS: &[&str] = &[
+    "optional",
+    "optionally",
+    "if possible",
+    "nice to have",
+    "nice-to-have",
+    "bonus",
+    "if time permits",
+    "ideally",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubgoalStatus {
+    Unaddressed,
+    Partial,
+    Addressed,
+}
+
+impl fmt::Display for SubgoalStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SubgoalStatus::Unaddressed => "unaddressed",
+            SubgoalStatus::Partial => "partially addressed",
+            SubgoalStatus::Addressed => "addressed",
+        })
+    }
+}
+
+/// One requirement or sub-question of the query
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Subgoal {
+    pub text: String,
+    /// Conclusions wait for critical subgoals; optional ones only get reported
+    pub critical: bool,
+    pub status: SubgoalStatus,
+    /// Best share of the subgoal's concepts covered by a single thought
+    pub coverage: f64,
+    /// Thought that addressed it
+    pub addressed_in: Option<usize>,
+    /// Stemmed concepts the subgoal is about, and those thoughts have covered
+    concepts: BTreeSet<String>,
+    covered: BTreeSet<String>,
+}
+
+impl Subgoal {
+    fn new(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let concepts = concepts(text);
+        if concepts.is_empty() {
+            return None;
+        }
+        let lower = text.to_lowercase();
+        Some(Self {
+            text: text.to_string(),
+            critical: !OPTIONAL_MARKERS.iter().any(|m| lower.contains(m)),
+            status: SubgoalStatus::Unaddressed,
+            coverage: 0.0,
+            addressed_in: None,
+            concepts,
+            covered: BTreeSet::new(),
+        })
+    }
+
+    fn observe(&mut self, thought: &BTreeSet<String>, thought_number: usize) {
+        let matched: Vec<&String> = self.concepts.intersection(thought).collect();
+        if matched.is_empty() {
+            return;
+        }
+        self.coverage = self
+            .coverage
+            .max(matched.len() as f64 / self.concepts.len() as f64);
+        self.covered.extend(matched.into_iter().cloned());
+
+        // A subgoal stays addressed once one thought dealt with it
+        if self.coverage >= ADDRESSED {
+            self.status = SubgoalStatus::Addressed;
+            self.addressed_in.get_or_insert(thought_number);
+        } else if self.covered.len() as f64 / self.concepts.len() as f64 >= PARTIAL {
+            self.status = SubgoalStatus::Partial;
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.status != SubgoalStatus::Addressed
+    }
+}
+
+/// Subgoals of a session and their coverage so far
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SubgoalTracker {
+    subgoals: Vec<Subgoal>,
+    /// Thought the subgoals came from; it restates them, so it doesn't count
+    source_thought: usize,
+}
+
+impl SubgoalTracker {
+    /// Subgoals found in `query`, which was given with thought `source_thought`
+    pub fn from_query(query: &str, source_thought: usize) -> Self {
+        Self::from_list(&extract(query), source_thought)
+    }
+
+    /// Track exactly these subgoals
+    pub fn from_list(items: &[String], source_thought: usize) -> Self {
+        let mut subgoals: Vec<Subgoal> = Vec::new();
+        for subgoal in items.iter().filter_map(|item| Subgoal::new(item)) {
+            if !subgoals.iter().any(|s| s.concepts == subgoal.concepts) {
+                subgoals.push(subgoal);
+            }
+        }
+        subgoals.truncate(MAX_SUBGOALS);
+        Self {
+            subgoals,
+            source_thought,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.subgoals.is_empty()
+    }
+
+    /// Mark the subgoals this thought covers
+    pub fn observe(&mut self, thought: &str, thought_number: usize) {
+        if thought_number <= self.source_thought || self.subgoals.is_empty() {
+            return;
+        }
+        let concepts = concepts(thought);
+        for subgoal in &mut self.subgoals {
+            subgoal.observe(&concepts, thought_number);
+        }
+    }
+
+    /// Coverage of every subgoal, for tool responses
+    pub fn coverage(&self) -> CoverageMap {
+        let count = |status| self.subgoals.iter().filter(|s| s.status == status).count();
+        CoverageMap {
+            subgoals: self
+                .subgoals
+                .iter()
+                .enumerate()
+                .map(|(i, s)| SubgoalCoverage {
+                    id: i + 1,
+                    text: s.text.clone(),
+                    critical: s.critical,
+                    status: s.status,
+                    coverage: (s.coverage * 100.0).round() / 100.0,
+                    addressed_in: s.addressed_in,
+                })
+                .collect(),
+            addressed: count(SubgoalStatus::Addressed),
+            partial: count(SubgoalStatus::Partial),
+            unaddressed: count(SubgoalStatus::Unaddressed),
+            open_critical: self
+                .subgoals
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| s.critical && s.is_open())
+                .map(|(i, _)| i + 1)
+                .collect(),
+        }
+    }
+}
+
+/// Which subgoals the thoughts addressed so far
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CoverageMap {
+    pub subgoals: Vec<SubgoalCoverage>,
+    pub addressed: usize,
+    pub partial: usize,
+    pub unaddressed: usize,
+    /// Critical subgoals not yet addressed, by id
+    pub open_critical: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SubgoalCoverage {
+    pub id: usize,
+    pub text: String,
+    pub critical: bool,
+    pub status: SubgoalStatus,
+    pub coverage: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub addressed_in: Option<usize>,
+}
+
+impl CoverageMap {
+    /// Critical subgoals still open, which hold back a conclusion
+    pub fn open_critical(&self) -> impl Iterator<Item = &SubgoalCoverage> {
+        self.subgoals
+            .iter()
+            .filter(|s| self.open_critical.contains(&s.id))
+    }
+}
+
+impl fmt::Display for CoverageMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{} addressed, {} partially",
+            self.addressed,
+            self.subgoals.len(),
+            self.partial
+        )?;
+        for subgoal in &self.subgoals {
+            let mark = match subgoal.status {
+                SubgoalStatus::Addressed => "✓",
+                SubgoalStatus::Partial => "◐",
+                SubgoalStatus::Unaddressed => "○",
+            };
+            write!(f, "\n{} {}. {}", mark, subgoal.id, subgoal.text)?;
+            if !subgoal.critical {
+                f.write_str(" (optional)")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Requirements and sub-questions of a query: its list items if it has at
+/// least two, otherwise the sentences that ask for something, otherwise the
+/// whole query
+fn extract(query: &str) -> Vec<String> {
+    let items: Vec<String> = query
+        .lines()
+        .filter_map(strip_list_marker)
+        .map(str::to_string)
+        .collect();
+    if items.len() >= 2 {
+        return items;
+    }
+
+    let requests: Vec<String> = sentences(query)
+        .into_iter()
+        .flat_map(|(sentence, question)| {
+            split_requests(&sentence)
+                .into_iter()
+                .filter(move |clause| question || is_request(clause))
+        })
+        .collect();
+    if requests.is_empty() {
+        vec![query.trim().to_string()]
+    } else {
+        requests
+    }
+}
+
+/// The item text of a `-`, `*`, `•`, `1.`, `1)` or `a)` list line
+fn strip_list_marker(line: &str) -> Option<&str> {
+    let line = line.trim_start();
+    if let Some(rest) = line.strip_prefix(['-', '*', '•']) {
+        return rest.starts_with(' ').then(|| rest.trim());
+    }
+    let marker_len = line
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|&len| len > 0 && len <= 2)?;
+    let (marker, rest) = line.split_at(marker_len);
+    let numbered = marker.chars().all(|c| c.is_ascii_digit());
+    let lettered = marker.len() == 1 && marker.chars().all(|c| c.is_ascii_lowercase());
+    let rest = if numbered {
+        rest.strip_prefix(['.', ')'])?
+    } else if lettered {
+        rest.strip_prefix(')')?
+    } else {
+        return None;
+    };
+    rest.starts_with(' ').then(|| rest.trim())
+}
+
+/// Sentences of `text`, each with whether it is a question
+fn sentences(text: &str) -> Vec<(String, bool)> {
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let ends = match c {
+            '\n' | ';' | '？' | '。' => true,
+            '.' | '?' | '!' => chars.peek().is_none_or(|next| next.is_whitespace()),
+            _ => false,
+        };
+        if !ends {
+            current.push(c);
+            continue;
+        }
+        let sentence = current.trim();
+        if !sentence.is_empty() {
+            sentences.push((sentence.to_string(), c == '?' || c == '？'));
+        }
+        current.clear();
+    }
+    let sentence = current.trim();
+    if !sentence.is_empty() {
+        sentences.push((sentence.to_string(), false));
+    }
+    sentences
+}
+
+/// Split "explain X and compare it with Y" into one clause per request
+fn split_requests(sentence: &str) -> Vec<String> {
+    let words: Vec<&str> = sentence.split_whitespace().collect();
+    let mut clauses = Vec::new();
+    let mut start = 0;
+    for i in 1..words.len().saturating_sub(1) {
+        let next = normalize(words[i + 1]);
+        let starts_request =
+            REQUEST_VERBS.contains(&next.as_str()) || QUESTION_WORDS.contains(&next.as_str());
+        if normalize(words[i]) == "and" && starts_request && i > start {
+            clauses.push(words[start..i].join(" "));
+            start = i + 1;
+        }
+    }
+    clauses.push(words[start..].join(" "));
+    clauses
+        .into_iter()
+        .map(|c| c.trim_end_matches(',').to_string())
+        .collect()
+}
+
+fn is_request(clause: &str) -> bool {
+    let words: Vec<String> = clause.split_whitespace().map(normalize).collect();
+    let first = words.iter().find(|w| {
+        !matches!(
+            w.as_str(),
+            "please" | "also" | "then" | "finally" | "ideally" | "optionally"
+        )
+    });
+    first.is_some_and(|w| REQUEST_VERBS.contains(&w.as_str()))
+        || words
+            .iter()
+            .any(|w| REQUIREMENT_WORDS.contains(&w.as_str()))
+        || clause.to_lowercase().contains("make sure")
+}
+
+fn normalize(word: &str) -> String {
+    word.trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase()
+}
+
+/// Stemmed content words, leaving out the verbs that only ask for work
+fn concepts(text: &str) -> BTreeSet<String> {
+    let language = Language::detect(text);
+    language
+        .tokenize(text)
+        .into_iter()
+        .filter(|word| {
+            !language.is_stop_word(word)
+                && is_meaningful_word(word)
+                && !REQUEST_VERBS.contains(&word.as_str())
+                && !REQUIREMENT_WORDS.contains(&word.as_str())
+        })
+        .map(|word| language.stem(&word))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requirements_are_extracted() {
+        let coverage = SubgoalTracker::from_query(
+            "We run a payment service in Rust. Explain why the settlement queue \
+             stalls under load and propose a retry strategy. The fix must keep \
+             idempotency guarantees. Ideally, suggest monitoring dashboards.",
+            1,
+        )
+        .coverage();
+        let texts: Vec<&str> = coverage.subgoals.iter().map(|s| s.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            [
+                "Explain why the settlement queue stalls under load",
+                "propose a retry strategy",
+                "The fix must keep idempotency guarantees",
+                "Ideally, suggest monitoring dashboards",
+            ]
+        );
+        assert!(coverage.subgoals[..3].iter().all(|s| s.critical));
+        assert!(!coverage.subgoals[3].critical);
+
+        let listed = SubgoalTracker::from_query(
+            "Requirements:\n1. Parse the config file\n2) Validate port ranges\n- Report errors",
+            1,
+        );
+        assert_eq!(listed.coverage().subgoals.len(), 3);
+    }
+
+    #[test]
+    fn test_coverage_follows_the_thoughts() {
+        let mut tracker = SubgoalTracker::from_list(
+            &[
+                "Explain why the settlement queue stalls".to_string(),
+                "Propose a retry strategy with backoff".to_string(),
+                "Keep idempotency guarantees".to_string(),
+            ],
+            1,
+        );
+        // The thought that stated the subgoals does not address them
+        tracker.observe("Keep idempotency guarantees", 1);
+        assert_eq!(tracker.coverage().open_critical, [1, 2, 3]);
+
+        tracker.observe(
+            "The settlement queue stalls because workers hold locks while waiting.",
+            2,
+        );
+        tracker.observe("A retry would help here.", 3);
+        let coverage = tracker.coverage();
+        assert_eq!(coverage.subgoals[0].status, SubgoalStatus::Addressed);
+        assert_eq!(coverage.subgoals[0].addressed_in, Some(2));
+        assert_eq!(coverage.subgoals[1].status, SubgoalStatus::Partial);
+        assert_eq!(coverage.subgoals[2].status, SubgoalStatus::Unaddressed);
+        assert_eq!(coverage.open_critical, [2, 3]);
+    }
+}
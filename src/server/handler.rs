@@ -4,7 +4,7 @@ use rmcp::{
         ListPromptsResult, ListToolsResult, PaginatedRequestParam, Prompt, PromptMessage,
         PromptMessageContent, PromptMessageRole, ServerInfo, Tool,
     },
-    service::RequestContext,
+    service::{NotificationContext, RequestContext},
     Error as McpError, ServerHandler,
 };
 use serde_json::{json, Map, Value};
@@ -12,8 +12,15 @@ use std::sync::Arc;
 use tracing::{debug, error, info};
 
 use super::LuxServer;
+use crate::feedback::FeedbackRequest;
+use crate::llm::client::{ChatMessage, Role};
+use crate::llm::openai::OpenAIClient;
+use crate::metachain::IlluminateRequest;
+use crate::monitoring::lexicon::builtin_lexicons;
+use crate::threading::context::Role as ThreadRole;
 use crate::tools::{
-    BiasedReasoningRequest, ChatRequest, PlannerRequest, StepType, TracedReasoningRequest,
+    BiasedReasoningRequest, ChatRequest, ConsensusRequest, PlannerRequest, StepType,
+    TracedReasoningRequest,
 };
 use lux_synthesis_db::PostgresSink;
 
@@ -21,12 +28,94 @@ fn json_to_arc_map(value: Value) -> Arc<Map<String, Value>> {
     Arc::new(value.as_object().cloned().unwrap_or_default())
 }
 
+/// Schema of the per-call `monitoring` overrides shared by the reasoning tools
+fn monitoring_schema() -> Value {
+    let threshold = |description: &str| json!({ "type": "number", "description": description });
+    json!({
+        "type": "object",
+        "description": "Optional monitoring preset and thresholds for this call only; unset values come from the server's [monitoring] config",
+        "properties": {
+            "preset": {
+                "type": "string",
+                "enum": ["strict", "balanced", "lenient"],
+                "description": "Start from a preset instead of the server's thresholds"
+            },
+            "circular_similarity_threshold": threshold("Similarity at which a thought repeats an earlier one"),
+            "circular_intervention_threshold": threshold("Circular score that raises an intervention"),
+            "relevance_threshold": threshold("Relevance to the query below which a thought drifts"),
+            "detail_density_threshold": threshold("Detail density that counts as fixation"),
+            "distractor_window": { "type": "integer", "description": "Thoughts the distractor detector looks back over" },
+            "vocab_decline_threshold": threshold("Vocabulary decline that counts as degradation"),
+            "coherence_decline_threshold": threshold("Coherence decline that counts as degradation"),
+            "reasoning_decline_threshold": threshold("Reasoning depth decline that counts as degradation"),
+            "degradation_min_thoughts": { "type": "integer", "description": "Thoughts needed before degradation is judged" },
+            "sudden_drop_threshold": { "type": "number", "description": "Accumulated metric fall, in noise units, that counts as a sudden drop" },
+            "disabled_detectors": {
+                "type": "array",
+                "items": { "type": "string" },
+                "description": "Detectors to skip, e.g. [\"distractor_fixation\"]"
+            },
+            "lexicons": lexicons_schema("Domain lexicons for this call, replacing the session's"),
+            "rules": {
+                "type": "array",
+                "description": "Intervention rules replacing the configured ones; the first match decides",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string" },
+                        "when": { "type": "string", "description": "Condition, e.g. \"circular_score > 0.6 && step > 3\"" },
+                        "action": { "type": "string", "enum": ["warn", "force_revision", "force_conclusion", "switch_model"] },
+                        "model": { "type": "string", "description": "Model to continue with; required by switch_model" },
+                        "message": { "type": "string", "description": "Guidance; {name} placeholders are filled from the facts" }
+                    },
+                    "required": ["name", "when", "action", "message"],
+                    "additionalProperties": false
+                }
+            }
+        },
+        "additionalProperties": false
+    })
+}
+
+/// Schema of the optional `reasoning_effort` parameter
+fn reasoning_effort_schema() -> Value {
+    json!({
+        "type": "string",
+        "enum": ["minimal", "low", "medium", "high"],
+        "description": "Optional reasoning effort for reasoning models (GPT-5, O3, O4; 'minimal' is GPT-5 only). Ignored by models that don't support it. Default: high"
+    })
+}
+
+/// Schema of the optional `verbosity` parameter
+fn verbosity_schema() -> Value {
+    json!({
+        "type": "string",
+        "enum": ["low", "medium", "high"],
+        "description": "Optional answer verbosity (GPT-5 only). Ignored by other models. Default: high"
+    })
+}
+
+/// Schema of a `lexicons` list, with the built-in packs named
+fn lexicons_schema(description: &str) -> Value {
+    json!({
+        "type": "array",
+        "items": { "type": "string" },
+        "description": format!(
+            "{}. Built-in: {}; other names are read from lexicons/<name>.toml",
+            description,
+            builtin_lexicons().collect::<Vec<_>>().join(", ")
+        )
+    })
+}
+
 impl ServerHandler for LuxServer {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             protocol_version: Default::default(),
             capabilities: rmcp::model::ServerCapabilities {
-                tools: Some(rmcp::model::ToolsCapability { list_changed: None }),
+                tools: Some(rmcp::model::ToolsCapability {
+                    list_changed: Some(true),
+                }),
                 prompts: Some(rmcp::model::PromptsCapability { list_changed: None }),
                 ..Default::default()
             },
@@ -40,6 +129,12 @@ impl ServerHandler for LuxServer {
         }
     }
 
+    async fn on_initialized(&self, context: NotificationContext<rmcp::service::RoleServer>) {
+        info!("Client initialized");
+        // Kept so config reloads can send tools/list_changed
+        *self.peer.write() = Some(context.peer);
+    }
+
     async fn list_tools(
         &self,
         _request: Option<PaginatedRequestParam>,
@@ -64,6 +159,8 @@ impl ServerHandler for LuxServer {
                             "type": "number",
                             "description": "Optional temperature (0.0-1.0). Note: O3/O4 models may ignore custom temperatures"
                         },
+                        "reasoning_effort": reasoning_effort_schema(),
+                        "verbosity": verbosity_schema(),
                         "continuation_id": {
                             "type": "string",
                             "description": "Optional thread ID to continue a previous conversation"
@@ -94,6 +191,50 @@ impl ServerHandler for LuxServer {
                 })),
                 annotations: None,
             },
+            Tool {
+                name: "consensus".into(),
+                description: Some("Ask several models the same question IN PARALLEL, then have a judge model extract agreements, disagreements and a merged recommendation. Pass file paths as STRINGS in 'file_paths' - every model gets the same file context. Returns a continuation_id to continue the consensus thread.".into()),
+                input_schema: json_to_arc_map(json!({
+                    "type": "object",
+                    "properties": {
+                        "prompt": {
+                            "type": "string",
+                            "description": "The question every model answers"
+                        },
+                        "models": {
+                            "type": "array",
+                            "items": {
+                                "type": "string"
+                            },
+                            "description": "Optional models to consult (e.g., ['gpt5', 'opus', 'grok']). Default: the normal model plus the configured opus and grok aliases. Models whose provider has no API key are skipped"
+                        },
+                        "judge_model": {
+                            "type": "string",
+                            "description": "Optional model that compares the answers. Default: configured reasoning model"
+                        },
+                        "reasoning_effort": reasoning_effort_schema(),
+                        "verbosity": verbosity_schema(),
+                        "continuation_id": {
+                            "type": "string",
+                            "description": "Optional thread ID to continue a previous consensus"
+                        },
+                        "file_paths": {
+                            "type": "array",
+                            "items": {
+                                "type": "string"
+                            },
+                            "description": "Optional file paths to include in every model's context"
+                        },
+                        "include_file_contents": {
+                            "type": "boolean",
+                            "description": "Whether to include file contents in the prompt (default: true)"
+                        }
+                    },
+                    "required": ["prompt"],
+                    "additionalProperties": false
+                })),
+                annotations: None,
+            },
             Tool {
                 name: "traced_reasoning".into(),
                 description: Some("Multi-call step-by-step reasoning with metacognitive monitoring. ⚠️ IMPORTANT: Uses MAXIMUM TOKEN ALLOCATION for deep reasoning. GPT-5 uses 128,000 tokens, O3 uses 100,000 tokens. The 'max_tokens' parameter is NOT supported and will be IGNORED.".into()),
@@ -146,6 +287,8 @@ impl ServerHandler for LuxServer {
                             "type": "number",
                             "description": "Optional temperature (0.0-1.0, default: 0.7)"
                         },
+                        "reasoning_effort": reasoning_effort_schema(),
+                        "verbosity": verbosity_schema(),
                         "guardrails": {
                             "type": "object",
                             "properties": {
@@ -160,9 +303,24 @@ impl ServerHandler for LuxServer {
                                 "perplexity_monitoring": {
                                     "type": "boolean",
                                     "description": "Enable perplexity monitoring (default: true)"
+                                },
+                                "subgoal_coverage": {
+                                    "type": "boolean",
+                                    "description": "Hold back a conclusion while critical subgoals of the query are open (default: true)"
                                 }
                             }
                         },
+                        "subgoals": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Requirements to track on thought 1, instead of those found in the query. Mark one optional by saying so (e.g. 'optional: add a dashboard')"
+                        },
+                        "lexicons": lexicons_schema("Domain lexicons for concept matching, kept for the rest of the session"),
+                        "illuminate_interventions": {
+                            "type": "boolean",
+                            "description": "When an intervention fires, also return a critique from the mini model: the assumption to question, what was skipped and what to try next (default: false)"
+                        },
+                        "monitoring": monitoring_schema(),
                         "continuation_id": {
                             "type": "string",
                             "description": "Optional thread ID to continue a previous conversation"
@@ -209,7 +367,9 @@ impl ServerHandler for LuxServer {
                         "max_analysis_rounds": {
                             "type": "integer",
                             "description": "Maximum analysis rounds (default: 3)"
-                        }
+                        },
+                        "reasoning_effort": reasoning_effort_schema(),
+                        "verbosity": verbosity_schema()
                     },
                     "required": ["query"],
                     "additionalProperties": false,
@@ -223,7 +383,70 @@ impl ServerHandler for LuxServer {
                 description: Some("Check the current illumination status and metacognitive state".into()),
                 input_schema: json_to_arc_map(json!({
                     "type": "object",
-                    "properties": {}
+                    "properties": {
+                        "session_id": {
+                            "type": "string",
+                            "description": "Optional session whose monitoring thresholds and rolling summaries to show (default: the server's thresholds)"
+                        }
+                    }
+                })),
+                annotations: None,
+            },
+            Tool {
+                name: "illuminate".into(),
+                description: Some("Metacognitive critique of a reasoning step: runs the thought through the session's monitor and has the mini model name the assumption to question, what was skipped and what to try next. Pass the session_id of a traced_reasoning or planner session to use its monitoring signals and recent thoughts.".into()),
+                input_schema: json_to_arc_map(json!({
+                    "type": "object",
+                    "properties": {
+                        "thought": {
+                            "type": "string",
+                            "description": "The reasoning step to critique"
+                        },
+                        "thought_number": {
+                            "type": "integer",
+                            "minimum": 1,
+                            "description": "Position of the thought in the session (default: after the session's last thought)"
+                        },
+                        "session_id": {
+                            "type": "string",
+                            "description": "Optional session whose monitor and recent thoughts to use (default: a new session)"
+                        },
+                        "model": {
+                            "type": "string",
+                            "description": "Optional model for the critique. Default: configured mini model"
+                        }
+                    },
+                    "required": ["thought"],
+                    "additionalProperties": false
+                })),
+                annotations: None,
+            },
+            Tool {
+                name: "feedback".into(),
+                description: Some("Report what became of an answer whose confidence Lux reported (traced_reasoning, sequential_thinking_external, biased_reasoning). Outcomes are stored and used to calibrate later confidences per model and tool. Pass the answer_id printed next to the confidence, or a session_id to rate that session's latest answer.".into()),
+                input_schema: json_to_arc_map(json!({
+                    "type": "object",
+                    "properties": {
+                        "answer_id": {
+                            "type": "string",
+                            "description": "Answer ID shown next to the reported confidence"
+                        },
+                        "session_id": {
+                            "type": "string",
+                            "description": "Rate the latest answer of this session instead of a specific answer"
+                        },
+                        "outcome": {
+                            "type": "string",
+                            "enum": ["accepted", "rejected", "correct", "incorrect"],
+                            "description": "Accepted and correct count as the answer holding up; rejected and incorrect as not"
+                        },
+                        "note": {
+                            "type": "string",
+                            "description": "Optional note stored with the outcome"
+                        }
+                    },
+                    "required": ["outcome"],
+                    "additionalProperties": false
                 })),
                 annotations: None,
             },
@@ -294,6 +517,15 @@ impl ServerHandler for LuxServer {
                             "type": "string",
                             "description": "Optional model to use for planning (defaults to gpt-5 for maximum capability)"
                         },
+                        "monitoring": monitoring_schema(),
+                        "subgoals": {
+                            "type": "array",
+                            "items": { "type": "string" },
+                            "description": "Requirements to track on step 1, instead of those found in the task. Mark one optional by saying so"
+                        },
+                        "lexicons": lexicons_schema("Domain lexicons for concept matching, kept for the rest of the session"),
+                        "reasoning_effort": reasoning_effort_schema(),
+                        "verbosity": verbosity_schema(),
                         "temperature": {
                             "type": "number",
                             "description": "Optional temperature (0.0-1.0, default: 0.7)"
@@ -494,7 +726,7 @@ impl ServerHandler for LuxServer {
             },
             Tool {
                 name: "setup_config".into(),
-                description: Some("Configure Lux MCP environment settings. This tool guides the host LLM through creating or updating the .env configuration file with API keys and model preferences. The user only needs to provide their API keys.".into()),
+                description: Some("Configure Lux MCP environment settings. This tool guides the host LLM through creating or updating the .env configuration file with API keys and model preferences. The user only needs to provide their API keys. With apply=true, Lux validates each key and model with the providers and writes .env itself (keeping a .env.bak backup), returning a validation report.".into()),
                 input_schema: json_to_arc_map(json!({
                     "type": "object",
                     "properties": {
@@ -528,6 +760,11 @@ impl ServerHandler for LuxServer {
                                 }
                             },
                             "description": "Custom model preferences"
+                        },
+                        "apply": {
+                            "type": "boolean",
+                            "description": "Validate keys and models with the providers and write .env directly (default: false, which only returns instructions)",
+                            "default": false
                         }
                     },
                     "additionalProperties": false
@@ -536,6 +773,11 @@ impl ServerHandler for LuxServer {
             },
         ];
 
+        let tools = tools
+            .into_iter()
+            .filter(|tool| self.tool_available(&tool.name))
+            .collect();
+
         Ok(ListToolsResult {
             next_cursor: None,
             tools,
@@ -546,13 +788,171 @@ impl ServerHandler for LuxServer {
         &self,
         request: CallToolRequestParam,
         _context: RequestContext<rmcp::service::RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        self.invoke_tool(request).await
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<rmcp::service::RoleServer>,
+    ) -> Result<ListPromptsResult, McpError> {
+        let prompts = vec![
+            Prompt {
+                name: "confer".to_string(),
+                description: Some("Start a conversation with metacognitive awareness".to_string()),
+                arguments: Some(vec![
+                    rmcp::model::PromptArgument {
+                        name: "message".to_string(),
+                        description: Some("What you want to chat about".to_string()),
+                        required: Some(true),
+                    }
+                ]),
+            },
+            Prompt {
+                name: "traced_reasoning".to_string(),
+                description: Some("Multi-call step-by-step reasoning with metacognitive monitoring - Generate variable thoughts".to_string()),
+                arguments: Some(vec![
+                    rmcp::model::PromptArgument {
+                        name: "thought".to_string(),
+                        description: Some("Initial query or problem to reason through".to_string()),
+                        required: Some(true),
+                    }
+                ]),
+            },
+            Prompt {
+                name: "biased_reasoning".to_string(),
+                description: Some("Dual-model reasoning with bias detection".to_string()),
+                arguments: Some(vec![
+                    rmcp::model::PromptArgument {
+                        name: "query".to_string(),
+                        description: Some("The question or problem to analyze for bias".to_string()),
+                        required: Some(true),
+                    }
+                ]),
+            },
+            Prompt {
+                name: "planner".to_string(),
+                description: Some("Interactive sequential planner - Break down complex tasks through step-by-step planning".to_string()),
+                arguments: Some(vec![
+                    rmcp::model::PromptArgument {
+                        name: "step".to_string(),
+                        description: Some("Your planning step or task description".to_string()),
+                        required: Some(true),
+                    }
+                ]),
+            },
+        ];
+
+        Ok(ListPromptsResult {
+            next_cursor: None,
+            prompts,
+        })
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParam,
+        _context: RequestContext<rmcp::service::RoleServer>,
+    ) -> Result<GetPromptResult, McpError> {
+        let prompt_text = match request.name.as_ref() {
+            "confer" => {
+                let message = request
+                    .arguments
+                    .as_ref()
+                    .and_then(|args| args.get("message"))
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("");
+
+                format!("Start a conversation about: {}", message)
+            }
+
+            "traced_reasoning" => {
+                let thought = request
+                    .arguments
+                    .as_ref()
+                    .and_then(|args| args.get("thought"))
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("");
+
+                format!("Begin multi-step reasoning about: {}", thought)
+            }
+
+            "biased_reasoning" => {
+                let query = request
+                    .arguments
+                    .as_ref()
+                    .and_then(|args| args.get("query"))
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("");
+
+                format!("Analyze for potential biases: {}", query)
+            }
+
+            "planner" => {
+                let step = request
+                    .arguments
+                    .as_ref()
+                    .and_then(|args| args.get("step"))
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("");
+
+                format!("Create an interactive sequential plan for: {}", step)
+            }
+
+            "illumination_status" => {
+                "Check the current metacognitive monitoring status".to_string()
+            }
+
+            _ => {
+                return Err(McpError::invalid_params(
+                    format!("Prompt '{}' not found", request.name),
+                    None,
+                ))
+            }
+        };
+
+        let message = PromptMessage {
+            role: PromptMessageRole::User,
+            content: PromptMessageContent::text(prompt_text),
+        };
+
+        Ok(GetPromptResult {
+            description: Some("Metacognitive guidance for illuminated thinking".to_string()),
+            messages: vec![message],
+        })
+    }
+}
+
+impl LuxServer {
+    /// Run one tool call; also used by `lux-mcp call` outside an MCP host
+    pub async fn invoke_tool(
+        &self,
+        request: CallToolRequestParam,
     ) -> Result<CallToolResult, McpError> {
         info!("Tool request: {}", request.name);
         debug!("Tool arguments: {:?}", request.arguments);
 
+        // Attribute any LLM requests made by this call in the audit log
+        let session = request.arguments.as_ref().and_then(|args| {
+            ["session_id", "continuation_id"]
+                .iter()
+                .find_map(|key| args.get(*key).and_then(|v| v.as_str()))
+                .map(String::from)
+        });
+        // Tools that create a thread or session update it via `set_session`
+        let audit_context =
+            crate::llm::audit::AuditContext::new(Some(request.name.to_string()), session);
+        crate::llm::audit::scope(audit_context, self.dispatch_tool(request)).await
+    }
+
+    async fn dispatch_tool(
+        &self,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, McpError> {
         match request.name.as_ref() {
             "confer" => {
-                let mut args = request.arguments.clone().unwrap_or_default();
+                let args = request.arguments.clone().unwrap_or_default();
 
                 // Extract and handle continuation_id
                 let continuation_id = args
@@ -560,46 +960,73 @@ impl ServerHandler for LuxServer {
                     .and_then(|v| v.as_str())
                     .and_then(|s| uuid::Uuid::parse_str(s).ok());
 
-                // Reconstruct context if continuation exists
+                let mut req: ChatRequest =
+                    match serde_json::from_value(serde_json::Value::Object(args)) {
+                        Ok(req) => req,
+                        Err(e) => {
+                            error!("Failed to parse chat request: {}", e);
+                            return Err(McpError::invalid_params(
+                                format!("Invalid chat params: {}", e),
+                                None,
+                            ));
+                        }
+                    };
+
+                let chat_tool = self.chat_tool();
                 let thread_id = if let Some(id) = continuation_id {
-                    if let Some(context) =
+                    // Responses API models keep the history server-side, so chain
+                    // from the last response instead of resending the whole thread
+                    let previous_response_id =
+                        self.thread_manager.get_last_response_id(&id).filter(|_| {
+                            OpenAIClient::uses_responses_api(&chat_tool.resolve_model(&req))
+                        });
+
+                    if let Some(previous_response_id) = previous_response_id {
+                        info!(
+                            "Chaining confer thread {} from response {}",
+                            id, previous_response_id
+                        );
+                        req.previous_response_id = Some(previous_response_id);
+                        // Resent instead if the answer comes from a fallback model
+                        req.history = self
+                            .thread_manager
+                            .get_thread(&id)
+                            .map(|thread| {
+                                thread
+                                    .turns
+                                    .iter()
+                                    .map(|turn| ChatMessage {
+                                        role: match turn.role {
+                                            ThreadRole::User => Role::User,
+                                            ThreadRole::Assistant => Role::Assistant,
+                                            ThreadRole::System => Role::System,
+                                        },
+                                        content: turn.content.clone(),
+                                    })
+                                    .collect()
+                            })
+                            .unwrap_or_default();
+                    } else if let Some(context) =
                         crate::threading::reconstruction::ContextReconstructor::reconstruct(
                             &self.thread_manager,
                             &id,
                         )
                     {
                         // Prepend context to message
-                        if let Some(message) = args.get_mut("message") {
-                            if let Some(msg_str) = message.as_str() {
-                                let enhanced_message =
-                                    format!("{}\n\nCurrent message: {}", context, msg_str);
-                                *message = serde_json::Value::String(enhanced_message);
-                            }
-                        }
+                        req.message = format!("{}\n\nCurrent message: {}", context, req.message);
                     }
                     id
                 } else {
                     // Create new thread
                     self.thread_manager.create_thread("confer")
                 };
-
-                let req: ChatRequest = match serde_json::from_value(serde_json::Value::Object(args))
-                {
-                    Ok(req) => req,
-                    Err(e) => {
-                        error!("Failed to parse chat request: {}", e);
-                        return Err(McpError::invalid_params(
-                            format!("Invalid chat params: {}", e),
-                            None,
-                        ));
-                    }
-                };
+                crate::llm::audit::set_session(thread_id.to_string());
 
                 info!(
                     "Processing confer request with message: {} (thread: {})",
                     req.message, thread_id
                 );
-                match self.chat_tool.chat(req.clone()).await {
+                match chat_tool.chat(req.clone()).await {
                     Ok(response) => {
                         info!("Confer request successful - Model used: {}", response.model);
 
@@ -613,12 +1040,30 @@ impl ServerHandler for LuxServer {
                             timestamp: std::time::Instant::now(),
                         };
                         self.thread_manager.add_turn(&thread_id, turn);
+                        self.thread_manager
+                            .set_last_response_id(&thread_id, response.response_id.clone());
+                        self.record_turn_quality(&thread_id, "confer").await;
+
+                        // Code references the provided files don't back up
+                        let grounding = if response.ungrounded.is_empty() {
+                            String::new()
+                        } else {
+                            format!(
+                                "🔎 **UNGROUNDED REFERENCES** (verify before acting on them):\n{}\n\n",
+                                response
+                                    .ungrounded
+                                    .iter()
+                                    .map(|u| format!("• {}", u))
+                                    .collect::<Vec<_>>()
+                                    .join("\n")
+                            )
+                        };
 
                         // Format response with continuation_id
                         let formatted_response = format!(
                             "🔍 **LUX ANALYSIS COMPLETE** 🔍\n\n\
                             ⚠️ **IMPORTANT: You MUST now SYNTHESIZE and ACT on the following analysis from the {} model:**\n\n\
-                            ---\n\n{}\n\n---\n\n\
+                            ---\n\n{}\n\n---\n\n{}\
                             🎯 **REQUIRED ACTIONS:**\n\
                             1. ✅ SUMMARIZE the key findings\n\
                             2. ✅ IDENTIFY the most critical recommendations\n\
@@ -628,6 +1073,7 @@ impl ServerHandler for LuxServer {
                             📎 **Continuation ID**: {} (Use this to continue the conversation)",
                             response.model,
                             response.content,
+                            grounding,
                             thread_id
                         );
 
@@ -643,6 +1089,143 @@ impl ServerHandler for LuxServer {
                 }
             }
 
+            "consensus" => {
+                let args = request.arguments.clone().unwrap_or_default();
+                let req: ConsensusRequest = serde_json::from_value(serde_json::Value::Object(args))
+                    .map_err(|e| {
+                        McpError::invalid_params(format!("Invalid consensus params: {}", e), None)
+                    })?;
+
+                let continuation_id = req
+                    .continuation_id
+                    .as_deref()
+                    .and_then(|s| uuid::Uuid::parse_str(s).ok());
+                let (thread_id, history) = match continuation_id {
+                    Some(id) => (
+                        id,
+                        crate::threading::reconstruction::ContextReconstructor::reconstruct(
+                            &self.thread_manager,
+                            &id,
+                        ),
+                    ),
+                    None => (self.thread_manager.create_thread("consensus"), None),
+                };
+                crate::llm::audit::set_session(thread_id.to_string());
+
+                let response = self
+                    .consensus_tool()
+                    .run(&req, history)
+                    .await
+                    .map_err(|e| {
+                        McpError::internal_error(format!("Consensus error: {}", e), None)
+                    })?;
+
+                // Record the question, every answer and the verdict in one thread
+                let record = |role, content: String, source: &str| {
+                    self.thread_manager.add_turn(
+                        &thread_id,
+                        crate::threading::ConversationTurn {
+                            role,
+                            content,
+                            tool_used: Some(source.to_string()),
+                            synthesis_snapshot: None,
+                            quality_metrics: None,
+                            timestamp: std::time::Instant::now(),
+                        },
+                    );
+                };
+                if let Some(paths) = &req.file_paths {
+                    self.thread_manager.add_files(&thread_id, paths.clone());
+                }
+                record(
+                    crate::threading::context::Role::User,
+                    req.prompt.clone(),
+                    "consensus",
+                );
+
+                let mut output = String::from("🤝 **LUX CONSENSUS** 🤝\n\n");
+                for answer in &response.answers {
+                    match (&answer.content, &answer.error) {
+                        (Some(content), _) => {
+                            record(
+                                crate::threading::context::Role::Assistant,
+                                content.clone(),
+                                &format!("consensus:{}", answer.model),
+                            );
+                            output.push_str(&format!(
+                                "### 🤖 {} ({:.1}s)\n\n{}\n\n",
+                                answer.model,
+                                answer.elapsed_ms as f64 / 1000.0,
+                                content
+                            ));
+                            if !answer.ungrounded.is_empty() {
+                                output.push_str(&format!(
+                                    "🔎 **Ungrounded references** (verify before acting on them):\n{}\n\n",
+                                    answer
+                                        .ungrounded
+                                        .iter()
+                                        .map(|u| format!("• {}", u))
+                                        .collect::<Vec<_>>()
+                                        .join("\n")
+                                ));
+                            }
+                        }
+                        (None, error) => {
+                            output.push_str(&format!(
+                                "### ❌ {} failed\n\n{}\n\n",
+                                answer.model,
+                                error.as_deref().unwrap_or("unknown error")
+                            ));
+                        }
+                    }
+                }
+
+                output.push_str("━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━\n\n");
+                match (&response.verdict, &response.judge_error) {
+                    (Some(verdict), _) => {
+                        let bullets = |items: &[String]| {
+                            if items.is_empty() {
+                                "- (none)\n".to_string()
+                            } else {
+                                items.iter().map(|item| format!("- {}\n", item)).collect()
+                            }
+                        };
+                        let summary = format!(
+                            "✅ **Agreements:**\n{}\n⚖️ **Disagreements:**\n{}\n🎯 **Recommendation:**\n{}",
+                            bullets(&verdict.agreements),
+                            bullets(&verdict.disagreements),
+                            verdict.recommendation
+                        );
+                        record(
+                            crate::threading::context::Role::Assistant,
+                            summary.clone(),
+                            &format!("consensus:judge:{}", response.judge_model),
+                        );
+                        output.push_str(&format!(
+                            "🧑‍⚖️ **Judge ({})**\n\n{}\n\n",
+                            response.judge_model, summary
+                        ));
+                    }
+                    (None, Some(error)) => output.push_str(&format!(
+                        "⚠️ **Judge ({}) failed**, compare the answers above yourself.\n\n{}\n\n",
+                        response.judge_model, error
+                    )),
+                    (None, None) => output.push_str(
+                        "⚠️ Only one model answered, so there is nothing to compare.\n\n",
+                    ),
+                }
+                output.push_str(&format!(
+                    "📎 **Continuation ID**: {} (Use this to continue the consensus)",
+                    thread_id
+                ));
+                self.record_turn_quality(&thread_id, "consensus").await;
+
+                Ok(CallToolResult {
+                    content: vec![Content::text(output)],
+                    is_error: Some(false),
+                })
+            }
+
             "traced_reasoning" => {
                 let req: TracedReasoningRequest = if let Some(args) = request.arguments {
                     serde_json::from_value(serde_json::Value::Object(args)).map_err(|e| {
@@ -663,23 +1246,19 @@ impl ServerHandler for LuxServer {
                     info!("Using specified model: {}", model);
                 }
 
-                // Lock the mutex to access the mutable traced reasoning tool
-                let mut reasoning_tool = self.traced_reasoning_tool.lock().await;
-
                 // Set up synthesis sink if database is available
+                let reasoning_tool = self.traced_reasoning_tool();
                 if let Some(db) = &self.db_service {
                     let pool = db.pool();
                     let sink = Arc::new(PostgresSink::new(pool));
-                    reasoning_tool.set_synthesis_sink(sink);
+                    reasoning_tool.set_synthesis_sink(sink).await;
                 }
 
+                let session_id = req.session_id.clone();
                 let response = reasoning_tool.process_thought(req).await.map_err(|e| {
                     McpError::internal_error(format!("Reasoning error: {}", e), None)
                 })?;
 
-                // Drop the lock immediately after use
-                drop(reasoning_tool);
-
                 info!("Response model_used: {:?}", response.model_used);
 
                 // Format the response based on status
@@ -692,11 +1271,18 @@ impl ServerHandler for LuxServer {
                             .cloned()
                             .unwrap_or_else(|| "ERROR: Model not specified".to_string());
                         let model_display = format!("Model: {} 🤖\n", model_name);
+                        let confidence = self.feedback.record(
+                            "traced_reasoning",
+                            "step",
+                            &model_name,
+                            session_id.as_deref(),
+                            response.metadata.current_confidence,
+                        );
 
                         let mut result = format!(
                             "💭 Thought {} of {}: {}\n\
                             {}\
-                            Confidence: {:.2}\n\n\
+                            Confidence: {} · Answer ID: {}\n\n\
                             ---\n\n\
                             {}\n\n\
                             ---\n\n\
@@ -708,10 +1294,11 @@ impl ServerHandler for LuxServer {
                             response.total_thoughts,
                             response.thought_type,
                             model_display,
-                            response.metadata.current_confidence,
+                            confidence,
+                            confidence.answer_id,
                             response.thought_content,
                             response.metadata.semantic_coherence,
-                            response.metadata.current_confidence,
+                            confidence.confidence,
                             response.metadata.interventions_count
                         );
 
@@ -741,6 +1328,14 @@ impl ServerHandler for LuxServer {
                             }
                         }
 
+                        if let Some(illumination) = &response.illumination {
+                            result.push_str(&format!("\n\n🔦 **Illumination:**\n{}", illumination));
+                        }
+
+                        if let Some(coverage) = &response.coverage {
+                            result.push_str(&format!("\n\n🧩 **Subgoals:** {}", coverage));
+                        }
+
                         result.push_str(&format!(
                             "\n\n➡️ **Next Action:** {}\n\n\
                             Use traced_reasoning again with thought_number: {} to continue.",
@@ -762,6 +1357,11 @@ impl ServerHandler for LuxServer {
                             .cloned()
                             .unwrap_or_else(|| "ERROR: Model not specified".to_string());
                         let model_display = format!("Model: {}", model_name);
+                        let illumination = response
+                            .illumination
+                            .as_ref()
+                            .map(|c| format!("🔦 **Illumination:**\n{}\n", c))
+                            .unwrap_or_default();
 
                         format!(
                             "⚠️ **REASONING INTERVENTION** ⚠️\n\n\
@@ -773,7 +1373,8 @@ impl ServerHandler for LuxServer {
                             **Description:** {}\n\n\
                             💭 **Thought Content:**\n{}\n\n\
                             ---\n\n\
-                            🔧 **Required Action:** Adjust your reasoning to address the intervention.\n\n\
+                            {}\
+                            🔧 **Required Action:** {}\n\n\
                             Continue with thought_number: {} after considering the intervention.",
                             response.thought_number,
                             response.total_thoughts,
@@ -782,6 +1383,11 @@ impl ServerHandler for LuxServer {
                             intervention.severity,
                             intervention.description,
                             response.thought_content,
+                            illumination,
+                            response
+                                .next_steps
+                                .as_deref()
+                                .unwrap_or("Adjust your reasoning to address the intervention."),
                             response.thought_number + 1
                         )
                     }
@@ -793,6 +1399,24 @@ impl ServerHandler for LuxServer {
                             .cloned()
                             .unwrap_or_else(|| "ERROR: Model not specified".to_string());
                         let model_display = format!("Model: {}", model_name);
+                        let final_confidence = response
+                            .overall_metrics
+                            .as_ref()
+                            .map(|m| {
+                                let confidence = self.feedback.record(
+                                    "traced_reasoning",
+                                    "final",
+                                    &model_name,
+                                    session_id.as_deref(),
+                                    m.final_confidence,
+                                );
+                                format!(
+                                    "• Final Confidence: {}\n\n\
+                                    🏷️ **Answer ID:** {} (report whether the answer held up with the feedback tool)\n",
+                                    confidence, confidence.answer_id
+                                )
+                            })
+                            .unwrap_or_default();
 
                         format!(
                             "✅ **REASONING COMPLETE** ✅\n\n\
@@ -805,7 +1429,8 @@ impl ServerHandler for LuxServer {
                             • Total Thoughts: {}\n\
                             • Average Confidence: {:.2}\n\
                             • Reasoning Quality: {:.2}\n\
-                            • Semantic Coherence: {:.2}\n\n\
+                            • Semantic Coherence: {:.2}\n\
+                            {}\n\
                             🔍 **Instructions:**\n{}",
                             response.thought_number,
                             response.total_thoughts,
@@ -834,6 +1459,7 @@ impl ServerHandler for LuxServer {
                                 .as_ref()
                                 .map(|m| m.semantic_coherence)
                                 .unwrap_or(0.0),
+                            final_confidence,
                             response
                                 .next_steps
                                 .as_ref()
@@ -876,15 +1502,15 @@ impl ServerHandler for LuxServer {
                 };
 
                 // Set up synthesis sink if database is available
+                let biased_reasoning_tool = self.biased_reasoning_tool();
                 if let Some(db) = &self.db_service {
                     let pool = db.pool();
                     let sink = Arc::new(PostgresSink::new(pool));
-                    self.biased_reasoning_tool.set_synthesis_sink(sink);
+                    biased_reasoning_tool.set_synthesis_sink(sink);
                 }
 
                 let start_time = std::time::Instant::now();
-                let response = self
-                    .biased_reasoning_tool
+                let response = biased_reasoning_tool
                     .process_step(req.clone())
                     .await
                     .map_err(|e| {
@@ -962,12 +1588,20 @@ impl ServerHandler for LuxServer {
                     }
                     StepType::BiasAnalysis => {
                         let bias_info = if let Some(ref bias) = response.bias_analysis {
+                            let confidence = self.feedback.record(
+                                "biased_reasoning",
+                                "bias_check",
+                                &response.model_used,
+                                Some(&response.session_id),
+                                bias.confidence,
+                            );
                             format!(
                                 "\n\n📊 **Detailed Analysis:**\n\
-                                • Confidence: {:.2}\n\
+                                • Confidence: {}\n\
                                 • Severity: {:?}\n\
-                                • Bias Types: {:?}",
-                                bias.confidence, bias.severity, bias.bias_types
+                                • Bias Types: {:?}\n\
+                                • Answer ID: {}",
+                                confidence, bias.severity, bias.bias_types, confidence.answer_id
                             )
                         } else {
                             String::new()
@@ -1042,12 +1676,22 @@ impl ServerHandler for LuxServer {
             }
 
             "illumination_status" => {
+                let session_id = request
+                    .arguments
+                    .as_ref()
+                    .and_then(|args| args.get("session_id"))
+                    .and_then(|v| v.as_str());
                 let status = json!({
                     "illumination": "active",
                     "brightness": 0.95,
                     "shadows_detected": "none",
                     "metacognitive_state": "clear",
-                    "message": "Your thinking is illuminated and clear 🔦"
+                    "message": "Your thinking is illuminated and clear 🔦",
+                    "last_config_reload": self.last_reload(),
+                    "embeddings": self.session_manager.embedder().provider_name(),
+                    "monitoring": self.session_manager.monitoring_config(session_id),
+                    "session_summaries": session_id
+                        .and_then(|id| self.session_manager.session_summaries(id))
                 });
 
                 Ok(CallToolResult {
@@ -1063,6 +1707,112 @@ impl ServerHandler for LuxServer {
                 })
             }
 
+            "illuminate" => {
+                let req: IlluminateRequest =
+                    serde_json::from_value(Value::Object(request.arguments.unwrap_or_default()))
+                        .map_err(|e| {
+                            McpError::invalid_params(
+                                format!("Invalid illuminate params: {}", e),
+                                None,
+                            )
+                        })?;
+
+                // The thought joins the session like any other, so the critique
+                // sees the same signals a reasoning tool would
+                let session_id = self
+                    .session_manager
+                    .get_or_create_session(req.session_id.clone());
+                crate::llm::audit::set_session(session_id.clone());
+                let monitor = self
+                    .session_manager
+                    .get_monitor(&session_id)
+                    .map_err(|e| McpError::internal_error(e.to_string(), None))?;
+                self.session_manager
+                    .prefetch_embeddings(&monitor, &[&req.thought])
+                    .await;
+                let (thought_number, signals, recent_thoughts) = {
+                    let mut monitor = monitor.lock();
+                    let thought_number = req.thought_number.unwrap_or_else(|| {
+                        monitor.memory().last_thought_number().map_or(1, |n| n + 1)
+                    });
+                    let recent_thoughts = monitor.recent_thoughts();
+                    let signals = monitor.analyze_thought(&req.thought, thought_number);
+                    (thought_number, signals, recent_thoughts)
+                };
+
+                let response = self
+                    .metachain()
+                    .process_thought(
+                        &req.thought,
+                        thought_number,
+                        Some(&signals),
+                        &recent_thoughts,
+                        req.model.as_deref(),
+                    )
+                    .await
+                    .map_err(|e| {
+                        McpError::internal_error(format!("Illumination error: {}", e), None)
+                    })?;
+
+                let mut text: String = response.content.iter().map(|c| c.text.as_str()).collect();
+                text.push_str(&format!(
+                    "\nModel: {}\n📎 Session: {} (pass it as session_id to keep illuminating this line of reasoning)",
+                    response.metadata.model, session_id
+                ));
+
+                Ok(CallToolResult {
+                    content: vec![Content::text(text)],
+                    is_error: Some(false),
+                })
+            }
+
+            "feedback" => {
+                let req: FeedbackRequest =
+                    serde_json::from_value(Value::Object(request.arguments.unwrap_or_default()))
+                        .map_err(|e| {
+                            McpError::invalid_params(
+                                format!("Invalid feedback params: {}", e),
+                                None,
+                            )
+                        })?;
+                let target = req
+                    .target()
+                    .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+                let answer = self
+                    .feedback
+                    .record_outcome(&target, req.outcome, req.note)
+                    .map_err(|e| McpError::invalid_params(e.to_string(), None))?;
+
+                let config = self.feedback.config();
+                let summaries: Vec<String> = self
+                    .feedback
+                    .summary(&answer.tool, &answer.score, &answer.model)
+                    .iter()
+                    .map(|summary| format!("• {}", summary))
+                    .collect();
+                let text = format!(
+                    "📝 Recorded **{}** for answer {}\n\
+                     • Tool: {} ({} confidence)\n\
+                     • Model: {}\n\
+                     • Reported confidence (raw): {:.2}\n\n\
+                     Calibration ({}, min {} outcomes):\n{}",
+                    req.outcome,
+                    answer.answer_id,
+                    answer.tool,
+                    answer.score,
+                    answer.model,
+                    answer.confidence,
+                    config.method,
+                    config.min_samples,
+                    summaries.join("\n")
+                );
+
+                Ok(CallToolResult {
+                    content: vec![Content::text(text)],
+                    is_error: Some(false),
+                })
+            }
+
             "planner" => {
                 let req: PlannerRequest = if let Some(args) = request.arguments {
                     serde_json::from_value(serde_json::Value::Object(args)).map_err(|e| {
@@ -1080,14 +1830,12 @@ impl ServerHandler for LuxServer {
                     req.step_number, req.total_steps
                 );
 
-                // Lock the mutex to access the mutable planner tool
-                let mut planner = self.planner_tool.lock().await;
-
                 // Set up synthesis sink if database is available
+                let planner = self.planner_tool();
                 if let Some(db) = &self.db_service {
                     let pool = db.pool();
                     let sink = Arc::new(PostgresSink::new(pool));
-                    planner.set_synthesis_sink(sink);
+                    planner.set_synthesis_sink(sink).await;
                 }
 
                 let response = planner
@@ -1095,9 +1843,6 @@ impl ServerHandler for LuxServer {
                     .await
                     .map_err(|e| McpError::internal_error(format!("Planner error: {}", e), None))?;
 
-                // Drop the lock immediately after use
-                drop(planner);
-
                 // Always show the actual model being used
                 let model_name = response
                     .model_used
@@ -1252,6 +1997,10 @@ impl ServerHandler for LuxServer {
                                 .unwrap_or(&"Plan completed".to_string())
                         );
 
+                        if let Some(coverage) = &response.coverage {
+                            result.push_str(&format!("🧩 **Subgoals:** {}\n\n", coverage));
+                        }
+
                         // Add mandatory actions if present
                         if let Some(mandatory_actions) = &response.mandatory_actions {
                             result.push_str("⚠️ **MANDATORY ACTIONS YOU MUST TAKE NOW:**\n");
@@ -1381,7 +2130,7 @@ impl ServerHandler for LuxServer {
                     };
 
                 let response = self
-                    .sequential_thinking_external_tool
+                    .sequential_thinking_external_tool()
                     .process_thought(req)
                     .await
                     .map_err(|e| {
@@ -1399,7 +2148,19 @@ impl ServerHandler for LuxServer {
 
                 let confidence_display = response
                     .confidence
-                    .map(|c| format!("Confidence: {:.2}", c))
+                    .map(|c| {
+                        let confidence = self.feedback.record(
+                            "sequential_thinking_external",
+                            "thought",
+                            response.model_used.as_deref().unwrap_or("unknown"),
+                            response.session_id.as_deref(),
+                            c,
+                        );
+                        format!(
+                            "Confidence: {} · Answer ID: {}",
+                            confidence, confidence.answer_id
+                        )
+                    })
                     .unwrap_or_default();
 
                 let formatted_response = format!(
@@ -1463,7 +2224,7 @@ impl ServerHandler for LuxServer {
                     };
 
                 let response = self
-                    .hybrid_biased_reasoning_tool
+                    .hybrid_biased_reasoning_tool()
                     .check_reasoning_bias(req)
                     .await
                     .map_err(|e| {
@@ -1551,10 +2312,12 @@ impl ServerHandler for LuxServer {
                         openrouter_api_key: None,
                         use_advanced_models: true,
                         custom_models: None,
+                        apply: false,
                     }
                 };
 
-                let tool = crate::tools::SetupConfigTool::new();
+                let tool = crate::tools::SetupConfigTool::new()
+                    .map_err(|e| McpError::internal_error(e.to_string(), None))?;
                 let response = tool
                     .setup_config(req)
                     .await
@@ -1575,135 +2338,4 @@ impl ServerHandler for LuxServer {
             )),
         }
     }
-
-    async fn list_prompts(
-        &self,
-        _request: Option<PaginatedRequestParam>,
-        _context: RequestContext<rmcp::service::RoleServer>,
-    ) -> Result<ListPromptsResult, McpError> {
-        let prompts = vec![
-            Prompt {
-                name: "confer".to_string(),
-                description: Some("Start a conversation with metacognitive awareness".to_string()),
-                arguments: Some(vec![
-                    rmcp::model::PromptArgument {
-                        name: "message".to_string(),
-                        description: Some("What you want to chat about".to_string()),
-                        required: Some(true),
-                    }
-                ]),
-            },
-            Prompt {
-                name: "traced_reasoning".to_string(),
-                description: Some("Multi-call step-by-step reasoning with metacognitive monitoring - Generate variable thoughts".to_string()),
-                arguments: Some(vec![
-                    rmcp::model::PromptArgument {
-                        name: "thought".to_string(),
-                        description: Some("Initial query or problem to reason through".to_string()),
-                        required: Some(true),
-                    }
-                ]),
-            },
-            Prompt {
-                name: "biased_reasoning".to_string(),
-                description: Some("Dual-model reasoning with bias detection".to_string()),
-                arguments: Some(vec![
-                    rmcp::model::PromptArgument {
-                        name: "query".to_string(),
-                        description: Some("The question or problem to analyze for bias".to_string()),
-                        required: Some(true),
-                    }
-                ]),
-            },
-            Prompt {
-                name: "planner".to_string(),
-                description: Some("Interactive sequential planner - Break down complex tasks through step-by-step planning".to_string()),
-                arguments: Some(vec![
-                    rmcp::model::PromptArgument {
-                        name: "step".to_string(),
-                        description: Some("Your planning step or task description".to_string()),
-                        required: Some(true),
-                    }
-                ]),
-            },
-        ];
-
-        Ok(ListPromptsResult {
-            next_cursor: None,
-            prompts,
-        })
-    }
-
-    async fn get_prompt(
-        &self,
-        request: GetPromptRequestParam,
-        _context: RequestContext<rmcp::service::RoleServer>,
-    ) -> Result<GetPromptResult, McpError> {
-        let prompt_text = match request.name.as_ref() {
-            "confer" => {
-                let message = request
-                    .arguments
-                    .as_ref()
-                    .and_then(|args| args.get("message"))
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("");
-
-                format!("Start a conversation about: {}", message)
-            }
-
-            "traced_reasoning" => {
-                let thought = request
-                    .arguments
-                    .as_ref()
-                    .and_then(|args| args.get("thought"))
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("");
-
-                format!("Begin multi-step reasoning about: {}", thought)
-            }
-
-            "biased_reasoning" => {
-                let query = request
-                    .arguments
-                    .as_ref()
-                    .and_then(|args| args.get("query"))
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("");
-
-                format!("Analyze for potential biases: {}", query)
-            }
-
-            "planner" => {
-                let step = request
-                    .arguments
-                    .as_ref()
-                    .and_then(|args| args.get("step"))
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("");
-
-                format!("Create an interactive sequential plan for: {}", step)
-            }
-
-            "illumination_status" => {
-                "Check the current metacognitive monitoring status".to_string()
-            }
-
-            _ => {
-                return Err(McpError::invalid_params(
-                    format!("Prompt '{}' not found", request.name),
-                    None,
-                ))
-            }
-        };
-
-        let message = PromptMessage {
-            role: PromptMessageRole::User,
-            content: PromptMessageContent::text(prompt_text),
-        };
-
-        Ok(GetPromptResult {
-            description: Some("Metacognitive guidance for illuminated thinking".to_string()),
-            messages: vec![message],
-        })
-    }
 }
@@ -1,64 +1,102 @@
 pub mod handler;
+pub mod reload;
 
 use crate::db::{DatabaseConnection, DatabaseService};
+use crate::feedback::FeedbackStore;
+use crate::llm::embeddings::Embedder;
 use crate::llm::LLMConfig;
 use crate::metachain::MetachainEngine;
 use crate::session::SessionManager;
+use crate::settings::LuxSettings;
+use crate::threading::context::Role;
 use crate::threading::{QualityThreadIntegration, SynthesisThreadIntegration, ThreadManager};
 use crate::tools::{
-    BiasedReasoningTool, ChatTool, HybridBiasedReasoningTool, PlannerTool,
+    BiasedReasoningTool, ChatTool, ConsensusTool, HybridBiasedReasoningTool, PlannerTool,
     SequentialThinkingExternalTool, SequentialThinkingTool, TracedReasoningTool,
 };
+use reload::ReloadStatus;
+use rmcp::service::{Peer, RoleServer};
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use tracing::warn;
+use uuid::Uuid;
 
 #[derive(Clone)]
 pub struct LuxServer {
-    chat_tool: Arc<ChatTool>,
-    traced_reasoning_tool: Arc<Mutex<TracedReasoningTool>>,
-    biased_reasoning_tool: Arc<BiasedReasoningTool>,
-    planner_tool: Arc<Mutex<PlannerTool>>,
+    // Tools holding LLM clients are swapped as a whole on config reload
+    config: Arc<parking_lot::RwLock<LLMConfig>>,
+    chat_tool: Arc<parking_lot::RwLock<Arc<ChatTool>>>,
+    consensus_tool: Arc<parking_lot::RwLock<Arc<ConsensusTool>>>,
+    traced_reasoning_tool: Arc<parking_lot::RwLock<Arc<TracedReasoningTool>>>,
+    biased_reasoning_tool: Arc<parking_lot::RwLock<Arc<BiasedReasoningTool>>>,
+    planner_tool: Arc<parking_lot::RwLock<Arc<PlannerTool>>>,
     sequential_thinking_tool: Arc<SequentialThinkingTool>,
-    sequential_thinking_external_tool: Arc<SequentialThinkingExternalTool>,
-    hybrid_biased_reasoning_tool: Arc<HybridBiasedReasoningTool>,
-    metachain: Arc<MetachainEngine>,
+    sequential_thinking_external_tool:
+        Arc<parking_lot::RwLock<Arc<SequentialThinkingExternalTool>>>,
+    hybrid_biased_reasoning_tool: Arc<parking_lot::RwLock<Arc<HybridBiasedReasoningTool>>>,
+    metachain: Arc<parking_lot::RwLock<Arc<MetachainEngine>>>,
     session_manager: Arc<SessionManager>,
     thread_manager: Arc<ThreadManager>,
     synthesis_integration: Arc<SynthesisThreadIntegration>,
     quality_integration: Arc<QualityThreadIntegration>,
     db_service: Option<Arc<DatabaseService>>,
+    /// Outcomes of reported confidences, and their calibration
+    feedback: Arc<FeedbackStore>,
+    peer: Arc<parking_lot::RwLock<Option<Peer<RoleServer>>>>,
+    reload_lock: Arc<Mutex<()>>,
+    last_reload: Arc<parking_lot::RwLock<Option<ReloadStatus>>>,
 }
 
 impl LuxServer {
     pub async fn new() -> anyhow::Result<Self> {
-        let config = LLMConfig::from_env()?;
+        let settings = LuxSettings::load()?;
+        let config = LLMConfig::from_settings(&settings)?;
+        crate::llm::audit::install(config.audit.as_ref())?;
 
-        let session_manager = Arc::new(SessionManager::new(30)); // 30 minute TTL
-        let thread_manager = Arc::new(ThreadManager::new()); // 3 hour TTL by default
+        let session_manager = Arc::new(
+            SessionManager::with_config(
+                settings.session.session_ttl_minutes,
+                settings.monitoring.clone(),
+            )
+            .with_embedder(Arc::new(Embedder::from_settings(&settings)?)),
+        );
+        let thread_manager = Arc::new(ThreadManager::with_ttl(std::time::Duration::from_secs(
+            settings.session.thread_ttl_minutes * 60,
+        )));
         let synthesis_integration =
             Arc::new(SynthesisThreadIntegration::new(thread_manager.clone()));
         let quality_integration = Arc::new(QualityThreadIntegration::new(thread_manager.clone()));
-        let chat_tool = Arc::new(ChatTool::new(config.clone())?);
-        let traced_reasoning_tool = Arc::new(Mutex::new(TracedReasoningTool::new(
+        let chat_tool = Arc::new(parking_lot::RwLock::new(Arc::new(ChatTool::new(
             config.clone(),
-            session_manager.clone(),
-        )?));
-        let biased_reasoning_tool = Arc::new(BiasedReasoningTool::new(
+        )?)));
+        let consensus_tool = Arc::new(parking_lot::RwLock::new(Arc::new(ConsensusTool::new(
             config.clone(),
-            session_manager.clone(),
-        )?);
-        let planner_tool = Arc::new(Mutex::new(PlannerTool::new(
+        ))));
+        let traced_reasoning_tool = Arc::new(parking_lot::RwLock::new(Arc::new(
+            TracedReasoningTool::new(config.clone(), session_manager.clone())?,
+        )));
+        let biased_reasoning_tool = Arc::new(parking_lot::RwLock::new(Arc::new(
+            BiasedReasoningTool::new(config.clone(), session_manager.clone())?,
+        )));
+        let planner_tool = Arc::new(parking_lot::RwLock::new(Arc::new(PlannerTool::new(
             config.clone(),
             session_manager.clone(),
-        )?));
+        )?)));
         let sequential_thinking_tool = Arc::new(SequentialThinkingTool::new());
-        let sequential_thinking_external_tool = Arc::new(SequentialThinkingExternalTool::new());
-        let hybrid_biased_reasoning_tool = Arc::new(HybridBiasedReasoningTool::new());
-        let metachain = Arc::new(MetachainEngine::new());
+        let sequential_thinking_external_tool = Arc::new(parking_lot::RwLock::new(Arc::new(
+            SequentialThinkingExternalTool::new(config.clone()),
+        )));
+        let hybrid_biased_reasoning_tool = Arc::new(parking_lot::RwLock::new(Arc::new(
+            HybridBiasedReasoningTool::new(config.clone()),
+        )));
+        let metachain = Arc::new(parking_lot::RwLock::new(Arc::new(MetachainEngine::new(
+            &settings.monitoring,
+            config.clone(),
+        ))));
 
-        // Initialize database service if DATABASE_URL is set
-        let db_service = if std::env::var("DATABASE_URL").is_ok() {
-            match DatabaseConnection::new().await {
+        // Initialize database service if a database URL is configured
+        let db_service = if settings.database.url.is_some() {
+            match DatabaseConnection::with_settings(&settings.database).await {
                 Ok(db_conn) => {
                     let service = Arc::new(DatabaseService::new(db_conn));
                     tracing::info!("Database service initialized successfully");
@@ -70,12 +108,25 @@ impl LuxServer {
                 }
             }
         } else {
-            tracing::info!("DATABASE_URL not set, running without database logging");
+            tracing::info!("No database URL configured, running without database logging");
             None
         };
 
+        let feedback = match FeedbackStore::open(settings.feedback.clone()) {
+            Ok(store) => store,
+            Err(e) => {
+                warn!(
+                    "Failed to open feedback log: {}. Keeping outcomes in memory.",
+                    e
+                );
+                FeedbackStore::in_memory(settings.feedback.clone())
+            }
+        };
+
         Ok(Self {
+            config: Arc::new(parking_lot::RwLock::new(config)),
             chat_tool,
+            consensus_tool,
             traced_reasoning_tool,
             biased_reasoning_tool,
             planner_tool,
@@ -88,10 +139,83 @@ impl LuxServer {
             synthesis_integration,
             quality_integration,
             db_service,
+            feedback: Arc::new(feedback),
+            peer: Arc::new(parking_lot::RwLock::new(None)),
+            reload_lock: Arc::new(Mutex::new(())),
+            last_reload: Arc::new(parking_lot::RwLock::new(None)),
         })
     }
 
     pub fn session_manager(&self) -> Arc<SessionManager> {
         self.session_manager.clone()
     }
+
+    fn chat_tool(&self) -> Arc<ChatTool> {
+        self.chat_tool.read().clone()
+    }
+
+    fn consensus_tool(&self) -> Arc<ConsensusTool> {
+        self.consensus_tool.read().clone()
+    }
+
+    fn traced_reasoning_tool(&self) -> Arc<TracedReasoningTool> {
+        self.traced_reasoning_tool.read().clone()
+    }
+
+    fn biased_reasoning_tool(&self) -> Arc<BiasedReasoningTool> {
+        self.biased_reasoning_tool.read().clone()
+    }
+
+    fn planner_tool(&self) -> Arc<PlannerTool> {
+        self.planner_tool.read().clone()
+    }
+
+    fn sequential_thinking_external_tool(&self) -> Arc<SequentialThinkingExternalTool> {
+        self.sequential_thinking_external_tool.read().clone()
+    }
+
+    fn hybrid_biased_reasoning_tool(&self) -> Arc<HybridBiasedReasoningTool> {
+        self.hybrid_biased_reasoning_tool.read().clone()
+    }
+
+    fn metachain(&self) -> Arc<MetachainEngine> {
+        self.metachain.read().clone()
+    }
+
+    /// Run a thread's latest reply through the monitor of the thread's session
+    /// and attach the measured quality to that turn
+    async fn record_turn_quality(&self, thread_id: &Uuid, tool_name: &str) {
+        let Some(reply) = self
+            .thread_manager
+            .get_thread(thread_id)
+            .and_then(|context| context.turns.last().cloned())
+            .filter(|turn| matches!(turn.role, Role::Assistant))
+        else {
+            return;
+        };
+
+        let session_id = self
+            .session_manager
+            .get_or_create_session(Some(thread_id.to_string()));
+        let monitor = match self.session_manager.get_monitor(&session_id) {
+            Ok(monitor) => monitor,
+            Err(e) => {
+                warn!("No monitor for thread {}: {}", thread_id, e);
+                return;
+            }
+        };
+        self.session_manager
+            .prefetch_embeddings(&monitor, &[&reply.content])
+            .await;
+
+        let mut monitor = monitor.lock();
+        let thought_number = monitor.memory().last_thought_number().map_or(1, |n| n + 1);
+        monitor.analyze_thought(&reply.content, thought_number);
+        if let Err(e) =
+            self.quality_integration
+                .process_quality(&thread_id.to_string(), &monitor, tool_name)
+        {
+            warn!("Failed to record quality for thread {}: {}", thread_id, e);
+        }
+    }
 }
@@ -0,0 +1,295 @@
+//! Hot reload of `lux.toml` and `.env` without restarting the server.
+//!
+//! A background task polls the config files. On change `.env` is re-read into
+//! an overlay on the process environment (which is never modified), the layered
+//! settings are re-read and every tool holding LLM clients is rebuilt before any
+//! of them is swapped in, so a bad config leaves the running one untouched.
+//! Calls already in progress finish on the tools they started with. Sessions and
+//! threads live in `SessionManager`/`ThreadManager` and are not affected; new
+//! sessions use the reloaded `[monitoring]` thresholds. The embedder is shared
+//! by every session, so all of them use the reloaded `[embeddings]` at once.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tracing::{error, info, warn};
+
+use super::LuxServer;
+use crate::llm::embeddings::Embedder;
+use crate::llm::LLMConfig;
+use crate::metachain::MetachainEngine;
+use crate::settings::{Env, LuxSettings};
+use crate::tools::{ChatTool, ConsensusTool};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Tools that need an LLM provider; they leave the tool list while no API key is configured
+const LLM_TOOLS: &[&str] = &[
+    "confer",
+    "consensus",
+    "traced_reasoning",
+    "biased_reasoning",
+    "planner",
+    "sequential_thinking_external",
+    "hybrid_biased_reasoning",
+    "illuminate",
+];
+
+pub fn tool_available(config: &LLMConfig, name: &str) -> bool {
+    !LLM_TOOLS.contains(&name)
+        || config.openai_api_key.is_some()
+        || config.openrouter_api_key.is_some()
+}
+
+/// Outcome of the most recent reload, shown by `illumination_status`
+#[derive(Debug, Clone, Serialize)]
+pub struct ReloadStatus {
+    pub at: DateTime<Utc>,
+    pub succeeded: bool,
+    pub error: Option<String>,
+    pub tools_changed: bool,
+}
+
+impl LuxServer {
+    /// Re-read the layered settings, with environment variables read through
+    /// `env`, and swap in rebuilt LLM clients.
+    /// Returns whether the set of available tools changed.
+    pub async fn reload_config(&self, env: Env) -> Result<bool> {
+        let _guard = self.reload_lock.lock().await;
+
+        let settings = LuxSettings::load_with(env)?;
+        let config = LLMConfig::from_settings(&settings)?;
+        if config.validate().is_err() {
+            warn!("No API keys configured after reload; LLM-backed tools are unavailable");
+        }
+
+        // Build everything first so a failure leaves the running config in place.
+        // Rebuilt tools share the sessions and chains of the ones they replace.
+        let chat_tool = Arc::new(ChatTool::new(config.clone())?);
+        let consensus_tool = Arc::new(ConsensusTool::new(config.clone()));
+        let biased_reasoning_tool =
+            Arc::new(self.biased_reasoning_tool().reconfigured(config.clone())?);
+        let traced_reasoning_tool = Arc::new(
+            self.traced_reasoning_tool()
+                .reconfigured(config.clone(), &settings.monitoring)?,
+        );
+        let planner_tool = Arc::new(self.planner_tool().reconfigured(config.clone())?);
+        let metachain = Arc::new(MetachainEngine::new(&settings.monitoring, config.clone()));
+        let sequential_thinking_external_tool = Arc::new(
+            self.sequential_thinking_external_tool()
+                .reconfigured(config.clone()),
+        );
+        let hybrid_biased_reasoning_tool = Arc::new(
+            self.hybrid_biased_reasoning_tool()
+                .reconfigured(config.clone()),
+        );
+        let embedder = Embedder::from_settings(&settings)?;
+        crate::llm::audit::install(config.audit.as_ref())?;
+
+        // In-flight calls hold their own `Arc`, so they finish on the old clients
+        *self.chat_tool.write() = chat_tool;
+        *self.consensus_tool.write() = consensus_tool;
+        *self.traced_reasoning_tool.write() = traced_reasoning_tool;
+        *self.biased_reasoning_tool.write() = biased_reasoning_tool;
+        *self.planner_tool.write() = planner_tool;
+        *self.sequential_thinking_external_tool.write() = sequential_thinking_external_tool;
+        *self.hybrid_biased_reasoning_tool.write() = hybrid_biased_reasoning_tool;
+        *self.metachain.write() = metachain;
+        self.session_manager
+            .set_monitoring_config(settings.monitoring.clone());
+        self.session_manager.embedder().replace(embedder);
+
+        let mut current = self.config.write();
+        let tools_changed = LLM_TOOLS
+            .iter()
+            .any(|name| tool_available(&current, name) != tool_available(&config, name));
+        *current = config;
+
+        Ok(tools_changed)
+    }
+
+    pub(super) fn tool_available(&self, name: &str) -> bool {
+        tool_available(&self.config.read(), name)
+    }
+
+    pub(super) fn last_reload(&self) -> Option<ReloadStatus> {
+        self.last_reload.read().clone()
+    }
+
+    /// Watch `lux.toml` and `.env` and reload when either changes
+    pub fn spawn_config_watcher(&self) {
+        let server = self.clone();
+        tokio::spawn(async move {
+            let dotenv =
+                DotenvState::new(dotenv::dotenv().unwrap_or_else(|_| PathBuf::from(".env")));
+            let mut paths = LuxSettings::config_paths();
+            paths.push(dotenv.path.clone());
+
+            let mut last_seen = snapshot(&paths);
+            let mut interval = tokio::time::interval(POLL_INTERVAL);
+            loop {
+                interval.tick().await;
+                let seen = snapshot(&paths);
+                if seen == last_seen {
+                    continue;
+                }
+                last_seen = seen;
+
+                info!("Configuration change detected, reloading");
+                let result = match dotenv.env() {
+                    Ok(env) => server.reload_config(env).await,
+                    Err(e) => Err(e),
+                };
+                server.record_reload(result).await;
+            }
+        });
+    }
+
+    async fn record_reload(&self, result: Result<bool>) {
+        let status = match result {
+            Ok(tools_changed) => {
+                info!("Configuration reloaded");
+                if tools_changed {
+                    self.notify_tool_list_changed().await;
+                }
+                ReloadStatus {
+                    at: Utc::now(),
+                    succeeded: true,
+                    error: None,
+                    tools_changed,
+                }
+            }
+            Err(e) => {
+                error!(
+                    "Configuration reload failed, keeping previous configuration: {:#}",
+                    e
+                );
+                ReloadStatus {
+                    at: Utc::now(),
+                    succeeded: false,
+                    error: Some(format!("{:#}", e)),
+                    tools_changed: false,
+                }
+            }
+        };
+        *self.last_reload.write() = Some(status);
+    }
+
+    async fn notify_tool_list_changed(&self) {
+        let peer = self.peer.read().clone();
+        if let Some(peer) = peer {
+            if let Err(e) = peer.notify_tool_list_changed().await {
+                warn!("Failed to send tools/list_changed notification: {}", e);
+            }
+        }
+    }
+}
+
+/// Modification time and size of each watched file (`None` when missing)
+fn snapshot(paths: &[PathBuf]) -> Vec<Option<(SystemTime, u64)>> {
+    paths
+        .iter()
+        .map(|path| {
+            let metadata = std::fs::metadata(path).ok()?;
+            Some((metadata.modified().ok()?, metadata.len()))
+        })
+        .collect()
+}
+
+/// `.env` as it was loaded at startup. Variables it put in the process
+/// environment then follow the file; ones the parent process set keep
+/// precedence over it.
+struct DotenvState {
+    path: PathBuf,
+    loaded: HashSet<String>,
+}
+
+impl DotenvState {
+    fn new(path: PathBuf) -> Self {
+        let loaded = read_dotenv(&path)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(key, value)| env::var(key).as_deref() == Ok(value.as_str()))
+            .map(|(key, _)| key)
+            .collect();
+        Self { path, loaded }
+    }
+
+    /// The file's current values, laid over the process environment
+    fn env(&self) -> Result<Env> {
+        let mut entries = read_dotenv(&self.path)?;
+
+        // Variables removed from the file are hidden, not left at their startup value
+        let mut overlay: HashMap<String, Option<String>> = self
+            .loaded
+            .iter()
+            .map(|key| (key.clone(), entries.remove(key)))
+            .collect();
+        for (key, value) in entries {
+            if env::var_os(&key).is_none() {
+                overlay.insert(key, Some(value));
+            }
+        }
+        Ok(Env::with_overlay(overlay))
+    }
+}
+
+// The iterator API is the only one in dotenv 0.15 that reads values without
+// applying them, which we need to override previously loaded ones
+#[allow(deprecated)]
+fn read_dotenv(path: &Path) -> Result<HashMap<String, String>> {
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    dotenv::from_path_iter(path)
+        .and_then(|iter| iter.collect())
+        .with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_availability() {
+        let mut config = LLMConfig::default();
+        assert!(!tool_available(&config, "confer"));
+        assert!(tool_available(&config, "sequential_thinking"));
+
+        config.openrouter_api_key = Some("sk-or-test".to_string());
+        assert!(tool_available(&config, "confer"));
+    }
+
+    #[test]
+    fn test_dotenv_overlay_keeps_process_env() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".env");
+        let process_path = env::var("PATH").unwrap();
+
+        std::fs::write(&path, "PATH=from-file\nLUX_RELOAD_TEST_FILE=one\n").unwrap();
+        let dotenv = DotenvState {
+            path: path.clone(),
+            loaded: HashSet::new(),
+        };
+        let vars = dotenv.env().unwrap();
+        assert_eq!(vars.var("PATH").unwrap(), process_path);
+        assert_eq!(vars.var("LUX_RELOAD_TEST_FILE").unwrap(), "one");
+
+        // A variable `.env` loaded at startup follows the file, even once removed
+        let dotenv = DotenvState {
+            path: path.clone(),
+            loaded: HashSet::from(["PATH".to_string()]),
+        };
+        assert_eq!(dotenv.env().unwrap().var("PATH").unwrap(), "from-file");
+        std::fs::write(&path, "").unwrap();
+        let vars = dotenv.env().unwrap();
+        assert!(vars.var("PATH").is_none());
+        assert!(vars.var("LUX_RELOAD_TEST_FILE").is_none());
+        assert_eq!(env::var("PATH").unwrap(), process_path);
+    }
+}
@@ -1,12 +1,14 @@
 use anyhow::Result;
-use parking_lot::Mutex;
-use std::collections::HashMap;
+use parking_lot::{Mutex, RwLock};
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 
-use crate::monitoring::MetacognitiveMonitor;
+use crate::llm::embeddings::Embedder;
+use crate::monitoring::session_memory::RollingSummary;
+use crate::monitoring::{DetectorFactory, MetacognitiveMonitor, MonitorSnapshot, MonitoringConfig};
 
 /// Session data containing monitor and metadata
 #[derive(Clone)]
@@ -16,19 +18,104 @@ pub struct SessionData {
     pub created_at: Instant,
 }
 
+/// Expired sessions whose monitor state is kept for a later resume
+const MAX_PARKED_SESSIONS: usize = 256;
+
 /// Manages per-conversation sessions with automatic cleanup
 pub struct SessionManager {
     sessions: Arc<Mutex<HashMap<String, SessionData>>>,
+    /// Monitor state of expired sessions, oldest first. A session resumed
+    /// under the same ID continues from it.
+    parked: Arc<Mutex<VecDeque<(String, MonitorSnapshot)>>>,
     ttl: Duration,
+    /// Thresholds for new sessions; replaced on config reload
+    monitoring: Arc<RwLock<MonitoringConfig>>,
+    /// Extra detectors added to every new session's monitor
+    detector_factories: Arc<Mutex<Vec<DetectorFactory>>>,
+    /// Shared by all monitors so embeddings are cached across sessions
+    embedder: Arc<Embedder>,
 }
 
 impl SessionManager {
     /// Create a new session manager with specified TTL
     pub fn new(ttl_minutes: u64) -> Self {
+        Self::with_config(ttl_minutes, MonitoringConfig::default())
+    }
+
+    /// Create a session manager whose monitors use the given thresholds
+    pub fn with_config(ttl_minutes: u64, monitoring: MonitoringConfig) -> Self {
         Self {
             sessions: Arc::new(Mutex::new(HashMap::new())),
+            parked: Arc::new(Mutex::new(VecDeque::new())),
             ttl: Duration::from_secs(ttl_minutes * 60),
+            monitoring: Arc::new(RwLock::new(monitoring)),
+            detector_factories: Arc::new(Mutex::new(Vec::new())),
+            embedder: Arc::new(Embedder::local()),
+        }
+    }
+
+    /// Use the given embeddings for semantic similarity instead of local ones
+    pub fn with_embedder(mut self, embedder: Arc<Embedder>) -> Self {
+        self.embedder = embedder;
+        self
+    }
+
+    pub fn embedder(&self) -> Arc<Embedder> {
+        self.embedder.clone()
+    }
+
+    /// Fetch embeddings for the thoughts about to be analyzed and for every
+    /// text the monitor compares them with, before the monitor is locked
+    pub async fn prefetch_embeddings(&self, monitor: &Mutex<MetacognitiveMonitor>, texts: &[&str]) {
+        let compared = monitor.lock().compared_texts();
+        let mut all: Vec<&str> = texts.to_vec();
+        all.extend(compared.iter().map(String::as_str));
+        self.embedder.prefetch(&all).await;
+    }
+
+    /// Add a detector to every session created from now on. Existing sessions
+    /// keep their detectors.
+    #[allow(dead_code)] // Extension point for library users
+    pub fn register_detector(&self, factory: DetectorFactory) {
+        self.detector_factories.lock().push(factory);
+    }
+
+    /// Use the given thresholds for sessions created from now on. Existing
+    /// sessions keep the ones they started with.
+    pub fn set_monitoring_config(&self, monitoring: MonitoringConfig) {
+        *self.monitoring.write() = monitoring;
+    }
+
+    fn new_monitor(&self) -> Arc<Mutex<MetacognitiveMonitor>> {
+        let monitoring = self.monitoring.read().clone();
+        let mut monitor =
+            MetacognitiveMonitor::with_embedder(monitoring.clone(), self.embedder.clone());
+        for factory in self.detector_factories.lock().iter() {
+            monitor.detectors_mut().register(factory(&monitoring));
+        }
+        Arc::new(Mutex::new(monitor))
+    }
+
+    /// Monitor for a session that isn't live: its parked state if it
+    /// expired earlier, else a fresh one
+    fn resume_monitor(&self, session_id: &str) -> Arc<Mutex<MetacognitiveMonitor>> {
+        let snapshot = {
+            let mut parked = self.parked.lock();
+            parked
+                .iter()
+                .position(|(id, _)| id == session_id)
+                .and_then(|i| parked.remove(i))
+                .map(|(_, snapshot)| snapshot)
+        };
+        let monitor = self.new_monitor();
+        if let Some(snapshot) = snapshot {
+            debug!("Resuming monitor of expired session: {}", session_id);
+            if let Err(e) = monitor.lock().restore(snapshot) {
+                warn!("Could not resume monitor of session {}: {}", session_id, e);
+                monitor.lock().reset_session();
+            }
         }
+        monitor
     }
 
     /// Get or create a session, returning the session ID
@@ -40,7 +127,7 @@ impl SessionManager {
         if !sessions.contains_key(&id) {
             debug!("Creating new session: {}", id);
             let session_data = SessionData {
-                monitor: Arc::new(Mutex::new(MetacognitiveMonitor::new())),
+                monitor: self.resume_monitor(&id),
                 last_accessed: Instant::now(),
                 created_at: Instant::now(),
             };
@@ -68,7 +155,7 @@ impl SessionManager {
                 // Auto-create if missing
                 debug!("Session {} not found, creating new one", session_id);
                 let session_data = SessionData {
-                    monitor: Arc::new(Mutex::new(MetacognitiveMonitor::new())),
+                    monitor: self.resume_monitor(session_id),
                     last_accessed: Instant::now(),
                     created_at: Instant::now(),
                 };
@@ -79,23 +166,49 @@ impl SessionManager {
         }
     }
 
-    /// Clean up expired sessions
+    /// Thresholds a session's monitor last ran with, or the server's defaults
+    /// when there is no such session. Does not create or touch the session.
+    pub fn monitoring_config(&self, session_id: Option<&str>) -> MonitoringConfig {
+        session_id
+            .and_then(|id| {
+                let sessions = self.sessions.lock();
+                sessions
+                    .get(id)
+                    .map(|session| session.monitor.lock().config().clone())
+            })
+            .unwrap_or_else(|| self.monitoring.read().clone())
+    }
+
+    /// Rolling summaries of a session's thoughts, if the session exists
+    pub fn session_summaries(&self, session_id: &str) -> Option<Vec<RollingSummary>> {
+        let sessions = self.sessions.lock();
+        let session = sessions.get(session_id)?;
+        let summaries = session.monitor.lock().memory().summaries().to_vec();
+        Some(summaries)
+    }
+
+    /// Clean up expired sessions, parking their monitor state
     pub fn cleanup_expired_sessions(&self) -> usize {
         let mut sessions = self.sessions.lock();
         let now = Instant::now();
-        let initial_count = sessions.len();
+        let mut parked = self.parked.lock();
+        let mut removed = 0;
 
         sessions.retain(|id, session| {
             let age = now.duration_since(session.last_accessed);
             if age > self.ttl {
                 debug!("Removing expired session: {} (age: {:?})", id, age);
+                parked.push_back((id.clone(), session.monitor.lock().snapshot()));
+                removed += 1;
                 false
             } else {
                 true
             }
         });
+        while parked.len() > MAX_PARKED_SESSIONS {
+            parked.pop_front();
+        }
 
-        let removed = initial_count - sessions.len();
         if removed > 0 {
             info!("Cleaned up {} expired sessions", removed);
         }
@@ -149,7 +262,11 @@ impl Clone for SessionManager {
     fn clone(&self) -> Self {
         Self {
             sessions: self.sessions.clone(),
+            parked: self.parked.clone(),
             ttl: self.ttl,
+            monitoring: self.monitoring.clone(),
+            detector_factories: self.detector_factories.clone(),
+            embedder: self.embedder.clone(),
         }
     }
 }
@@ -220,4 +337,23 @@ mod tests {
         assert_eq!(removed, 2);
         assert_eq!(manager.session_count(), 0);
     }
+
+    #[test]
+    fn test_expired_session_resumes_its_monitor() {
+        let manager = SessionManager::new(0);
+        let id = manager.get_or_create_session(Some("resumed".to_string()));
+        manager
+            .get_monitor(&id)
+            .unwrap()
+            .lock()
+            .analyze_thought("The retry queue drops messages under load.", 1);
+
+        thread::sleep(Duration::from_millis(10));
+        assert_eq!(manager.cleanup_expired_sessions(), 1);
+
+        let monitor = manager.get_monitor(&id).unwrap();
+        let snapshot = monitor.lock().snapshot();
+        assert_eq!(snapshot.thought_history.len(), 1);
+        assert!(manager.parked.lock().is_empty());
+    }
 }
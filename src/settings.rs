@@ -0,0 +1,252 @@
+//! Layered configuration: built-in defaults, then `~/.config/lux/lux.toml`,
+//! then `./lux.toml`, then environment variables.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+use tracing::info;
+
+use crate::feedback::FeedbackConfig;
+use crate::monitoring::lexicon::Lexicon;
+use crate::monitoring::MonitoringConfig;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LuxSettings {
+    pub llm: LlmSettings,
+    /// Per-tool overrides, keyed by tool name (`[tools.confer]`, `[tools.planner]`, ...)
+    pub tools: HashMap<String, ToolSettings>,
+    pub monitoring: MonitoringConfig,
+    pub embeddings: EmbeddingSettings,
+    pub session: SessionSettings,
+    pub database: DatabaseSettings,
+    pub feedback: FeedbackConfig,
+    /// Environment the layers were read with, for `LLMConfig` to apply in turn
+    #[serde(skip)]
+    pub env: Env,
+}
+
+/// Model and endpoint settings. API keys are only read from the environment.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LlmSettings {
+    pub model_reasoning: Option<String>,
+    pub model_normal: Option<String>,
+    pub model_mini: Option<String>,
+    pub model_opus: Option<String>,
+    pub model_sonnet: Option<String>,
+    pub model_grok: Option<String>,
+    pub openai_base_url: Option<String>,
+    pub openrouter_base_url: Option<String>,
+    pub request_timeout_secs: Option<u64>,
+    pub max_retries: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ToolSettings {
+    /// Default model when the request doesn't name one
+    pub model: Option<String>,
+    /// Default temperature when the request doesn't set one
+    pub temperature: Option<f32>,
+    /// Upper bound on completion tokens
+    pub max_tokens: Option<u32>,
+    /// Models to try, in order, when the requested model is unavailable
+    pub fallback_models: Option<Vec<String>>,
+}
+
+impl ToolSettings {
+    /// Apply the token cap to a tool's computed allocation
+    pub fn cap_tokens(&self, max_tokens: u32) -> u32 {
+        self.max_tokens
+            .map_or(max_tokens, |cap| max_tokens.min(cap))
+    }
+}
+
+/// Remote embeddings for semantic similarity. Without a model, local hashed
+/// n-gram embeddings are used.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmbeddingSettings {
+    /// Embedding model at an OpenAI-compatible endpoint, e.g. `text-embedding-3-small`
+    pub model: Option<String>,
+    /// Defaults to where the LLM clients send requests: the Azure deployment
+    /// in Azure mode, else OPENAI_BASE_URL, then the OpenAI API
+    pub base_url: Option<String>,
+    /// Requested vector size, for models that support shortening
+    pub dimensions: Option<u32>,
+    /// Number of texts whose embeddings are kept
+    pub cache_size: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionSettings {
+    /// Idle time before a monitoring session is dropped
+    pub session_ttl_minutes: u64,
+    /// Idle time before a conversation thread is dropped
+    pub thread_ttl_minutes: u64,
+}
+
+impl Default for SessionSettings {
+    fn default() -> Self {
+        Self {
+            session_ttl_minutes: 30,
+            thread_ttl_minutes: 180,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DatabaseSettings {
+    /// Database logging is enabled when a URL is configured
+    pub url: Option<String>,
+    pub max_connections: Option<u32>,
+    pub connect_timeout_secs: Option<u64>,
+}
+
+impl LuxSettings {
+    /// Load all layers. Missing files are skipped; malformed files are an error.
+    pub fn load() -> Result<Self> {
+        Self::load_with(Env::default())
+    }
+
+    /// Load all layers, reading environment variables through `env`
+    pub fn load_with(env: Env) -> Result<Self> {
+        let mut builder = config::Config::builder();
+        for path in Self::config_paths() {
+            if path.is_file() {
+                info!("Loading configuration from {}", path.display());
+            }
+            builder = builder.add_source(config::File::from(path).required(false));
+        }
+
+        let mut settings: Self = builder
+            .build()
+            .context("Failed to read lux.toml")?
+            .try_deserialize()
+            .context("Invalid lux.toml")?;
+        settings.env = env;
+        settings.apply_env();
+        Lexicon::load(&settings.monitoring.lexicons).context("Invalid [monitoring] lexicons")?;
+        Ok(settings)
+    }
+
+    /// Config files in increasing order of precedence
+    pub fn config_paths() -> Vec<PathBuf> {
+        let user_config_dir = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+
+        user_config_dir
+            .map(|dir| dir.join("lux").join("lux.toml"))
+            .into_iter()
+            .chain(std::iter::once(PathBuf::from("lux.toml")))
+            .collect()
+    }
+
+    /// Environment variables that aren't LLM settings (those are applied by `LLMConfig`)
+    fn apply_env(&mut self) {
+        let env = &self.env;
+        if let Some(minutes) = env.parse("LUX_SESSION_TTL_MINUTES") {
+            self.session.session_ttl_minutes = minutes;
+        }
+        if let Some(minutes) = env.parse("LUX_THREAD_TTL_MINUTES") {
+            self.session.thread_ttl_minutes = minutes;
+        }
+        if let Some(url) = env.var("DATABASE_URL") {
+            self.database.url = Some(url);
+        }
+        if let Some(model) = env.var("LUX_EMBEDDINGS_MODEL") {
+            self.embeddings.model = Some(model).filter(|m| !m.is_empty());
+        }
+        if let Some(url) = env.var("LUX_EMBEDDINGS_BASE_URL") {
+            self.embeddings.base_url = Some(url);
+        }
+        if let Some(path) = env.var("LUX_FEEDBACK_LOG") {
+            self.feedback.path = Some(PathBuf::from(path));
+        }
+    }
+}
+
+/// Environment variables as the settings see them: the process environment,
+/// with the `.env` values a config reload re-read laid over it. Reloads run
+/// alongside other threads, so they never modify the process environment.
+#[derive(Debug, Clone, Default)]
+pub struct Env {
+    /// `None` hides a variable the process environment still has
+    overlay: HashMap<String, Option<String>>,
+}
+
+impl Env {
+    pub fn with_overlay(overlay: HashMap<String, Option<String>>) -> Self {
+        Self { overlay }
+    }
+
+    pub fn var(&self, key: &str) -> Option<String> {
+        match self.overlay.get(key) {
+            Some(value) => value.clone(),
+            None => env::var(key).ok(),
+        }
+    }
+
+    pub fn parse<T: std::str::FromStr>(&self, key: &str) -> Option<T> {
+        self.var(key).and_then(|s| s.parse().ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sections() {
+        let toml = r#"
+            [llm]
+            model_normal = "gpt-5-mini"
+
+            [tools.confer]
+            model = "opus"
+            temperature = 0.2
+            max_tokens = 8000
+            fallback_models = ["gpt-5", "gpt-5-mini"]
+
+            [monitoring]
+            circular_intervention_threshold = 0.7
+            lexicons = ["kubernetes"]
+
+            [[monitoring.rules]]
+            name = "late_loop"
+            when = "circular_score > 0.6 && step > 3"
+            action = "force_revision"
+            message = "Thought {step} repeats earlier ones"
+
+            [session]
+            thread_ttl_minutes = 60
+        "#;
+        let settings: LuxSettings = config::Config::builder()
+            .add_source(config::File::from_str(toml, config::FileFormat::Toml))
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap();
+
+        assert_eq!(settings.llm.model_normal.as_deref(), Some("gpt-5-mini"));
+        let confer = settings.tools["confer"].clone();
+        assert_eq!(confer.model.as_deref(), Some("opus"));
+        assert_eq!(confer.cap_tokens(128000), 8000);
+        assert_eq!(confer.fallback_models.unwrap().len(), 2);
+        assert!(!settings.tools.contains_key("planner"));
+
+        // Unset keys keep their defaults
+        assert_eq!(settings.monitoring.circular_intervention_threshold, 0.7);
+        assert_eq!(settings.monitoring.circular_similarity_threshold, 0.6);
+        assert_eq!(settings.monitoring.rules[0].name, "late_loop");
+        assert_eq!(settings.monitoring.lexicons, ["kubernetes"]);
+        assert_eq!(settings.session.thread_ttl_minutes, 60);
+        assert_eq!(settings.session.session_ttl_minutes, 30);
+    }
+}
@@ -7,13 +7,18 @@ pub enum Role {
     System,
 }
 
+/// Quality of one turn, as the thread's monitor measured it
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QualityMetrics {
     pub circular_reasoning_score: f32,
     pub distractor_fixation_score: f32,
+    #[serde(default)]
+    pub quality_degradation_score: f32,
     pub coherence_score: f32,
     pub depth_score: f32,
-    pub perplexity: f32,
+    /// Token perplexity, for turns whose model returned log probabilities
+    #[serde(default)]
+    pub perplexity: Option<f32>,
 }
 
 impl QualityMetrics {
@@ -21,21 +26,32 @@ impl QualityMetrics {
         Self {
             circular_reasoning_score: 0.0,
             distractor_fixation_score: 0.0,
+            quality_degradation_score: 0.0,
             coherence_score: 1.0,
             depth_score: 0.5,
-            perplexity: 20.0,
+            perplexity: None,
         }
     }
 
-    pub fn from_monitor(_monitor: &crate::monitoring::MetacognitiveMonitor) -> Self {
-        // TODO: Extract actual metrics from monitor when methods are available
-        // For now, return default values
-        Self {
-            circular_reasoning_score: 0.0,
-            distractor_fixation_score: 0.0,
-            coherence_score: 1.0,
-            depth_score: 0.5,
-            perplexity: 20.0,
-        }
+    /// Readings of the monitor's latest thought, or `None` before it analyzed one
+    pub fn from_monitor(monitor: &crate::monitoring::MetacognitiveMonitor) -> Option<Self> {
+        let metrics = monitor.metrics()?;
+        Some(Self {
+            circular_reasoning_score: metrics.circular_score as f32,
+            distractor_fixation_score: metrics.distractor_score as f32,
+            quality_degradation_score: metrics.degradation_score as f32,
+            coherence_score: metrics.coherence as f32,
+            depth_score: metrics.reasoning_depth as f32,
+            perplexity: None,
+        })
+    }
+
+    /// 0.0 = poor, 1.0 = excellent: none of circling, distraction or degradation
+    pub fn overall(&self) -> f32 {
+        let problems = (self.circular_reasoning_score
+            + self.distractor_fixation_score
+            + self.quality_degradation_score)
+            / 3.0;
+        (1.0 - problems).clamp(0.0, 1.0)
     }
 }
@@ -27,6 +27,9 @@ pub struct ThreadContext {
     pub tool_name: String,
     pub turns: Vec<ConversationTurn>,
     pub initial_files: Vec<String>,
+    /// Last provider response ID, used to chain Responses API turns
+    #[serde(default)]
+    pub last_response_id: Option<String>,
     #[serde(skip, default = "Instant::now")]
     pub created_at: Instant,
     #[serde(skip, default = "Instant::now")]
@@ -57,6 +60,7 @@ impl ThreadManager {
             tool_name: tool_name.to_string(),
             turns: Vec::new(),
             initial_files: Vec::new(),
+            last_response_id: None,
             created_at: Instant::now(),
             last_accessed: Instant::now(),
         };
@@ -119,6 +123,21 @@ impl ThreadManager {
         }
     }
 
+    /// Record the provider response ID of the latest turn so the next turn can chain from it
+    pub fn set_last_response_id(&self, id: &Uuid, response_id: Option<String>) {
+        let mut threads = self.threads.lock();
+
+        if let Some(context) = threads.get_mut(id) {
+            context.last_response_id = response_id;
+            context.last_accessed = Instant::now();
+        }
+    }
+
+    pub fn get_last_response_id(&self, id: &Uuid) -> Option<String> {
+        self.get_thread(id)
+            .and_then(|context| context.last_response_id)
+    }
+
     pub fn attach_synthesis(&self, thread_id: &Uuid, synthesis: SynthesisState) {
         let mut threads = self.threads.lock();
 
@@ -308,6 +327,23 @@ mod tests {
         assert!(manager.get_thread(&id).is_none());
     }
 
+    #[test]
+    fn test_last_response_id() {
+        let manager = ThreadManager::new();
+        let id = manager.create_thread("confer");
+        assert!(manager.get_last_response_id(&id).is_none());
+
+        manager.set_last_response_id(&id, Some("resp_abc".to_string()));
+        assert_eq!(
+            manager.get_last_response_id(&id).as_deref(),
+            Some("resp_abc")
+        );
+
+        // A turn from a model without chaining support clears it
+        manager.set_last_response_id(&id, None);
+        assert!(manager.get_last_response_id(&id).is_none());
+    }
+
     #[test]
     fn test_file_attachment() {
         let manager = ThreadManager::new();
@@ -256,6 +256,7 @@ mod tests {
             tool_name: "test_tool".to_string(),
             turns: Vec::new(),
             initial_files: Vec::new(),
+            last_response_id: None,
             created_at: Instant::now(),
             last_accessed: Instant::now(),
         };
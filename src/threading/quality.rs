@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use tracing::{info, warn};
+use uuid::Uuid;
 
 use crate::monitoring::MetacognitiveMonitor;
 
@@ -55,22 +56,25 @@ impl ThreadQualityManager {
         }
     }
 
-    /// Update quality metrics from a monitoring session
+    /// Record the quality of a thread's latest turn, as measured by the monitor
+    /// that just analyzed it. Nothing is recorded before the monitor analyzed a
+    /// thought.
     pub fn update_from_monitor(
         &self,
         thread_id: &str,
         monitor: &MetacognitiveMonitor,
         tool_name: &str,
     ) -> Result<()> {
-        // Calculate quality scores from monitor
-        let circular_score = self.calculate_circular_score(monitor);
-        let distractor_score = self.calculate_distractor_score(monitor);
-        let degradation_score = self.calculate_degradation_score(monitor);
-        let overall_quality =
-            self.calculate_overall_quality(circular_score, distractor_score, degradation_score);
-
-        // Get intervention count
+        let Some(quality) = QualityMetrics::from_monitor(monitor) else {
+            return Ok(());
+        };
+        let overall_quality = quality.overall();
+
+        // Only interventions raised since the previous update count for this turn
         let intervention_count = monitor.get_intervention_count();
+        let previous_interventions = self
+            .get_thread_quality(thread_id)
+            .map_or(0, |m| m.intervention_count);
 
         // Determine quality trend
         let trend = self.determine_trend(thread_id, overall_quality);
@@ -79,9 +83,9 @@ impl ThreadQualityManager {
         let metrics = ThreadQualityMetrics {
             thread_id: thread_id.to_string(),
             overall_quality,
-            circular_reasoning_score: circular_score,
-            distractor_fixation_score: distractor_score,
-            quality_degradation_score: degradation_score,
+            circular_reasoning_score: quality.circular_reasoning_score,
+            distractor_fixation_score: quality.distractor_fixation_score,
+            quality_degradation_score: quality.quality_degradation_score,
             intervention_count,
             last_updated: Utc::now(),
             quality_trend: trend,
@@ -90,13 +94,14 @@ impl ThreadQualityManager {
         // Store metrics
         let mut cache = self.metrics_cache.lock().unwrap();
         cache.insert(thread_id.to_string(), metrics.clone());
+        drop(cache);
 
         // Add to history
         let snapshot = QualitySnapshot {
             timestamp: Utc::now(),
             overall_quality,
             tool_name: tool_name.to_string(),
-            intervention_triggered: intervention_count > 0,
+            intervention_triggered: intervention_count > previous_interventions,
         };
 
         let mut history = self.quality_history.lock().unwrap();
@@ -104,9 +109,10 @@ impl ThreadQualityManager {
             .entry(thread_id.to_string())
             .or_insert_with(Vec::new)
             .push(snapshot);
+        drop(history);
 
-        // Update thread context with quality metrics
-        self.update_thread_context(thread_id, &metrics)?;
+        // The turn itself carries its metrics
+        self.update_thread_context(thread_id, quality);
 
         info!(
             "Updated quality metrics for thread {}: overall={:.2}, trend={:?}",
@@ -116,35 +122,6 @@ impl ThreadQualityManager {
         Ok(())
     }
 
-    /// Calculate circular reasoning score (0.0 = no circular, 1.0 = highly circular)
-    fn calculate_circular_score(&self, monitor: &MetacognitiveMonitor) -> f32 {
-        // In a real implementation, this would analyze the monitor's circular reasoning detector
-        // For now, return a placeholder based on intervention count
-        let interventions = monitor.get_intervention_count();
-        (interventions as f32 * 0.2).min(1.0)
-    }
-
-    /// Calculate distractor fixation score (0.0 = focused, 1.0 = highly distracted)
-    fn calculate_distractor_score(&self, monitor: &MetacognitiveMonitor) -> f32 {
-        // In a real implementation, this would analyze the monitor's distractor detector
-        // For now, return a placeholder
-        0.15
-    }
-
-    /// Calculate quality degradation score (0.0 = high quality, 1.0 = degraded)
-    fn calculate_degradation_score(&self, monitor: &MetacognitiveMonitor) -> f32 {
-        // In a real implementation, this would analyze the monitor's quality detector
-        // For now, return a placeholder
-        0.10
-    }
-
-    /// Calculate overall quality (0.0 = poor, 1.0 = excellent)
-    fn calculate_overall_quality(&self, circular: f32, distractor: f32, degradation: f32) -> f32 {
-        // Invert scores (since high circular/distractor/degradation is bad)
-        let quality = 1.0 - ((circular + distractor + degradation) / 3.0);
-        quality.max(0.0).min(1.0)
-    }
-
     /// Determine quality trend based on history
     fn determine_trend(&self, thread_id: &str, current_quality: f32) -> QualityTrend {
         let history = self.quality_history.lock().unwrap();
@@ -178,33 +155,12 @@ impl ThreadQualityManager {
         }
     }
 
-    /// Update thread context with quality metrics
-    fn update_thread_context(&self, thread_id: &str, metrics: &ThreadQualityMetrics) -> Result<()> {
-        // Create quality metrics for thread context
-        let quality = QualityMetrics {
-            circular_reasoning_score: metrics.circular_reasoning_score as f32,
-            distractor_fixation_score: metrics.distractor_fixation_score as f32,
-            coherence_score: metrics.overall_quality as f32,
-            depth_score: 0.5,
-            perplexity: 20.0,
-        };
-
-        // Update thread metadata
-        // TODO: Implement update_thread_metadata in ThreadManager
-        // self.thread_manager.update_thread_metadata(
-        //     thread_id,
-        //     "quality_metrics",
-        //     &serde_json::to_value(metrics)?,
-        // )?;
-
-        // Update thread quality in context
-        // TODO: Parse thread_id to Uuid and update
-        // if let Some(mut context) = self.thread_manager.get_thread(thread_id) {
-        //     context.quality_metrics = Some(quality);
-        //     // Note: In a real implementation, we'd update the thread in the manager
-        // }
-
-        Ok(())
+    /// Attach the metrics to the thread's latest turn
+    fn update_thread_context(&self, thread_id: &str, quality: QualityMetrics) {
+        match Uuid::parse_str(thread_id) {
+            Ok(id) => self.thread_manager.attach_quality_metrics(&id, quality),
+            Err(_) => warn!("Quality metrics for unknown thread id {}", thread_id),
+        }
     }
 
     /// Get quality metrics for a thread
@@ -230,12 +186,13 @@ impl ThreadQualityManager {
         }
     }
 
-    /// Generate quality report for a thread
+    /// Generate quality report for a thread: the latest metrics and the
+    /// quality of every turn measured so far
     pub fn generate_quality_report(&self, thread_id: &str) -> Option<String> {
         let metrics = self.get_thread_quality(thread_id)?;
         let history = self.get_quality_history(thread_id);
 
-        Some(format!(
+        let mut report = format!(
             "Quality Report for Thread {}\n\
              ================================\n\
              Overall Quality: {:.2}/1.0 ({})\n\
@@ -243,9 +200,7 @@ impl ThreadQualityManager {
              Distractor Fixation: {:.2}\n\
              Quality Degradation: {:.2}\n\
              Interventions: {}\n\
-             Trend: {:?}\n\
-             History: {} snapshots\n\
-             Last Updated: {}",
+             Trend: {:?}\n",
             thread_id,
             metrics.overall_quality,
             if metrics.overall_quality > 0.7 {
@@ -260,9 +215,57 @@ impl ThreadQualityManager {
             metrics.quality_degradation_score,
             metrics.intervention_count,
             metrics.quality_trend,
+        );
+
+        let turns: Vec<(usize, Option<String>, QualityMetrics)> = Uuid::parse_str(thread_id)
+            .ok()
+            .and_then(|id| self.thread_manager.get_thread(&id))
+            .map(|context| {
+                context
+                    .turns
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(|(i, turn)| Some((i + 1, turn.tool_used, turn.quality_metrics?)))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        if !turns.is_empty() {
+            report.push_str(&format!("Trajectory ({} turns measured):\n", turns.len()));
+            for (turn, tool, quality) in &turns {
+                report.push_str(&format!(
+                    "  Turn {} ({}): quality {:.2}, coherence {:.2}, depth {:.2}, circular {:.2}, distractor {:.2}\n",
+                    turn,
+                    tool.as_deref().unwrap_or("unknown"),
+                    quality.overall(),
+                    quality.coherence_score,
+                    quality.depth_score,
+                    quality.circular_reasoning_score,
+                    quality.distractor_fixation_score
+                ));
+            }
+
+            let (lowest_turn, _, lowest) = turns
+                .iter()
+                .min_by(|a, b| a.2.overall().total_cmp(&b.2.overall()))?;
+            let change = turns.last()?.2.overall() - turns.first()?.2.overall();
+            report.push_str(&format!(
+                "Lowest: turn {} ({:.2}); change since first measured turn: {:+.2}\n",
+                lowest_turn,
+                lowest.overall(),
+                change
+            ));
+        }
+
+        let interventions = history.iter().filter(|s| s.intervention_triggered).count();
+        report.push_str(&format!(
+            "Turns with new interventions: {} of {}\n\
+             Last Updated: {}",
+            interventions,
             history.len(),
             metrics.last_updated.format("%Y-%m-%d %H:%M:%S UTC")
-        ))
+        ));
+        Some(report)
     }
 }
 
@@ -326,4 +329,53 @@ mod tests {
         let trend = quality_manager.determine_trend("test-thread", 0.8);
         assert!(matches!(trend, QualityTrend::Unknown));
     }
+
+    #[test]
+    fn test_turns_carry_measured_quality() {
+        use crate::threading::context::Role;
+        use crate::threading::ConversationTurn;
+
+        let thread_manager = Arc::new(ThreadManager::new());
+        let integration = QualityThreadIntegration::new(thread_manager.clone());
+        let id = thread_manager.create_thread("confer");
+        let thread_id = id.to_string();
+        let mut monitor = MetacognitiveMonitor::new();
+
+        // Nothing is measured before the monitor saw a reply
+        integration
+            .process_quality(&thread_id, &monitor, "confer")
+            .unwrap();
+        assert!(integration.get_quality_context(&thread_id).is_none());
+
+        let replies = [
+            "Connection pooling reduces latency because each request reuses an open \
+             connection. Therefore the pool size should match the database's limit.",
+            "Pooling is good.",
+        ];
+        for (i, reply) in replies.iter().enumerate() {
+            thread_manager.add_turn(
+                &id,
+                ConversationTurn {
+                    role: Role::Assistant,
+                    content: reply.to_string(),
+                    tool_used: Some("confer".to_string()),
+                    synthesis_snapshot: None,
+                    quality_metrics: None,
+                    timestamp: std::time::Instant::now(),
+                },
+            );
+            monitor.analyze_thought(reply, i + 1);
+            integration
+                .process_quality(&thread_id, &monitor, "confer")
+                .unwrap();
+        }
+
+        let trajectory = thread_manager.get_quality_trajectory(&id);
+        assert_eq!(trajectory.len(), 2);
+        assert!(trajectory[0].depth_score > trajectory[1].depth_score);
+
+        let report = integration.get_quality_context(&thread_id).unwrap();
+        assert!(report.contains("Trajectory (2 turns measured)"));
+        assert!(report.contains("Turn 2 (confer): quality"));
+    }
 }
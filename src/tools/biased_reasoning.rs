@@ -9,12 +9,12 @@ use std::time::Instant;
 use tracing::{debug, error, info, warn};
 
 use crate::llm::{
-    client::{ChatMessage, LLMClient},
+    audit,
+    client::{ChatMessage, CompletionOptions, LLMClient},
     config::LLMConfig,
     model_aliases::ModelResolver,
-    openai::OpenAIClient,
     openrouter::OpenRouterClient,
-    Role,
+    ReasoningEffort, Role, Verbosity,
 };
 use crate::session::SessionManager;
 use lux_synthesis::{
@@ -399,6 +399,14 @@ pub struct BiasedReasoningRequest {
     /// Whether to include file contents (default: true)
     #[serde(default = "default_true")]
     pub include_file_contents: bool,
+
+    /// Reasoning effort for reasoning models (ignored by models without support)
+    #[serde(default)]
+    pub reasoning_effort: Option<ReasoningEffort>,
+
+    /// Answer verbosity for GPT-5 (ignored by other models)
+    #[serde(default)]
+    pub verbosity: Option<Verbosity>,
 }
 
 fn default_max_steps() -> u32 {
@@ -538,7 +546,6 @@ struct SessionState {
 
 pub struct BiasedReasoningTool {
     session_manager: Arc<SessionManager>,
-    openai_client: Option<Arc<dyn LLMClient>>,
     openrouter_clients: Vec<(String, Arc<dyn LLMClient>)>,
     model_resolver: ModelResolver,
     config: LLMConfig,
@@ -549,17 +556,6 @@ impl BiasedReasoningTool {
     pub fn new(config: LLMConfig, session_manager: Arc<SessionManager>) -> Result<Self> {
         let model_resolver = ModelResolver::with_config(Some(config.clone()));
 
-        let openai_client = if let Some(api_key) = &config.openai_api_key {
-            let client = OpenAIClient::new(
-                api_key.clone(),
-                config.model_reasoning.clone(),
-                config.openai_base_url.clone(),
-            )?;
-            Some(Arc::new(client) as Arc<dyn LLMClient>)
-        } else {
-            None
-        };
-
         let mut openrouter_clients = Vec::new();
         if let Some(api_key) = &config.openrouter_api_key {
             let common_models = vec!["anthropic/claude-3-opus", "google/gemini-2.5-pro"];
@@ -577,7 +573,6 @@ impl BiasedReasoningTool {
 
         Ok(Self {
             session_manager,
-            openai_client,
             openrouter_clients,
             model_resolver,
             config,
@@ -585,6 +580,13 @@ impl BiasedReasoningTool {
         })
     }
 
+    /// Build a tool with a new configuration that shares this tool's sessions
+    pub fn reconfigured(&self, config: LLMConfig) -> Result<Self> {
+        let mut fresh = Self::new(config, self.session_manager.clone())?;
+        fresh.sessions = self.sessions.clone();
+        Ok(fresh)
+    }
+
     // New step-by-step API with proper async handling
     pub async fn process_step(
         &self,
@@ -606,9 +608,22 @@ impl BiasedReasoningTool {
             hasher.update(request.query.as_bytes());
             format!("bias_{}", hex::encode(&hasher.finalize()[..8]))
         };
+        audit::set_session(session_id.clone());
+
+        let options = CompletionOptions {
+            reasoning_effort: request.reasoning_effort,
+            verbosity: request.verbosity,
+            ..Default::default()
+        };
 
         // STRICT POLICY: Only GPT-5 family allowed
-        let mut primary_model = self.model_resolver.resolve("gpt-5");
+        let mut primary_model = self.model_resolver.resolve(
+            self.config
+                .tool("biased_reasoning")
+                .model
+                .as_deref()
+                .unwrap_or("gpt-5"),
+        );
         let mut verifier_model = self.model_resolver.resolve("gpt-5-mini");
         if !self.model_resolver.is_allowed_model(&primary_model) {
             primary_model = "gpt-5".to_string();
@@ -765,7 +780,7 @@ impl BiasedReasoningTool {
             }
 
             StepType::Reasoning => {
-                self.handle_reasoning_step(session_id, step_count, primary_model)
+                self.handle_reasoning_step(session_id, step_count, primary_model, options)
                     .await
             }
 
@@ -775,12 +790,13 @@ impl BiasedReasoningTool {
                     step_count,
                     verifier_model,
                     request.max_analysis_rounds,
+                    options,
                 )
                 .await
             }
 
             StepType::Synthesis => {
-                self.handle_synthesis_step(session_id, step_count, primary_model)
+                self.handle_synthesis_step(session_id, step_count, primary_model, options)
                     .await
             }
 
@@ -796,6 +812,7 @@ impl BiasedReasoningTool {
         session_id: String,
         step_count: u32,
         primary_model: String,
+        options: CompletionOptions,
     ) -> Result<BiasedReasoningResponse> {
         use chrono::Utc;
 
@@ -851,7 +868,7 @@ impl BiasedReasoningTool {
 
             // Call LLM
             let response = primary_client
-                .complete(
+                .complete_with_options(
                     messages,
                     if crate::llm::token_config::TokenConfig::requires_default_temperature(
                         &primary_model,
@@ -863,6 +880,7 @@ impl BiasedReasoningTool {
                     Some(crate::llm::token_config::TokenConfig::get_reasoning_tokens(
                         &primary_model,
                     )),
+                    options,
                 )
                 .await?;
 
@@ -1013,6 +1031,7 @@ impl BiasedReasoningTool {
         step_count: u32,
         verifier_model: String,
         max_rounds: u32,
+        options: CompletionOptions,
     ) -> Result<BiasedReasoningResponse> {
         use chrono::Utc;
 
@@ -1079,7 +1098,7 @@ impl BiasedReasoningTool {
 
             // Call LLM
             let response = verifier_client
-                .complete(
+                .complete_with_options(
                     messages,
                     if crate::llm::token_config::TokenConfig::requires_default_temperature(
                         &verifier_model,
@@ -1091,6 +1110,7 @@ impl BiasedReasoningTool {
                     Some(crate::llm::token_config::TokenConfig::get_optimal_tokens(
                         &verifier_model,
                     )),
+                    options,
                 )
                 .await?;
 
@@ -1284,6 +1304,7 @@ Pattern Analysis:
         session_id: String,
         step_count: u32,
         primary_model: String,
+        options: CompletionOptions,
     ) -> Result<BiasedReasoningResponse> {
         use chrono::Utc;
 
@@ -1340,7 +1361,7 @@ Pattern Analysis:
 
             // Call LLM
             let response = primary_client
-                .complete(
+                .complete_with_options(
                     messages,
                     if crate::llm::token_config::TokenConfig::requires_default_temperature(
                         &primary_model,
@@ -1352,6 +1373,7 @@ Pattern Analysis:
                     Some(crate::llm::token_config::TokenConfig::get_reasoning_tokens(
                         &primary_model,
                     )),
+                    options,
                 )
                 .await?;
 
@@ -1685,42 +1707,10 @@ Pattern Analysis:
     }
 
     fn get_client_for_model(&self, model: &str) -> Result<Arc<dyn LLMClient>> {
-        if self.model_resolver.is_openrouter_model(model) {
-            if self.config.openrouter_api_key.is_none() {
-                anyhow::bail!("OpenRouter API key not configured");
-            }
-
-            if let Some((_, client)) = self.openrouter_clients.iter().find(|(m, _)| m == model) {
-                Ok(client.clone())
-            } else {
-                let api_key = self
-                    .config
-                    .openrouter_api_key
-                    .as_ref()
-                    .ok_or_else(|| anyhow::anyhow!("OpenRouter API key not available"))?;
-                let new_client = OpenRouterClient::new(
-                    api_key.clone(),
-                    model.to_string(),
-                    self.config.openrouter_base_url.clone(),
-                )?;
-                Ok(Arc::new(new_client) as Arc<dyn LLMClient>)
-            }
-        } else {
-            if self.openai_client.is_some() {
-                if let Some(api_key) = &self.config.openai_api_key {
-                    let new_client = OpenAIClient::new(
-                        api_key.clone(),
-                        model.to_string(),
-                        self.config.openai_base_url.clone(),
-                    )?;
-                    Ok(Arc::new(new_client) as Arc<dyn LLMClient>)
-                } else {
-                    anyhow::bail!("OpenAI API key not configured");
-                }
-            } else {
-                anyhow::bail!("OpenAI API key not configured");
-            }
+        if let Some((_, client)) = self.openrouter_clients.iter().find(|(m, _)| m == model) {
+            return Ok(client.clone());
         }
+        self.config.client_for(model)
     }
 
     /// Set the synthesis sink for all sessions
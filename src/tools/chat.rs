@@ -1,18 +1,18 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::fs;
-use std::path::Path;
 use std::sync::Arc;
 use tracing::{debug, error, info, warn};
 
 use crate::llm::{
-    client::{ChatMessage, LLMClient},
+    client::{ChatMessage, CompletionOptions, LLMClient},
     config::LLMConfig,
     model_aliases::ModelResolver,
     openai::OpenAIClient,
     openrouter::OpenRouterClient,
-    Role,
+    ReasoningEffort, Role, Verbosity,
 };
+use crate::monitoring::grounding::GroundingSources;
+use crate::tools::files::{file_context, read_files};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatRequest {
@@ -35,6 +35,19 @@ pub struct ChatRequest {
     /// Use mini model for cost savings (overrides model selection)
     #[serde(default)]
     pub use_mini: bool,
+    /// Reasoning effort for reasoning models (ignored by models without support)
+    #[serde(default)]
+    pub reasoning_effort: Option<ReasoningEffort>,
+    /// Answer verbosity for GPT-5 (ignored by other models)
+    #[serde(default)]
+    pub verbosity: Option<Verbosity>,
+    /// Responses API turn to chain from (set by the server for continued threads)
+    #[serde(skip)]
+    pub previous_response_id: Option<String>,
+    /// Earlier turns of the thread, sent as messages when the model that ends
+    /// up answering can't chain from `previous_response_id`
+    #[serde(skip)]
+    pub history: Vec<ChatMessage>,
 }
 
 fn default_true() -> bool {
@@ -46,6 +59,12 @@ pub struct ChatResponse {
     pub content: String,
     pub model: String,
     pub usage: Option<TokenUsage>,
+    /// Provider response ID for chaining the next turn, if the model supports it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_id: Option<String>,
+    /// Code references in the answer that the provided files don't contain
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ungrounded: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -56,57 +75,16 @@ pub struct TokenUsage {
 }
 
 pub struct ChatTool {
-    openai_client: Option<Arc<dyn LLMClient>>,
     openrouter_clients: Vec<(String, Arc<dyn LLMClient>)>,
     model_resolver: ModelResolver,
     config: LLMConfig,
 }
 
 impl ChatTool {
-    /// Read files and return their contents
-    fn read_files(&self, file_paths: &[String]) -> Vec<(String, String)> {
-        let mut file_contents = Vec::new();
-
-        for path in file_paths {
-            let file_path = Path::new(path);
-            if file_path.exists() && file_path.is_file() {
-                match fs::read_to_string(file_path) {
-                    Ok(content) => {
-                        info!("Read file for chat context: {}", path);
-                        // Truncate very large files to avoid token limits
-                        let truncated = if content.len() > 10000 {
-                            format!("{}... [truncated]", &content[..10000])
-                        } else {
-                            content
-                        };
-                        file_contents.push((path.clone(), truncated));
-                    }
-                    Err(e) => {
-                        warn!("Failed to read file {}: {}", path, e);
-                    }
-                }
-            } else {
-                info!("File not found or not a file: {}", path);
-            }
-        }
-
-        file_contents
-    }
-
     pub fn new(config: LLMConfig) -> Result<Self> {
         let model_resolver = ModelResolver::with_config(Some(config.clone()));
 
         // Initialize OpenAI client if API key is available
-        let openai_client = if let Some(api_key) = &config.openai_api_key {
-            let client = OpenAIClient::new(
-                api_key.clone(),
-                config.model_normal.clone(),
-                config.openai_base_url.clone(),
-            )?;
-            Some(Arc::new(client) as Arc<dyn LLMClient>)
-        } else {
-            None
-        };
 
         // Initialize OpenRouter clients for commonly used models
         let mut openrouter_clients = Vec::new();
@@ -134,48 +112,29 @@ impl ChatTool {
         }
 
         Ok(Self {
-            openai_client,
             openrouter_clients,
             model_resolver,
             config,
         })
     }
 
-    pub async fn chat(&self, request: ChatRequest) -> Result<ChatResponse> {
+    pub async fn chat(&self, mut request: ChatRequest) -> Result<ChatResponse> {
+        // lux.toml supplies the temperature when the request doesn't
+        request.temperature = request
+            .temperature
+            .or(self.config.tool("confer").temperature);
+
         info!(
             "Chat request received - Message: {}, Model: {:?}, Temperature: {:?}, File paths: {:?}",
             request.message, request.model, request.temperature, request.file_paths
         );
 
-        // Resolve model alias - use mini model if requested for cost savings
-        let requested_model = if request.use_mini {
-            self.config.model_mini.clone()
-        } else {
-            request
-                .model
-                .as_ref()
-                .map(|m| self.model_resolver.resolve(m))
-                .unwrap_or_else(|| self.config.model_normal.clone())
-        };
-
-        info!(
-            "Resolved model: {} (requested: {:?}, use_mini: {}, default: {})",
-            requested_model, request.model, request.use_mini, self.config.model_normal
-        );
-
-        // If requested model is blocked (e.g., gpt-4o family), switch to default immediately
-        let requested_model = if self.model_resolver.is_blocked_model(&requested_model) {
-            warn!(
-                "Requested model '{}' is blocked by policy. Using default '{}' instead.",
-                requested_model, self.config.model_normal
-            );
-            self.config.model_normal.clone()
-        } else {
-            requested_model
-        };
+        let requested_model = self.resolve_model(&request);
 
         // Define fallback models in order of preference
-        let fallback_models = self.get_fallback_models(&requested_model);
+        let fallback_models =
+            self.config
+                .fallback_models("confer", &requested_model, &self.model_resolver);
 
         // Try requested model and fallbacks
         let mut last_error = None;
@@ -228,19 +187,35 @@ impl ChatTool {
         }))
     }
 
-    fn get_fallback_models(&self, requested_model: &str) -> Vec<String> {
-        // Strict policy: Only GPT-5 and GPT-5-mini are allowed
-        let mut fallbacks = Vec::new();
-        if requested_model != "gpt-5" {
-            fallbacks.push("gpt-5".to_string());
-        }
-        if requested_model != "gpt-5-mini" {
-            fallbacks.push("gpt-5-mini".to_string());
+    /// Resolve the model a request will be sent to first (before any fallbacks)
+    pub fn resolve_model(&self, request: &ChatRequest) -> String {
+        // Resolve model alias - use mini model if requested for cost savings
+        let requested_model = if request.use_mini {
+            self.config.model_mini.clone()
+        } else {
+            request
+                .model
+                .as_ref()
+                .or(self.config.tool("confer").model.as_ref())
+                .map(|m| self.model_resolver.resolve(m))
+                .unwrap_or_else(|| self.config.model_normal.clone())
+        };
+
+        info!(
+            "Resolved model: {} (requested: {:?}, use_mini: {}, default: {})",
+            requested_model, request.model, request.use_mini, self.config.model_normal
+        );
+
+        // If requested model is blocked (e.g., gpt-4o family), switch to default immediately
+        if self.model_resolver.is_blocked_model(&requested_model) {
+            warn!(
+                "Requested model '{}' is blocked by policy. Using default '{}' instead.",
+                requested_model, self.config.model_normal
+            );
+            self.config.model_normal.clone()
+        } else {
+            requested_model
         }
-        // Remove duplicates while preserving order
-        let mut seen = std::collections::HashSet::new();
-        fallbacks.retain(|model| seen.insert(model.clone()));
-        fallbacks
     }
 
     async fn try_chat_with_model(
@@ -265,72 +240,26 @@ impl ChatTool {
             anyhow::bail!("model_not_found: '{}' is blocked by policy", model);
         }
 
-        // Determine which client to use
-        let client: Arc<dyn LLMClient> = if self.model_resolver.is_openrouter_model(&model) {
-            // OpenRouter model
-            info!("Using OpenRouter for model: {}", model);
-            if self.config.openrouter_api_key.is_none() {
-                error!("OpenRouter API key not configured");
-                anyhow::bail!("OpenRouter API key not configured. Please set OPENROUTER_API_KEY");
-            }
-
-            // Check if we have a pre-created client
-            if let Some((_, client)) = self.openrouter_clients.iter().find(|(m, _)| m == &model) {
-                client.clone()
-            } else {
-                // Create a new client for this model
-                let api_key = self
-                    .config
-                    .openrouter_api_key
-                    .as_ref()
-                    .ok_or_else(|| anyhow::anyhow!("OpenRouter API key not available"))?;
-                let new_client = OpenRouterClient::new(
-                    api_key.clone(),
-                    model.clone(),
-                    self.config.openrouter_base_url.clone(),
-                )?;
-                Arc::new(new_client) as Arc<dyn LLMClient>
-            }
-        } else {
-            // OpenAI model
-            info!("Using OpenAI for model: {}", model);
-            if let Some(_client) = &self.openai_client {
-                // Create a new client with the specific model
-                if let Some(api_key) = &self.config.openai_api_key {
-                    debug!("Creating OpenAI client for model: {}", model);
-                    let new_client = OpenAIClient::new(
-                        api_key.clone(),
-                        model.clone(),
-                        self.config.openai_base_url.clone(),
-                    )?;
-                    Arc::new(new_client) as Arc<dyn LLMClient>
-                } else {
-                    error!("OpenAI API key not configured");
-                    anyhow::bail!("OpenAI API key not configured. Please set OPENAI_API_KEY");
-                }
-            } else {
-                error!("OpenAI client not initialized - API key missing");
-                anyhow::bail!("OpenAI API key not configured. Please set OPENAI_API_KEY");
-            }
+        // Reuse a pre-created OpenRouter client when one matches
+        let client = match self.openrouter_clients.iter().find(|(m, _)| m == &model) {
+            Some((_, client)) => client.clone(),
+            None => self.config.client_for(&model)?,
         };
+        info!("Using client for model: {}", client.get_model_name());
 
         // Build message with optional file contents
         let mut full_message = String::new();
 
-        // Add file contents if provided
+        // Add file contents if provided; the answer is checked against them
+        let mut file_contents = Vec::new();
         if let Some(ref file_paths) = request.file_paths {
             info!("File paths provided: {:?}", file_paths);
             if request.include_file_contents && !file_paths.is_empty() {
                 info!("Attempting to read {} files", file_paths.len());
-                let file_contents = self.read_files(file_paths);
+                file_contents = read_files(file_paths);
                 info!("Successfully read {} files", file_contents.len());
                 if !file_contents.is_empty() {
-                    full_message.push_str("=== FILE CONTEXT ===\n");
-                    for (path, content) in file_contents {
-                        full_message.push_str(&format!("\n📄 File: {}\n", path));
-                        full_message.push_str(&format!("```\n{}\n```\n", content));
-                    }
-                    full_message.push_str("\n=== END FILE CONTEXT ===\n\n");
+                    full_message.push_str(&file_context(&file_contents));
                     info!("Added {} files to chat context", file_paths.len());
                 }
             }
@@ -339,11 +268,30 @@ impl ChatTool {
         // Add the actual message
         full_message.push_str(&request.message);
 
-        // Create messages
-        let messages = vec![ChatMessage {
+        // Chain from the previous turn when the thread has one on the Responses
+        // API; otherwise (e.g. after falling back to another model) resend the
+        // thread's turns
+        let chains = OpenAIClient::uses_responses_api(&model);
+        let mut messages = Vec::new();
+        if request.previous_response_id.is_some() && !chains {
+            if request.history.is_empty() {
+                warn!(
+                    "Model '{}' cannot chain from a previous response and the thread has no turns to resend",
+                    model
+                );
+            } else {
+                warn!(
+                    "Model '{}' cannot chain from a previous response; resending {} thread turns",
+                    model,
+                    request.history.len()
+                );
+                messages.extend(request.history.iter().cloned());
+            }
+        }
+        messages.push(ChatMessage {
             role: Role::User,
             content: full_message,
-        }];
+        });
 
         // ALWAYS USE OPTIMAL INTELLIGENCE - MAXIMUM TOKENS FOR DEEPEST REASONING
         // No user override - always use the maximum for each model
@@ -359,6 +307,7 @@ impl ChatTool {
         } else {
             20000 // Standard models: MAXIMUM THINKING ROOM
         };
+        let max_tokens = self.config.tool("confer").cap_tokens(max_tokens);
 
         info!(
             "🚀 Sending chat request to model '{}' with max_tokens: {}",
@@ -372,9 +321,16 @@ impl ChatTool {
             info!("💭 The model is thinking deeply about your question...");
         }
 
+        let options = CompletionOptions {
+            previous_response_id: request.previous_response_id.clone().filter(|_| chains),
+            reasoning_effort: request.reasoning_effort,
+            verbosity: request.verbosity,
+            ..Default::default()
+        };
+
         let start_time = std::time::Instant::now();
         let response = client
-            .complete(messages, request.temperature, Some(max_tokens))
+            .complete_with_options(messages, request.temperature, Some(max_tokens), options)
             .await
             .map_err(|e| {
                 let elapsed = start_time.elapsed();
@@ -385,6 +341,19 @@ impl ChatTool {
         let elapsed = start_time.elapsed();
         info!("✅ {} responded in {:?}", model, elapsed);
 
+        let ungrounded: Vec<String> = GroundingSources::new(&file_contents, &request.message)
+            .check(&response.content)
+            .ungrounded
+            .iter()
+            .map(|reference| reference.to_string())
+            .collect();
+        if !ungrounded.is_empty() {
+            warn!(
+                "Confer answer has {} ungrounded code references",
+                ungrounded.len()
+            );
+        }
+
         // Format the response with rich styling
         let formatted_content = self.format_chat_response(
             &response.content,
@@ -403,6 +372,8 @@ impl ChatTool {
                 completion_tokens: u.completion_tokens,
                 total_tokens: u.total_tokens,
             }),
+            response_id: response.response_id,
+            ungrounded,
         })
     }
 
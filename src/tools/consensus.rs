@@ -0,0 +1,412 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+use tokio::task::JoinSet;
+use tracing::{info, warn};
+
+use crate::llm::{
+    audit,
+    client::{ChatMessage, CompletionOptions},
+    config::LLMConfig,
+    model_aliases::ModelResolver,
+    openai::OpenAIClient,
+    ReasoningEffort, Role, Verbosity,
+};
+use crate::monitoring::grounding::GroundingSources;
+use crate::settings::ToolSettings;
+use crate::tools::files::{file_context, read_files};
+
+const JUDGE_MAX_TOKENS: u32 = 16000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsensusRequest {
+    pub prompt: String,
+    /// Models to consult (default: normal model plus the configured opus/grok aliases)
+    #[serde(default)]
+    pub models: Option<Vec<String>>,
+    /// Model that compares the answers (default: reasoning model)
+    #[serde(default)]
+    pub judge_model: Option<String>,
+    #[serde(default)]
+    pub continuation_id: Option<String>,
+    /// File paths to include in every model's context
+    #[serde(default)]
+    pub file_paths: Option<Vec<String>>,
+    /// Whether to include file contents (default: true)
+    #[serde(default = "default_true")]
+    pub include_file_contents: bool,
+    /// Reasoning effort for reasoning models (ignored by models without support)
+    #[serde(default)]
+    pub reasoning_effort: Option<ReasoningEffort>,
+    /// Answer verbosity for GPT-5 (ignored by other models)
+    #[serde(default)]
+    pub verbosity: Option<Verbosity>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// One model's answer, or the reason it has none
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelAnswer {
+    pub model: String,
+    pub content: Option<String>,
+    pub error: Option<String>,
+    pub elapsed_ms: u64,
+    /// Code references in the answer that the provided files don't contain
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ungrounded: Vec<String>,
+}
+
+/// The judge's comparison of the answers
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConsensusVerdict {
+    #[serde(default)]
+    pub agreements: Vec<String>,
+    #[serde(default)]
+    pub disagreements: Vec<String>,
+    #[serde(default)]
+    pub recommendation: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConsensusResponse {
+    pub answers: Vec<ModelAnswer>,
+    pub judge_model: String,
+    /// None when fewer than two models answered or the judge failed
+    pub verdict: Option<ConsensusVerdict>,
+    /// Why the judge gave no verdict; the answers are returned regardless
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub judge_error: Option<String>,
+}
+
+pub struct ConsensusTool {
+    model_resolver: ModelResolver,
+    config: LLMConfig,
+}
+
+impl ConsensusTool {
+    pub fn new(config: LLMConfig) -> Self {
+        Self {
+            model_resolver: ModelResolver::with_config(Some(config.clone())),
+            config,
+        }
+    }
+
+    /// Ask every model the same prompt concurrently, then have the judge compare the answers.
+    /// `history` is the reconstructed thread when continuing a previous consensus.
+    pub async fn run(
+        &self,
+        request: &ConsensusRequest,
+        history: Option<String>,
+    ) -> Result<ConsensusResponse> {
+        let models = self.resolve_models(request);
+        if models.is_empty() {
+            anyhow::bail!(
+                "No models available for consensus. Configure OPENAI_API_KEY or OPENROUTER_API_KEY"
+            );
+        }
+        info!("Consensus fan-out to {} models: {:?}", models.len(), models);
+
+        let settings = self.settings();
+        let files = if request.include_file_contents {
+            read_files(request.file_paths.as_deref().unwrap_or_default())
+        } else {
+            Vec::new()
+        };
+        let prompt = self.build_prompt(request, history.as_deref(), &files);
+        let options = CompletionOptions {
+            reasoning_effort: request.reasoning_effort,
+            verbosity: request.verbosity,
+            ..Default::default()
+        };
+
+        let mut tasks = JoinSet::new();
+        for (index, model) in models.iter().enumerate() {
+            let client = self.config.client_for(model);
+            let model = model.clone();
+            let prompt = prompt.clone();
+            let options = options.clone();
+            let temperature = settings.temperature;
+            let max_tokens = settings.cap_tokens(OpenAIClient::get_optimal_tokens(&model));
+            let audit_context = audit::current_context();
+            tasks.spawn(audit::scope(audit_context, async move {
+                let start = Instant::now();
+                let result = match client {
+                    Ok(client) => {
+                        client
+                            .complete_with_options(
+                                vec![ChatMessage {
+                                    role: Role::User,
+                                    content: prompt,
+                                }],
+                                temperature,
+                                Some(max_tokens),
+                                options,
+                            )
+                            .await
+                    }
+                    Err(e) => Err(e),
+                };
+                let elapsed_ms = start.elapsed().as_millis() as u64;
+                let answer = match result {
+                    Ok(response) => ModelAnswer {
+                        model,
+                        content: Some(response.content),
+                        error: None,
+                        elapsed_ms,
+                        ungrounded: Vec::new(),
+                    },
+                    Err(e) => {
+                        warn!("Consensus model '{}' failed: {}", model, e);
+                        ModelAnswer {
+                            model,
+                            content: None,
+                            error: Some(e.to_string()),
+                            elapsed_ms,
+                            ungrounded: Vec::new(),
+                        }
+                    }
+                };
+                (index, answer)
+            }));
+        }
+
+        let mut answers = Vec::with_capacity(models.len());
+        while let Some(joined) = tasks.join_next().await {
+            answers.push(joined.context("Consensus task panicked")?);
+        }
+        answers.sort_by_key(|(index, _)| *index);
+        let mut answers: Vec<ModelAnswer> = answers.into_iter().map(|(_, answer)| answer).collect();
+
+        // Code references each answer makes that the files don't back up
+        if !files.is_empty() {
+            let sources = GroundingSources::new(&files, &request.prompt);
+            for answer in &mut answers {
+                if let Some(content) = &answer.content {
+                    answer.ungrounded = sources
+                        .check(content)
+                        .ungrounded
+                        .iter()
+                        .map(|reference| reference.to_string())
+                        .collect();
+                }
+            }
+        }
+
+        let succeeded = answers.iter().filter(|a| a.content.is_some()).count();
+        if succeeded == 0 {
+            anyhow::bail!(
+                "All consensus models failed: {}",
+                answers
+                    .iter()
+                    .map(|a| format!("{}: {}", a.model, a.error.as_deref().unwrap_or("unknown")))
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            );
+        }
+
+        let judge_model = self.resolve_judge(request);
+        let (verdict, judge_error) = if succeeded < 2 {
+            warn!("Only one model answered; skipping consensus judge");
+            (None, None)
+        } else {
+            match self
+                .judge(&judge_model, &request.prompt, &answers, &settings, options)
+                .await
+            {
+                Ok(verdict) => (Some(verdict), None),
+                Err(e) => {
+                    warn!("{:#}", e);
+                    (None, Some(format!("{:#}", e)))
+                }
+            }
+        };
+
+        Ok(ConsensusResponse {
+            answers,
+            judge_model,
+            verdict,
+            judge_error,
+        })
+    }
+
+    fn resolve_models(&self, request: &ConsensusRequest) -> Vec<String> {
+        let requested = match &request.models {
+            Some(models) if !models.is_empty() => models.clone(),
+            _ => std::iter::once(self.config.model_normal.clone())
+                .chain(self.config.model_opus.clone())
+                .chain(self.config.model_grok.clone())
+                .collect(),
+        };
+
+        let mut models: Vec<String> = Vec::new();
+        for model in requested {
+            let resolved = self.model_resolver.resolve(&model);
+            if !self.is_available(&resolved) {
+                warn!(
+                    "Skipping consensus model '{}': provider not configured or model blocked",
+                    resolved
+                );
+                continue;
+            }
+            if !models.contains(&resolved) {
+                models.push(resolved);
+            }
+        }
+        models
+    }
+
+    /// `[tools.consensus]` settings
+    fn settings(&self) -> ToolSettings {
+        self.config.tool("consensus")
+    }
+
+    /// The requested judge, else `[tools.consensus] model`, else the reasoning
+    /// model. An unavailable judge is replaced by the first available model of
+    /// `fallback_models`, then the normal model.
+    fn resolve_judge(&self, request: &ConsensusRequest) -> String {
+        let settings = self.settings();
+        let judge = request
+            .judge_model
+            .as_ref()
+            .or(settings.model.as_ref())
+            .map(|m| self.model_resolver.resolve(m))
+            .unwrap_or_else(|| self.config.model_reasoning.clone());
+        if self.is_available(&judge) {
+            return judge;
+        }
+        let fallback = settings
+            .fallback_models
+            .unwrap_or_default()
+            .iter()
+            .map(|m| self.model_resolver.resolve(m))
+            .find(|m| self.is_available(m))
+            .unwrap_or_else(|| self.config.model_normal.clone());
+        warn!("Judge model '{}' unavailable, using '{}'", judge, fallback);
+        fallback
+    }
+
+    /// Whether a model's provider has a key. The GPT-5-only policy applies to
+    /// direct OpenAI models; other vendors are the point of a consensus run.
+    fn is_available(&self, model: &str) -> bool {
+        if self.model_resolver.is_openrouter_model(model) {
+            self.config.openrouter_api_key.is_some()
+        } else {
+            self.config.openai_api_key.is_some() && !self.model_resolver.is_blocked_model(model)
+        }
+    }
+
+    fn build_prompt(
+        &self,
+        request: &ConsensusRequest,
+        history: Option<&str>,
+        files: &[(String, String)],
+    ) -> String {
+        let mut prompt = String::new();
+
+        if let Some(history) = history {
+            prompt.push_str(history);
+            prompt.push_str("\n\n");
+        }
+
+        prompt.push_str(&file_context(files));
+        prompt.push_str(&request.prompt);
+        prompt
+    }
+
+    async fn judge(
+        &self,
+        judge_model: &str,
+        question: &str,
+        answers: &[ModelAnswer],
+        settings: &ToolSettings,
+        options: CompletionOptions,
+    ) -> Result<ConsensusVerdict> {
+        let mut prompt = format!(
+            "Several models answered the same question independently. Compare their answers.\n\n\
+             QUESTION:\n{}\n\n",
+            question
+        );
+        for answer in answers {
+            if let Some(content) = &answer.content {
+                prompt.push_str(&format!(
+                    "=== ANSWER FROM {} ===\n{}\n\n",
+                    answer.model, content
+                ));
+            }
+        }
+        prompt.push_str(
+            "Respond with only a JSON object of this shape:\n\
+             {\"agreements\": [\"points all models share\"], \
+             \"disagreements\": [\"points where models differ, naming which model holds which position\"], \
+             \"recommendation\": \"a merged recommendation that resolves the disagreements\"}",
+        );
+
+        info!("Consensus judge: {}", judge_model);
+        let response = self
+            .config
+            .client_for(judge_model)?
+            .complete_with_options(
+                vec![ChatMessage {
+                    role: Role::User,
+                    content: prompt,
+                }],
+                settings.temperature,
+                Some(settings.cap_tokens(JUDGE_MAX_TOKENS)),
+                options,
+            )
+            .await
+            .context("Consensus judge failed")?;
+
+        Ok(parse_verdict(&response.content))
+    }
+}
+
+/// Parse the judge's JSON, tolerating code fences and surrounding prose.
+/// Unparseable output becomes the recommendation as-is.
+fn parse_verdict(content: &str) -> ConsensusVerdict {
+    let json = match (content.find('{'), content.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &content[start..=end],
+        _ => content,
+    };
+
+    serde_json::from_str(json).unwrap_or_else(|_| ConsensusVerdict {
+        recommendation: content.trim().to_string(),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_verdict() {
+        let verdict = parse_verdict(
+            "```json\n{\"agreements\": [\"Use Postgres\"], \"disagreements\": [\"gpt-5 prefers a monolith\"], \"recommendation\": \"Start monolithic\"}\n```",
+        );
+        assert_eq!(verdict.agreements, vec!["Use Postgres"]);
+        assert_eq!(verdict.disagreements.len(), 1);
+        assert_eq!(verdict.recommendation, "Start monolithic");
+
+        let fallback = parse_verdict("The models broadly agree.");
+        assert!(fallback.agreements.is_empty());
+        assert_eq!(fallback.recommendation, "The models broadly agree.");
+    }
+
+    #[test]
+    fn test_default_models_follow_configured_providers() {
+        let config = LLMConfig {
+            openai_api_key: Some("key".to_string()),
+            ..Default::default()
+        };
+        let tool = ConsensusTool::new(config);
+        let request: ConsensusRequest =
+            serde_json::from_value(serde_json::json!({"prompt": "Monolith or services?"})).unwrap();
+
+        // Without an OpenRouter key only the OpenAI default survives
+        assert_eq!(tool.resolve_models(&request), vec!["gpt-5".to_string()]);
+    }
+}
@@ -0,0 +1,75 @@
+//! `file_paths` handling shared by the single-answer tools
+
+use std::fs;
+use std::path::Path;
+use tracing::{info, warn};
+
+/// Longest file excerpt put in a prompt, in bytes
+pub const MAX_FILE_CHARS: usize = 10000;
+
+/// Read the files that exist, truncating large ones. Missing and unreadable
+/// files are logged and skipped.
+pub fn read_files(file_paths: &[String]) -> Vec<(String, String)> {
+    let mut file_contents = Vec::new();
+
+    for path in file_paths {
+        if !Path::new(path).is_file() {
+            info!("File not found or not a file: {}", path);
+            continue;
+        }
+        match fs::read_to_string(path) {
+            Ok(content) => {
+                // Truncate very large files to avoid token limits
+                let truncated = if content.len() > MAX_FILE_CHARS {
+                    let mut end = MAX_FILE_CHARS;
+                    while !content.is_char_boundary(end) {
+                        end -= 1;
+                    }
+                    format!("{}... [truncated]", &content[..end])
+                } else {
+                    content
+                };
+                file_contents.push((path.clone(), truncated));
+            }
+            Err(e) => warn!("Failed to read file {}: {}", path, e),
+        }
+    }
+
+    file_contents
+}
+
+/// The files as a prompt section; empty when there are none
+pub fn file_context(files: &[(String, String)]) -> String {
+    if files.is_empty() {
+        return String::new();
+    }
+    let mut context = String::from("=== FILE CONTEXT ===\n");
+    for (path, content) in files {
+        context.push_str(&format!("\n📄 File: {}\n```\n{}\n```\n", path, content));
+    }
+    context.push_str("\n=== END FILE CONTEXT ===\n\n");
+    context
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_files_truncates_on_char_boundary() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wide.txt");
+        // Three-byte characters never end exactly at the limit
+        fs::write(&path, "€".repeat(MAX_FILE_CHARS)).unwrap();
+
+        let path = path.to_string_lossy().into_owned();
+        let files = read_files(&[
+            path.clone(),
+            dir.path().join("missing.txt").to_string_lossy().into(),
+        ]);
+        assert_eq!(files.len(), 1);
+        assert!(files[0].1.ends_with("... [truncated]"));
+        assert!(file_context(&files).contains(&format!("📄 File: {}", path)));
+        assert!(file_context(&[]).is_empty());
+    }
+}
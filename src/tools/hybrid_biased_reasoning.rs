@@ -102,14 +102,24 @@ struct SessionState {
 /// Tool for hybrid biased reasoning
 pub struct HybridBiasedReasoningTool {
     sessions: Arc<Mutex<HashMap<String, SessionState>>>,
+    config: LLMConfig,
     model_resolver: ModelResolver,
 }
 
 impl HybridBiasedReasoningTool {
-    pub fn new() -> Self {
+    pub fn new(config: LLMConfig) -> Self {
         Self {
             sessions: Arc::new(Mutex::new(HashMap::new())),
-            model_resolver: ModelResolver::new(),
+            model_resolver: ModelResolver::with_config(Some(config.clone())),
+            config,
+        }
+    }
+
+    /// Build a tool with a new configuration that shares this tool's sessions
+    pub fn reconfigured(&self, config: LLMConfig) -> Self {
+        Self {
+            sessions: self.sessions.clone(),
+            ..Self::new(config)
         }
     }
 
@@ -172,9 +182,8 @@ impl HybridBiasedReasoningTool {
             }
         }
 
-        // Get LLM configuration
-        let config = LLMConfig::from_env()?;
-        let model_resolver = ModelResolver::with_config(Some(config.clone()));
+        let config = &self.config;
+        let model_resolver = &self.model_resolver;
         let model_name = request
             .bias_check_model
             .as_deref()
@@ -186,17 +195,19 @@ impl HybridBiasedReasoningTool {
             Box::new(OpenRouterClient::new(
                 config
                     .openrouter_api_key
+                    .clone()
                     .context("OpenRouter API key not configured")?,
                 resolved_model.clone(),
-                config.openrouter_base_url,
+                config.openrouter_base_url.clone(),
             )?)
         } else {
-            Box::new(OpenAIClient::new(
+            Box::new(OpenAIClient::with_config(
                 config
                     .openai_api_key
+                    .clone()
                     .context("OpenAI API key not configured")?,
                 resolved_model.clone(),
-                config.openai_base_url,
+                config,
             )?)
         };
 
@@ -508,12 +519,6 @@ impl HybridBiasedReasoningTool {
     }
 }
 
-impl Default for HybridBiasedReasoningTool {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 const BIAS_CHECKER_SYSTEM_PROMPT: &str = r#"You are an expert bias detector and critical thinking analyst. Your role is to:
 
 1. Identify cognitive biases, logical fallacies, and problematic assumptions in reasoning
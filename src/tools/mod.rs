@@ -2,6 +2,8 @@ pub mod biased_reasoning;
 pub mod biased_reasoning_prompts;
 pub mod biased_reasoning_synthesis;
 pub mod chat;
+pub mod consensus;
+pub mod files;
 pub mod traced_reasoning;
 // pub mod biased_reasoning_integration; // Deprecated - using new synthesis architecture
 pub mod hybrid_biased_reasoning;
@@ -13,6 +15,7 @@ pub mod setup_config;
 
 pub use biased_reasoning::{BiasedReasoningRequest, BiasedReasoningTool, StepType};
 pub use chat::{ChatRequest, ChatTool};
+pub use consensus::{ConsensusRequest, ConsensusTool};
 pub use hybrid_biased_reasoning::{
     HybridBiasedReasoningRequest, HybridBiasedReasoningResponse, HybridBiasedReasoningTool,
 };
@@ -1,11 +1,15 @@
 use crate::llm::{
-    client::{ChatMessage, LLMClient},
+    audit,
+    client::{ChatMessage, CompletionOptions, LLMClient},
     config::LLMConfig,
     model_aliases::ModelResolver,
-    openai::OpenAIClient,
     openrouter::OpenRouterClient,
-    Role,
+    ReasoningEffort, Role, Verbosity,
 };
+use crate::monitoring::detector::GROUNDING;
+use crate::monitoring::grounding::GroundingSources;
+use crate::monitoring::subgoals::{CoverageMap, SubgoalTracker};
+use crate::monitoring::MonitoringOverrides;
 use crate::session::SessionManager;
 use anyhow::{Context, Result};
 use lux_synthesis::{
@@ -17,6 +21,7 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 use std::sync::{Arc, Mutex as StdMutex};
+use tokio::sync::Mutex;
 use tracing::{error, info, warn};
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -43,8 +48,9 @@ pub struct PlannerRequest {
     pub session_id: Option<String>,
     #[serde(default)]
     pub model: Option<String>,
-    #[serde(default = "default_temperature")]
-    pub temperature: f32,
+    /// Falls back to the tool's lux.toml temperature, then 0.7
+    #[serde(default)]
+    pub temperature: Option<f32>,
 
     /// File paths to include in planning context
     #[serde(default)]
@@ -61,6 +67,26 @@ pub struct PlannerRequest {
     /// Use mini model for cost savings
     #[serde(default)]
     pub use_mini: bool,
+
+    /// Reasoning effort for reasoning models (ignored by models without support)
+    #[serde(default)]
+    pub reasoning_effort: Option<ReasoningEffort>,
+
+    /// Answer verbosity for GPT-5 (ignored by other models)
+    #[serde(default)]
+    pub verbosity: Option<Verbosity>,
+
+    /// Monitoring preset or thresholds for this call only
+    #[serde(default)]
+    pub monitoring: Option<MonitoringOverrides>,
+
+    /// Requirements to track on step 1, instead of those found in the task
+    #[serde(default)]
+    pub subgoals: Option<Vec<String>>,
+
+    /// Domain lexicons for concept matching, kept for the rest of the session
+    #[serde(default)]
+    pub lexicons: Option<Vec<String>>,
 }
 
 fn default_temperature() -> f32 {
@@ -110,6 +136,10 @@ pub struct PlannerResponse {
     /// Files recommended for examination in next step
     #[serde(skip_serializing_if = "Option::is_none")]
     pub recommended_files: Option<Vec<String>>,
+
+    /// Which subgoals of the task the plan covered so far
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<CoverageMap>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -144,10 +174,15 @@ pub struct StepData {
 
 pub struct PlannerTool {
     session_manager: Arc<SessionManager>,
-    openai_client: Option<Arc<dyn LLMClient>>,
     openrouter_clients: Vec<(String, Arc<dyn LLMClient>)>,
     model_resolver: ModelResolver,
     config: LLMConfig,
+    /// Held for the whole of a call, and kept by the tool a config reload rebuilds
+    plan: Arc<Mutex<PlanState>>,
+}
+
+/// The plan in progress
+struct PlanState {
     step_history: Vec<StepData>,
     branches: HashMap<String, Vec<StepData>>,
     synthesis: Arc<StdMutex<EvolvingSynthesis>>,
@@ -158,12 +193,15 @@ pub struct PlannerTool {
 
 impl PlannerTool {
     /// Read files and return their contents
-    fn read_files(&mut self, file_paths: &[String]) -> Vec<(String, String)> {
+    fn read_files(
+        file_cache: &mut HashMap<String, String>,
+        file_paths: &[String],
+    ) -> Vec<(String, String)> {
         let mut file_contents = Vec::new();
 
         for path in file_paths {
             // Check cache first
-            if let Some(cached_content) = self.file_cache.get(path) {
+            if let Some(cached_content) = file_cache.get(path) {
                 info!("Using cached file for planning: {}", path);
                 file_contents.push((path.clone(), cached_content.clone()));
                 continue;
@@ -181,7 +219,7 @@ impl PlannerTool {
                             content.clone()
                         };
                         // Cache the content
-                        self.file_cache.insert(path.clone(), truncated.clone());
+                        file_cache.insert(path.clone(), truncated.clone());
                         file_contents.push((path.clone(), truncated));
                     }
                     Err(e) => {
@@ -275,17 +313,6 @@ impl PlannerTool {
     pub fn new(config: LLMConfig, session_manager: Arc<SessionManager>) -> Result<Self> {
         let model_resolver = ModelResolver::with_config(Some(config.clone()));
 
-        let openai_client = if let Some(api_key) = &config.openai_api_key {
-            let client = OpenAIClient::new(
-                api_key.clone(),
-                config.model_reasoning.clone(),
-                config.openai_base_url.clone(),
-            )?;
-            Some(Arc::new(client) as Arc<dyn LLMClient>)
-        } else {
-            None
-        };
-
         let mut openrouter_clients = Vec::new();
         if let Some(api_key) = &config.openrouter_api_key {
             let common_models = vec![
@@ -311,21 +338,29 @@ impl PlannerTool {
 
         Ok(Self {
             session_manager,
-            openai_client,
             openrouter_clients,
             model_resolver,
             config,
-            step_history: Vec::new(),
-            branches: HashMap::new(),
-            synthesis,
-            synthesis_sink: None,
-            file_cache: HashMap::new(),
+            plan: Arc::new(Mutex::new(PlanState {
+                step_history: Vec::new(),
+                branches: HashMap::new(),
+                synthesis,
+                synthesis_sink: None,
+                file_cache: HashMap::new(),
+            })),
         })
     }
 
+    /// Build a tool with a new configuration that continues this tool's plan
+    pub fn reconfigured(&self, config: LLMConfig) -> Result<Self> {
+        let mut fresh = Self::new(config, self.session_manager.clone())?;
+        fresh.plan = self.plan.clone();
+        Ok(fresh)
+    }
+
     /// Set synthesis sink for database persistence
-    pub fn set_synthesis_sink(&mut self, sink: Arc<dyn SynthesisSink>) {
-        self.synthesis_sink = Some(sink);
+    pub async fn set_synthesis_sink(&self, sink: Arc<dyn SynthesisSink>) {
+        self.plan.lock().await.synthesis_sink = Some(sink);
     }
 
     /// Generate mandatory actions that the caller MUST take
@@ -394,22 +429,31 @@ impl PlannerTool {
         actions
     }
 
-    pub async fn create_plan(&mut self, request: PlannerRequest) -> Result<PlannerResponse> {
+    pub async fn create_plan(&self, request: PlannerRequest) -> Result<PlannerResponse> {
+        let mut guard = self.plan.lock().await;
+        let plan = &mut *guard;
         let session_id = self
             .session_manager
             .get_or_create_session(request.session_id.clone());
+        audit::set_session(session_id.clone());
         let monitor = self.session_manager.get_monitor(&session_id)?;
+        if let Some(lexicons) = &request.lexicons {
+            monitor.lock().set_lexicons(lexicons.clone())?;
+        }
+        monitor
+            .lock()
+            .apply_overrides(request.monitoring.as_ref())?;
 
         // Create synthesis for this session with appropriate sink
-        if let Some(sink) = &self.synthesis_sink {
+        if let Some(_sink) = &plan.synthesis_sink {
             // For now, we'll use in-memory synthesis even with a sink available
             // TODO: Create a constructor that accepts a custom sink
-            self.synthesis = Arc::new(StdMutex::new(EvolvingSynthesis::new_in_memory(
+            plan.synthesis = Arc::new(StdMutex::new(EvolvingSynthesis::new_in_memory(
                 "planner",
                 &session_id,
             )));
         } else {
-            self.synthesis = Arc::new(StdMutex::new(EvolvingSynthesis::new_in_memory(
+            plan.synthesis = Arc::new(StdMutex::new(EvolvingSynthesis::new_in_memory(
                 "planner",
                 &session_id,
             )));
@@ -431,9 +475,14 @@ impl PlannerTool {
             request
                 .model
                 .as_ref()
+                .or(self.config.tool("planner").model.as_ref())
                 .map(|m| self.model_resolver.resolve(m))
                 .unwrap_or_else(|| self.config.model_reasoning.clone())
         };
+        let temperature = request
+            .temperature
+            .or(self.config.tool("planner").temperature)
+            .unwrap_or_else(default_temperature);
 
         // Block GPT-4o family; switch to default reasoning model (usually o3)
         if self.model_resolver.is_blocked_model(&model) {
@@ -446,11 +495,7 @@ impl PlannerTool {
 
         info!(
             "Planner request - Step {}/{}, Model: {}, Temperature: {}, Session: {:?}",
-            request.step_number,
-            request.total_steps,
-            model,
-            request.temperature,
-            request.session_id
+            request.step_number, request.total_steps, model, temperature, request.session_id
         );
 
         // Log processing status for long-running models
@@ -466,7 +511,7 @@ impl PlannerTool {
         let file_contents = if let Some(ref file_paths) = request.file_paths {
             if request.include_file_contents {
                 files_examined = file_paths.clone();
-                self.read_files(file_paths)
+                Self::read_files(&mut plan.file_cache, file_paths)
             } else {
                 Vec::new()
             }
@@ -479,13 +524,18 @@ impl PlannerTool {
                     discovered.len()
                 );
                 files_examined = discovered.clone();
-                self.read_files(&discovered)
+                Self::read_files(&mut plan.file_cache, &discovered)
             } else {
                 Vec::new()
             }
         } else {
             Vec::new()
         };
+        if !file_contents.is_empty() {
+            monitor
+                .lock()
+                .set_sources(GroundingSources::new(&file_contents, &request.step));
+        }
 
         // Generate planning content using LLM
         let generated_content = if request.step_number == 1 {
@@ -493,7 +543,7 @@ impl PlannerTool {
             request.step.clone()
         } else {
             // Build context from previous steps and files
-            let context = self.build_planning_context(&request, &file_contents);
+            let context = self.build_planning_context(plan, &request, &file_contents);
 
             // Create prompt for LLM
             let system_prompt = self.build_system_prompt(&request);
@@ -516,7 +566,9 @@ impl PlannerTool {
             ];
 
             // Use optimal tokens for each model type - GPT-5 gets MAXIMUM
-            let max_tokens = crate::llm::token_config::TokenConfig::get_reasoning_tokens(&model);
+            let max_tokens = self.config.tool("planner").cap_tokens(
+                crate::llm::token_config::TokenConfig::get_reasoning_tokens(&model),
+            );
 
             info!(
                 "🚀 Sending planning request to {} (max_tokens: {})",
@@ -533,11 +585,17 @@ impl PlannerTool {
                 if crate::llm::token_config::TokenConfig::requires_default_temperature(&model) {
                     None // Use default temperature
                 } else {
-                    Some(request.temperature)
+                    Some(temperature)
                 };
 
+            let options = CompletionOptions {
+                reasoning_effort: request.reasoning_effort,
+                verbosity: request.verbosity,
+                ..Default::default()
+            };
+
             let response = client
-                .complete(messages, temperature_opt, Some(max_tokens))
+                .complete_with_options(messages, temperature_opt, Some(max_tokens), options)
                 .await
                 .map_err(|e| {
                     let elapsed = start_time.elapsed();
@@ -571,7 +629,7 @@ impl PlannerTool {
         // Handle branching
         if request.is_branch_point && request.branch_id.is_some() {
             let branch_id = request.branch_id.as_ref().unwrap();
-            self.branches
+            plan.branches
                 .entry(branch_id.clone())
                 .or_insert_with(Vec::new)
                 .push(step_data.clone());
@@ -580,16 +638,27 @@ impl PlannerTool {
         // Add to main history (revisions replace the original step)
         if request.is_step_revision && request.revises_step_number.is_some() {
             let revises_idx = request.revises_step_number.unwrap() as usize - 1;
-            if revises_idx < self.step_history.len() {
-                self.step_history[revises_idx] = step_data;
+            if revises_idx < plan.step_history.len() {
+                plan.step_history[revises_idx] = step_data;
             }
         } else {
-            self.step_history.push(step_data);
+            plan.step_history.push(step_data);
         }
 
         // Monitor for circular reasoning
-        {
+        self.session_manager
+            .embedder()
+            .prefetch(&[&generated_content])
+            .await;
+        let (mandatory, coverage) = {
             let mut monitor_guard = monitor.lock();
+            // A new plan is checked against the subgoals of its task
+            if request.step_number == 1 {
+                monitor_guard.set_subgoals(match &request.subgoals {
+                    Some(subgoals) => SubgoalTracker::from_list(subgoals, 1),
+                    None => SubgoalTracker::from_query(&request.step, 1),
+                });
+            }
             let signals =
                 monitor_guard.analyze_thought(&generated_content, request.step_number as usize);
 
@@ -599,13 +668,24 @@ impl PlannerTool {
                     request.step_number
                 );
             }
-        }
+
+            // A matching monitoring rule speaks first, then ungrounded references
+            let mandatory = match signals.decision {
+                Some(decision) => Some(decision.message),
+                None => signals
+                    .detector_signals
+                    .into_iter()
+                    .find(|s| s.detector == GROUNDING && s.triggered)
+                    .and_then(|s| s.message),
+            };
+            (mandatory, signals.coverage)
+        };
 
         // Update synthesis with this planning step
         {
             use lux_synthesis::events::SynthesisEvent;
 
-            let synthesis = self.synthesis.lock().unwrap();
+            let synthesis = plan.synthesis.lock().unwrap();
 
             // Update understanding
             let understanding = if request.step_number == 1 {
@@ -675,20 +755,35 @@ impl PlannerTool {
 
         // Build response with generated content
         let mut response =
-            self.build_planning_response(&request, generated_content.clone(), &model);
+            self.build_planning_response(plan, &request, generated_content.clone(), &model);
 
         // Add files examined if any
         if !files_examined.is_empty() {
             response.files_examined = Some(files_examined);
         }
 
-        // Add mandatory actions for the caller
-        response.mandatory_actions =
-            Some(self.generate_mandatory_actions(&request, &generated_content));
+        // Add mandatory actions for the caller; a rule's guidance or a plan built
+        // on code that isn't there needs handling first
+        let mut mandatory_actions = self.generate_mandatory_actions(&request, &generated_content);
+        if let Some(mandatory) = mandatory {
+            mandatory_actions.insert(0, format!("⚠️ MANDATORY: {}", mandatory));
+        }
+        if let Some(coverage) = coverage.as_ref().filter(|_| !request.next_step_required) {
+            let open: Vec<String> = coverage
+                .open_critical()
+                .map(|subgoal| format!(
+                    "⚠️ MANDATORY: The plan does not cover subgoal {} yet ({}): {}. Add steps for it before presenting the plan.",
+                    subgoal.id, subgoal.status, subgoal.text
+                ))
+                .collect();
+            mandatory_actions.splice(0..0, open);
+        }
+        response.mandatory_actions = Some(mandatory_actions);
+        response.coverage = coverage;
 
         // Add synthesis snapshot to response
         {
-            let synthesis = self.synthesis.lock().unwrap();
+            let synthesis = plan.synthesis.lock().unwrap();
             let state = synthesis.snapshot();
             response.synthesis_snapshot = Some(SynthesisSnapshot {
                 current_plan: state.current_understanding.clone(),
@@ -720,7 +815,7 @@ impl PlannerTool {
         // Handle plan completion
         if !request.next_step_required {
             response.planning_complete = Some(true);
-            response.plan_summary = Some(self.generate_plan_summary(&request));
+            response.plan_summary = Some(self.generate_plan_summary(plan, &request));
             response.next_steps = Some(
                 "⚠️ CRITICAL - YOU MUST TAKE THESE ACTIONS:\n\
                 1. MANDATORY: Present the complete plan with NUMBERED STEPS and clear structure\n\
@@ -736,9 +831,9 @@ impl PlannerTool {
 
             // Add recommended files for implementation
             let mut recommended_files = Vec::new();
-            if self.file_cache.len() > 0 {
+            if !plan.file_cache.is_empty() {
                 // Recommend examining the files we've already seen
-                for (path, _) in self.file_cache.iter().take(5) {
+                for (path, _) in plan.file_cache.iter().take(5) {
                     recommended_files.push(path.clone());
                 }
             }
@@ -752,6 +847,7 @@ impl PlannerTool {
 
     fn build_planning_context(
         &self,
+        plan: &PlanState,
         request: &PlannerRequest,
         file_contents: &[(String, String)],
     ) -> String {
@@ -779,7 +875,7 @@ impl PlannerTool {
 
         // Add previous steps
         context.push_str("Previous planning steps:\n");
-        for (i, step) in self.step_history.iter().enumerate() {
+        for (i, step) in plan.step_history.iter().enumerate() {
             context.push_str(&format!("Step {}: {}\n", i + 1, step.content));
         }
 
@@ -847,13 +943,14 @@ impl PlannerTool {
 
     fn build_planning_response(
         &self,
+        plan: &PlanState,
         request: &PlannerRequest,
         generated_content: String,
         model: &str,
     ) -> PlannerResponse {
         let metadata = PlannerMetadata {
-            branches: self.branches.keys().cloned().collect(),
-            step_history_length: self.step_history.len() as u32,
+            branches: plan.branches.keys().cloned().collect(),
+            step_history_length: plan.step_history.len() as u32,
             is_step_revision: request.is_step_revision,
             revises_step_number: request.revises_step_number,
             is_branch_point: request.is_branch_point,
@@ -898,6 +995,7 @@ impl PlannerTool {
             mandatory_actions: None,  // Will be set after building response
             files_examined: None,     // Will be set after building response
             recommended_files: None,  // Will be set after building response
+            coverage: None,           // Will be set after building response
         }
     }
 
@@ -992,7 +1090,7 @@ impl PlannerTool {
         response
     }
 
-    fn generate_plan_summary(&self, request: &PlannerRequest) -> String {
+    fn generate_plan_summary(&self, plan: &PlanState, request: &PlannerRequest) -> String {
         let mut summary = format!(
             "COMPLETE PLAN: {} (Total {} steps completed)\n\n",
             request.step, request.total_steps
@@ -1000,7 +1098,7 @@ impl PlannerTool {
 
         // Add step history summary
         summary.push_str("PLANNING JOURNEY:\n");
-        for (i, step) in self.step_history.iter().enumerate() {
+        for (i, step) in plan.step_history.iter().enumerate() {
             summary.push_str(&format!(
                 "Step {}: {}\n",
                 i + 1,
@@ -1023,9 +1121,9 @@ impl PlannerTool {
         }
 
         // Add branch summary if any
-        if !self.branches.is_empty() {
+        if !plan.branches.is_empty() {
             summary.push_str("\nBRANCHES EXPLORED:\n");
-            for (branch_id, steps) in &self.branches {
+            for (branch_id, steps) in &plan.branches {
                 summary.push_str(&format!("- {}: {} steps\n", branch_id, steps.len()));
             }
         }
@@ -1034,37 +1132,9 @@ impl PlannerTool {
     }
 
     fn get_client_for_model(&self, model: &str) -> Result<Arc<dyn LLMClient>> {
-        if self.model_resolver.is_openrouter_model(model) {
-            if self.config.openrouter_api_key.is_none() {
-                anyhow::bail!("OpenRouter API key not configured");
-            }
-
-            if let Some((_, client)) = self.openrouter_clients.iter().find(|(m, _)| m == model) {
-                Ok(client.clone())
-            } else {
-                let api_key = self
-                    .config
-                    .openrouter_api_key
-                    .as_ref()
-                    .ok_or_else(|| anyhow::anyhow!("OpenRouter API key not available"))?;
-                let new_client = OpenRouterClient::new(
-                    api_key.clone(),
-                    model.to_string(),
-                    self.config.openrouter_base_url.clone(),
-                )?;
-                Ok(Arc::new(new_client) as Arc<dyn LLMClient>)
-            }
-        } else {
-            if let Some(api_key) = &self.config.openai_api_key {
-                let new_client = OpenAIClient::new(
-                    api_key.clone(),
-                    model.to_string(),
-                    self.config.openai_base_url.clone(),
-                )?;
-                Ok(Arc::new(new_client) as Arc<dyn LLMClient>)
-            } else {
-                anyhow::bail!("OpenAI API key not configured");
-            }
+        if let Some((_, client)) = self.openrouter_clients.iter().find(|(m, _)| m == model) {
+            return Ok(client.clone());
         }
+        self.config.client_for(model)
     }
 }
@@ -111,15 +111,18 @@ impl ReasoningFormatter {
             ));
         }
 
-        // Perplexity (inverse for display - lower is better)
-        if let Some(perplexity) = response.metrics.perplexity {
-            let perplexity_score = (100.0 - perplexity.min(100.0)) / 100.0;
-            let perplexity_bar = Self::create_mini_bar(perplexity_score);
-            output.push_str(&format!(
-                "│ Clarity:      {} {:.0}% │\n",
-                perplexity_bar,
-                perplexity_score * 100.0
-            ));
+        // Clarity is the inverse of token entropy; models without logprobs have none
+        match response.metrics.attention_entropy {
+            Some(entropy) => {
+                let clarity_score = 1.0 - entropy;
+                let clarity_bar = Self::create_mini_bar(clarity_score);
+                output.push_str(&format!(
+                    "│ Clarity:      {} {:.0}% │\n",
+                    clarity_bar,
+                    clarity_score * 100.0
+                ));
+            }
+            None => output.push_str("│ Clarity:      unavailable    │\n"),
         }
 
         // Confidence
@@ -3,7 +3,7 @@ use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info};
 
 use crate::llm::{
     client::{ChatMessage, LLMClient},
@@ -112,12 +112,22 @@ struct ExternalSessionState {
 /// Tool for managing sequential thinking with external LLM
 pub struct SequentialThinkingExternalTool {
     sessions: Arc<Mutex<HashMap<String, ExternalSessionState>>>,
+    config: LLMConfig,
 }
 
 impl SequentialThinkingExternalTool {
-    pub fn new() -> Self {
+    pub fn new(config: LLMConfig) -> Self {
         Self {
             sessions: Arc::new(Mutex::new(HashMap::new())),
+            config,
+        }
+    }
+
+    /// Build a tool with a new configuration that shares this tool's sessions
+    pub fn reconfigured(&self, config: LLMConfig) -> Self {
+        Self {
+            sessions: self.sessions.clone(),
+            ..Self::new(config)
         }
     }
 
@@ -152,6 +162,7 @@ impl SequentialThinkingExternalTool {
         // Generate thought content using LLM if requested
         let (thought_content, model_used, confidence) = if request.use_llm {
             Self::generate_thought_with_llm(
+                &self.config,
                 &request,
                 &history_clone,
                 original_query_clone.as_deref(),
@@ -263,22 +274,11 @@ impl SequentialThinkingExternalTool {
     }
 
     async fn generate_thought_with_llm(
+        config: &LLMConfig,
         request: &SequentialThinkingExternalRequest,
         history: &[ExternalThoughtData],
         original_query: Option<&str>,
     ) -> Result<(String, Option<String>, Option<f32>)> {
-        // Get LLM configuration
-        let config = match LLMConfig::from_env() {
-            Ok(c) => {
-                info!("Loaded LLM config successfully");
-                c
-            }
-            Err(e) => {
-                warn!("Failed to load LLM config: {}. Using defaults.", e);
-                LLMConfig::default()
-            }
-        };
-
         // Determine which model to use with fallback chain
         let model_name = request
             .model
@@ -298,7 +298,7 @@ impl SequentialThinkingExternalTool {
         info!("Creating LLM client for resolved model: {}", resolved_model);
         let client: Box<dyn LLMClient> = if model_resolver.is_openrouter_model(&resolved_model) {
             info!("Model is OpenRouter model, checking API key...");
-            let api_key = config.openrouter_api_key.ok_or_else(|| {
+            let api_key = config.openrouter_api_key.clone().ok_or_else(|| {
                 anyhow::anyhow!(
                     "OpenRouter API key not configured for model: {}",
                     resolved_model
@@ -306,12 +306,16 @@ impl SequentialThinkingExternalTool {
             })?;
             info!("Creating OpenRouter client...");
             Box::new(
-                OpenRouterClient::new(api_key, resolved_model.clone(), config.openrouter_base_url)
-                    .map_err(|e| anyhow::anyhow!("Failed to create OpenRouter client: {}", e))?,
+                OpenRouterClient::new(
+                    api_key,
+                    resolved_model.clone(),
+                    config.openrouter_base_url.clone(),
+                )
+                .map_err(|e| anyhow::anyhow!("Failed to create OpenRouter client: {}", e))?,
             )
         } else {
             info!("Model is OpenAI model, checking API key...");
-            let api_key = config.openai_api_key.ok_or_else(|| {
+            let api_key = config.openai_api_key.clone().ok_or_else(|| {
                 anyhow::anyhow!(
                     "OpenAI API key not configured for model: {}",
                     resolved_model
@@ -319,7 +323,7 @@ impl SequentialThinkingExternalTool {
             })?;
             info!("API key found, creating OpenAI client...");
             Box::new(
-                OpenAIClient::new(api_key, resolved_model.clone(), config.openai_base_url)
+                OpenAIClient::with_config(api_key, resolved_model.clone(), config)
                     .map_err(|e| anyhow::anyhow!("Failed to create OpenAI client: {}", e))?,
             )
         };
@@ -513,9 +517,3 @@ impl SequentialThinkingExternalTool {
         Ok(())
     }
 }
-
-impl Default for SequentialThinkingExternalTool {
-    fn default() -> Self {
-        Self::new()
-    }
-}
@@ -1,8 +1,17 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::env;
-use std::path::Path;
-use tracing::info;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::llm::model_aliases::ModelResolver;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SetupConfigRequest {
@@ -21,6 +30,10 @@ pub struct SetupConfigRequest {
     /// Custom model preferences
     #[serde(default)]
     pub custom_models: Option<CustomModels>,
+
+    /// Validate the keys and models and write `.env` directly instead of returning instructions
+    #[serde(default)]
+    pub apply: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +51,127 @@ pub struct SetupConfigResponse {
     pub env_file_path: String,
     pub current_config: ConfigStatus,
     pub next_steps: Vec<String>,
+    /// Present in `apply` mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validation: Option<ValidationReport>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValidationReport {
+    pub keys: Vec<KeyValidation>,
+    pub models: Vec<ModelValidation>,
+    /// Whether `.env` was written
+    pub written: bool,
+    pub backup_path: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KeyValidation {
+    pub provider: Provider,
+    pub configured: bool,
+    pub valid: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModelValidation {
+    /// Environment variable the model is configured by
+    pub setting: String,
+    pub model: String,
+    pub provider: Provider,
+    pub accessible: bool,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Provider {
+    OpenAI,
+    OpenRouter,
+}
+
+impl Provider {
+    pub fn key_var(self) -> &'static str {
+        match self {
+            Provider::OpenAI => "OPENAI_API_KEY",
+            Provider::OpenRouter => "OPENROUTER_API_KEY",
+        }
+    }
+}
+
+/// Cheap provider calls used to check keys and model access
+#[async_trait]
+pub trait ProviderProbe: Send + Sync {
+    /// IDs of the models the key can use; fails when the key is rejected
+    async fn list_models(&self, provider: Provider, api_key: &str) -> Result<Vec<String>>;
+}
+
+/// Probes the real provider APIs. Base URLs follow `OPENAI_BASE_URL` and
+/// `OPENROUTER_BASE_URL`, so a local mock server can stand in for them.
+pub struct HttpProbe {
+    client: Client,
+    openai_base_url: String,
+    openrouter_base_url: String,
+}
+
+impl HttpProbe {
+    pub fn from_env() -> Result<Self> {
+        Ok(Self {
+            client: Client::builder()
+                .timeout(Duration::from_secs(15))
+                .build()
+                .context("Failed to build HTTP client")?,
+            openai_base_url: env::var("OPENAI_BASE_URL")
+                .unwrap_or_else(|_| "https://api.openai.com/v1".to_string()),
+            openrouter_base_url: env::var("OPENROUTER_BASE_URL")
+                .unwrap_or_else(|_| "https://openrouter.ai/api/v1".to_string()),
+        })
+    }
+
+    async fn get_json(&self, url: &str, api_key: &str) -> Result<serde_json::Value> {
+        let response = self
+            .client
+            .get(url)
+            .bearer_auth(api_key)
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach {}", url))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!("{} returned {}: {}", url, status, body.trim());
+        }
+        response.json().await.context("Invalid JSON response")
+    }
+}
+
+#[async_trait]
+impl ProviderProbe for HttpProbe {
+    async fn list_models(&self, provider: Provider, api_key: &str) -> Result<Vec<String>> {
+        let base_url = match provider {
+            Provider::OpenAI => &self.openai_base_url,
+            Provider::OpenRouter => {
+                // OpenRouter lists models without authentication, so check the key separately
+                self.get_json(&format!("{}/key", self.openrouter_base_url), api_key)
+                    .await?;
+                &self.openrouter_base_url
+            }
+        };
+
+        let body = self
+            .get_json(&format!("{}/models", base_url), api_key)
+            .await?;
+        Ok(body["data"]
+            .as_array()
+            .map(|models| {
+                models
+                    .iter()
+                    .filter_map(|m| m["id"].as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -59,19 +193,28 @@ pub struct ModelConfig {
     pub grok: String,
 }
 
-pub struct SetupConfigTool;
+pub struct SetupConfigTool {
+    probe: Arc<dyn ProviderProbe>,
+    env_path: PathBuf,
+}
 
 impl SetupConfigTool {
-    pub fn new() -> Self {
-        Self
+    pub fn new() -> Result<Self> {
+        Ok(Self::with_probe(
+            Arc::new(HttpProbe::from_env()?),
+            PathBuf::from(".env"),
+        ))
+    }
+
+    pub fn with_probe(probe: Arc<dyn ProviderProbe>, env_path: PathBuf) -> Self {
+        Self { probe, env_path }
     }
 
     pub async fn setup_config(&self, request: SetupConfigRequest) -> Result<SetupConfigResponse> {
         info!("Setup config request received");
 
         // Check current configuration status
-        let env_path = Path::new(".env");
-        let env_file_exists = env_path.exists();
+        let env_file_exists = self.env_path.exists();
 
         // Check current environment variables
         let openai_configured =
@@ -119,6 +262,23 @@ impl SetupConfigTool {
             .and_then(|m| m.mini_model.as_deref())
             .unwrap_or(mini_model);
 
+        let current_config = ConfigStatus {
+            env_file_exists,
+            openai_configured,
+            openrouter_configured,
+            models_configured,
+            current_models,
+        };
+
+        if request.apply {
+            let models = [
+                ("LUX_MODEL_REASONING", reasoning_model),
+                ("LUX_MODEL_NORMAL", normal_model),
+                ("LUX_MODEL_MINI", mini_model),
+            ];
+            return self.apply(&request, models, current_config).await;
+        }
+
         // Generate .env template
         let env_template = self.generate_env_template(
             request.openai_api_key.as_deref(),
@@ -208,15 +368,121 @@ impl SetupConfigTool {
             .to_string(),
             instructions_for_host_llm,
             env_template,
-            env_file_path: "./.env".to_string(),
-            current_config: ConfigStatus {
-                env_file_exists,
-                openai_configured,
-                openrouter_configured,
-                models_configured,
-                current_models,
-            },
+            env_file_path: self.env_path.display().to_string(),
+            current_config,
             next_steps,
+            validation: None,
+        })
+    }
+
+    /// Validate keys and models with the providers, then write `.env` if every
+    /// configured key was accepted
+    async fn apply(
+        &self,
+        request: &SetupConfigRequest,
+        models: [(&str, &str); 3],
+        current_config: ConfigStatus,
+    ) -> Result<SetupConfigResponse> {
+        let openai_key = provided_key(request.openai_api_key.as_deref());
+        let openrouter_key = provided_key(request.openrouter_api_key.as_deref());
+
+        let current = &current_config.current_models;
+        let all_models: Vec<(&str, &str)> = models
+            .into_iter()
+            .chain([
+                ("LUX_MODEL_OPUS", current.opus.as_str()),
+                ("LUX_MODEL_SONNET", current.sonnet.as_str()),
+                ("LUX_MODEL_GROK", current.grok.as_str()),
+            ])
+            .collect();
+        let (keys, model_checks) = validate_providers(
+            self.probe.as_ref(),
+            [
+                (Provider::OpenAI, openai_key),
+                (Provider::OpenRouter, openrouter_key),
+            ],
+            &all_models,
+        )
+        .await;
+
+        let any_valid = keys.iter().any(|k| k.valid);
+        let any_rejected = keys.iter().any(|k| k.configured && !k.valid);
+        let (written, backup_path) = if any_valid && !any_rejected {
+            let mut updates: Vec<(&str, &str)> = Vec::new();
+            if let Some(key) = openai_key {
+                updates.push(("OPENAI_API_KEY", key));
+            }
+            if let Some(key) = openrouter_key {
+                updates.push(("OPENROUTER_API_KEY", key));
+            }
+            updates.extend(models);
+
+            let contents = if self.env_path.exists() {
+                let existing = fs::read_to_string(&self.env_path)
+                    .with_context(|| format!("Failed to read {}", self.env_path.display()))?;
+                merge_env(&existing, &updates)
+            } else {
+                // Empty values rather than placeholders, which would count as configured keys
+                self.generate_env_template(
+                    Some(openai_key.unwrap_or_default()),
+                    Some(openrouter_key.unwrap_or_default()),
+                    models[0].1,
+                    models[1].1,
+                    models[2].1,
+                )
+            };
+            let backup = write_env_atomically(&self.env_path, &contents)?;
+            info!("Wrote {}", self.env_path.display());
+            (true, backup)
+        } else {
+            (false, None)
+        };
+
+        let mut next_steps = Vec::new();
+        for key in keys.iter().filter(|k| k.configured && !k.valid) {
+            next_steps.push(format!(
+                "❌ {} was rejected; .env was not changed",
+                key.provider.key_var()
+            ));
+        }
+        if !any_valid && !any_rejected {
+            next_steps.push("⚠️ Provide at least one API key".to_string());
+        }
+        for model in model_checks.iter().filter(|m| !m.accessible) {
+            next_steps.push(format!(
+                "⚠️ {}={} is not accessible: {}",
+                model.setting,
+                model.model,
+                model.reason.as_deref().unwrap_or("unknown")
+            ));
+        }
+        if written {
+            next_steps
+                .push("✅ .env written; the running server reloads it automatically".to_string());
+        }
+
+        let status = if !written {
+            "validation_failed"
+        } else if model_checks.iter().any(|m| !m.accessible) {
+            "applied_with_warnings"
+        } else {
+            "applied"
+        };
+
+        Ok(SetupConfigResponse {
+            status: status.to_string(),
+            instructions_for_host_llm: Vec::new(),
+            // Not echoed back in apply mode since it contains the keys
+            env_template: String::new(),
+            env_file_path: self.env_path.display().to_string(),
+            current_config,
+            next_steps,
+            validation: Some(ValidationReport {
+                keys,
+                models: model_checks,
+                written,
+                backup_path: backup_path.map(|p| p.display().to_string()),
+            }),
         })
     }
 
@@ -298,3 +564,254 @@ RUST_LOG="info"
         )
     }
 }
+
+/// A key passed by the user, ignoring blanks and template placeholders
+fn provided_key(key: Option<&str>) -> Option<&str> {
+    key.map(str::trim)
+        .filter(|k| !k.is_empty() && !k.starts_with("YOUR_"))
+}
+
+/// Check each key with a provider probe, then whether each `(setting, model)` is
+/// reachable with the accepted keys. Keys passed as `None` are read from the environment.
+pub async fn validate_providers(
+    probe: &dyn ProviderProbe,
+    keys_to_check: [(Provider, Option<&str>); 2],
+    models: &[(&str, &str)],
+) -> (Vec<KeyValidation>, Vec<ModelValidation>) {
+    let mut keys = Vec::new();
+    let mut reachable: Vec<(Provider, HashSet<String>)> = Vec::new();
+    for (provider, key) in keys_to_check {
+        // Keys not passed in are checked from the current environment
+        let env_key = env::var(provider.key_var()).ok();
+        let Some(key) = key.or_else(|| provided_key(env_key.as_deref())) else {
+            keys.push(KeyValidation {
+                provider,
+                configured: false,
+                valid: false,
+                error: None,
+            });
+            continue;
+        };
+
+        match probe.list_models(provider, key).await {
+            Ok(ids) => {
+                reachable.push((provider, ids.into_iter().collect()));
+                keys.push(KeyValidation {
+                    provider,
+                    configured: true,
+                    valid: true,
+                    error: None,
+                });
+            }
+            Err(e) => {
+                warn!("{} rejected by provider: {:#}", provider.key_var(), e);
+                keys.push(KeyValidation {
+                    provider,
+                    configured: true,
+                    valid: false,
+                    error: Some(format!("{:#}", e)),
+                });
+            }
+        }
+    }
+
+    let resolver = ModelResolver::new();
+    let model_checks = models
+        .iter()
+        .map(|(setting, model)| check_model(&resolver, &reachable, setting, model))
+        .collect();
+    (keys, model_checks)
+}
+
+fn check_model(
+    resolver: &ModelResolver,
+    reachable: &[(Provider, HashSet<String>)],
+    setting: &str,
+    model: &str,
+) -> ModelValidation {
+    let resolved = resolver.resolve(model);
+    let provider = if resolver.is_openrouter_model(&resolved) {
+        Provider::OpenRouter
+    } else {
+        Provider::OpenAI
+    };
+
+    let reason = if provider == Provider::OpenAI && resolver.is_blocked_model(&resolved) {
+        Some("blocked by the model policy".to_string())
+    } else {
+        match reachable.iter().find(|(p, _)| *p == provider) {
+            None => Some(format!("{} is missing or was rejected", provider.key_var())),
+            Some((_, ids)) if !ids.contains(&resolved) => {
+                Some("not in the provider's model list".to_string())
+            }
+            Some(_) => None,
+        }
+    };
+
+    ModelValidation {
+        setting: setting.to_string(),
+        model: resolved,
+        provider,
+        accessible: reason.is_none(),
+        reason,
+    }
+}
+
+/// Replace `KEY=` lines in an existing `.env`, appending keys it doesn't have
+fn merge_env(existing: &str, updates: &[(&str, &str)]) -> String {
+    let mut seen = HashSet::new();
+    let mut lines: Vec<String> = existing
+        .lines()
+        .map(|line| {
+            let key = line
+                .trim_start()
+                .trim_start_matches("export ")
+                .split('=')
+                .next()
+                .unwrap_or_default()
+                .trim();
+            match updates.iter().find(|(k, _)| *k == key) {
+                Some((k, v)) if line.contains('=') && !line.trim_start().starts_with('#') => {
+                    seen.insert(*k);
+                    format!("{}=\"{}\"", k, v)
+                }
+                _ => line.to_string(),
+            }
+        })
+        .collect();
+
+    let missing: Vec<_> = updates.iter().filter(|(k, _)| !seen.contains(k)).collect();
+    if !missing.is_empty() {
+        lines.push(String::new());
+        lines.push("# Added by setup_config".to_string());
+        lines.extend(missing.iter().map(|(k, v)| format!("{}=\"{}\"", k, v)));
+    }
+
+    let mut merged = lines.join("\n");
+    merged.push('\n');
+    merged
+}
+
+/// Write via a temp file and rename so readers never see a partial `.env`.
+/// An existing file is first copied to `<name>.bak`, whose path is returned.
+fn write_env_atomically(path: &Path, contents: &str) -> Result<Option<PathBuf>> {
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or(".env");
+    let tmp_path = path.with_file_name(format!("{}.tmp", file_name));
+
+    {
+        let mut file = fs::File::create(&tmp_path)
+            .with_context(|| format!("Failed to create {}", tmp_path.display()))?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&tmp_path, fs::Permissions::from_mode(0o600))?;
+    }
+
+    let backup = if path.exists() {
+        let backup_path = path.with_file_name(format!("{}.bak", file_name));
+        fs::copy(path, &backup_path)
+            .with_context(|| format!("Failed to back up {}", path.display()))?;
+        Some(backup_path)
+    } else {
+        None
+    };
+
+    fs::rename(&tmp_path, path).with_context(|| format!("Failed to replace {}", path.display()))?;
+    Ok(backup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MockProbe;
+
+    #[async_trait]
+    impl ProviderProbe for MockProbe {
+        async fn list_models(&self, provider: Provider, api_key: &str) -> Result<Vec<String>> {
+            if api_key.starts_with("bad") {
+                anyhow::bail!("401 Unauthorized");
+            }
+            Ok(match provider {
+                Provider::OpenAI => vec!["gpt-5".to_string(), "gpt-5-mini".to_string()],
+                Provider::OpenRouter => vec!["anthropic/claude-4-sonnet".to_string()],
+            })
+        }
+    }
+
+    fn request(openai: &str, openrouter: &str) -> SetupConfigRequest {
+        SetupConfigRequest {
+            openai_api_key: Some(openai.to_string()),
+            openrouter_api_key: Some(openrouter.to_string()),
+            use_advanced_models: true,
+            custom_models: None,
+            apply: true,
+        }
+    }
+
+    /// An `.env` path in a directory removed when the guard drops
+    fn temp_env_path() -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".env");
+        (dir, path)
+    }
+
+    #[tokio::test]
+    async fn test_apply_writes_env_with_backup() {
+        let (_dir, path) = temp_env_path();
+        fs::write(&path, "RUST_LOG=debug\nOPENAI_API_KEY=\"old\"\n").unwrap();
+        let tool = SetupConfigTool::with_probe(Arc::new(MockProbe), path.clone());
+
+        let response = tool
+            .setup_config(request("sk-good", "sk-or-good"))
+            .await
+            .unwrap();
+        let report = response.validation.unwrap();
+
+        assert!(report.written);
+        assert!(report.keys.iter().all(|k| k.valid));
+        let sonnet = report
+            .models
+            .iter()
+            .find(|m| m.setting == "LUX_MODEL_SONNET")
+            .unwrap();
+        assert!(sonnet.accessible);
+        let opus = report
+            .models
+            .iter()
+            .find(|m| m.setting == "LUX_MODEL_OPUS")
+            .unwrap();
+        assert!(!opus.accessible);
+        assert_eq!(response.status, "applied_with_warnings");
+
+        let written = fs::read_to_string(&path).unwrap();
+        assert!(written.contains("RUST_LOG=debug"));
+        assert!(written.contains("OPENAI_API_KEY=\"sk-good\""));
+        assert!(written.contains("LUX_MODEL_NORMAL=\"gpt-5\""));
+        let backup = fs::read_to_string(report.backup_path.unwrap()).unwrap();
+        assert!(backup.contains("OPENAI_API_KEY=\"old\""));
+    }
+
+    #[tokio::test]
+    async fn test_apply_rejected_key_leaves_env_untouched() {
+        let (_dir, path) = temp_env_path();
+        let tool = SetupConfigTool::with_probe(Arc::new(MockProbe), path.clone());
+
+        let response = tool
+            .setup_config(request("sk-good", "bad-key"))
+            .await
+            .unwrap();
+        let report = response.validation.unwrap();
+
+        assert_eq!(response.status, "validation_failed");
+        assert!(!report.written);
+        assert!(report
+            .keys
+            .iter()
+            .any(|k| k.provider == Provider::OpenRouter && k.error.is_some()));
+        assert!(!path.exists());
+    }
+}
@@ -2,6 +2,7 @@
 mod tests {
     use super::*;
     use crate::llm::config::LLMConfig;
+    use crate::settings::LuxSettings;
     
     #[test]
     fn test_config_backward_compatibility() {
@@ -10,7 +11,7 @@ mod tests {
         std::env::set_var("LUX_MODEL_NORMAL", "test-normal");
         std::env::set_var("LUX_MODEL_MINI", "test-mini");
         
-        let config = LLMConfig::from_env().unwrap();
+        let config = LLMConfig::from_settings(&LuxSettings::default()).unwrap();
         assert_eq!(config.model_reasoning, "test-reasoning");
         assert_eq!(config.model_normal, "test-normal");
         assert_eq!(config.model_mini, "test-mini");
@@ -25,7 +26,7 @@ mod tests {
         std::env::set_var("LUX_DEFAULT_CHAT_MODEL", "old-chat");
         std::env::set_var("LUX_DEFAULT_BIAS_CHECKER_MODEL", "old-bias");
         
-        let config = LLMConfig::from_env().unwrap();
+        let config = LLMConfig::from_settings(&LuxSettings::default()).unwrap();
         assert_eq!(config.model_reasoning, "old-reasoning");
         assert_eq!(config.model_normal, "old-chat");
         assert_eq!(config.model_mini, "old-bias");
@@ -46,7 +47,7 @@ mod tests {
         std::env::remove_var("LUX_DEFAULT_CHAT_MODEL");
         std::env::remove_var("LUX_DEFAULT_BIAS_CHECKER_MODEL");
         
-        let config = LLMConfig::from_env().unwrap();
+        let config = LLMConfig::from_settings(&LuxSettings::default()).unwrap();
         assert_eq!(config.model_reasoning, "gpt-5");
         assert_eq!(config.model_normal, "gpt-5");
         assert_eq!(config.model_mini, "gpt-5-mini");
@@ -58,7 +59,7 @@ mod tests {
         std::env::set_var("LUX_MODEL_REASONING", "new-reasoning");
         std::env::set_var("LUX_DEFAULT_REASONING_MODEL", "old-reasoning");
         
-        let config = LLMConfig::from_env().unwrap();
+        let config = LLMConfig::from_settings(&LuxSettings::default()).unwrap();
         // New variables should take precedence
         assert_eq!(config.model_reasoning, "new-reasoning");
         
@@ -1,18 +1,26 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
 use std::fs;
 use std::path::Path;
 use std::sync::{Arc, Mutex as StdMutex};
+use tokio::sync::Mutex;
 use tracing::{debug, error, info, warn};
 
 use crate::llm::{
-    client::{ChatMessage, LLMClient},
+    audit,
+    client::{ChatMessage, CompletionOptions, LLMClient, LLMResponse, LogprobStats, TokenLogprob},
     config::LLMConfig,
     model_aliases::ModelResolver,
-    openai::OpenAIClient,
     openrouter::OpenRouterClient,
-    Role,
+    ReasoningEffort, Role, Verbosity,
+};
+use crate::metachain::{Critique, MetachainEngine};
+use crate::monitoring::contradiction::{find_contradiction, Contradiction, ContradictionKind};
+use crate::monitoring::grounding::GroundingSources;
+use crate::monitoring::policy::{PolicyAction, PolicyDecision};
+use crate::monitoring::subgoals::{CoverageMap, SubgoalTracker};
+use crate::monitoring::{
+    MetacognitiveMonitor, MonitoringConfig, MonitoringOverrides, MonitoringSignals,
 };
 use crate::session::SessionManager;
 use lux_synthesis::{
@@ -44,8 +52,9 @@ pub struct TracedReasoningRequest {
     pub continuation_id: Option<String>, // Thread continuation support
     #[serde(default)]
     pub model: Option<String>,
-    #[serde(default = "default_temperature")]
-    pub temperature: f32,
+    /// Falls back to the tool's lux.toml temperature, then 0.7
+    #[serde(default)]
+    pub temperature: Option<f32>,
     #[serde(default)]
     pub guardrails: GuardrailConfig,
 
@@ -56,6 +65,30 @@ pub struct TracedReasoningRequest {
     /// Whether to include file contents (default: true)
     #[serde(default = "default_true")]
     pub include_file_contents: bool,
+
+    /// Reasoning effort for reasoning models (ignored by models without support)
+    #[serde(default)]
+    pub reasoning_effort: Option<ReasoningEffort>,
+
+    /// Answer verbosity for GPT-5 (ignored by other models)
+    #[serde(default)]
+    pub verbosity: Option<Verbosity>,
+
+    /// Monitoring preset or thresholds for this call only
+    #[serde(default)]
+    pub monitoring: Option<MonitoringOverrides>,
+
+    /// Ask the illumination step for a critique whenever an intervention fires
+    #[serde(default)]
+    pub illuminate_interventions: bool,
+
+    /// Requirements to track on thought 1, instead of those found in the query
+    #[serde(default)]
+    pub subgoals: Option<Vec<String>>,
+
+    /// Domain lexicons for concept matching, kept for the rest of the session
+    #[serde(default)]
+    pub lexicons: Option<Vec<String>>,
 }
 
 fn default_temperature() -> f32 {
@@ -88,6 +121,9 @@ pub struct GuardrailConfig {
     pub consistency_validation: bool,
     #[serde(default = "default_true")]
     pub attention_entropy_analysis: bool,
+    /// Hold back a conclusion while critical subgoals of the query are open
+    #[serde(default = "default_true")]
+    pub subgoal_coverage: bool,
 }
 
 fn default_true() -> bool {
@@ -97,7 +133,7 @@ fn default_semantic_drift_threshold() -> f32 {
     0.3
 }
 fn default_perplexity_threshold() -> f32 {
-    50.0
+    10.0
 }
 
 impl Default for GuardrailConfig {
@@ -106,10 +142,11 @@ impl Default for GuardrailConfig {
             semantic_drift_check: true,
             semantic_drift_threshold: 0.3,
             perplexity_monitoring: true,
-            perplexity_threshold: 50.0,
+            perplexity_threshold: 10.0,
             circular_reasoning_detection: true,
             consistency_validation: true,
             attention_entropy_analysis: true,
+            subgoal_coverage: true,
         }
     }
 }
@@ -141,6 +178,13 @@ pub struct TracedReasoningResponse {
     pub model_used: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub synthesis_snapshot: Option<SynthesisSnapshot>,
+    /// Critique of the thought, when an intervention fired and
+    /// `illuminate_interventions` was set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub illumination: Option<Critique>,
+    /// Which subgoals of the query the thoughts addressed so far
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<CoverageMap>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -191,9 +235,24 @@ impl std::fmt::Display for StepType {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct StepMetrics {
     pub semantic_similarity: Option<f32>,
+    /// Token perplexity of the generated step, from provider logprobs
     pub perplexity: Option<f32>,
+    /// Mean normalized entropy of the per-token candidate distribution (0-1)
     pub attention_entropy: Option<f32>,
     pub consistency_score: Option<f32>,
+    /// Where `perplexity` and `attention_entropy` came from
+    #[serde(default)]
+    pub token_metrics: TokenMetricsSource,
+}
+
+/// `Unavailable` when the model doesn't return logprobs (reasoning models, some
+/// OpenRouter providers) or the step wasn't generated by a model
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenMetricsSource {
+    Logprobs,
+    #[default]
+    Unavailable,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -203,6 +262,9 @@ pub struct ReasoningMetrics {
     pub semantic_coherence: f32,
     pub reasoning_quality: f32,
     pub path_consensus: Option<f32>,
+    /// Confidence in the final answer: reasoning quality less a penalty per intervention
+    #[serde(default)]
+    pub final_confidence: f32,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -211,6 +273,12 @@ pub struct Intervention {
     pub intervention_type: InterventionType,
     pub description: String,
     pub severity: Severity,
+    /// Earlier step the intervention refers to, e.g. the step a thought contradicts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub related_step: Option<u32>,
+    /// Monitoring rule behind the intervention, with the action it asks for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<PolicyDecision>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -222,6 +290,9 @@ pub enum InterventionType {
     InconsistentLogic,
     AttentionScatter,
     HallucinationRisk,
+    Contradiction,
+    /// Raised by a monitoring rule alone
+    Policy,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -247,33 +318,43 @@ pub struct ThoughtData {
 
 pub struct TracedReasoningTool {
     session_manager: Arc<SessionManager>,
-    openai_client: Option<Arc<dyn LLMClient>>,
     openrouter_clients: Vec<(String, Arc<dyn LLMClient>)>,
     model_resolver: ModelResolver,
     config: LLMConfig,
+    /// Critiques thoughts that raised an intervention
+    metachain: MetachainEngine,
+    /// Held for the whole of a call, and kept by the tool a config reload rebuilds
+    chain: Arc<Mutex<ChainState>>,
+}
+
+/// The reasoning chain in progress
+struct ChainState {
     thought_history: Vec<ThoughtData>,
     interventions: Vec<Intervention>,
     branches: std::collections::HashMap<String, Vec<ThoughtData>>,
     original_query: Option<String>,
+    /// Model a `switch_model` rule chose for the rest of the session
+    model_override: Option<String>,
     synthesis: Arc<StdMutex<EvolvingSynthesis>>,
     synthesis_sink: Option<Arc<dyn SynthesisSink>>,
+    /// Conclusions held back this session for open subgoals
+    conclusion_holds: u32,
+}
+
+/// Conclusions held back for open subgoals before one is accepted anyway
+const MAX_CONCLUSION_HOLDS: u32 = 2;
+
+/// A thought as the model wrote it, before the monitor has seen it
+struct GeneratedThought {
+    content: String,
+    step_type: StepType,
+    logprobs: Option<Vec<TokenLogprob>>,
 }
 
 impl TracedReasoningTool {
     pub fn new(config: LLMConfig, session_manager: Arc<SessionManager>) -> Result<Self> {
         let model_resolver = ModelResolver::with_config(Some(config.clone()));
 
-        let openai_client = if let Some(api_key) = &config.openai_api_key {
-            let client = OpenAIClient::new(
-                api_key.clone(),
-                config.model_reasoning.clone(),
-                config.openai_base_url.clone(),
-            )?;
-            Some(Arc::new(client) as Arc<dyn LLMClient>)
-        } else {
-            None
-        };
-
         let mut openrouter_clients = Vec::new();
         if let Some(api_key) = &config.openrouter_api_key {
             let common_models = vec!["anthropic/claude-3-opus", "google/gemini-2.5-pro"];
@@ -294,24 +375,40 @@ impl TracedReasoningTool {
             "default_session",
         )));
 
+        let metachain =
+            MetachainEngine::new(&session_manager.monitoring_config(None), config.clone());
+
         Ok(Self {
             session_manager,
-            openai_client,
             openrouter_clients,
             model_resolver,
             config,
-            thought_history: Vec::new(),
-            interventions: Vec::new(),
-            branches: std::collections::HashMap::new(),
-            original_query: None,
-            synthesis,
-            synthesis_sink: None,
+            metachain,
+            chain: Arc::new(Mutex::new(ChainState {
+                thought_history: Vec::new(),
+                interventions: Vec::new(),
+                branches: std::collections::HashMap::new(),
+                original_query: None,
+                model_override: None,
+                synthesis,
+                synthesis_sink: None,
+                conclusion_holds: 0,
+            })),
         })
     }
 
+    /// Build a tool with a new configuration that continues this tool's reasoning chain
+    /// and critiques with the given thresholds
+    pub fn reconfigured(&self, config: LLMConfig, monitoring: &MonitoringConfig) -> Result<Self> {
+        let mut fresh = Self::new(config.clone(), self.session_manager.clone())?;
+        fresh.chain = self.chain.clone();
+        fresh.metachain = MetachainEngine::new(monitoring, config);
+        Ok(fresh)
+    }
+
     /// Set synthesis sink for database persistence
-    pub fn set_synthesis_sink(&mut self, sink: Arc<dyn SynthesisSink>) {
-        self.synthesis_sink = Some(sink);
+    pub async fn set_synthesis_sink(&self, sink: Arc<dyn SynthesisSink>) {
+        self.chain.lock().await.synthesis_sink = Some(sink);
     }
 
     /// Read files and return their contents
@@ -345,22 +442,31 @@ impl TracedReasoningTool {
     }
 
     pub async fn process_thought(
-        &mut self,
+        &self,
         request: TracedReasoningRequest,
     ) -> Result<TracedReasoningResponse> {
+        let mut chain = self.chain.lock().await;
+        let state = &mut *chain;
         let session_id = self
             .session_manager
             .get_or_create_session(request.session_id.clone());
+        audit::set_session(session_id.clone());
         let monitor = self.session_manager.get_monitor(&session_id)?;
+        if let Some(lexicons) = &request.lexicons {
+            monitor.lock().set_lexicons(lexicons.clone())?;
+        }
+        monitor
+            .lock()
+            .apply_overrides(request.monitoring.as_ref())?;
 
         // Create synthesis for this session
-        self.synthesis = Arc::new(StdMutex::new(EvolvingSynthesis::new_in_memory(
+        state.synthesis = Arc::new(StdMutex::new(EvolvingSynthesis::new_in_memory(
             "traced_reasoning",
             &session_id,
         )));
 
         // Connect to sink if available (for future database persistence)
-        if let Some(_sink) = &self.synthesis_sink {
+        if let Some(_sink) = &state.synthesis_sink {
             // TODO: Add constructor that accepts a custom sink
             // For now, we'll use in-memory synthesis
         }
@@ -374,10 +480,15 @@ impl TracedReasoningTool {
             anyhow::bail!("total_thoughts must be at least 1");
         }
 
-        // Get model for reasoning
+        // Get model for reasoning; a rule's switch lasts until the next session
+        if request.thought_number == 1 {
+            state.model_override = None;
+        }
         let mut model = request
             .model
             .as_ref()
+            .or(state.model_override.as_ref())
+            .or(self.config.tool("traced_reasoning").model.as_ref())
             .map(|m| self.model_resolver.resolve(m))
             .unwrap_or_else(|| self.config.model_reasoning.clone());
 
@@ -404,18 +515,19 @@ impl TracedReasoningTool {
             info!("🔮 Metacognitive analysis initiated...");
         }
 
-        // Read any provided files if requested
-        let file_context = if request.include_file_contents && request.file_paths.is_some() {
-            let files = self.read_files(request.file_paths.as_ref().unwrap());
-            if !files.is_empty() {
-                let mut context = String::from("\n\n=== File Context ===\n");
-                for (path, content) in files {
-                    context.push_str(&format!("\nFile: {}\n{}\n", path, content));
-                }
-                Some(context)
-            } else {
-                None
+        // Read any provided files; thoughts are checked against them even when
+        // their contents aren't added to the prompt
+        let files = request
+            .file_paths
+            .as_deref()
+            .map(|paths| self.read_files(paths))
+            .unwrap_or_default();
+        let file_context = if request.include_file_contents && !files.is_empty() {
+            let mut context = String::from("\n\n=== File Context ===\n");
+            for (path, content) in &files {
+                context.push_str(&format!("\nFile: {}\n{}\n", path, content));
             }
+            Some(context)
         } else {
             None
         };
@@ -426,28 +538,40 @@ impl TracedReasoningTool {
             if let Some(ref context) = file_context {
                 query_with_context.push_str(context);
             }
-            self.original_query = Some(query_with_context);
+            state.original_query = Some(query_with_context);
             // Reset state for new reasoning session
-            self.thought_history.clear();
-            self.interventions.clear();
-            self.branches.clear();
+            state.thought_history.clear();
+            state.interventions.clear();
+            state.branches.clear();
+            state.conclusion_holds = 0;
 
-            // Reset monitor
+            // Reset monitor; the thoughts are checked against the query's subgoals
             let mut monitor_guard = monitor.lock();
             monitor_guard.reset_session();
+            monitor_guard.set_subgoals(match &request.subgoals {
+                Some(subgoals) => SubgoalTracker::from_list(subgoals, 1),
+                None => SubgoalTracker::from_query(&request.thought, 1),
+            });
+        }
+
+        if !files.is_empty() {
+            let prompt = state.original_query.as_deref().unwrap_or(&request.thought);
+            monitor
+                .lock()
+                .set_sources(GroundingSources::new(&files, prompt));
         }
 
         // Generate thought content using LLM
-        let (generated_content, thought_type) = if request.thought_number == 1 {
+        let (generated_content, thought_type, logprobs) = if request.thought_number == 1 {
             // For first thought, acknowledge the query and begin exploration
             let initial_response = format!(
                 "Beginning analysis of: {}\n\nLet me explore this step by step.",
                 request.thought
             );
-            (initial_response, StepType::Initial)
+            (initial_response, StepType::Initial, None)
         } else {
             // Build context from previous thoughts
-            let context = self.build_reasoning_context(&request);
+            let context = self.build_reasoning_context(state, &request);
 
             // Create prompt for LLM
             let system_prompt = self.build_system_prompt(&request.guardrails);
@@ -467,45 +591,121 @@ impl TracedReasoningTool {
 
             // Call LLM with fallback logic
             // Use more tokens for GPT-5 in traced reasoning for deeper analysis
-            let (response_content, actual_model) = self
+            let options = CompletionOptions {
+                reasoning_effort: request.reasoning_effort,
+                verbosity: request.verbosity,
+                logprobs: request.guardrails.perplexity_monitoring
+                    || request.guardrails.attention_entropy_analysis,
+                ..Default::default()
+            };
+            let response = self
                 .call_llm_with_fallback(
                     &model,
                     messages,
-                    request.temperature,
+                    request
+                        .temperature
+                        .or(self.config.tool("traced_reasoning").temperature)
+                        .unwrap_or_else(default_temperature),
                     request.thought_number,
+                    options,
                 )
                 .await?;
 
-            let (step_type, _, _) = self.parse_step_response(&response_content);
-            (response_content, step_type)
+            let (step_type, _, _) = self.parse_step_response(&response.content);
+            (response.content, step_type, response.logprobs)
+        };
+
+        let thought = GeneratedThought {
+            content: generated_content,
+            step_type: thought_type,
+            logprobs,
         };
+        self.record_thought(state, &request, &monitor, thought, &model)
+            .await
+    }
+
+    /// Run a generated thought through the monitor and the consistency
+    /// checks, add it to the chain and build the response
+    async fn record_thought(
+        &self,
+        state: &mut ChainState,
+        request: &TracedReasoningRequest,
+        monitor: &parking_lot::Mutex<MetacognitiveMonitor>,
+        thought: GeneratedThought,
+        model: &str,
+    ) -> Result<TracedReasoningResponse> {
+        let GeneratedThought {
+            content: generated_content,
+            step_type: thought_type,
+            logprobs,
+        } = thought;
+
+        // Fetch embeddings before taking the monitor lock; the monitor reads them from the cache
+        let original_query = state.original_query.clone().unwrap_or_default();
+        self.session_manager
+            .prefetch_embeddings(monitor, &[&generated_content, &original_query])
+            .await;
+
+        // Analyze the thought once: a second pass would find it in the
+        // monitor's history and compare it with itself
+        let signals = monitor
+            .lock()
+            .analyze_thought(&generated_content, request.thought_number as usize);
 
         // Calculate metrics for this thought
-        let step_metrics = self
-            .calculate_step_metrics(
-                &generated_content,
-                self.original_query.as_ref().unwrap_or(&"".to_string()),
-                &self.thought_history,
-                &request.guardrails,
-                monitor.clone(),
-            )
-            .await?;
+        let step_metrics = self.calculate_step_metrics(
+            &generated_content,
+            &original_query,
+            logprobs.as_deref(),
+            &signals,
+        );
 
         let confidence = self.calculate_step_confidence(&step_metrics);
 
         // Check for interventions
         let intervention = self.check_thought_interventions(
-            request.thought_number,
+            request,
+            &state.thought_history,
             &step_metrics,
             &generated_content,
-            &request.guardrails,
-            monitor.clone(),
+            &signals,
         );
 
         if let Some(ref interv) = intervention {
-            self.interventions.push(interv.clone());
+            state.interventions.push(interv.clone());
         }
 
+        // A critique of what went wrong, before this thought joins the history
+        let illumination = if intervention.is_some() && request.illuminate_interventions {
+            let recent: Vec<String> = state
+                .thought_history
+                .iter()
+                .map(|t| t.content.clone())
+                .collect();
+            match self
+                .metachain
+                .process_thought(
+                    &generated_content,
+                    request.thought_number as usize,
+                    Some(&signals),
+                    &recent,
+                    None,
+                )
+                .await
+            {
+                Ok(response) => Some(response.critique),
+                Err(e) => {
+                    warn!(
+                        "Illumination of thought {} failed: {}",
+                        request.thought_number, e
+                    );
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
         // Store thought data
         let thought_data = ThoughtData {
             thought_number: request.thought_number,
@@ -522,7 +722,7 @@ impl TracedReasoningTool {
         {
             use lux_synthesis::events::SynthesisEvent;
 
-            let synthesis = self.synthesis.lock().unwrap();
+            let synthesis = state.synthesis.lock().unwrap();
 
             // Update understanding
             let understanding = if request.thought_number == 1 {
@@ -587,7 +787,8 @@ impl TracedReasoningTool {
         // Handle branching
         if request.branch_from_thought.is_some() && request.branch_id.is_some() {
             let branch_id = request.branch_id.as_ref().unwrap();
-            self.branches
+            state
+                .branches
                 .entry(branch_id.clone())
                 .or_insert_with(Vec::new)
                 .push(thought_data.clone());
@@ -596,25 +797,44 @@ impl TracedReasoningTool {
         // Add to main history (revisions replace the original thought)
         if request.is_revision && request.revises_thought.is_some() {
             let revises_idx = request.revises_thought.unwrap() as usize - 1;
-            if revises_idx < self.thought_history.len() {
-                self.thought_history[revises_idx] = thought_data;
+            if revises_idx < state.thought_history.len() {
+                state.thought_history[revises_idx] = thought_data.clone();
             }
         } else {
-            self.thought_history.push(thought_data);
+            state.thought_history.push(thought_data.clone());
         }
 
         // Build response
-        let mut response = self.build_reasoning_response(
-            &request,
-            generated_content,
-            thought_type.clone(),
-            step_metrics,
-            confidence,
-            &model,
-        );
+        let mut response = self.build_reasoning_response(state, request, &thought_data, model);
+
+        // Carry out what a monitoring rule asked for
+        if let Some(decision) = intervention.as_ref().and_then(|i| i.policy.as_ref()) {
+            match decision.action {
+                PolicyAction::Warn => {}
+                PolicyAction::ForceRevision => {
+                    response.status = "intervention_needed".to_string();
+                    response.next_steps = Some(format!(
+                        "Revise thought {} (is_revision: true, revises_thought: {}) before continuing: {}",
+                        request.thought_number, request.thought_number, decision.message
+                    ));
+                }
+                PolicyAction::ForceConclusion => {
+                    response.status = "conclusion_reached".to_string();
+                    response.next_thought_needed = false;
+                }
+                PolicyAction::SwitchModel => {
+                    state.model_override = decision
+                        .model
+                        .as_deref()
+                        .map(|m| self.model_resolver.resolve(m));
+                }
+            }
+        }
 
         // Set intervention if needed
         response.intervention = intervention;
+        response.illumination = illumination;
+        response.coverage = signals.coverage;
 
         // Check if we've reached a conclusion
         let is_conclusion = thought_type == StepType::Conclusion
@@ -627,34 +847,98 @@ impl TracedReasoningTool {
                 .to_lowercase()
                 .contains("conclusion");
 
-        // Handle reasoning completion
-        if !request.next_thought_needed || is_conclusion {
-            response.reasoning_complete = Some(true);
-            response.final_answer = Some(self.extract_final_answer(&response.thought_content));
-            response.overall_metrics = Some(self.calculate_overall_metrics(&self.thought_history));
-            response.next_steps = Some(
-                "Reasoning complete. Present the final answer and reasoning chain to the user with:\n\
-                1. Clear conclusion based on the reasoning\n\
-                2. Summary of key insights from each thought\n\
-                3. Confidence assessment\n\
-                4. Any caveats or limitations identified".to_string()
+        let concluding = !response.next_thought_needed || is_conclusion;
+        self.settle_conclusion(state, request, &mut response, concluding);
+
+        Ok(response)
+    }
+
+    /// Finish the reasoning on a concluding thought. While critical subgoals
+    /// of the query are open the conclusion is held back, at most
+    /// `MAX_CONCLUSION_HOLDS` times a session; after that it is accepted and
+    /// the open subgoals are reported as a caveat.
+    fn settle_conclusion(
+        &self,
+        state: &mut ChainState,
+        request: &TracedReasoningRequest,
+        response: &mut TracedReasoningResponse,
+        concluding: bool,
+    ) {
+        if !concluding {
+            return;
+        }
+        let open_subgoals: Vec<String> = response
+            .coverage
+            .iter()
+            .flat_map(|coverage| coverage.open_critical())
+            .map(|subgoal| format!("{}. {} ({})", subgoal.id, subgoal.text, subgoal.status))
+            .collect();
+        let guarded = request.guardrails.subgoal_coverage && !open_subgoals.is_empty();
+
+        if guarded && state.conclusion_holds < MAX_CONCLUSION_HOLDS {
+            state.conclusion_holds += 1;
+            if response.status == "conclusion_reached" {
+                response.status = "thinking".to_string();
+            }
+            response.next_thought_needed = true;
+            response.total_thoughts = response.total_thoughts.max(request.thought_number + 1);
+            response.metadata.needs_more_thoughts = true;
+            let open = format!(
+                "Not ready to conclude: these subgoals of the query are still open:\n{}\n\
+                Address them in the next thoughts before concluding.",
+                open_subgoals.join("\n")
             );
+            // A rule's revision request still comes first
+            response.next_steps = Some(match response.next_steps.take() {
+                Some(steps) if response.status == "intervention_needed" => {
+                    format!("{}\n\n{}", steps, open)
+                }
+                _ => open,
+            });
+            return;
         }
 
-        Ok(response)
+        response.reasoning_complete = Some(true);
+        response.final_answer = Some(self.extract_final_answer(&response.thought_content));
+        response.overall_metrics =
+            Some(self.calculate_overall_metrics(&state.thought_history, &state.interventions));
+        let mut next_steps =
+            "Reasoning complete. Present the final answer and reasoning chain to the user with:\n\
+            1. Clear conclusion based on the reasoning\n\
+            2. Summary of key insights from each thought\n\
+            3. Confidence assessment\n\
+            4. Any caveats or limitations identified"
+                .to_string();
+        if guarded {
+            warn!(
+                "Concluding after {} holds with {} critical subgoals open",
+                state.conclusion_holds,
+                open_subgoals.len()
+            );
+            next_steps.push_str(&format!(
+                "\n\n⚠️ WARNING: the reasoning concluded with these subgoals of the query still open; \
+                name them as limitations:\n{}",
+                open_subgoals.join("\n")
+            ));
+        }
+        response.next_steps = Some(next_steps);
     }
 
-    fn build_reasoning_context(&self, request: &TracedReasoningRequest) -> String {
+    fn build_reasoning_context(
+        &self,
+        state: &ChainState,
+        request: &TracedReasoningRequest,
+    ) -> String {
         let mut context = String::new();
 
         // Add original query
-        if let Some(ref query) = self.original_query {
+        if let Some(ref query) = state.original_query {
             context.push_str(&format!("Original Query: {}\n\n", query));
         }
 
         // Add previous thoughts
         context.push_str("Previous reasoning thoughts:\n");
-        for thought in &self.thought_history {
+        for thought in &state.thought_history {
             context.push_str(&format!(
                 "Thought {}: [Type: {:?}, Confidence: {:.2}]\n{}\n\n",
                 thought.thought_number, thought.thought_type, thought.confidence, thought.content
@@ -662,9 +946,9 @@ impl TracedReasoningTool {
         }
 
         // Add intervention history if any
-        if !self.interventions.is_empty() {
+        if !state.interventions.is_empty() {
             context.push_str("\nInterventions triggered:\n");
-            for intervention in &self.interventions {
+            for intervention in &state.interventions {
                 context.push_str(&format!(
                     "- Thought {}: {:?} - {}\n",
                     intervention.step, intervention.intervention_type, intervention.description
@@ -723,35 +1007,64 @@ impl TracedReasoningTool {
 
     fn check_thought_interventions(
         &self,
-        thought_number: u32,
+        request: &TracedReasoningRequest,
+        thought_history: &[ThoughtData],
         metrics: &StepMetrics,
         thought_content: &str,
-        guardrails: &GuardrailConfig,
-        monitor: Arc<parking_lot::Mutex<crate::monitoring::MetacognitiveMonitor>>,
+        signals: &MonitoringSignals,
     ) -> Option<Intervention> {
+        let thought_number = request.thought_number;
+        let guardrails = &request.guardrails;
+
         // Check monitor signals
-        {
-            let mut monitor_guard = monitor.lock();
-            let signals = monitor_guard.analyze_thought(thought_content, thought_number as usize);
-
-            if let Some(intervention_msg) = signals.intervention {
-                let intervention_type = if signals.circular_score > 0.85 {
-                    InterventionType::CircularReasoning
-                } else if signals.distractor_alert {
-                    InterventionType::SemanticDrift
-                } else {
-                    InterventionType::InconsistentLogic
-                };
-
-                return Some(Intervention {
-                    step: thought_number,
-                    intervention_type,
-                    description: intervention_msg,
-                    severity: Severity::Medium,
-                });
+        // Configured rules outrank the built-in checks
+        if let Some(decision) = &signals.decision {
+            return Some(Intervention {
+                step: thought_number,
+                intervention_type: Self::monitor_intervention_type(
+                    signals.intervention_type.as_deref(),
+                ),
+                related_step: None,
+                description: decision.message.clone(),
+                severity: match decision.action {
+                    PolicyAction::ForceRevision | PolicyAction::ForceConclusion => Severity::High,
+                    PolicyAction::Warn | PolicyAction::SwitchModel => Severity::Medium,
+                },
+                policy: Some(decision.clone()),
+            });
+        }
+
+        // A contradiction names the exact earlier step, so it outranks the monitor's signals
+        if guardrails.consistency_validation {
+            let history = thought_history
+                .iter()
+                .filter(|t| {
+                    // A revision is meant to disagree with the thought it revises, and
+                    // a branch only answers to its own line of reasoning
+                    Some(t.thought_number) != request.revises_thought
+                        && (t.branch_id.is_none() || t.branch_id == request.branch_id)
+                })
+                .map(|t| (t.thought_number, t.content.as_str()));
+            if let Some(contradiction) =
+                find_contradiction(thought_content, thought_number, history)
+            {
+                return Some(Self::contradiction_intervention(&contradiction));
             }
         }
 
+        if let Some(intervention_msg) = signals.intervention.clone() {
+            return Some(Intervention {
+                step: thought_number,
+                intervention_type: Self::monitor_intervention_type(
+                    signals.intervention_type.as_deref(),
+                ),
+                related_step: None,
+                description: intervention_msg,
+                severity: Severity::Medium,
+                policy: None,
+            });
+        }
+
         // Semantic drift check
         if guardrails.semantic_drift_check {
             if let Some(similarity) = metrics.semantic_similarity {
@@ -759,6 +1072,8 @@ impl TracedReasoningTool {
                     return Some(Intervention {
                         step: thought_number,
                         intervention_type: InterventionType::SemanticDrift,
+                        related_step: None,
+                        policy: None,
                         description: format!(
                             "Reasoning drifting from original query (similarity: {:.2})",
                             similarity
@@ -780,8 +1095,10 @@ impl TracedReasoningTool {
                     return Some(Intervention {
                         step: thought_number,
                         intervention_type: InterventionType::HighPerplexity,
+                        related_step: None,
+                        policy: None,
                         description: format!("High perplexity detected: {:.1}", perplexity),
-                        severity: if perplexity > 80.0 {
+                        severity: if perplexity > guardrails.perplexity_threshold * 2.0 {
                             Severity::High
                         } else {
                             Severity::Medium
@@ -792,12 +1109,14 @@ impl TracedReasoningTool {
         }
 
         // Circular reasoning check
-        if guardrails.circular_reasoning_detection && self.thought_history.len() > 2 {
-            for prev_thought in self.thought_history.iter().rev().take(3) {
+        if guardrails.circular_reasoning_detection && thought_history.len() > 2 {
+            for prev_thought in thought_history.iter().rev().take(3) {
                 if self.text_similarity(thought_content, &prev_thought.content) > 0.85 {
                     return Some(Intervention {
                         step: thought_number,
                         intervention_type: InterventionType::CircularReasoning,
+                        related_step: None,
+                        policy: None,
                         description: "Potential circular reasoning detected".to_string(),
                         severity: Severity::Medium,
                     });
@@ -808,18 +1127,28 @@ impl TracedReasoningTool {
         None
     }
 
+    fn monitor_intervention_type(intervention_type: Option<&str>) -> InterventionType {
+        match intervention_type {
+            Some("circular_reasoning") => InterventionType::CircularReasoning,
+            Some("distractor_fixation") => InterventionType::SemanticDrift,
+            Some("ungrounded_reference") => InterventionType::HallucinationRisk,
+            Some("policy") => InterventionType::Policy,
+            _ => InterventionType::InconsistentLogic,
+        }
+    }
+
     fn build_reasoning_response(
         &self,
+        chain: &ChainState,
         request: &TracedReasoningRequest,
-        generated_content: String,
-        thought_type: StepType,
-        metrics: StepMetrics,
-        confidence: f32,
+        thought: &ThoughtData,
         model: &str,
     ) -> TracedReasoningResponse {
+        let metrics = thought.metrics.clone();
+        let confidence = thought.confidence;
         let metadata = TracedReasoningMetadata {
-            thought_history_length: self.thought_history.len() as u32,
-            interventions_count: self.interventions.len() as u32,
+            thought_history_length: chain.thought_history.len() as u32,
+            interventions_count: chain.interventions.len() as u32,
             semantic_coherence: metrics.semantic_similarity.unwrap_or(1.0),
             current_confidence: confidence,
             is_revision: request.is_revision,
@@ -850,8 +1179,8 @@ impl TracedReasoningTool {
             thought_number: request.thought_number,
             total_thoughts: request.total_thoughts,
             next_thought_needed: request.next_thought_needed,
-            thought_content: generated_content,
-            thought_type,
+            thought_content: thought.content.clone(),
+            thought_type: thought.thought_type.clone(),
             metrics,
             metadata,
             continuation_id: None,
@@ -862,11 +1191,13 @@ impl TracedReasoningTool {
             overall_metrics: None,
             model_used: Some(model.to_string()),
             synthesis_snapshot: None,
+            illumination: None,
+            coverage: None,
         };
 
         // Add synthesis snapshot
         {
-            let synthesis = self.synthesis.lock().unwrap();
+            let synthesis = chain.synthesis.lock().unwrap();
             let state = synthesis.snapshot();
             response.synthesis_snapshot = Some(SynthesisSnapshot {
                 current_understanding: state.current_understanding.clone(),
@@ -945,29 +1276,35 @@ impl TracedReasoningTool {
         (step_type, content.to_string(), is_final)
     }
 
-    async fn calculate_step_metrics(
+    /// Metrics of a thought, from the monitor's signals for it. Expects the
+    /// thought's embeddings to be prefetched.
+    fn calculate_step_metrics(
         &self,
         thought: &str,
-        _original_query: &str,
-        previous_thoughts: &[ThoughtData],
-        _guardrails: &GuardrailConfig,
-        monitor: Arc<parking_lot::Mutex<crate::monitoring::MetacognitiveMonitor>>,
-    ) -> Result<StepMetrics> {
-        // Use real monitor to analyze the thought
-        let mut monitor_guard = monitor.lock();
-        let thought_number = previous_thoughts.len() + 1;
-        let signals = monitor_guard.analyze_thought(thought, thought_number);
-
-        // Map MonitoringSignals to StepMetrics
-        // circular_score of 0 means no circular reasoning (good), 1 means high circular (bad)
-        // So semantic_similarity should be 1.0 - circular_score
-        let semantic_similarity = Some((1.0 - signals.circular_score) as f32);
-
-        // Monitor doesn't calculate perplexity yet, keep placeholder
-        let perplexity = Some(20.0 + (thought.len() as f32 / 100.0));
-
-        // Monitor doesn't calculate attention entropy yet
-        let attention_entropy = Some(0.7);
+        original_query: &str,
+        logprobs: Option<&[TokenLogprob]>,
+        signals: &MonitoringSignals,
+    ) -> StepMetrics {
+        // Semantic similarity to the original query; low values mean drift
+        let semantic_similarity = if original_query.trim().is_empty() {
+            Some((1.0 - signals.circular_score) as f32)
+        } else {
+            Some(
+                self.session_manager
+                    .embedder()
+                    .similarity(thought, original_query) as f32,
+            )
+        };
+
+        // Perplexity and entropy need token logprobs; leave them empty rather than guess
+        let stats = logprobs.and_then(LogprobStats::from_logprobs);
+        let token_metrics = if stats.is_some() {
+            TokenMetricsSource::Logprobs
+        } else {
+            TokenMetricsSource::Unavailable
+        };
+        let perplexity = stats.map(|s| s.perplexity);
+        let attention_entropy = stats.and_then(|s| s.mean_entropy);
 
         // Use quality trend to estimate consistency
         let consistency_score = match signals.quality_trend.as_str() {
@@ -977,19 +1314,22 @@ impl TracedReasoningTool {
             _ => Some(0.8),
         };
 
-        Ok(StepMetrics {
+        StepMetrics {
             semantic_similarity,
             perplexity,
             attention_entropy,
             consistency_score,
-        })
+            token_metrics,
+        }
     }
 
     fn calculate_step_confidence(&self, metrics: &StepMetrics) -> f32 {
         let mut confidence = 1.0;
 
+        // On-topic text rarely scores near 1.0 against the query, so only low
+        // similarity (drift) lowers confidence
         if let Some(similarity) = metrics.semantic_similarity {
-            confidence *= similarity;
+            confidence *= (similarity / 0.5).min(1.0);
         }
 
         if let Some(perplexity) = metrics.perplexity {
@@ -1003,6 +1343,20 @@ impl TracedReasoningTool {
         confidence.max(0.1).min(1.0)
     }
 
+    fn contradiction_intervention(contradiction: &Contradiction) -> Intervention {
+        Intervention {
+            step: contradiction.later_step,
+            intervention_type: InterventionType::Contradiction,
+            related_step: Some(contradiction.earlier_step),
+            policy: None,
+            description: contradiction.to_string(),
+            severity: match contradiction.kind {
+                ContradictionKind::Polarity => Severity::High,
+                ContradictionKind::Numeric => Severity::Medium,
+            },
+        }
+    }
+
     fn check_interventions(
         &self,
         step: u32,
@@ -1011,6 +1365,16 @@ impl TracedReasoningTool {
         previous_steps: &[ReasoningStep],
         guardrails: &GuardrailConfig,
     ) -> Option<Intervention> {
+        // Contradiction with an earlier step
+        if guardrails.consistency_validation {
+            let history = previous_steps
+                .iter()
+                .map(|s| (s.step_number, s.thought.as_str()));
+            if let Some(contradiction) = find_contradiction(thought, step, history) {
+                return Some(Self::contradiction_intervention(&contradiction));
+            }
+        }
+
         // Semantic drift check
         if guardrails.semantic_drift_check {
             if let Some(similarity) = metrics.semantic_similarity {
@@ -1018,6 +1382,8 @@ impl TracedReasoningTool {
                     return Some(Intervention {
                         step,
                         intervention_type: InterventionType::SemanticDrift,
+                        related_step: None,
+                        policy: None,
                         description: format!(
                             "Reasoning drifting from original query (similarity: {:.2})",
                             similarity
@@ -1039,8 +1405,10 @@ impl TracedReasoningTool {
                     return Some(Intervention {
                         step,
                         intervention_type: InterventionType::HighPerplexity,
+                        related_step: None,
+                        policy: None,
                         description: format!("High perplexity detected: {:.1}", perplexity),
-                        severity: if perplexity > 80.0 {
+                        severity: if perplexity > guardrails.perplexity_threshold * 2.0 {
                             Severity::High
                         } else {
                             Severity::Medium
@@ -1057,6 +1425,8 @@ impl TracedReasoningTool {
                     return Some(Intervention {
                         step,
                         intervention_type: InterventionType::CircularReasoning,
+                        related_step: None,
+                        policy: None,
                         description: "Potential circular reasoning detected".to_string(),
                         severity: Severity::Medium,
                     });
@@ -1068,17 +1438,7 @@ impl TracedReasoningTool {
     }
 
     fn text_similarity(&self, text1: &str, text2: &str) -> f32 {
-        // Simple word overlap similarity
-        let words1: std::collections::HashSet<_> = text1.split_whitespace().collect();
-        let words2: std::collections::HashSet<_> = text2.split_whitespace().collect();
-        let intersection = words1.intersection(&words2).count();
-        let union = words1.union(&words2).count();
-
-        if union == 0 {
-            0.0
-        } else {
-            intersection as f32 / union as f32
-        }
+        self.session_manager.embedder().similarity(text1, text2) as f32
     }
 
     fn extract_final_answer(&self, content: &str) -> String {
@@ -1091,7 +1451,11 @@ impl TracedReasoningTool {
         }
     }
 
-    fn calculate_overall_metrics(&self, thoughts: &[ThoughtData]) -> ReasoningMetrics {
+    fn calculate_overall_metrics(
+        &self,
+        thoughts: &[ThoughtData],
+        interventions: &[Intervention],
+    ) -> ReasoningMetrics {
         let total_steps = thoughts.len() as u32;
         let average_confidence = if thoughts.is_empty() {
             0.0
@@ -1107,13 +1471,16 @@ impl TracedReasoningTool {
 
         let reasoning_quality = average_confidence * semantic_coherence;
 
-        ReasoningMetrics {
+        let mut metrics = ReasoningMetrics {
             total_steps,
             average_confidence,
             semantic_coherence,
             reasoning_quality,
             path_consensus: None, // Would require multiple reasoning paths
-        }
+            final_confidence: 0.0,
+        };
+        metrics.final_confidence = self.calculate_final_confidence(&metrics, interventions);
+        metrics
     }
 
     fn calculate_final_confidence(
@@ -1142,12 +1509,15 @@ impl TracedReasoningTool {
         messages: Vec<ChatMessage>,
         temperature: f32,
         thought_number: u32,
-    ) -> Result<(String, String)> {
+        options: CompletionOptions,
+    ) -> Result<LLMResponse> {
         info!("🚀 Sending thought {} to LLM for reasoning", thought_number);
         let start_time = std::time::Instant::now();
 
         // Define fallback models
-        let fallback_models = self.get_fallback_models(requested_model);
+        let fallback_models =
+            self.config
+                .fallback_models("traced_reasoning", requested_model, &self.model_resolver);
 
         // Try requested model and fallbacks
         let mut last_error = None;
@@ -1164,7 +1534,7 @@ impl TracedReasoningTool {
             }
 
             match self
-                .try_llm_call(&model, messages.clone(), temperature)
+                .try_llm_call(&model, messages.clone(), temperature, options.clone())
                 .await
             {
                 Ok(response) => {
@@ -1177,7 +1547,7 @@ impl TracedReasoningTool {
                     } else {
                         info!("✅ Thought {} generated in {:?}", thought_number, elapsed);
                     }
-                    return Ok((response.content, model));
+                    return Ok(response);
                 }
                 Err(e) => {
                     let error_str = e.to_string();
@@ -1217,7 +1587,8 @@ impl TracedReasoningTool {
         model: &str,
         messages: Vec<ChatMessage>,
         temperature: f32,
-    ) -> Result<crate::llm::client::LLMResponse> {
+        options: CompletionOptions,
+    ) -> Result<LLMResponse> {
         let client = self
             .get_client_for_model(model)
             .context("Failed to get LLM client")?;
@@ -1242,105 +1613,150 @@ impl TracedReasoningTool {
             Some(50000) // O4: Fast but deep
         } else {
             Some(20000) // Standard: Still give room to think
-        };
-
-        client.complete(messages, temperature_opt, max_tokens).await
-    }
-
-    fn get_fallback_models(&self, requested_model: &str) -> Vec<String> {
-        let mut fallbacks = Vec::new();
-
-        // If a Claude model was requested, try other Claude variants
-        if requested_model.contains("claude") {
-            fallbacks.push("anthropic/claude-3.5-sonnet".to_string());
-            fallbacks.push("anthropic/claude-3-opus".to_string());
-            fallbacks.push("anthropic/claude-3-sonnet".to_string());
         }
+        .map(|tokens| self.config.tool("traced_reasoning").cap_tokens(tokens));
 
-        // Add default reasoning model as fallback if not already requested
-        if requested_model != self.config.model_reasoning {
-            fallbacks.push(self.config.model_reasoning.clone());
-        }
-
-        // Add o3 as fallback for reasoning tasks
-        if !requested_model.starts_with("o3") {
-            fallbacks.push("o3".to_string());
-        }
+        client
+            .complete_with_options(messages, temperature_opt, max_tokens, options)
+            .await
+    }
 
-        // Add o4-mini as fallback if o4 model requested
-        if requested_model.starts_with("o4") && requested_model != "o4-mini" {
-            fallbacks.push("o4-mini".to_string());
+    fn get_client_for_model(&self, model: &str) -> Result<Arc<dyn LLMClient>> {
+        if let Some((_, client)) = self.openrouter_clients.iter().find(|(m, _)| m == model) {
+            return Ok(client.clone());
         }
+        self.config.client_for(model)
+    }
+}
 
-        // Strict policy: Only GPT-5 and GPT-5-mini are allowed
-        if requested_model != "gpt-5" {
-            fallbacks.push("gpt-5".to_string());
-        }
-        if requested_model != "gpt-5-mini" {
-            fallbacks.push("gpt-5-mini".to_string());
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_contradicting_thoughts_reach_the_response() {
+        let session_manager = Arc::new(SessionManager::new(30));
+        let tool = TracedReasoningTool::new(LLMConfig::default(), session_manager.clone()).unwrap();
+        let thought = |thought: &str, thought_number: u32| -> TracedReasoningRequest {
+            serde_json::from_value(serde_json::json!({
+                "thought": thought,
+                "thought_number": thought_number,
+                "total_thoughts": 4,
+                "next_thought_needed": true,
+                "session_id": "contradiction",
+            }))
+            .unwrap()
+        };
 
-        // Remove duplicates while preserving order
-        let mut seen = HashSet::new();
-        fallbacks.retain(|model| seen.insert(model.clone()));
+        // Thought 1 needs no model and starts the session
+        tool.process_thought(thought("Is the session cache safe to share?", 1))
+            .await
+            .unwrap();
+        let monitor = session_manager.get_monitor("contradiction").unwrap();
+        let mut chain = tool.chain.lock().await;
+        let generated = |content: &str| GeneratedThought {
+            content: content.to_string(),
+            step_type: StepType::Analysis,
+            logprobs: None,
+        };
 
-        fallbacks
+        let first = tool
+            .record_thought(
+                &mut chain,
+                &thought("Continue", 2),
+                &monitor,
+                generated("The session cache is thread-safe because every access takes the lock."),
+                "o3",
+            )
+            .await
+            .unwrap();
+        assert!(first.intervention.is_none());
+
+        let second = tool
+            .record_thought(
+                &mut chain,
+                &thought("Continue", 3),
+                &monitor,
+                generated(
+                    "Looking closer, the session cache is not thread-safe when eviction runs.",
+                ),
+                "o3",
+            )
+            .await
+            .unwrap();
+        let intervention = second.intervention.unwrap();
+        assert!(matches!(
+            intervention.intervention_type,
+            InterventionType::Contradiction
+        ));
+        assert_eq!(intervention.related_step, Some(2));
+        assert!(intervention
+            .description
+            .starts_with("Step 3 contradicts step 2"));
+        assert_eq!(second.metadata.interventions_count, 1);
+        assert_eq!(chain.interventions.len(), 1);
     }
 
-    fn get_client_for_model(&self, model: &str) -> Result<Arc<dyn LLMClient>> {
-        debug!("Getting client for model: {}", model);
-        debug!(
-            "OpenAI key available: {}",
-            self.config.openai_api_key.is_some()
-        );
-        debug!(
-            "OpenRouter key available: {}",
-            self.config.openrouter_api_key.is_some()
+    #[tokio::test]
+    async fn test_open_subgoals_hold_a_conclusion_at_most_twice() {
+        let tool =
+            TracedReasoningTool::new(LLMConfig::default(), Arc::new(SessionManager::new(30)))
+                .unwrap();
+        let mut chain = tool.chain.lock().await;
+        let tracker = SubgoalTracker::from_list(
+            &[
+                "Explain why the queue stalls".to_string(),
+                "Propose a retry strategy".to_string(),
+            ],
+            1,
         );
 
-        if self.model_resolver.is_openrouter_model(model) {
-            info!("Using OpenRouter for model: {}", model);
-            if self.config.openrouter_api_key.is_none() {
-                error!("OpenRouter API key not configured for model: {}", model);
-                anyhow::bail!("OpenRouter API key not configured");
-            }
+        let mut attempt = |thought_number: u32| {
+            let request: TracedReasoningRequest = serde_json::from_value(serde_json::json!({
+                "thought": "Final answer",
+                "thought_number": thought_number,
+                "total_thoughts": thought_number,
+                "next_thought_needed": false,
+            }))
+            .unwrap();
+            let metrics = StepMetrics {
+                semantic_similarity: None,
+                perplexity: None,
+                attention_entropy: None,
+                consistency_score: None,
+                token_metrics: TokenMetricsSource::default(),
+            };
+            let thought = ThoughtData {
+                thought_number,
+                content: "Final answer: raise the worker count.".to_string(),
+                thought_type: StepType::Conclusion,
+                metrics,
+                confidence: 0.8,
+                is_revision: false,
+                revises_thought: None,
+                branch_id: None,
+            };
+            let mut response = tool.build_reasoning_response(&chain, &request, &thought, "gpt-5");
+            response.coverage = Some(tracker.coverage());
+            tool.settle_conclusion(&mut chain, &request, &mut response, true);
+            response
+        };
 
-            if let Some((_, client)) = self.openrouter_clients.iter().find(|(m, _)| m == model) {
-                debug!("Found pre-created OpenRouter client for model: {}", model);
-                Ok(client.clone())
-            } else {
-                debug!("Creating new OpenRouter client for model: {}", model);
-                let api_key = self
-                    .config
-                    .openrouter_api_key
-                    .as_ref()
-                    .ok_or_else(|| anyhow::anyhow!("OpenRouter API key not available"))?;
-                let new_client = OpenRouterClient::new(
-                    api_key.clone(),
-                    model.to_string(),
-                    self.config.openrouter_base_url.clone(),
-                )?;
-                Ok(Arc::new(new_client) as Arc<dyn LLMClient>)
-            }
-        } else {
-            info!("Using OpenAI for model: {}", model);
-            if self.openai_client.is_some() {
-                if let Some(api_key) = &self.config.openai_api_key {
-                    debug!("Creating OpenAI client for model: {}", model);
-                    let new_client = OpenAIClient::new(
-                        api_key.clone(),
-                        model.to_string(),
-                        self.config.openai_base_url.clone(),
-                    )?;
-                    Ok(Arc::new(new_client) as Arc<dyn LLMClient>)
-                } else {
-                    error!("OpenAI API key not configured but client exists");
-                    anyhow::bail!("OpenAI API key not configured");
-                }
-            } else {
-                error!("OpenAI client not initialized - API key missing");
-                anyhow::bail!("OpenAI API key not configured");
-            }
+        for thought_number in [3, 4] {
+            let held = attempt(thought_number);
+            assert_eq!(held.status, "thinking");
+            assert!(held.next_thought_needed && held.reasoning_complete.is_none());
+            assert!(held
+                .next_steps
+                .unwrap()
+                .starts_with("Not ready to conclude"));
         }
+
+        let accepted = attempt(5);
+        assert_eq!(accepted.status, "conclusion_reached");
+        assert_eq!(accepted.reasoning_complete, Some(true));
+        let next_steps = accepted.next_steps.unwrap();
+        assert!(next_steps.contains("WARNING"));
+        assert!(next_steps.contains("2. Propose a retry strategy (unaddressed)"));
     }
 }
@@ -3,6 +3,7 @@ use lux_mcp::db::{DatabaseConnection, DatabaseService};
 use lux_mcp::llm::config::LLMConfig;
 use lux_mcp::server::LuxServer;
 use lux_mcp::session::SessionManager;
+use lux_mcp::settings::LuxSettings;
 use lux_mcp::tools::{BiasedReasoningRequest, BiasedReasoningTool, StepType};
 use std::sync::Arc;
 
@@ -16,7 +17,7 @@ async fn test_biased_reasoning_synthesis_evolution() -> Result<()> {
     );
 
     // Create config
-    let config = LLMConfig::from_env()?;
+    let config = LLMConfig::from_settings(&LuxSettings::load()?)?;
 
     // Create session manager with 30 minute TTL
     let session_manager = Arc::new(SessionManager::new(30));
@@ -32,6 +33,10 @@ async fn test_biased_reasoning_synthesis_evolution() -> Result<()> {
         primary_model: None,
         verifier_model: None,
         max_analysis_rounds: 3,
+        file_paths: None,
+        include_file_contents: true,
+        reasoning_effort: None,
+        verbosity: None,
     };
 
     println!("Test 1: Initial query");
@@ -49,6 +54,10 @@ async fn test_biased_reasoning_synthesis_evolution() -> Result<()> {
         primary_model: None,
         verifier_model: None,
         max_analysis_rounds: 3,
+        file_paths: None,
+        include_file_contents: true,
+        reasoning_effort: None,
+        verbosity: None,
     };
 
     println!("\nTest 2: Continue reasoning");
@@ -71,6 +80,10 @@ async fn test_biased_reasoning_synthesis_evolution() -> Result<()> {
         primary_model: None,
         verifier_model: None,
         max_analysis_rounds: 3,
+        file_paths: None,
+        include_file_contents: true,
+        reasoning_effort: None,
+        verbosity: None,
     };
 
     println!("\nTest 3: Bias check");
@@ -86,6 +99,10 @@ async fn test_biased_reasoning_synthesis_evolution() -> Result<()> {
         primary_model: None,
         verifier_model: None,
         max_analysis_rounds: 3,
+        file_paths: None,
+        include_file_contents: true,
+        reasoning_effort: None,
+        verbosity: None,
     };
 
     println!("\nTest 4: Final synthesis");
@@ -122,6 +139,10 @@ async fn test_synthesis_database_persistence() -> Result<()> {
         primary_model: None,
         verifier_model: None,
         max_analysis_rounds: 1,
+        file_paths: None,
+        include_file_contents: true,
+        reasoning_effort: None,
+        verbosity: None,
     };
 
     // Create or get session